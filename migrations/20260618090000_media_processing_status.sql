@@ -0,0 +1,20 @@
+-- Background media processing.
+--
+-- Image uploads now store the original and return immediately; variants, dimensions
+-- and the blurhash are filled in by the media worker pool afterwards. These columns
+-- track that lifecycle:
+--   status             — pending → processing → ready | failed
+--   processing_attempts — how many times a worker has picked the row up
+--   processing_error   — last failure message (NULL once ready)
+-- Existing rows were processed inline at upload time, so they default to 'ready'.
+CREATE TYPE media_status AS ENUM ('pending', 'processing', 'ready', 'failed');
+
+ALTER TABLE project_media
+    ADD COLUMN status media_status NOT NULL DEFAULT 'ready',
+    ADD COLUMN processing_attempts INT NOT NULL DEFAULT 0,
+    ADD COLUMN processing_error TEXT;
+
+-- Startup recovery scans for unfinished work; keep that lookup cheap.
+CREATE INDEX idx_project_media_unfinished
+    ON project_media (created_at)
+    WHERE status IN ('pending', 'processing');
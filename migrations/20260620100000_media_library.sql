@@ -0,0 +1,70 @@
+-- Site-level media library.
+--
+-- Stored files and their processing state move out of project_media into
+-- media_assets, so one asset can be attached to several projects, placed in
+-- figure blocks (attrs.mediaId), and used as the site avatar/favicon.
+-- project_media becomes the ordered attachment of an asset to a project.
+-- References are counted on demand; an asset can only be deleted once none remain.
+CREATE TABLE media_assets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    media_type media_type NOT NULL,
+    original_filename TEXT NOT NULL,
+    r2_base_path TEXT NOT NULL,
+    variants JSONB NOT NULL,
+    width INT,
+    height INT,
+    size_bytes BIGINT NOT NULL,
+    blurhash TEXT,
+    metadata JSONB,
+    status media_status NOT NULL DEFAULT 'ready',
+    processing_attempts INT NOT NULL DEFAULT 0,
+    processing_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Existing media keep their id as the asset id, so queued processing jobs and
+-- storage paths carry over unchanged.
+INSERT INTO media_assets (
+    id, media_type, original_filename, r2_base_path, variants, width, height,
+    size_bytes, blurhash, metadata, status, processing_attempts, processing_error,
+    created_at
+)
+SELECT
+    id, media_type, original_filename, r2_base_path, variants, width, height,
+    size_bytes, blurhash, metadata, status, processing_attempts, processing_error,
+    created_at
+FROM project_media;
+
+ALTER TABLE project_media ADD COLUMN asset_id UUID;
+UPDATE project_media SET asset_id = id;
+
+DROP INDEX idx_project_media_unfinished;
+
+ALTER TABLE project_media
+    ALTER COLUMN asset_id SET NOT NULL,
+    ADD CONSTRAINT project_media_asset_id_fkey
+        FOREIGN KEY (asset_id) REFERENCES media_assets(id) ON DELETE RESTRICT,
+    ADD CONSTRAINT project_media_project_asset_key UNIQUE (project_id, asset_id),
+    DROP COLUMN media_type,
+    DROP COLUMN original_filename,
+    DROP COLUMN r2_base_path,
+    DROP COLUMN variants,
+    DROP COLUMN width,
+    DROP COLUMN height,
+    DROP COLUMN size_bytes,
+    DROP COLUMN blurhash,
+    DROP COLUMN metadata,
+    DROP COLUMN status,
+    DROP COLUMN processing_attempts,
+    DROP COLUMN processing_error;
+
+CREATE INDEX idx_project_media_asset_id ON project_media(asset_id);
+
+-- Startup recovery scans for unfinished work; keep that lookup cheap.
+CREATE INDEX idx_media_assets_unfinished
+    ON media_assets (created_at)
+    WHERE status IN ('pending', 'processing');
+
+ALTER TABLE site_identity
+    ADD COLUMN avatar_media_id UUID REFERENCES media_assets(id) ON DELETE RESTRICT,
+    ADD COLUMN favicon_media_id UUID REFERENCES media_assets(id) ON DELETE RESTRICT;
@@ -0,0 +1,20 @@
+-- TOTP two-factor authentication for admin users.
+-- `totp_secret` is written at enrollment and only takes effect once the user
+-- confirms a code (`totp_enabled_at`). `totp_last_step` is the last accepted
+-- 30-second step, so a code can't be replayed within its validity window.
+ALTER TABLE admin_users
+    ADD COLUMN totp_secret TEXT,
+    ADD COLUMN totp_enabled_at TIMESTAMPTZ,
+    ADD COLUMN totp_last_step BIGINT;
+
+-- One-time recovery codes. Like CLI tokens, only a SHA-256 hash is stored.
+CREATE TABLE admin_recovery_codes (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES admin_users(id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_admin_recovery_codes_hash
+    ON admin_recovery_codes (user_id, code_hash);
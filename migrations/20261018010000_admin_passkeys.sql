@@ -0,0 +1,16 @@
+-- WebAuthn passkeys for admin users. `public_key` is the credential's COSE_Key
+-- as the authenticator encoded it; `sign_count` is the last signature counter
+-- seen, used to detect cloned authenticators.
+CREATE TABLE admin_passkeys (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES admin_users(id) ON DELETE CASCADE,
+    credential_id BYTEA NOT NULL UNIQUE,
+    public_key BYTEA NOT NULL,
+    algorithm INTEGER NOT NULL,
+    sign_count BIGINT NOT NULL DEFAULT 0,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_admin_passkeys_user_id ON admin_passkeys (user_id);
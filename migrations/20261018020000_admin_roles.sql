@@ -0,0 +1,9 @@
+-- Admin roles: owners manage the site (settings, sessions, users), editors
+-- manage content, viewers have read-only access to the admin panel. Existing
+-- users predate roles and had full access, so they become owners; new users
+-- default to the least privileged role.
+ALTER TABLE admin_users
+    ADD COLUMN role TEXT NOT NULL DEFAULT 'owner'
+        CHECK (role IN ('owner', 'editor', 'viewer'));
+
+ALTER TABLE admin_users ALTER COLUMN role SET DEFAULT 'viewer';
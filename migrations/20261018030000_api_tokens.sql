@@ -0,0 +1,11 @@
+-- Scoped API tokens for automation. They're bearer sessions like CLI tokens
+-- (hashed in token_hash), but carry an explicit scope list, an optional
+-- project allowlist, and a fixed expiry that never slides.
+ALTER TABLE sessions DROP CONSTRAINT sessions_session_type_check;
+ALTER TABLE sessions
+    ADD CONSTRAINT sessions_session_type_check
+        CHECK (session_type IN ('browser', 'cli', 'token')),
+    -- NULL for browser and CLI sessions, which aren't scoped
+    ADD COLUMN scopes TEXT[],
+    -- Project ids the token may touch; NULL allows every project
+    ADD COLUMN project_ids UUID[];
@@ -0,0 +1,9 @@
+-- Where sessions come from, so a stolen cookie or token stands out in the
+-- sessions list. Addresses are stored as text; NULL for sessions created
+-- before this migration.
+ALTER TABLE sessions
+    ADD COLUMN created_ip TEXT,
+    -- Updated with last_active_at
+    ADD COLUMN last_ip TEXT,
+    -- Of the client that created the session (the CLI itself for CLI logins)
+    ADD COLUMN user_agent TEXT;
@@ -0,0 +1,252 @@
+//! Scoped API tokens for automation.
+//!
+//! CLI logins carry the user's full role. An API token is minted without a
+//! browser (`xevion tokens create`) and can only do what its scopes allow.
+//! Reads need no scope. Each write route maps to one scope in
+//! [`required_scope`], and routes missing from that table are off-limits to
+//! tokens. A token can also be limited to some projects, and then it can only
+//! write under `/projects/{ref}`: tags, library media, and project creation
+//! reach beyond any one project. Every token expires on a fixed date.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// Longest lifetime a token can be created with.
+pub const MAX_TOKEN_DAYS: u32 = 365;
+/// Lifetime when the request doesn't give one.
+pub const DEFAULT_TOKEN_DAYS: u32 = 30;
+
+/// A permission granted to an API token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub enum ApiScope {
+    /// Create, update, delete, and sync projects, and set their tags.
+    #[serde(rename = "projects:write")]
+    ProjectsWrite,
+    /// Edit project content documents.
+    #[serde(rename = "content:write")]
+    ContentWrite,
+    /// Upload media to projects or the library, and attach it.
+    #[serde(rename = "media:upload")]
+    MediaUpload,
+    /// Delete and reorder media.
+    #[serde(rename = "media:write")]
+    MediaWrite,
+    /// Create, update, and delete tags.
+    #[serde(rename = "tags:write")]
+    TagsWrite,
+    /// Purge the page cache.
+    #[serde(rename = "cache:purge")]
+    CachePurge,
+}
+
+impl ApiScope {
+    pub const ALL: [Self; 6] = [
+        Self::ProjectsWrite,
+        Self::ContentWrite,
+        Self::MediaUpload,
+        Self::MediaWrite,
+        Self::TagsWrite,
+        Self::CachePurge,
+    ];
+
+    /// Canonical wire/storage string.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ProjectsWrite => "projects:write",
+            Self::ContentWrite => "content:write",
+            Self::MediaUpload => "media:upload",
+            Self::MediaWrite => "media:write",
+            Self::TagsWrite => "tags:write",
+            Self::CachePurge => "cache:purge",
+        }
+    }
+}
+
+impl std::fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| {
+                let known: Vec<_> = Self::ALL.iter().map(|s| s.as_str()).collect();
+                format!("unknown scope '{s}' (expected one of {})", known.join(", "))
+            })
+    }
+}
+
+/// `POST /api/tokens` request body.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// Project slugs or ids the token is limited to; empty allows every project.
+    /// A limited token can't write outside its projects (tags, library media).
+    #[serde(default)]
+    pub projects: Vec<String>,
+    /// Defaults to [`DEFAULT_TOKEN_DAYS`]; at most [`MAX_TOKEN_DAYS`].
+    #[serde(default)]
+    pub expires_in_days: Option<u32>,
+}
+
+/// An API token as listed by `GET /api/tokens`. The secret is never listed.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// Projects the token is limited to; absent when it may touch any project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub project_ids: Option<Vec<Uuid>>,
+    pub created_at: String,
+    /// Last request made with the token (recorded at most every few minutes).
+    pub last_used_at: String,
+    pub expires_at: String,
+}
+
+/// `POST /api/tokens` response: the token's details and its secret, which is
+/// shown exactly once.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: ApiToken,
+}
+
+/// The scope a token needs to call a write route. `route` is the matched route
+/// template below `/api` (e.g. `/projects/{ref}/content`). `None` means tokens
+/// may not call it at all: account, session, user, and settings management stay
+/// with interactive logins.
+#[cfg(feature = "server")]
+pub fn required_scope(method: &axum::http::Method, route: &str) -> Option<ApiScope> {
+    use axum::http::Method;
+
+    let scope = match *method {
+        Method::POST => match route {
+            "/projects" | "/projects/{ref}/sync" | "/projects/{ref}/tags" | "/github/sync" => {
+                ApiScope::ProjectsWrite
+            }
+            "/projects/{ref}/media"
+            | "/projects/{ref}/media/uploads"
+            | "/projects/{ref}/media/uploads/{upload_id}/complete"
+            | "/projects/{ref}/media/attach"
+            | "/media" => ApiScope::MediaUpload,
+            "/tags" | "/tags/recalculate-cooccurrence" => ApiScope::TagsWrite,
+            "/cache/purge" => ApiScope::CachePurge,
+            _ => return None,
+        },
+        Method::PUT => match route {
+            "/projects/{ref}" => ApiScope::ProjectsWrite,
+            "/projects/{ref}/content" => ApiScope::ContentWrite,
+            "/projects/{ref}/media/reorder" => ApiScope::MediaWrite,
+            "/tags/{ref}" => ApiScope::TagsWrite,
+            _ => return None,
+        },
+        Method::PATCH => match route {
+            "/projects/{ref}/content" => ApiScope::ContentWrite,
+            "/projects/{ref}/media/uploads/{upload_id}" => ApiScope::MediaUpload,
+            _ => return None,
+        },
+        Method::DELETE => match route {
+            "/projects/{ref}" | "/projects/{ref}/tags/{tag_ref}" => ApiScope::ProjectsWrite,
+            "/projects/{ref}/media/uploads/{upload_id}" => ApiScope::MediaUpload,
+            "/projects/{ref}/media/{media_id}" | "/media/{id}" => ApiScope::MediaWrite,
+            "/tags/{ref}" => ApiScope::TagsWrite,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(scope)
+}
+
+/// Whether `route` acts on a single project, the only place a token limited
+/// to some projects may write. Every other write route (creating or syncing
+/// projects, library media, tags) can affect projects outside the allowlist.
+#[cfg(feature = "server")]
+pub fn targets_one_project(route: &str) -> bool {
+    route == "/projects/{ref}" || route.starts_with("/projects/{ref}/")
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use axum::http::Method;
+
+    #[test]
+    fn scopes_round_trip_through_strings() {
+        for scope in ApiScope::ALL {
+            assert_eq!(scope.as_str().parse::<ApiScope>(), Ok(scope));
+            assert_eq!(
+                serde_json::to_value(scope).unwrap(),
+                serde_json::json!(scope.as_str())
+            );
+        }
+        assert!("projects:admin".parse::<ApiScope>().is_err());
+    }
+
+    #[test]
+    fn write_routes_map_to_scopes() {
+        assert_eq!(
+            required_scope(&Method::PATCH, "/projects/{ref}/content"),
+            Some(ApiScope::ContentWrite)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/projects/{ref}/media/uploads"),
+            Some(ApiScope::MediaUpload)
+        );
+        assert_eq!(
+            required_scope(&Method::DELETE, "/media/{id}"),
+            Some(ApiScope::MediaWrite)
+        );
+        assert_eq!(
+            required_scope(&Method::DELETE, "/tags/{ref}"),
+            Some(ApiScope::TagsWrite)
+        );
+    }
+
+    #[test]
+    fn management_routes_are_closed_to_tokens() {
+        for (method, route) in [
+            (Method::PUT, "/settings"),
+            (Method::POST, "/users"),
+            (Method::POST, "/tokens"),
+            (Method::DELETE, "/sessions/{id}"),
+            (Method::POST, "/auth/device/approve"),
+            (Method::POST, "/account/totp/setup"),
+            (Method::POST, "/storage/gc"),
+        ] {
+            assert_eq!(required_scope(&method, route), None, "{method} {route}");
+        }
+    }
+
+    #[test]
+    fn only_project_routes_target_one_project() {
+        assert!(targets_one_project("/projects/{ref}"));
+        assert!(targets_one_project("/projects/{ref}/media/{media_id}"));
+        for route in [
+            "/projects",
+            "/github/sync",
+            "/media",
+            "/media/{id}",
+            "/tags/{ref}",
+            "/tags/recalculate-cooccurrence",
+            "/cache/purge",
+        ] {
+            assert!(!targets_one_project(route), "{route}");
+        }
+    }
+}
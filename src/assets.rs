@@ -1,119 +1,237 @@
+use axum::body::Bytes;
 use axum::{
-    http::{HeaderMap, StatusCode, Uri, header},
+    http::{HeaderMap, HeaderValue, StatusCode, Uri, header},
     response::{IntoResponse, Response},
 };
-use include_dir::{Dir, include_dir};
+use dashmap::DashMap;
+use include_dir::{Dir, File, include_dir};
+use std::{collections::HashMap, path::Path, sync::LazyLock};
 
-use crate::encoding;
+use crate::{
+    conditional,
+    encoding::{self, ContentEncoding},
+};
 
 pub(crate) static CLIENT_ASSETS: Dir = include_dir!("$CARGO_MANIFEST_DIR/web/build/client");
 static ERROR_PAGES: Dir = include_dir!("$CARGO_MANIFEST_DIR/web/build/prerendered/errors");
 static PRERENDERED_PAGES: Dir = include_dir!("$CARGO_MANIFEST_DIR/web/build/prerendered");
 static ENV_JS: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/web/build/env.js"));
 
-pub async fn serve_embedded_asset(uri: Uri) -> Response {
+/// Strong `ETag`s of every embedded client asset and prerendered page, keyed
+/// by path. Compressed variants are tagged from these with
+/// [`conditional::encoded_etag`].
+static CLIENT_ETAGS: LazyLock<HashMap<&'static Path, HeaderValue>> =
+    LazyLock::new(|| etags(&CLIENT_ASSETS));
+static PRERENDERED_ETAGS: LazyLock<HashMap<&'static Path, HeaderValue>> =
+    LazyLock::new(|| etags(&PRERENDERED_PAGES));
+
+/// Compressed variants of embedded files by bundle, file, and encoding; `None`
+/// records that the encoding doesn't help, so it isn't retried
+static COMPRESSED_FILES: LazyLock<DashMap<CompressedKey, Option<Bytes>>> =
+    LazyLock::new(DashMap::new);
+
+type CompressedKey = (Bundle, &'static Path, ContentEncoding);
+
+/// The embedded trees served with content encoding negotiation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Bundle {
+    Client,
+    Prerendered,
+}
+
+impl Bundle {
+    fn dir(self) -> &'static Dir<'static> {
+        match self {
+            Self::Client => &CLIENT_ASSETS,
+            Self::Prerendered => &PRERENDERED_PAGES,
+        }
+    }
+
+    fn etag(self, file: &File<'_>) -> Option<&'static HeaderValue> {
+        match self {
+            Self::Client => CLIENT_ETAGS.get(file.path()),
+            Self::Prerendered => PRERENDERED_ETAGS.get(file.path()),
+        }
+    }
+}
+
+fn etags(dir: &'static Dir<'static>) -> HashMap<&'static Path, HeaderValue> {
+    fn walk(dir: &'static Dir<'static>, out: &mut HashMap<&'static Path, HeaderValue>) {
+        for file in dir.files() {
+            out.insert(file.path(), conditional::etag(file.contents()));
+        }
+        for child in dir.dirs() {
+            walk(child, out);
+        }
+    }
+    let mut out = HashMap::new();
+    walk(dir, &mut out);
+    out
+}
+
+/// Hash the embedded bundle up front so the first request for each asset
+/// doesn't pay for it.
+pub fn precompute_etags() -> usize {
+    CLIENT_ETAGS.len() + PRERENDERED_ETAGS.len()
+}
+
+pub async fn serve_embedded_asset(uri: Uri, headers: HeaderMap) -> Response {
     let path = uri.path();
-    serve_asset_by_path(path)
+    serve_asset_by_path(path, &headers)
+}
+
+/// Respond with an embedded file, or 304 if the request already holds it
+fn serve_file(
+    body: Bytes,
+    etag: Option<&HeaderValue>,
+    mut headers: HeaderMap,
+    request_headers: &HeaderMap,
+) -> Response {
+    if let Some(etag) = etag {
+        headers.insert(header::ETAG, etag.clone());
+    }
+    if conditional::is_not_modified(request_headers, etag, None) {
+        return conditional::not_modified(&headers);
+    }
+    (StatusCode::OK, headers, body).into_response()
+}
+
+fn content_type(path: &str) -> HeaderValue {
+    mime_guess::from_path(path)
+        .first_or_octet_stream()
+        .as_ref()
+        .parse()
+        .unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream"))
+}
+
+fn asset_cache_control(path: &str) -> HeaderValue {
+    if path.contains("/immutable/") {
+        HeaderValue::from_static("public, max-age=31536000, immutable")
+    } else {
+        HeaderValue::from_static("public, max-age=3600")
+    }
+}
+
+/// Whether an asset is worth compressing: a text-like type, above the
+/// threshold shared with the frontend's compress script
+fn is_compressible(asset_path: &str, file: &File<'_>) -> bool {
+    file.contents().len() >= usize::from(encoding::COMPRESSION_MIN_SIZE)
+        && encoding::is_compressible_content_type(content_type(asset_path).to_str().unwrap_or(""))
+}
+
+/// `file` in `encoding`: the frontend build's precompressed sibling if it
+/// emitted one, otherwise compressed here on first request and memoized.
+/// `None` if the encoding doesn't shrink it.
+fn compressed_file(
+    bundle: Bundle,
+    asset_path: &str,
+    file: &'static File<'static>,
+    encoding: ContentEncoding,
+) -> Option<Bytes> {
+    let key = (bundle, file.path(), encoding);
+    if let Some(memoized) = COMPRESSED_FILES.get(&key) {
+        return memoized.clone();
+    }
+
+    let sibling = bundle
+        .dir()
+        .get_file(format!("{asset_path}{}", encoding.extension()));
+    let compressed = match sibling {
+        Some(sibling) => Some(Bytes::from_static(sibling.contents())),
+        None => encoding::compress(file.contents(), encoding)
+            .filter(|compressed| compressed.len() < file.contents().len())
+            .map(Bytes::from),
+    };
+    COMPRESSED_FILES.insert(key, compressed.clone());
+    compressed
+}
+
+/// Respond with the best accepted encoding of `file` (see
+/// [`compressed_file`]), tagged with an `ETag` derived from `identity_etag`.
+/// Files not worth compressing are served as-is.
+fn serve_negotiated(
+    bundle: Bundle,
+    asset_path: &str,
+    file: &'static File<'static>,
+    identity_etag: Option<&HeaderValue>,
+    mut headers: HeaderMap,
+    request_headers: &HeaderMap,
+) -> Response {
+    if !is_compressible(asset_path, file) {
+        let body = Bytes::from_static(file.contents());
+        return serve_file(body, identity_etag, headers, request_headers);
+    }
+
+    // Try each encoding in order of client preference, identity last
+    let variant = encoding::parse_accepted_encodings(request_headers)
+        .into_iter()
+        .filter(|&encoding| encoding != ContentEncoding::Identity)
+        .find_map(|encoding| {
+            Some((
+                encoding,
+                compressed_file(bundle, asset_path, file, encoding)?,
+            ))
+        });
+
+    headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+    let (body, etag) = match variant {
+        Some((encoding, body)) => {
+            if let Some(encoding_value) = encoding.header_value() {
+                headers.insert(header::CONTENT_ENCODING, encoding_value);
+            }
+            let etag = identity_etag.map(|etag| conditional::encoded_etag(etag, encoding));
+            (body, etag)
+        }
+        None => (Bytes::from_static(file.contents()), identity_etag.cloned()),
+    };
+
+    serve_file(body, etag.as_ref(), headers, request_headers)
 }
 
 /// Serve an embedded asset by path, or return None if not found
-pub fn try_serve_embedded_asset(path: &str) -> Option<Response> {
+pub fn try_serve_embedded_asset(path: &str, request_headers: &HeaderMap) -> Option<Response> {
     let asset_path = path.strip_prefix('/').unwrap_or(path);
 
     CLIENT_ASSETS.get_file(asset_path).map(|file| {
-        let mime_type = mime_guess::from_path(asset_path)
-            .first_or_octet_stream()
-            .as_ref()
-            .to_string();
-
-        let mut headers = axum::http::HeaderMap::new();
-        headers.insert(
-            header::CONTENT_TYPE,
-            mime_type
-                .parse()
-                .unwrap_or_else(|_| header::HeaderValue::from_static("application/octet-stream")),
-        );
-
-        if path.contains("/immutable/") {
-            headers.insert(
-                header::CACHE_CONTROL,
-                header::HeaderValue::from_static("public, max-age=31536000, immutable"),
-            );
-        } else {
-            headers.insert(
-                header::CACHE_CONTROL,
-                header::HeaderValue::from_static("public, max-age=3600"),
-            );
-        }
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, content_type(asset_path));
+        headers.insert(header::CACHE_CONTROL, asset_cache_control(path));
 
-        (StatusCode::OK, headers, file.contents()).into_response()
+        serve_file(
+            Bytes::from_static(file.contents()),
+            CLIENT_ETAGS.get(file.path()),
+            headers,
+            request_headers,
+        )
     })
 }
 
 /// Serve an embedded asset with content encoding negotiation.
 ///
-/// Attempts to serve pre-compressed variants (.br, .gz, .zst) based on
-/// Accept-Encoding. Falls back to uncompressed if no suitable variant is found.
-/// Pre-compressed assets are generated at build time by scripts/compress-assets.ts.
+/// Serves the best accepted compressed variant of any compressible asset:
+/// the frontend's precompressed `.zst`/`.br`/`.gz` sibling when the build
+/// emitted one (scripts/compress-assets.ts), otherwise one compressed here
+/// once and memoized. Falls back to uncompressed if no variant helps.
 pub fn try_serve_embedded_asset_with_encoding(path: &str, headers: &HeaderMap) -> Option<Response> {
     let asset_path = path.strip_prefix('/').unwrap_or(path);
+    let file = CLIENT_ASSETS.get_file(asset_path)?;
 
-    // Parse accepted encodings in priority order
-    let accepted_encodings = encoding::parse_accepted_encodings(headers);
-
-    // Try each encoding in order of client preference
-    for encoding in accepted_encodings {
-        // Skip identity - we'll use it as final fallback
-        if encoding == encoding::ContentEncoding::Identity {
-            continue;
-        }
-
-        // Build path to pre-compressed variant
-        let compressed_path = format!("{}{}", asset_path, encoding.extension());
-
-        // Check if pre-compressed variant exists
-        if let Some(file) = CLIENT_ASSETS.get_file(&compressed_path) {
-            // Get MIME type from ORIGINAL path (not .br/.gz/.zst extension)
-            let mime_type = mime_guess::from_path(asset_path)
-                .first_or_octet_stream()
-                .as_ref()
-                .to_string();
-
-            let mut response_headers = axum::http::HeaderMap::new();
-            response_headers.insert(
-                header::CONTENT_TYPE,
-                mime_type.parse().unwrap_or_else(|_| {
-                    header::HeaderValue::from_static("application/octet-stream")
-                }),
-            );
-
-            // Set Content-Encoding header
-            if let Some(encoding_value) = encoding.header_value() {
-                response_headers.insert(header::CONTENT_ENCODING, encoding_value);
-            }
-
-            // Set cache headers (same as uncompressed)
-            if path.contains("/immutable/") {
-                response_headers.insert(
-                    header::CACHE_CONTROL,
-                    header::HeaderValue::from_static("public, max-age=31536000, immutable"),
-                );
-            } else {
-                response_headers.insert(
-                    header::CACHE_CONTROL,
-                    header::HeaderValue::from_static("public, max-age=3600"),
-                );
-            }
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::CONTENT_TYPE, content_type(asset_path));
+    response_headers.insert(header::CACHE_CONTROL, asset_cache_control(path));
 
-            return Some((StatusCode::OK, response_headers, file.contents()).into_response());
-        }
-    }
-
-    try_serve_embedded_asset(path)
+    Some(serve_negotiated(
+        Bundle::Client,
+        asset_path,
+        file,
+        Bundle::Client.etag(file),
+        response_headers,
+        headers,
+    ))
 }
 
-fn serve_asset_by_path(path: &str) -> Response {
-    if let Some(response) = try_serve_embedded_asset(path) {
+fn serve_asset_by_path(path: &str, request_headers: &HeaderMap) -> Response {
+    if let Some(response) = try_serve_embedded_asset_with_encoding(path, request_headers) {
         response
     } else {
         tracing::debug!(path, "Embedded asset not found");
@@ -148,19 +266,19 @@ pub fn get_env_js() -> &'static [u8] {
 /// Serve prerendered content by path, if it exists.
 ///
 /// Resolution: exact file → `{path}.html` → `{path}/index.html`
-pub fn try_serve_prerendered_page(path: &str) -> Option<Response> {
+pub fn try_serve_prerendered_page(path: &str, request_headers: &HeaderMap) -> Option<Response> {
     let path = path.strip_prefix('/').unwrap_or(path);
 
     // Try exact file match first (handles __data.json, etc.)
     if let Some(file) = PRERENDERED_PAGES.get_file(path) {
-        return Some(serve_prerendered_file(path, file.contents()));
+        return Some(serve_prerendered_file(path, file, request_headers));
     }
 
     let path = path.strip_suffix('/').unwrap_or(path);
 
     let html_path = format!("{path}.html");
     if let Some(file) = PRERENDERED_PAGES.get_file(&html_path) {
-        return Some(serve_prerendered_file(&html_path, file.contents()));
+        return Some(serve_prerendered_file(&html_path, file, request_headers));
     }
 
     let index_path = if path.is_empty() {
@@ -169,29 +287,128 @@ pub fn try_serve_prerendered_page(path: &str) -> Option<Response> {
         format!("{path}/index.html")
     };
     if let Some(file) = PRERENDERED_PAGES.get_file(&index_path) {
-        return Some(serve_prerendered_file(&index_path, file.contents()));
+        return Some(serve_prerendered_file(&index_path, file, request_headers));
     }
 
     None
 }
 
-fn serve_prerendered_file(path: &str, content: &'static [u8]) -> Response {
-    let mime_type = mime_guess::from_path(path)
-        .first_or_octet_stream()
-        .as_ref()
-        .to_string();
-
-    let mut headers = axum::http::HeaderMap::new();
-    headers.insert(
-        header::CONTENT_TYPE,
-        mime_type
-            .parse()
-            .unwrap_or_else(|_| header::HeaderValue::from_static("application/octet-stream")),
-    );
+/// Prerendered pages carry strong `ETag`s, which the compression layer leaves
+/// alone, so they're compressed here the same way as client assets.
+fn serve_prerendered_file(
+    path: &str,
+    file: &'static File<'static>,
+    request_headers: &HeaderMap,
+) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, content_type(path));
     headers.insert(
         header::CACHE_CONTROL,
-        header::HeaderValue::from_static("public, max-age=3600"),
+        HeaderValue::from_static("public, max-age=3600"),
     );
 
-    (StatusCode::OK, headers, content).into_response()
+    serve_negotiated(
+        Bundle::Prerendered,
+        path,
+        file,
+        Bundle::Prerendered.etag(file),
+        headers,
+        request_headers,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Repetitive enough that every encoding shrinks it
+    static SCRIPT: [u8; 4096] = [b'x'; 4096];
+    static SMALL_SCRIPT: [u8; 64] = [b'x'; 64];
+
+    fn accepting(encodings: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_ENCODING, encodings.parse().unwrap());
+        headers
+    }
+
+    fn serve(file: &'static File<'static>, request_headers: &HeaderMap) -> Response {
+        let path = file.path().to_str().unwrap();
+        let etag = conditional::etag(file.contents());
+        serve_negotiated(
+            Bundle::Client,
+            path,
+            file,
+            Some(&etag),
+            HeaderMap::new(),
+            request_headers,
+        )
+    }
+
+    #[test]
+    fn compresses_only_large_text_assets() {
+        assert!(is_compressible(
+            "app.js",
+            &File::new("gate/app.js", &SCRIPT)
+        ));
+        assert!(!is_compressible(
+            "small.js",
+            &File::new("gate/small.js", &SMALL_SCRIPT)
+        ));
+        assert!(!is_compressible(
+            "logo.png",
+            &File::new("gate/logo.png", &SCRIPT)
+        ));
+    }
+
+    #[test]
+    fn negotiates_each_encoding() {
+        static FILE: File<'static> = File::new("negotiate/app.js", &SCRIPT);
+        for encoding in [
+            ContentEncoding::Zstd,
+            ContentEncoding::Brotli,
+            ContentEncoding::Gzip,
+        ] {
+            let response = serve(&FILE, &accepting(encoding.name()));
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response.headers()[header::CONTENT_ENCODING],
+                encoding.name()
+            );
+            assert_eq!(response.headers()[header::VARY], "Accept-Encoding");
+        }
+
+        let response = serve(&FILE, &accepting("identity"));
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+
+    #[test]
+    fn compression_is_memoized() {
+        static FILE: File<'static> = File::new("memo/app.js", &SCRIPT);
+        let first =
+            compressed_file(Bundle::Client, "memo/app.js", &FILE, ContentEncoding::Gzip).unwrap();
+        assert!(first.len() < SCRIPT.len());
+        assert!(COMPRESSED_FILES.contains_key(&(
+            Bundle::Client,
+            FILE.path(),
+            ContentEncoding::Gzip
+        )));
+
+        // The second request gets the same bytes back rather than recompressing
+        let second =
+            compressed_file(Bundle::Client, "memo/app.js", &FILE, ContentEncoding::Gzip).unwrap();
+        assert_eq!(first.as_ptr(), second.as_ptr());
+    }
+
+    #[test]
+    fn encoded_variants_get_their_own_etag() {
+        static FILE: File<'static> = File::new("etag/app.js", &SCRIPT);
+        let identity = serve(&FILE, &accepting("identity"));
+        let gzip = serve(&FILE, &accepting("gzip"));
+        let brotli = serve(&FILE, &accepting("br"));
+
+        let identity_etag = &identity.headers()[header::ETAG];
+        assert_eq!(identity_etag, &conditional::etag(&SCRIPT));
+        assert_ne!(&gzip.headers()[header::ETAG], identity_etag);
+        assert_ne!(gzip.headers()[header::ETAG], brotli.headers()[header::ETAG]);
+    }
 }
@@ -6,55 +6,27 @@ use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use sqlx::PgPool;
-use std::fmt;
+use std::net::IpAddr;
 use std::sync::Arc;
 use time::{Duration, OffsetDateTime};
-use ts_rs::TS;
 use ulid::Ulid;
-
-/// Browser sessions are short-lived; the cookie is refreshed on each login.
-const SESSION_DURATION_DAYS: i64 = 7;
-/// CLI tokens are long-lived and slide forward on use (see `validate_bearer`).
-const CLI_SESSION_DURATION_DAYS: i64 = 90;
-/// Don't persist a sliding-expiry bump more often than this, to keep authn cheap.
-const CLI_SLIDE_THROTTLE: Duration = Duration::hours(6);
-/// Browser sessions don't slide their expiry, but we still record activity so
-/// the admin UI shows a real "last active". Throttled to avoid a write per page.
-const BROWSER_TOUCH_THROTTLE: Duration = Duration::minutes(5);
-
-/// How a session authenticates: an interactive browser cookie, or a long-lived
-/// CLI bearer token.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
-#[serde(rename_all = "lowercase")]
-#[ts(export)]
-pub enum SessionType {
-    Browser,
-    Cli,
-}
-
-impl SessionType {
-    /// Canonical wire/storage string. Single source of truth for the column
-    /// values and the `Display` impl.
-    pub const fn as_str(self) -> &'static str {
-        match self {
-            Self::Browser => "browser",
-            Self::Cli => "cli",
-        }
-    }
-
-    fn from_db(s: &str) -> Self {
-        match s {
-            "cli" => Self::Cli,
-            _ => Self::Browser,
-        }
-    }
-}
-
-impl fmt::Display for SessionType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.as_str())
-    }
-}
+use uuid::Uuid;
+
+use crate::api_tokens::ApiScope;
+use crate::handlers::AdminRole;
+pub use crate::handlers::SessionType;
+use crate::middleware::SessionRenewal;
+use crate::session_policy::SessionPolicies;
+
+/// Activity is recorded so the admin UI shows a real "last active" and idle
+/// sessions can expire. Throttled to avoid a write per request, unless the
+/// client's address changed or the session is due for renewal.
+const TOUCH_THROTTLE: Duration = Duration::minutes(5);
+/// How new a session can be and still survive [`SessionManager::forget_revoked`]
+/// without its row.
+const REVOCATION_GRACE: Duration = Duration::minutes(1);
+/// Longer user agents are cut off before they're stored.
+const MAX_USER_AGENT_LEN: usize = 512;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(clippy::struct_field_names)]
@@ -68,6 +40,101 @@ pub struct Session {
     pub session_type: SessionType,
     /// Human-readable label for CLI tokens (e.g. device hostname). `None` for browsers.
     pub label: Option<String>,
+    /// The user's role, kept in sync when an owner changes it.
+    pub role: AdminRole,
+    /// What an API token may do. `None` for browser and CLI sessions, which
+    /// have the role's full access.
+    pub grant: Option<TokenGrant>,
+    /// Address the session was created from.
+    pub created_ip: Option<IpAddr>,
+    /// Address of the latest recorded request.
+    pub last_ip: Option<IpAddr>,
+    /// User agent of the client that created the session.
+    pub user_agent: Option<String>,
+}
+
+/// Where a request came from, recorded on the sessions it creates.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn from_headers(headers: &axum::http::HeaderMap, ip: Option<IpAddr>) -> Self {
+        let user_agent = headers
+            .get(axum::http::header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|ua| {
+                ua.trim()
+                    .chars()
+                    .take(MAX_USER_AGENT_LEN)
+                    .collect::<String>()
+            })
+            .filter(|ua| !ua.is_empty());
+        Self { ip, user_agent }
+    }
+}
+
+/// Describe a user agent as "browser on OS" (e.g. "Firefox on Linux"): enough
+/// to tell your own devices apart, with no lookup beyond the string itself.
+pub fn describe_user_agent(user_agent: &str) -> Option<String> {
+    if let Some(version) = user_agent.strip_prefix("xevion/") {
+        return Some(format!("xevion CLI {version}"));
+    }
+    if let Some(version) = user_agent.strip_prefix("curl/") {
+        return Some(format!("curl {version}"));
+    }
+
+    // Order matters: Edge and Opera also claim Chrome, Chrome claims Safari,
+    // Android claims Linux, and iOS claims macOS
+    const BROWSERS: [(&str, &str); 5] = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+    ];
+    const SYSTEMS: [(&str, &str); 7] = [
+        ("Windows", "Windows"),
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Android", "Android"),
+        ("CrOS", "ChromeOS"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ];
+    let find = |table: &[(&str, &'static str)]| {
+        table
+            .iter()
+            .find(|(marker, _)| user_agent.contains(marker))
+            .map(|&(_, name)| name)
+    };
+    match (find(&BROWSERS), find(&SYSTEMS)) {
+        (Some(browser), Some(os)) => Some(format!("{browser} on {os}")),
+        (Some(name), None) | (None, Some(name)) => Some(name.to_string()),
+        (None, None) => None,
+    }
+}
+
+/// Scopes and project allowlist of an API token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenGrant {
+    pub scopes: Vec<ApiScope>,
+    /// `None` allows every project.
+    pub project_ids: Option<Vec<Uuid>>,
+}
+
+impl TokenGrant {
+    pub fn allows(&self, scope: ApiScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    pub fn allows_project(&self, project_id: Uuid) -> bool {
+        self.project_ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&project_id))
+    }
 }
 
 /// Row shape for loading sessions from the database.
@@ -82,6 +149,12 @@ struct SessionRow {
     session_type: String,
     label: Option<String>,
     token_hash: Option<String>,
+    role: String,
+    scopes: Option<Vec<String>>,
+    project_ids: Option<Vec<Uuid>>,
+    created_ip: Option<String>,
+    last_ip: Option<String>,
+    user_agent: Option<String>,
 }
 
 /// SHA-256 hex digest of a raw CLI token. Only the hash is ever persisted.
@@ -95,11 +168,25 @@ fn generate_cli_token() -> String {
     format!("xev_{}", nanoid::nanoid!(40))
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, sqlx::FromRow)]
 pub struct AdminUser {
     pub id: i32,
     pub username: String,
     pub password_hash: String,
+    #[sqlx(try_from = "String")]
+    pub role: AdminRole,
+    /// Base32 TOTP secret. Set at enrollment; only enforced once `totp_enabled`.
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+    /// Last accepted TOTP step, to reject replays.
+    pub totp_last_step: Option<i64>,
+}
+
+/// Which second factor a code matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecondFactor {
+    Totp,
+    RecoveryCode,
 }
 
 #[derive(Clone)]
@@ -107,14 +194,16 @@ pub struct SessionManager {
     sessions: Arc<DashMap<Ulid, Session>>,
     /// Maps a CLI token's SHA-256 hash to its session id for bearer lookup.
     token_index: Arc<DashMap<String, Ulid>>,
+    policies: SessionPolicies,
     pool: PgPool,
 }
 
 impl SessionManager {
-    pub async fn new(pool: PgPool) -> Result<Self, sqlx::Error> {
+    pub async fn new(pool: PgPool, policies: SessionPolicies) -> Result<Self, sqlx::Error> {
         let manager = Self {
             sessions: Arc::new(DashMap::new()),
             token_index: Arc::new(DashMap::new()),
+            policies,
             pool,
         };
 
@@ -129,7 +218,8 @@ impl SessionManager {
         let sessions: Vec<SessionRow> = sqlx::query_as(
             r"
             SELECT s.id, s.user_id, u.username, s.created_at, s.last_active_at,
-                   s.expires_at, s.session_type, s.label, s.token_hash
+                   s.expires_at, s.session_type, s.label, s.token_hash, u.role,
+                   s.scopes, s.project_ids, s.created_ip, s.last_ip, s.user_agent
             FROM sessions s
             JOIN admin_users u ON s.user_id = u.id
             WHERE s.expires_at > $1
@@ -149,6 +239,12 @@ impl SessionManager {
             session_type: ty,
             label,
             token_hash,
+            role,
+            scopes,
+            project_ids,
+            created_ip,
+            last_ip,
+            user_agent,
         } in sessions
         {
             if let Ok(id) = Ulid::from_string(&id_str) {
@@ -161,6 +257,15 @@ impl SessionManager {
                     expires_at,
                     session_type: SessionType::from_db(&ty),
                     label,
+                    // Unknown roles and scopes get the least access
+                    role: role.parse().unwrap_or(AdminRole::Viewer),
+                    grant: scopes.map(|scopes| TokenGrant {
+                        scopes: scopes.iter().filter_map(|s| s.parse().ok()).collect(),
+                        project_ids,
+                    }),
+                    created_ip: created_ip.and_then(|ip| ip.parse().ok()),
+                    last_ip: last_ip.and_then(|ip| ip.parse().ok()),
+                    user_agent,
                 };
                 if let Some(hash) = token_hash {
                     self.token_index.insert(hash, id);
@@ -181,15 +286,20 @@ impl SessionManager {
         &self,
         user_id: i32,
         username: String,
+        role: AdminRole,
+        client: ClientInfo,
     ) -> Result<Session, sqlx::Error> {
         let id = Ulid::new();
         let created_at = OffsetDateTime::now_utc();
-        let expires_at = created_at + Duration::days(SESSION_DURATION_DAYS);
+        let expires_at = self.policies.browser.initial_expiry(created_at);
+        let ip = client.ip.map(|ip| ip.to_string());
 
         sqlx::query(
             r"
-            INSERT INTO sessions (id, user_id, created_at, expires_at, last_active_at, session_type)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO sessions
+                (id, user_id, created_at, expires_at, last_active_at, session_type,
+                 created_ip, last_ip, user_agent)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $7, $8)
             ",
         )
         .bind(id.to_string())
@@ -198,6 +308,8 @@ impl SessionManager {
         .bind(expires_at)
         .bind(created_at)
         .bind(SessionType::Browser.as_str())
+        .bind(&ip)
+        .bind(&client.user_agent)
         .execute(&self.pool)
         .await?;
 
@@ -210,6 +322,11 @@ impl SessionManager {
             expires_at,
             session_type: SessionType::Browser,
             label: None,
+            role,
+            grant: None,
+            created_ip: client.ip,
+            last_ip: client.ip,
+            user_agent: client.user_agent,
         };
 
         self.sessions.insert(id, session.clone());
@@ -219,25 +336,29 @@ impl SessionManager {
         Ok(session)
     }
 
-    /// Create a long-lived CLI session, returning the session and the raw token.
+    /// Create a CLI session, returning the session and the raw token.
     /// The raw token is shown to the client exactly once; only its hash persists.
     pub async fn create_cli_session(
         &self,
         user_id: i32,
         username: String,
+        role: AdminRole,
         label: Option<String>,
+        client: ClientInfo,
     ) -> Result<(Session, String), sqlx::Error> {
         let id = Ulid::new();
         let token = generate_cli_token();
         let token_hash = hash_token(&token);
         let created_at = OffsetDateTime::now_utc();
-        let expires_at = created_at + Duration::days(CLI_SESSION_DURATION_DAYS);
+        let expires_at = self.policies.cli.initial_expiry(created_at);
+        let ip = client.ip.map(|ip| ip.to_string());
 
         sqlx::query(
             r"
             INSERT INTO sessions
-                (id, user_id, created_at, expires_at, last_active_at, session_type, token_hash, label)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                (id, user_id, created_at, expires_at, last_active_at, session_type, token_hash,
+                 label, created_ip, last_ip, user_agent)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $9, $10)
             ",
         )
         .bind(id.to_string())
@@ -248,6 +369,8 @@ impl SessionManager {
         .bind(SessionType::Cli.as_str())
         .bind(&token_hash)
         .bind(&label)
+        .bind(&ip)
+        .bind(&client.user_agent)
         .execute(&self.pool)
         .await?;
 
@@ -260,6 +383,11 @@ impl SessionManager {
             expires_at,
             session_type: SessionType::Cli,
             label,
+            role,
+            grant: None,
+            created_ip: client.ip,
+            last_ip: client.ip,
+            user_agent: client.user_agent,
         };
 
         self.token_index.insert(token_hash, id);
@@ -270,6 +398,73 @@ impl SessionManager {
         Ok((session, token))
     }
 
+    /// Mint a scoped API token for the user of `owner` that expires at a fixed
+    /// time, returning the session and the raw token (shown once, like a CLI
+    /// token).
+    pub async fn create_api_token(
+        &self,
+        owner: &Session,
+        name: String,
+        grant: TokenGrant,
+        lifetime: Duration,
+        client: ClientInfo,
+    ) -> Result<(Session, String), sqlx::Error> {
+        let id = Ulid::new();
+        let token = generate_cli_token();
+        let token_hash = hash_token(&token);
+        let created_at = OffsetDateTime::now_utc();
+        let expires_at = created_at + lifetime;
+        let scopes: Vec<&str> = grant.scopes.iter().map(|s| s.as_str()).collect();
+        let ip = client.ip.map(|ip| ip.to_string());
+
+        sqlx::query(
+            r"
+            INSERT INTO sessions
+                (id, user_id, created_at, expires_at, last_active_at, session_type, token_hash,
+                 label, scopes, project_ids, created_ip, user_agent)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ",
+        )
+        .bind(id.to_string())
+        .bind(owner.user_id)
+        .bind(created_at)
+        .bind(expires_at)
+        .bind(created_at)
+        .bind(SessionType::Token.as_str())
+        .bind(&token_hash)
+        .bind(&name)
+        .bind(&scopes)
+        .bind(&grant.project_ids)
+        .bind(&ip)
+        .bind(&client.user_agent)
+        .execute(&self.pool)
+        .await?;
+
+        let session = Session {
+            id,
+            user_id: owner.user_id,
+            username: owner.username.clone(),
+            created_at,
+            last_active_at: created_at,
+            expires_at,
+            session_type: SessionType::Token,
+            label: Some(name),
+            role: owner.role,
+            grant: Some(grant),
+            created_ip: client.ip,
+            // Set by the token's first use, not by where it was created
+            last_ip: None,
+            user_agent: client.user_agent,
+        };
+
+        self.token_index.insert(token_hash, id);
+        self.sessions.insert(id, session.clone());
+
+        tracing::info!(session_id = %id, user_id = owner.user_id, "Created API token");
+
+        Ok((session, token))
+    }
+
     pub fn get_session(&self, session_id: Ulid) -> Option<Session> {
         self.sessions.get(&session_id).map(|s| s.clone())
     }
@@ -277,7 +472,7 @@ impl SessionManager {
     pub fn validate_session(&self, session_id: Ulid) -> Option<Session> {
         let session = self.get_session(session_id)?;
 
-        if session.expires_at < OffsetDateTime::now_utc() {
+        if self.has_expired(&session, OffsetDateTime::now_utc()) {
             self.sessions.remove(&session_id);
             return None;
         }
@@ -285,57 +480,77 @@ impl SessionManager {
         Some(session)
     }
 
-    /// Validate a CLI bearer token. On success, slides the expiry forward (at
-    /// most once per `CLI_SLIDE_THROTTLE` to avoid a write per request).
-    pub async fn validate_bearer(&self, token: &str) -> Option<Session> {
+    /// Past its expiry, or lapsed under its type's policy (idle too long, or
+    /// older than the cap).
+    fn has_expired(&self, session: &Session, now: OffsetDateTime) -> bool {
+        session.expires_at < now
+            || self
+                .policies
+                .get(session.session_type)
+                .is_some_and(|policy| policy.has_lapsed(session, now))
+    }
+
+    /// Validate a CLI or API bearer token. Use is recorded separately, with
+    /// [`Self::touch_session`].
+    pub fn validate_bearer(&self, token: &str) -> Option<Session> {
         let token_hash = hash_token(token);
         let session_id = *self.token_index.get(&token_hash)?;
-        let session = self.validate_session(session_id)?;
-
-        let now = OffsetDateTime::now_utc();
-        if now - session.last_active_at >= CLI_SLIDE_THROTTLE {
-            let expires_at = now + Duration::days(CLI_SESSION_DURATION_DAYS);
-            if let Err(e) = sqlx::query(
-                "UPDATE sessions SET last_active_at = $1, expires_at = $2 WHERE id = $3",
-            )
-            .bind(now)
-            .bind(expires_at)
-            .bind(session_id.to_string())
-            .execute(&self.pool)
-            .await
-            {
-                tracing::warn!(error = %e, session_id = %session_id, "Failed to slide CLI session expiry");
-            } else if let Some(mut entry) = self.sessions.get_mut(&session_id) {
-                entry.last_active_at = now;
-                entry.expires_at = expires_at;
-            }
-        }
-
-        Some(session)
+        self.validate_session(session_id)
     }
 
-    /// Record activity on a browser session. Unlike CLI tokens this does not
-    /// slide the expiry (the 7-day window is fixed at login); it only refreshes
-    /// `last_active_at`, throttled so navigation doesn't write on every request.
-    pub async fn touch_session(&self, session_id: Ulid) {
+    /// Record a request from `client_ip` on a session, refreshing
+    /// `last_active_at` and `last_ip`. Throttled so navigation doesn't write on
+    /// every request; a new address is recorded at once. A browser or CLI
+    /// session close to expiring is renewed under its policy, and this returns
+    /// `true` so the client can be told.
+    pub async fn touch_session(&self, session_id: Ulid, client_ip: Option<IpAddr>) -> bool {
         let now = OffsetDateTime::now_utc();
 
-        match self.sessions.get(&session_id) {
-            Some(session) if now - session.last_active_at < BROWSER_TOUCH_THROTTLE => return,
-            Some(_) => {}
-            None => return,
-        }
+        let renewal = match self.sessions.get(&session_id) {
+            Some(session) => {
+                let renewal = self
+                    .policies
+                    .get(session.session_type)
+                    .and_then(|policy| policy.renewal(&session, now));
+                if renewal.is_none()
+                    && now - session.last_active_at < TOUCH_THROTTLE
+                    && client_ip.is_none_or(|ip| session.last_ip == Some(ip))
+                {
+                    return false;
+                }
+                renewal
+            }
+            None => return false,
+        };
 
-        if let Err(e) = sqlx::query("UPDATE sessions SET last_active_at = $1 WHERE id = $2")
-            .bind(now)
-            .bind(session_id.to_string())
-            .execute(&self.pool)
-            .await
+        if let Err(e) = sqlx::query(
+            r"
+            UPDATE sessions
+            SET last_active_at = $1, last_ip = COALESCE($2, last_ip),
+                expires_at = COALESCE($3, expires_at)
+            WHERE id = $4
+            ",
+        )
+        .bind(now)
+        .bind(client_ip.map(|ip| ip.to_string()))
+        .bind(renewal)
+        .bind(session_id.to_string())
+        .execute(&self.pool)
+        .await
         {
             tracing::warn!(error = %e, session_id = %session_id, "Failed to record session activity");
-        } else if let Some(mut entry) = self.sessions.get_mut(&session_id) {
+            return false;
+        }
+
+        if let Some(mut entry) = self.sessions.get_mut(&session_id) {
             entry.last_active_at = now;
+            entry.last_ip = client_ip.or(entry.last_ip);
+            if let Some(expires_at) = renewal {
+                entry.expires_at = expires_at;
+                tracing::debug!(session_id = %session_id, %expires_at, "Renewed session");
+            }
         }
+        renewal.is_some()
     }
 
     /// All active sessions for a user, newest first. Used by the admin UI.
@@ -350,9 +565,68 @@ impl SessionManager {
         sessions
     }
 
+    /// Apply a role change to the user's live sessions, so it takes effect on
+    /// their next request.
+    pub fn set_user_role(&self, user_id: i32, role: AdminRole) {
+        for mut session in self.sessions.iter_mut() {
+            if session.user_id == user_id {
+                session.role = role;
+            }
+        }
+    }
+
+    /// Drop a deleted user's sessions from memory. The rows themselves go with
+    /// the user (`ON DELETE CASCADE`).
+    pub fn forget_user_sessions(&self, user_id: i32) {
+        self.forget_where(|s| s.user_id == user_id);
+    }
+
+    /// Sign out every session and API token of a user except `keep`, e.g. after
+    /// a password change. Returns how many were revoked.
+    pub async fn revoke_other_sessions(
+        &self,
+        user_id: i32,
+        keep: Ulid,
+    ) -> Result<usize, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM sessions WHERE user_id = $1 AND id <> $2")
+            .bind(user_id)
+            .bind(keep.to_string())
+            .execute(&self.pool)
+            .await?;
+        self.forget_where(|s| s.user_id == user_id && s.id != keep);
+        Ok(result.rows_affected() as usize)
+    }
+
+    /// Drop sessions whose rows were deleted outside this server, such as by
+    /// `xevion-server reset-password`, so they stop working without a restart.
+    /// Sessions from the last minute are kept: their rows may have been
+    /// written after the query read the table. Returns how many were dropped.
+    pub async fn forget_revoked(&self) -> Result<usize, sqlx::Error> {
+        let cutoff = OffsetDateTime::now_utc() - REVOCATION_GRACE;
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM sessions")
+            .fetch_all(&self.pool)
+            .await?;
+        let live: std::collections::HashSet<String> = ids.into_iter().collect();
+        Ok(self.forget_where(|s| s.created_at < cutoff && !live.contains(&s.id.to_string())))
+    }
+
+    fn forget_where(&self, predicate: impl Fn(&Session) -> bool) -> usize {
+        let removed: Vec<Ulid> = self
+            .sessions
+            .iter()
+            .filter(|s| predicate(s))
+            .map(|s| s.id)
+            .collect();
+        for id in &removed {
+            self.sessions.remove(id);
+        }
+        self.token_index.retain(|_, id| !removed.contains(id));
+        removed.len()
+    }
+
     pub async fn delete_session(&self, session_id: Ulid) -> Result<(), sqlx::Error> {
         if let Some((_, session)) = self.sessions.remove(&session_id)
-            && session.session_type == SessionType::Cli
+            && session.session_type.is_bearer()
         {
             self.token_index.retain(|_, id| *id != session_id);
         }
@@ -367,17 +641,28 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Delete expired sessions, including ones that lapsed under their
+    /// policy (idle, or past the lifetime cap) before reaching `expires_at`.
     pub async fn cleanup_expired(&self) -> Result<usize, sqlx::Error> {
         let now = OffsetDateTime::now_utc();
 
-        let result = sqlx::query("DELETE FROM sessions WHERE expires_at < $1")
+        let lapsed: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|s| self.has_expired(s, now))
+            .map(|s| s.id.to_string())
+            .collect();
+
+        let result = sqlx::query("DELETE FROM sessions WHERE expires_at < $1 OR id = ANY($2)")
             .bind(now)
+            .bind(&lapsed)
             .execute(&self.pool)
             .await?;
 
         let expired_count = result.rows_affected() as usize;
 
-        self.sessions.retain(|_, session| session.expires_at >= now);
+        self.sessions
+            .retain(|_, session| !self.has_expired(session, now));
         self.token_index
             .retain(|_, id| self.sessions.contains_key(id));
 
@@ -411,62 +696,519 @@ pub async fn get_admin_user(
     pool: &PgPool,
     username: &str,
 ) -> Result<Option<AdminUser>, sqlx::Error> {
-    let user: Option<(i32, String, String)> = sqlx::query_as(
+    sqlx::query_as(
         r"
-        SELECT id, username, password_hash
+        SELECT id, username, password_hash, role, totp_secret,
+               totp_enabled_at IS NOT NULL AS totp_enabled, totp_last_step
         FROM admin_users
         WHERE username = $1
         ",
     )
     .bind(username)
     .fetch_optional(pool)
-    .await?;
+    .await
+}
 
-    Ok(user.map(|(id, username, password_hash)| AdminUser {
-        id,
-        username,
-        password_hash,
-    }))
+/// Shortest password accepted for admin accounts
+pub const MIN_PASSWORD_LEN: usize = 12;
+const MAX_USERNAME_LEN: usize = 64;
+
+/// Check a new username: letters, digits, `.`, `_`, and `-`.
+pub fn validate_username(username: &str) -> Result<(), &'static str> {
+    if username.is_empty() || username.len() > MAX_USERNAME_LEN {
+        return Err("Must be 1 to 64 characters");
+    }
+    if !username
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+    {
+        return Err("Only letters, digits, '.', '_', and '-' are allowed");
+    }
+    Ok(())
 }
 
-pub async fn create_admin_user(
+pub fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!("Must be at least {MIN_PASSWORD_LEN} characters"));
+    }
+    Ok(())
+}
+
+/// Insert a user with an already hashed password. Fails with a unique
+/// violation if the username is taken.
+pub async fn insert_admin_user(
     pool: &PgPool,
     username: &str,
-    password: &str,
-) -> Result<i32, Box<dyn std::error::Error>> {
-    let password_hash =
-        hash_password(password).map_err(|e| format!("Failed to hash password: {e}"))?;
-
-    let (id,): (i32,) = sqlx::query_as(
+    password_hash: &str,
+    role: AdminRole,
+) -> Result<i32, sqlx::Error> {
+    sqlx::query_scalar(
         r"
-        INSERT INTO admin_users (username, password_hash)
-        VALUES ($1, $2)
+        INSERT INTO admin_users (username, password_hash, role)
+        VALUES ($1, $2, $3)
         RETURNING id
         ",
     )
     .bind(username)
     .bind(password_hash)
+    .bind(role.as_str())
     .fetch_one(pool)
-    .await?;
+    .await
+}
+
+pub async fn create_admin_user(
+    pool: &PgPool,
+    username: &str,
+    password: &str,
+    role: AdminRole,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let password_hash =
+        hash_password(password).map_err(|e| format!("Failed to hash password: {e}"))?;
+    Ok(insert_admin_user(pool, username, &password_hash, role).await?)
+}
+
+/// Replace a user's password hash. Returns `false` if there is no such user.
+pub async fn set_admin_password(
+    pool: &PgPool,
+    user_id: i32,
+    password_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE admin_users SET password_hash = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(password_hash)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
 
-    Ok(id)
+/// Delete every session and API token of `user_id` straight from the database,
+/// for tools that run without a [`SessionManager`]. A running server notices
+/// through [`SessionManager::forget_revoked`]. Returns how many were deleted.
+pub async fn revoke_user_sessions(pool: &PgPool, user_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
 }
 
+/// Bootstrap the first owner from `ADMIN_USERNAME`/`ADMIN_PASSWORD` when there
+/// are no users yet. Once any user exists, accounts are managed through the
+/// users API, so a deleted bootstrap account stays deleted.
 pub async fn ensure_admin_user(pool: &PgPool) -> Result<(), Box<dyn std::error::Error>> {
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM admin_users")
+        .fetch_one(pool)
+        .await?;
+    if count > 0 {
+        tracing::debug!(users = count, "Admin users already exist");
+        return Ok(());
+    }
+
     let username = std::env::var("ADMIN_USERNAME").unwrap_or_else(|_| "admin".to_string());
     let password = std::env::var("ADMIN_PASSWORD")
         .map_err(|_| "ADMIN_PASSWORD environment variable must be set")?;
+    create_admin_user(pool, &username, &password, AdminRole::Owner).await?;
+    tracing::info!(username, "Created admin user");
 
-    if get_admin_user(pool, &username).await?.is_none() {
-        create_admin_user(pool, &username, &password).await?;
-        tracing::info!(username, "Created admin user");
-    } else {
-        tracing::debug!(username, "Admin user already exists");
+    Ok(())
+}
+
+/// An admin user as listed for user management.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AdminUserSummary {
+    pub id: i32,
+    pub username: String,
+    #[sqlx(try_from = "String")]
+    pub role: AdminRole,
+    pub totp_enabled: bool,
+    pub passkeys: i64,
+    pub created_at: OffsetDateTime,
+}
+
+pub async fn list_admin_users(pool: &PgPool) -> Result<Vec<AdminUserSummary>, sqlx::Error> {
+    sqlx::query_as(
+        r"
+        SELECT u.id, u.username, u.role, u.totp_enabled_at IS NOT NULL AS totp_enabled,
+               (SELECT COUNT(*) FROM admin_passkeys p WHERE p.user_id = u.id) AS passkeys,
+               u.created_at
+        FROM admin_users u
+        ORDER BY u.created_at, u.id
+        ",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Outcome of a role change or deletion, which must leave at least one owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserChange {
+    Done,
+    NotFound,
+    LastOwner,
+}
+
+/// Lock the owner rows and report whether `user_id` is the only owner. The
+/// lock serializes concurrent changes, so two owners can't demote each other
+/// at once and leave none.
+async fn is_last_owner(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i32,
+) -> Result<bool, sqlx::Error> {
+    let owners: Vec<i32> =
+        sqlx::query_scalar("SELECT id FROM admin_users WHERE role = 'owner' FOR UPDATE")
+            .fetch_all(&mut **tx)
+            .await?;
+    Ok(owners == [user_id])
+}
+
+pub async fn set_admin_role(
+    pool: &PgPool,
+    user_id: i32,
+    role: AdminRole,
+) -> Result<UserChange, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    if role != AdminRole::Owner && is_last_owner(&mut tx, user_id).await? {
+        return Ok(UserChange::LastOwner);
+    }
+    let result = sqlx::query("UPDATE admin_users SET role = $2, updated_at = NOW() WHERE id = $1")
+        .bind(user_id)
+        .bind(role.as_str())
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Ok(UserChange::NotFound);
+    }
+    tx.commit().await?;
+    Ok(UserChange::Done)
+}
+
+/// Delete a user along with its sessions, passkeys, and recovery codes.
+pub async fn delete_admin_user(pool: &PgPool, user_id: i32) -> Result<UserChange, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    if is_last_owner(&mut tx, user_id).await? {
+        return Ok(UserChange::LastOwner);
+    }
+    let result = sqlx::query("DELETE FROM admin_users WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Ok(UserChange::NotFound);
+    }
+    tx.commit().await?;
+    Ok(UserChange::Done)
+}
+
+/// Store a new, unconfirmed TOTP secret for `user_id`, replacing any earlier
+/// pending one. Returns `false` if 2FA is already enabled.
+pub async fn begin_totp_enrollment(
+    pool: &PgPool,
+    user_id: i32,
+    secret: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r"
+        UPDATE admin_users
+        SET totp_secret = $1, totp_last_step = NULL, updated_at = NOW()
+        WHERE id = $2 AND totp_enabled_at IS NULL
+        ",
+    )
+    .bind(secret)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Enable the pending TOTP secret of `user_id` (after a code from it passed
+/// [`verify_second_factor`]) and replace its recovery codes with
+/// `recovery_codes`. Returns `false` if there was no pending enrollment.
+pub async fn confirm_totp_enrollment(
+    pool: &PgPool,
+    user_id: i32,
+    recovery_codes: &[String],
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query(
+        r"
+        UPDATE admin_users
+        SET totp_enabled_at = NOW(), updated_at = NOW()
+        WHERE id = $1 AND totp_secret IS NOT NULL AND totp_enabled_at IS NULL
+        ",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() != 1 {
+        return Ok(false);
     }
+    replace_recovery_codes(&mut tx, user_id, recovery_codes).await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Replace the recovery codes of `user_id`, e.g. after they've been used up.
+pub async fn regenerate_recovery_codes(
+    pool: &PgPool,
+    user_id: i32,
+    recovery_codes: &[String],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    replace_recovery_codes(&mut tx, user_id, recovery_codes).await?;
+    tx.commit().await
+}
 
+async fn replace_recovery_codes(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i32,
+    recovery_codes: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM admin_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+    let hashes: Vec<String> = recovery_codes
+        .iter()
+        .map(|code| hash_token(&crate::totp::normalize_recovery_code(code)))
+        .collect();
+    sqlx::query(
+        r"
+        INSERT INTO admin_recovery_codes (user_id, code_hash)
+        SELECT $1, code_hash FROM UNNEST($2::text[]) AS code_hash
+        ",
+    )
+    .bind(user_id)
+    .bind(&hashes)
+    .execute(&mut **tx)
+    .await?;
     Ok(())
 }
 
+/// Turn 2FA off for `user_id`, dropping its secret and recovery codes.
+pub async fn disable_totp(pool: &PgPool, user_id: i32) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r"
+        UPDATE admin_users
+        SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL,
+            updated_at = NOW()
+        WHERE id = $1
+        ",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM admin_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// Unused recovery codes left for `user_id`.
+pub async fn recovery_codes_remaining(pool: &PgPool, user_id: i32) -> Result<i64, sqlx::Error> {
+    let (count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM admin_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+/// Check `code` as a second factor for `user`: a TOTP code from its secret
+/// (pending or enabled), or one of its unused recovery codes. Accepted codes
+/// are consumed, so each works at most once.
+pub async fn verify_second_factor(
+    pool: &PgPool,
+    user: &AdminUser,
+    code: &str,
+) -> Result<Option<SecondFactor>, sqlx::Error> {
+    if crate::totp::is_totp_code(code) {
+        let Some(secret) = &user.totp_secret else {
+            return Ok(None);
+        };
+        let now = OffsetDateTime::now_utc().unix_timestamp() as u64;
+        let last_step = user.totp_last_step.map(|step| step as u64);
+        let Some(step) = crate::totp::verify(secret, code, now, last_step) else {
+            return Ok(None);
+        };
+        // Conditional on the stored step so concurrent requests can't both
+        // spend the same code
+        let result = sqlx::query(
+            r"
+            UPDATE admin_users SET totp_last_step = $1
+            WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1)
+            ",
+        )
+        .bind(step as i64)
+        .bind(user.id)
+        .execute(pool)
+        .await?;
+        return Ok((result.rows_affected() == 1).then_some(SecondFactor::Totp));
+    }
+
+    if !user.totp_enabled {
+        return Ok(None);
+    }
+    let hash = hash_token(&crate::totp::normalize_recovery_code(code));
+    let result = sqlx::query(
+        r"
+        UPDATE admin_recovery_codes SET used_at = NOW()
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+        ",
+    )
+    .bind(user.id)
+    .bind(hash)
+    .execute(pool)
+    .await?;
+    Ok((result.rows_affected() == 1).then_some(SecondFactor::RecoveryCode))
+}
+
+/// A registered passkey, as listed to its owner.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AdminPasskey {
+    pub id: i32,
+    pub name: String,
+    pub created_at: OffsetDateTime,
+    pub last_used_at: Option<OffsetDateTime>,
+}
+
+pub async fn get_admin_user_by_id(
+    pool: &PgPool,
+    user_id: i32,
+) -> Result<Option<AdminUser>, sqlx::Error> {
+    sqlx::query_as(
+        r"
+        SELECT id, username, password_hash, role, totp_secret,
+               totp_enabled_at IS NOT NULL AS totp_enabled, totp_last_step
+        FROM admin_users
+        WHERE id = $1
+        ",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn list_passkeys(pool: &PgPool, user_id: i32) -> Result<Vec<AdminPasskey>, sqlx::Error> {
+    sqlx::query_as(
+        r"
+        SELECT id, name, created_at, last_used_at
+        FROM admin_passkeys
+        WHERE user_id = $1
+        ORDER BY created_at
+        ",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Credential IDs registered to `user_id`, so authenticators don't register
+/// a second passkey for the same account.
+pub async fn passkey_credential_ids(
+    pool: &PgPool,
+    user_id: i32,
+) -> Result<Vec<Vec<u8>>, sqlx::Error> {
+    sqlx::query_scalar("SELECT credential_id FROM admin_passkeys WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// Store a verified passkey. Returns `None` if the credential ID is already
+/// registered.
+pub async fn add_passkey(
+    pool: &PgPool,
+    credential: &crate::webauthn::NewCredential,
+    name: &str,
+) -> Result<Option<i32>, sqlx::Error> {
+    sqlx::query_scalar(
+        r"
+        INSERT INTO admin_passkeys
+            (user_id, credential_id, public_key, algorithm, sign_count, name)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (credential_id) DO NOTHING
+        RETURNING id
+        ",
+    )
+    .bind(credential.user_id)
+    .bind(&credential.credential_id)
+    .bind(&credential.public_key)
+    .bind(credential.algorithm)
+    .bind(i64::from(credential.sign_count))
+    .bind(name)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_passkey_credential(
+    pool: &PgPool,
+    credential_id: &[u8],
+) -> Result<Option<crate::webauthn::StoredCredential>, sqlx::Error> {
+    let row: Option<(i32, Vec<u8>, i32, i64)> = sqlx::query_as(
+        r"
+        SELECT user_id, public_key, algorithm, sign_count
+        FROM admin_passkeys
+        WHERE credential_id = $1
+        ",
+    )
+    .bind(credential_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(
+        |(user_id, public_key, algorithm, sign_count)| crate::webauthn::StoredCredential {
+            user_id,
+            credential_id: credential_id.to_vec(),
+            public_key,
+            algorithm,
+            sign_count: u32::try_from(sign_count).unwrap_or(u32::MAX),
+        },
+    ))
+}
+
+/// Record a successful passkey login. Conditional on the counter still being
+/// below `sign_count`, so two concurrent logins with one assertion can't both
+/// succeed; returns whether it was recorded.
+pub async fn record_passkey_use(
+    pool: &PgPool,
+    credential_id: &[u8],
+    sign_count: u32,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r"
+        UPDATE admin_passkeys SET sign_count = $2, last_used_at = NOW()
+        WHERE credential_id = $1 AND (sign_count < $2 OR $2 = 0)
+        ",
+    )
+    .bind(credential_id)
+    .bind(i64::from(sign_count))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Delete one of `user_id`'s passkeys. Returns whether it existed.
+pub async fn delete_passkey(pool: &PgPool, user_id: i32, id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM admin_passkeys WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Delete all of `user_id`'s passkeys. Returns how many there were.
+pub async fn delete_passkeys(pool: &PgPool, user_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM admin_passkeys WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
 /// Check if the request has a valid admin session cookie (from `AppState`).
 pub fn check_session(
     state: &crate::state::AppState,
@@ -487,18 +1229,78 @@ pub fn bearer_token(headers: &axum::http::HeaderMap) -> Option<String> {
     (!token.is_empty()).then(|| token.to_string())
 }
 
-/// Authenticate a request via either the browser session cookie or a CLI
-/// bearer token. Cookie is checked first (cheaper, no sliding-expiry write).
+/// Authenticate a request via either the browser session cookie or a bearer
+/// token (cookie first, as it's cheaper), recording the request on the
+/// session. A renewal is left in the request's [`SessionRenewal`] for the
+/// client to be told about.
 pub async fn authenticate(
     state: &crate::state::AppState,
     headers: &axum::http::HeaderMap,
+    extensions: &axum::http::Extensions,
 ) -> Option<Session> {
     let jar = axum_extra::extract::CookieJar::from_headers(headers);
-    if let Some(session) = check_session(state, &jar) {
+    let session = match check_session(state, &jar) {
+        Some(session) => session,
+        None => state
+            .session_manager
+            .validate_bearer(&bearer_token(headers)?)?,
+    };
+    let client_ip = crate::tarpit::request_client_ip(headers, extensions);
+    if !state
+        .session_manager
+        .touch_session(session.id, Some(client_ip))
+        .await
+    {
         return Some(session);
     }
-    let token = bearer_token(headers)?;
-    state.session_manager.validate_bearer(&token).await
+    let session = state
+        .session_manager
+        .get_session(session.id)
+        .unwrap_or(session);
+    if let Some(renewal) = extensions.get::<SessionRenewal>() {
+        renewal.record(&session);
+    }
+    Some(session)
+}
+
+/// Session state for tests that never reaches the database.
+#[cfg(test)]
+impl SessionManager {
+    pub(crate) fn in_memory(pool: PgPool) -> Self {
+        Self {
+            sessions: Arc::new(DashMap::new()),
+            token_index: Arc::new(DashMap::new()),
+            policies: SessionPolicies::default(),
+            pool,
+        }
+    }
+
+    /// Register an owner's API token without persisting it, returning the raw
+    /// token. Its last use is now, from `ip`, so requests from there don't
+    /// record activity.
+    pub(crate) fn insert_api_token(&self, grant: TokenGrant, ip: IpAddr) -> String {
+        let id = Ulid::new();
+        let token = generate_cli_token();
+        let now = OffsetDateTime::now_utc();
+        let session = Session {
+            id,
+            user_id: 1,
+            username: "admin".to_owned(),
+            created_at: now,
+            last_active_at: now,
+            expires_at: now + Duration::days(1),
+            session_type: SessionType::Token,
+            label: Some("test".to_owned()),
+            role: AdminRole::Owner,
+            grant: Some(grant),
+            created_ip: Some(ip),
+            last_ip: Some(ip),
+            user_agent: None,
+        };
+        self.token_index.insert(hash_token(&token), id);
+        self.sessions.insert(id, session);
+        token
+    }
 }
 
 #[cfg(test)]
@@ -535,4 +1337,75 @@ mod tests {
         let empty = axum::http::HeaderMap::new();
         assert_eq!(bearer_token(&empty), None);
     }
+
+    #[test]
+    fn usernames_are_restricted_to_a_safe_charset() {
+        assert!(validate_username("ryan.walters_2-dev").is_ok());
+        assert!(validate_username("").is_err());
+        assert!(validate_username("has space").is_err());
+        assert!(validate_username("ünicode").is_err());
+        assert!(validate_username(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn roles_nest_permissions() {
+        assert!(AdminRole::Owner.can_manage_site() && AdminRole::Owner.can_edit_content());
+        assert!(!AdminRole::Editor.can_manage_site() && AdminRole::Editor.can_edit_content());
+        assert!(!AdminRole::Viewer.can_manage_site() && !AdminRole::Viewer.can_edit_content());
+        for role in [AdminRole::Owner, AdminRole::Editor, AdminRole::Viewer] {
+            assert_eq!(role.as_str().parse::<AdminRole>(), Ok(role));
+        }
+        assert!("admin".parse::<AdminRole>().is_err());
+    }
+
+    #[test]
+    fn user_agents_describe_browser_and_os() {
+        let cases = [
+            (
+                "Mozilla/5.0 (X11; Linux x86_64; rv:131.0) Gecko/20100101 Firefox/131.0",
+                Some("Firefox on Linux"),
+            ),
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) \
+                 Chrome/130.0.0.0 Safari/537.36 Edg/130.0.0.0",
+                Some("Edge on Windows"),
+            ),
+            (
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 18_0 like Mac OS X) AppleWebKit/605.1.15 \
+                 (KHTML, like Gecko) Version/18.0 Mobile/15E148 Safari/604.1",
+                Some("Safari on iOS"),
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) \
+                 Chrome/130.0.0.0 Mobile Safari/537.36",
+                Some("Chrome on Android"),
+            ),
+            ("xevion/0.1.0", Some("xevion CLI 0.1.0")),
+            ("curl/8.10.1", Some("curl 8.10.1")),
+            ("python-requests/2.32", None),
+        ];
+        for (user_agent, expected) in cases {
+            assert_eq!(
+                describe_user_agent(user_agent).as_deref(),
+                expected,
+                "{user_agent}"
+            );
+        }
+    }
+
+    #[test]
+    fn client_info_trims_and_caps_user_agent() {
+        let mut headers = axum::http::HeaderMap::new();
+        assert_eq!(ClientInfo::from_headers(&headers, None).user_agent, None);
+
+        headers.insert(axum::http::header::USER_AGENT, "  ".parse().unwrap());
+        assert_eq!(ClientInfo::from_headers(&headers, None).user_agent, None);
+
+        let long = "x".repeat(MAX_USER_AGENT_LEN + 10);
+        headers.insert(axum::http::header::USER_AGENT, long.parse().unwrap());
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let info = ClientInfo::from_headers(&headers, Some(ip));
+        assert_eq!(info.ip, Some(ip));
+        assert_eq!(info.user_agent.map(|ua| ua.len()), Some(MAX_USER_AGENT_LEN));
+    }
 }
@@ -1,6 +1,6 @@
 //! `xevion-server` — the web server (reverse proxy, API, asset serving) plus the
-//! local database `seed` command. The remote content-management CLI lives in the
-//! `xevion` binary.
+//! local database `seed` and `reset-password` commands. The remote
+//! content-management CLI lives in the `xevion` binary.
 
 use clap::{Parser, Subcommand};
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
@@ -35,6 +35,55 @@ struct ServerCli {
 enum ServerCommand {
     /// Seed the database with sample data
     Seed,
+
+    /// Set an admin user's password directly in the database (account recovery)
+    #[command(group(
+        clap::ArgGroup::new("secret")
+            .required(true)
+            .args(["password", "password_stdin"]),
+    ))]
+    ResetPassword {
+        /// Login name of the user
+        username: String,
+
+        /// New password (prefer --password-stdin; flags show up in `ps`)
+        #[arg(long, env = "XEVION_RESET_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+
+        /// Read the new password from stdin
+        #[arg(long)]
+        password_stdin: bool,
+
+        /// Don't sign out the user's sessions and API tokens
+        #[arg(long)]
+        keep_sessions: bool,
+
+        /// Also turn off two-factor authentication and drop the recovery codes
+        #[arg(long = "disable-2fa")]
+        disable_2fa: bool,
+
+        /// Also remove every passkey registered to the user
+        #[arg(long)]
+        clear_passkeys: bool,
+    },
+}
+
+/// Connect to `DATABASE_URL` and bring the schema up to date.
+async fn connect_db() -> sqlx::PgPool {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL must be set in environment");
+
+    let pool = db::create_pool(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    // Run migrations first
+    sqlx::migrate!()
+        .run(&pool)
+        .await
+        .expect("Failed to run migrations");
+
+    pool
 }
 
 fn init_tracing() {
@@ -87,25 +136,39 @@ async fn main() {
 
     match args.command {
         Some(ServerCommand::Seed) => {
-            // Seed command - connect to database and run seeding
-            let database_url =
-                std::env::var("DATABASE_URL").expect("DATABASE_URL must be set in environment");
-
-            let pool = db::create_pool(&database_url)
-                .await
-                .expect("Failed to connect to database");
-
-            // Run migrations first
-            sqlx::migrate!()
-                .run(&pool)
-                .await
-                .expect("Failed to run migrations");
-
+            let pool = connect_db().await;
             if let Err(e) = cli::seed::run(&pool).await {
                 eprintln!("Error: {e}");
                 std::process::exit(1);
             }
         }
+        Some(ServerCommand::ResetPassword {
+            username,
+            password,
+            password_stdin,
+            keep_sessions,
+            disable_2fa,
+            clear_passkeys,
+        }) => {
+            let password = if password_stdin {
+                cli::api::users::read_password().unwrap_or_else(|e| {
+                    eprintln!("Error: {e}");
+                    std::process::exit(1);
+                })
+            } else {
+                password.unwrap_or_default()
+            };
+            let pool = connect_db().await;
+            let options = cli::reset_password::ResetOptions {
+                keep_sessions,
+                disable_2fa,
+                clear_passkeys,
+            };
+            if let Err(e) = cli::reset_password::run(&pool, &username, &password, options).await {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        }
         None => {
             // No subcommand - run the server
             init_tracing();
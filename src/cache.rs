@@ -3,22 +3,39 @@
 //! Provides in-memory caching for SSR pages with:
 //! - TTL-based expiration
 //! - Stale-while-revalidate pattern
-//! - Singleflight (via moka's built-in coalescing)
+//! - Single-flight renders: concurrent cold misses wait on one downstream render
 //! - Multi-encoding compressed storage (lazy)
-//! - On-demand invalidation
+//! - On-demand invalidation, by request target or by surrogate key
+//! - Optional on-disk tier that survives restarts (see [`crate::cache_store`])
+//!
+//! How long each response stays fresh, and which request headers select its
+//! variant, comes from Bun's own headers (see [`crate::cache_policy`]).
+//!
+//! Surrogate keys ("cache tags") name the entities a page was rendered from.
+//! Bun lists them in an [`CACHE_TAGS_HEADER`] response header, and mutations
+//! purge every page showing an entity with [`IsrCache::invalidate_tag`] rather
+//! than guessing which paths it appears on. See [`tags`] for the vocabulary.
 
-use axum::http::{HeaderMap, StatusCode};
-use dashmap::DashSet;
-use moka::future::Cache;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use dashmap::{DashMap, DashSet};
+use moka::{Expiry, future::Cache};
 use std::{
     collections::HashMap,
-    sync::Arc,
-    time::{Duration, Instant},
+    path::PathBuf,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
-use crate::encoding::{
-    COMPRESSION_MIN_SIZE, ContentEncoding, compress_brotli, compress_gzip, compress_zstd,
-};
+use tokio::sync::{mpsc, watch};
+
+use crate::cache_policy::{self, CacheTtl};
+use crate::cache_store::{DiskStore, StoredEntry};
+use crate::conditional;
+use crate::encoding::{COMPRESSION_MIN_SIZE, ContentEncoding, compress};
+use crate::handlers::{CacheEntryInfo, CacheEntryState, CacheStats};
 
 /// Cached response data with lazy compressed variants
 #[derive(Clone)]
@@ -30,19 +47,58 @@ pub struct CachedResponse {
     /// Compressed variants (lazily populated on first request per encoding)
     compressed: Arc<parking_lot::RwLock<HashMap<ContentEncoding, axum::body::Bytes>>>,
     pub cached_at: Instant,
+    /// Wall-clock render time, sent as `Last-Modified`
+    pub rendered_at: SystemTime,
+    /// Strong validator of the identity body (see [`conditional::encoded_etag`])
+    pub etag: HeaderValue,
+    /// Freshness windows from the response's cache policy
+    pub ttl: CacheTtl,
+    /// Surrogate keys this response was rendered from
+    pub tags: Vec<String>,
+    /// Distinguishes this insert from earlier entries under the same key, so
+    /// evicting a replaced entry never unindexes its successor
+    generation: u64,
 }
 
 impl CachedResponse {
+    /// A response with the default freshness windows; see [`Self::with_ttl`]
     pub fn new(status: StatusCode, headers: HeaderMap, body: axum::body::Bytes) -> Self {
         Self {
             status,
             headers,
+            etag: conditional::etag(&body),
             body,
             compressed: Arc::new(parking_lot::RwLock::new(HashMap::new())),
             cached_at: Instant::now(),
+            rendered_at: SystemTime::now(),
+            ttl: IsrCacheConfig::default().default_ttl(),
+            tags: Vec::new(),
+            generation: 0,
         }
     }
 
+    /// Set the freshness windows (from [`cache_policy::cache_policy`])
+    #[must_use]
+    pub const fn with_ttl(mut self, ttl: CacheTtl) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Record when the render started, matching the `Last-Modified` already
+    /// sent with it while it streamed
+    #[must_use]
+    pub const fn with_rendered_at(mut self, rendered_at: SystemTime) -> Self {
+        self.rendered_at = rendered_at;
+        self
+    }
+
+    /// Attach the surrogate keys the response was rendered from
+    #[must_use]
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
     /// Get body for a specific encoding, compressing on-demand if needed
     ///
     /// Returns (`body_bytes`, `actual_encoding`). The actual encoding may differ from
@@ -63,12 +119,7 @@ impl CachedResponse {
         }
 
         // Compress on-demand
-        let compressed_bytes = match encoding {
-            ContentEncoding::Zstd => compress_zstd(&self.body),
-            ContentEncoding::Brotli => compress_brotli(&self.body),
-            ContentEncoding::Gzip => compress_gzip(&self.body),
-            ContentEncoding::Identity => unreachable!(),
-        };
+        let compressed_bytes = compress(&self.body, encoding);
 
         // Only cache if compression actually helped
         if let Some(compressed) = compressed_bytes
@@ -83,15 +134,76 @@ impl CachedResponse {
         (self.body.clone(), ContentEncoding::Identity)
     }
 
-    /// Check if this response is still fresh (within `fresh_duration`)
-    pub fn is_fresh(&self, fresh_duration: Duration) -> bool {
-        self.cached_at.elapsed() < fresh_duration
+    /// Encodings servable without compressing, identity first
+    pub fn encodings(&self) -> Vec<ContentEncoding> {
+        let compressed = self.compressed.read();
+        std::iter::once(ContentEncoding::Identity)
+            .chain(
+                [
+                    ContentEncoding::Zstd,
+                    ContentEncoding::Brotli,
+                    ContentEncoding::Gzip,
+                ]
+                .into_iter()
+                .filter(|encoding| compressed.contains_key(encoding)),
+            )
+            .collect()
+    }
+
+    /// Rebuild a response read from the disk tier, keeping its original age
+    fn restore(entry: StoredEntry) -> Self {
+        let age = entry.age();
+        Self {
+            status: entry.status,
+            headers: entry.headers,
+            etag: conditional::etag(&entry.body),
+            body: entry.body,
+            compressed: Arc::new(parking_lot::RwLock::new(entry.variants)),
+            cached_at: Instant::now().checked_sub(age).unwrap_or_else(Instant::now),
+            rendered_at: entry.rendered_at,
+            ttl: entry.ttl,
+            tags: entry.tags,
+            generation: 0,
+        }
+    }
+
+    /// Snapshot this response for the disk tier, compressing every encoding
+    /// first so a restored entry never pays for compression on a request.
+    fn to_stored(&self, key: String) -> StoredEntry {
+        for encoding in [
+            ContentEncoding::Zstd,
+            ContentEncoding::Brotli,
+            ContentEncoding::Gzip,
+        ] {
+            self.get_body(encoding);
+        }
+        StoredEntry {
+            key,
+            status: self.status,
+            headers: self.headers.clone(),
+            body: self.body.clone(),
+            variants: self.compressed.read().clone(),
+            tags: self.tags.clone(),
+            ttl: self.ttl,
+            rendered_at: self.rendered_at,
+        }
     }
 
-    /// Check if this response is stale but still usable (within `stale_duration`)
-    pub fn is_stale_but_usable(&self, fresh_duration: Duration, stale_duration: Duration) -> bool {
+    /// Check if this response is still fresh (within its fresh window)
+    pub fn is_fresh(&self) -> bool {
+        self.cached_at.elapsed() < self.ttl.fresh
+    }
+
+    /// Check if this response is stale but still usable (within its lifetime)
+    pub fn is_stale_but_usable(&self) -> bool {
         let age = self.cached_at.elapsed();
-        age >= fresh_duration && age < stale_duration
+        age >= self.ttl.fresh && age < self.ttl.stale
+    }
+
+    /// Check if this response may stand in for a failed render (within its
+    /// stale-if-error window, or any earlier one)
+    pub fn is_usable_on_error(&self) -> bool {
+        self.cached_at.elapsed() < self.ttl.retain()
     }
 
     /// Get the age of this cached response
@@ -107,10 +219,20 @@ pub struct IsrCacheConfig {
     pub max_entries: u64,
     /// Duration a response is considered fresh (served without refresh)
     pub fresh_duration: Duration,
-    /// Total duration before entry is evicted (stale responses served during refresh)
+    /// Duration stale responses are served while refreshing in the background
     pub stale_duration: Duration,
+    /// How much longer than `stale_duration` entries are kept, to be served
+    /// only while Bun is unhealthy or failing to render
+    pub stale_if_error: Duration,
     /// Whether caching is enabled
     pub enabled: bool,
+    /// Directory for the on-disk tier; `None` keeps the cache memory-only
+    pub persist_dir: Option<PathBuf>,
+    /// Byte budget for the on-disk tier (oldest renders are deleted first)
+    pub persist_max_bytes: u64,
+    /// How long a cold miss waits on another request's render of the same
+    /// page before rendering it itself
+    pub coalesce_timeout: Duration,
 }
 
 impl Default for IsrCacheConfig {
@@ -119,12 +241,25 @@ impl Default for IsrCacheConfig {
             max_entries: 1000,
             fresh_duration: Duration::from_mins(1),
             stale_duration: Duration::from_mins(5),
+            stale_if_error: Duration::from_hours(24),
             enabled: true,
+            persist_dir: None,
+            persist_max_bytes: 256 * 1024 * 1024,
+            coalesce_timeout: Duration::from_secs(5),
         }
     }
 }
 
 impl IsrCacheConfig {
+    /// Windows for responses that don't set their own `Cache-Control`
+    pub const fn default_ttl(&self) -> CacheTtl {
+        CacheTtl {
+            fresh: self.fresh_duration,
+            stale: self.stale_duration,
+            stale_if_error: self.stale_if_error,
+        }
+    }
+
     /// Load configuration from environment variables
     pub fn from_env() -> Self {
         let max_entries = std::env::var("ISR_CACHE_MAX_ENTRIES")
@@ -142,13 +277,262 @@ impl IsrCacheConfig {
             .and_then(|v| v.parse().ok())
             .unwrap_or(300);
 
+        let stale_if_error_sec = std::env::var("ISR_CACHE_STALE_IF_ERROR_SEC")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(86400);
+
         let enabled = std::env::var("ISR_CACHE_ENABLED").map_or(true, |v| v != "false" && v != "0");
 
+        let persist_dir = std::env::var("ISR_CACHE_DIR")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+
+        let persist_max_mb: u64 = std::env::var("ISR_CACHE_DISK_MAX_MB")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(256);
+
+        let coalesce_timeout_ms = std::env::var("ISR_COALESCE_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5000);
+
         Self {
             max_entries,
             fresh_duration: Duration::from_secs(fresh_sec),
             stale_duration: Duration::from_secs(stale_sec),
+            stale_if_error: Duration::from_secs(stale_if_error_sec),
             enabled,
+            persist_dir,
+            persist_max_bytes: persist_max_mb * 1024 * 1024,
+            coalesce_timeout: Duration::from_millis(coalesce_timeout_ms),
+        }
+    }
+}
+
+/// Response header Bun uses to list a page's surrogate keys (comma-separated).
+/// It is consumed by the proxy and never forwarded to clients.
+pub const CACHE_TAGS_HEADER: &str = "x-cache-tags";
+
+/// The surrogate key vocabulary shared with Bun's `cacheTags` helper.
+pub mod tags {
+    use uuid::Uuid;
+
+    /// Every page: the layout renders site identity and social links
+    pub const SETTINGS: &str = "settings";
+    /// Pages listing projects, whose membership changes on create/delete/hide
+    pub const PROJECTS: &str = "projects";
+    /// Entries rendered without any tags (e.g. by an older Bun build). Every
+    /// tag purge also drops these, since nothing says what they depend on.
+    pub const UNTAGGED: &str = "untagged";
+
+    /// Pages showing a project (its detail page and any card linking to it)
+    pub fn project(id: Uuid) -> String {
+        format!("project:{id}")
+    }
+
+    /// Pages showing a tag
+    pub fn tag(id: Uuid) -> String {
+        format!("tag:{id}")
+    }
+}
+
+/// Remove the surrogate-key header from a downstream response, returning its tags.
+pub fn take_cache_tags(headers: &mut HeaderMap) -> Vec<String> {
+    let Some(value) = headers.remove(CACHE_TAGS_HEADER) else {
+        return Vec::new();
+    };
+    let mut tags: Vec<String> = value
+        .to_str()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+    tags.sort_unstable();
+    tags.dedup();
+    tags
+}
+
+/// How the proxy answered a page request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheOutcome {
+    /// Fresh entry served
+    Hit,
+    /// Stale entry served while re-rendering
+    Stale,
+    /// Nothing usable cached; rendered by Bun
+    Miss,
+    /// Not cacheable (authenticated or excluded path); rendered by Bun
+    Bypass,
+    /// Nothing cached, but served from a concurrent request's render
+    Coalesced,
+    /// Expired entry served because Bun is down or failed to render
+    StaleIfError,
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    stale: AtomicU64,
+    misses: AtomicU64,
+    bypasses: AtomicU64,
+    coalesced: AtomicU64,
+    stale_if_error: AtomicU64,
+}
+
+/// Which request targets an admin purge drops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PurgeSelector {
+    All,
+    /// Exact request target, query included
+    Target(String),
+    Prefix(String),
+    /// `*` matches any run of characters; everything else is literal
+    Glob(String),
+}
+
+impl PurgeSelector {
+    pub fn matches(&self, target: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Target(exact) => target == exact,
+            Self::Prefix(prefix) => target.starts_with(prefix.as_str()),
+            Self::Glob(pattern) => glob_matches(pattern, target),
+        }
+    }
+}
+
+/// Match `text` against a pattern whose only wildcard is `*`
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` at all: the pattern must match exactly
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Surrogate key → cache keys carrying it, with the generation of the entry
+/// that registered each key.
+type TagIndex = DashMap<String, HashMap<String, u64>>;
+
+/// Expires each entry at the end of its own stale window, measured from when
+/// it was rendered rather than inserted, so entries restored from disk keep
+/// their remaining lifetime.
+struct RenderAgeExpiry;
+
+impl RenderAgeExpiry {
+    fn remaining(value: &CachedResponse) -> Duration {
+        value.ttl.retain().saturating_sub(value.age())
+    }
+}
+
+impl Expiry<String, Arc<CachedResponse>> for RenderAgeExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        value: &Arc<CachedResponse>,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(Self::remaining(value))
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &String,
+        value: &Arc<CachedResponse>,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(Self::remaining(value))
+    }
+}
+
+/// A cold-miss render stored in the cache, shared with the requests that
+/// waited on it
+#[derive(Clone)]
+pub struct SharedRender {
+    /// Key the render was stored under, including its `Vary` variant
+    pub key: String,
+    pub response: Arc<CachedResponse>,
+}
+
+impl SharedRender {
+    /// Whether this render is the variant a request for `base_key` with
+    /// these forwarded headers would be served. A render that turned out to
+    /// vary on a header the waiting request sent differently is not.
+    pub fn matches(&self, base_key: &str, request: &HeaderMap) -> bool {
+        let vary = cache_policy::variant_names(&self.key).unwrap_or_default();
+        cache_policy::variant_key(base_key, &vary, request) == self.key
+    }
+}
+
+#[derive(Clone)]
+enum FlightState {
+    Rendering,
+    /// The cached render, or `None` if nothing was cached
+    Done(Option<SharedRender>),
+}
+
+type FlightReceiver = watch::Receiver<FlightState>;
+
+/// A request's part in the render of a missing key
+pub enum RenderFlight {
+    Leader(FlightLeader),
+    Follower(FlightFollower),
+}
+
+/// Renders a missing key on behalf of every request waiting on it. Dropping
+/// it without [`Self::finish`] releases the followers to render themselves.
+pub struct FlightLeader {
+    cache: Arc<IsrCache>,
+    key: String,
+    sender: watch::Sender<FlightState>,
+}
+
+impl FlightLeader {
+    /// Hand the cached render (or `None` if nothing was cached) to followers
+    pub fn finish(self, render: Option<SharedRender>) {
+        self.sender.send_replace(FlightState::Done(render));
+    }
+}
+
+impl Drop for FlightLeader {
+    fn drop(&mut self) {
+        self.cache.flights.lock().remove(&self.key);
+    }
+}
+
+/// Waits on another request's render of the same key
+pub struct FlightFollower {
+    receiver: FlightReceiver,
+}
+
+impl FlightFollower {
+    /// The leader's cached render, or `None` if it cached nothing, failed,
+    /// or took longer than `timeout`
+    pub async fn wait(mut self, timeout: Duration) -> Option<SharedRender> {
+        let done = self
+            .receiver
+            .wait_for(|state| matches!(state, FlightState::Done(_)));
+        match &*tokio::time::timeout(timeout, done).await.ok()?.ok()? {
+            FlightState::Done(render) => render.clone(),
+            FlightState::Rendering => None,
         }
     }
 }
@@ -156,28 +540,213 @@ impl IsrCacheConfig {
 /// ISR cache for SSR page responses
 pub struct IsrCache {
     cache: Cache<String, Arc<CachedResponse>>,
+    tag_index: Arc<TagIndex>,
+    /// Base key (`{host}{target}`) → request headers its latest render varied
+    /// on, so lookups can find the right variant
+    vary_index: DashMap<String, Vec<axum::http::HeaderName>>,
+    next_generation: AtomicU64,
+    store: Option<Arc<DiskStore>>,
+    /// Receives every key dropped by an explicit invalidation (see
+    /// [`Self::subscribe_invalidations`])
+    invalidated: OnceLock<mpsc::UnboundedSender<String>>,
+    counters: Counters,
     /// Tracks paths currently being refreshed in background
     refreshing: DashSet<String>,
+    /// Cold-miss renders in progress, by cache key (see [`Self::join_render`])
+    flights: parking_lot::Mutex<HashMap<String, FlightReceiver>>,
     pub config: IsrCacheConfig,
 }
 
 impl IsrCache {
     /// Create a new ISR cache with the given configuration
     pub fn new(config: IsrCacheConfig) -> Self {
+        let tag_index = Arc::new(TagIndex::new());
+        let store = config
+            .persist_dir
+            .as_ref()
+            .filter(|_| config.enabled)
+            .and_then(|dir| match DiskStore::open(dir, config.persist_max_bytes) {
+                Ok(store) => Some(Arc::new(store)),
+                Err(err) => {
+                    tracing::warn!(dir = %dir.display(), error = %err, "ISR disk cache unavailable, continuing memory-only");
+                    None
+                }
+            });
+
+        // Keep the tag index and disk tier in step with evictions (expiry,
+        // capacity, explicit invalidation, replacement); the generation check
+        // skips anything a newer insert under the same key has already claimed.
+        let listener_index = Arc::clone(&tag_index);
+        let listener_store = store.clone();
         let cache = Cache::builder()
             .max_capacity(config.max_entries)
-            // Use stale_duration as TTL - we handle fresh/stale logic ourselves
-            .time_to_live(config.stale_duration)
+            // Expire at the end of each entry's stale-if-error window - we
+            // handle fresh/stale logic ourselves
+            .expire_after(RenderAgeExpiry)
             .name("isr_cache")
+            .eviction_listener(
+                move |key: Arc<String>, value: Arc<CachedResponse>, _cause| {
+                    unindex(&listener_index, &key, &value);
+                    if let Some(store) = &listener_store {
+                        store.remove(&key, value.generation);
+                    }
+                },
+            )
             .build();
 
         Self {
             cache,
+            tag_index,
+            vary_index: DashMap::new(),
+            next_generation: AtomicU64::new(1),
+            store,
+            invalidated: OnceLock::new(),
+            counters: Counters::default(),
             refreshing: DashSet::new(),
+            flights: parking_lot::Mutex::new(HashMap::new()),
             config,
         }
     }
 
+    /// Stream the keys dropped by [`Self::invalidate`] and
+    /// [`Self::invalidate_tag`], so they can be re-rendered. Only the first
+    /// subscriber is served; later calls get a receiver that never yields.
+    pub fn subscribe_invalidations(&self) -> mpsc::UnboundedReceiver<String> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        if self.invalidated.set(sender).is_err() {
+            tracing::warn!("ISR invalidations already have a subscriber");
+        }
+        receiver
+    }
+
+    /// Count how the proxy answered a page request
+    pub fn record(&self, outcome: CacheOutcome) {
+        let counter = match outcome {
+            CacheOutcome::Hit => &self.counters.hits,
+            CacheOutcome::Stale => &self.counters.stale,
+            CacheOutcome::Miss => &self.counters.misses,
+            CacheOutcome::Bypass => &self.counters.bypasses,
+            CacheOutcome::Coalesced => &self.counters.coalesced,
+            CacheOutcome::StaleIfError => &self.counters.stale_if_error,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Every live entry, sorted by host then target
+    pub fn entries(&self) -> Vec<CacheEntryInfo> {
+        let mut entries: Vec<CacheEntryInfo> = self
+            .cache
+            .iter()
+            .filter_map(|(key, entry)| {
+                let (host, target) = split_cache_key(&key)?;
+                Some(CacheEntryInfo {
+                    host: host.to_string(),
+                    target: target.to_string(),
+                    variant: cache_policy::split_variant(&key).1.map(str::to_string),
+                    status: entry.status.as_u16(),
+                    age_secs: entry.age().as_secs(),
+                    size: entry.body.len(),
+                    encodings: entry
+                        .encodings()
+                        .into_iter()
+                        .map(|encoding| encoding.name().to_string())
+                        .collect(),
+                    state: if entry.is_fresh() {
+                        CacheEntryState::Fresh
+                    } else {
+                        CacheEntryState::Stale
+                    },
+                    tags: entry.tags.clone(),
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| (&a.host, &a.target).cmp(&(&b.host, &b.target)));
+        entries
+    }
+
+    /// Occupancy and request counters
+    pub fn stats(&self) -> CacheStats {
+        let mut stats = CacheStats {
+            enabled: self.config.enabled,
+            persisted_bytes: self.store.as_ref().map(|store| store.total_bytes()),
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            stale_hits: self.counters.stale.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            bypasses: self.counters.bypasses.load(Ordering::Relaxed),
+            coalesced: self.counters.coalesced.load(Ordering::Relaxed),
+            stale_if_error: self.counters.stale_if_error.load(Ordering::Relaxed),
+            ..CacheStats::default()
+        };
+        for (_, entry) in &self.cache {
+            stats.entries += 1;
+            stats.bytes += entry.body.len() as u64;
+            if entry.is_fresh() {
+                stats.fresh_entries += 1;
+            } else {
+                stats.stale_entries += 1;
+            }
+        }
+        stats
+    }
+
+    /// Drop every entry whose target matches `selector`, optionally on one
+    /// host only. Returns the dropped cache keys, sorted.
+    pub async fn purge(&self, host: Option<&str>, selector: &PurgeSelector) -> Vec<String> {
+        let mut purged: Vec<String> = self
+            .cache
+            .iter()
+            .filter_map(|(key, _)| {
+                let (key_host, target) = split_cache_key(&key)?;
+                (host.is_none_or(|host| host == key_host) && selector.matches(target))
+                    .then(|| key.to_string())
+            })
+            .collect();
+        purged.sort_unstable();
+        for key in &purged {
+            self.cache.invalidate(key).await;
+            self.notify_invalidated(key);
+        }
+        tracing::debug!(selector = ?selector, purged = purged.len(), "Cache entries purged");
+        purged
+    }
+
+    /// Whether entries are mirrored to disk
+    pub const fn is_persistent(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Load entries persisted by a previous run, with their original age.
+    /// Returns the number restored; a no-op without a disk tier.
+    pub async fn load_persisted(&self) -> usize {
+        let Some(store) = self.store.clone() else {
+            return 0;
+        };
+        let entries = match tokio::task::spawn_blocking(move || store.load()).await {
+            Ok(Ok(entries)) => entries,
+            Ok(Err(err)) => {
+                tracing::warn!(error = %err, "Failed to read ISR disk cache");
+                return 0;
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, "ISR disk cache load panicked");
+                return 0;
+            }
+        };
+
+        let restored = entries.len();
+        for entry in entries {
+            let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+            if let Some(store) = &self.store {
+                store.track(&entry, generation);
+            }
+            let key = entry.key.clone();
+            let mut response = CachedResponse::restore(entry);
+            response.generation = generation;
+            self.admit(key, response).await;
+        }
+        restored
+    }
+
     /// Get a cached response if it exists
     pub async fn get(&self, path: &str) -> Option<Arc<CachedResponse>> {
         if !self.config.enabled {
@@ -186,12 +755,94 @@ impl IsrCache {
         self.cache.get(path).await
     }
 
-    /// Insert a response into the cache
-    pub async fn insert(&self, path: String, response: CachedResponse) {
+    /// Insert a response into the cache, indexing it under its tags. Returns
+    /// the stored entry, or `None` if caching is disabled.
+    pub async fn insert(
+        &self,
+        path: String,
+        mut response: CachedResponse,
+    ) -> Option<Arc<CachedResponse>> {
         if !self.config.enabled {
-            return;
+            return None;
+        }
+        response.generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        if response.tags.is_empty() {
+            response.tags.push(tags::UNTAGGED.to_string());
+        }
+        // Claim the key on disk before the entry becomes evictable, so an
+        // invalidation racing the write below cancels it.
+        if let Some(store) = &self.store {
+            store.expect(&path, response.generation);
+        }
+        let response = self.admit(path.clone(), response).await;
+
+        if let Some(store) = self.store.clone() {
+            let response = Arc::clone(&response);
+            tokio::task::spawn_blocking(move || {
+                let generation = response.generation;
+                if let Err(err) = store.write(&response.to_stored(path.clone()), generation) {
+                    tracing::warn!(key = %path, error = %err, "Failed to persist ISR cache entry");
+                }
+            });
+        }
+        Some(response)
+    }
+
+    /// Index a response under its tags and make it visible
+    async fn admit(&self, path: String, response: CachedResponse) -> Arc<CachedResponse> {
+        let (base, _) = cache_policy::split_variant(&path);
+        match cache_policy::variant_names(&path) {
+            Some(names) => {
+                self.vary_index.insert(base.to_string(), names);
+            }
+            None => {
+                self.vary_index.remove(base);
+            }
+        }
+        for tag in &response.tags {
+            self.tag_index
+                .entry(tag.clone())
+                .or_default()
+                .insert(path.clone(), response.generation);
         }
-        self.cache.insert(path, Arc::new(response)).await;
+        let response = Arc::new(response);
+        self.cache.insert(path, Arc::clone(&response)).await;
+        response
+    }
+
+    /// The key to look `base_key` up under: the variant selected by the
+    /// request's headers if the page varies, otherwise `base_key` itself
+    pub fn lookup_key(&self, base_key: &str, request: &HeaderMap) -> String {
+        match self.vary_index.get(base_key) {
+            Some(vary) => cache_policy::variant_key(base_key, &vary, request),
+            None => base_key.to_string(),
+        }
+    }
+
+    /// Drop one entry without publishing it as an invalidation (used when a
+    /// re-render says the page is no longer cacheable)
+    pub async fn remove(&self, key: &str) {
+        self.cache.invalidate(key).await;
+    }
+
+    /// Join the render of a key nothing usable is cached under. The first
+    /// request leads (renders and [`FlightLeader::finish`]es); requests arriving
+    /// while it runs follow and [`FlightFollower::wait`] for its result.
+    pub fn join_render(self: &Arc<Self>, key: &str) -> RenderFlight {
+        let mut flights = self.flights.lock();
+        if let Some(receiver) = flights.get(key) {
+            return RenderFlight::Follower(FlightFollower {
+                receiver: receiver.clone(),
+            });
+        }
+        let (sender, receiver) = watch::channel(FlightState::Rendering);
+        flights.insert(key.to_string(), receiver);
+        drop(flights);
+        RenderFlight::Leader(FlightLeader {
+            cache: Arc::clone(self),
+            key: key.to_string(),
+            sender,
+        })
     }
 
     /// Mark a path as being refreshed. Returns true if it wasn't already refreshing.
@@ -204,24 +855,117 @@ impl IsrCache {
         self.refreshing.remove(path);
     }
 
-    /// Invalidate a cached request target across every public host.
+    /// Invalidate a cached path across every public host and query string.
     ///
     /// Keys are `{host}{path}[?query]` (see [`cache_key`]); the host never
     /// contains `/`, so the first `/` begins the request target. Callers pass a
-    /// bare target (e.g. `/` or `/projects/foo`), and every host's variant of
-    /// that exact target is dropped.
+    /// bare path (e.g. `/` or `/projects/foo`), and every host's variant of that
+    /// path is dropped, with or without a query. Prefer
+    /// [`Self::invalidate_tag`] for entity changes.
     pub async fn invalidate(&self, path: &str) {
         let mut invalidated = 0u32;
         for (key, _) in &self.cache {
-            if let Some(idx) = key.find('/')
-                && &key[idx..] == path
+            if let Some((_, target)) = split_cache_key(&key)
+                && target_matches_path(target, path)
             {
                 self.cache.invalidate(key.as_str()).await;
+                self.notify_invalidated(&key);
                 invalidated += 1;
             }
         }
         tracing::debug!(path = %path, invalidated, "Cache entries invalidated");
     }
+
+    /// Invalidate every cached page carrying the surrogate key `tag`, across all
+    /// hosts and query variants, plus any untagged entries. Returns the number of
+    /// entries dropped.
+    pub async fn invalidate_tag(&self, tag: &str) -> usize {
+        let mut entries: Vec<(String, u64)> = Vec::new();
+        for tag in [tag, tags::UNTAGGED] {
+            if let Some((_, tagged)) = self.tag_index.remove(tag) {
+                entries.extend(tagged);
+            }
+        }
+
+        let mut invalidated = 0;
+        for (key, generation) in entries {
+            // Skip keys whose entry has since been replaced by a render that no
+            // longer carries this tag (its stale index entry is still pending
+            // removal by the eviction listener).
+            let current = self.cache.get(&key).await;
+            if current.is_some_and(|entry| entry.generation == generation) {
+                self.cache.invalidate(&key).await;
+                self.notify_invalidated(&key);
+                invalidated += 1;
+            }
+        }
+        tracing::debug!(tag = %tag, invalidated, "Cache tag invalidated");
+        invalidated
+    }
+
+    fn notify_invalidated(&self, key: &str) {
+        if let Some(sender) = self.invalidated.get() {
+            let _ = sender.send(key.to_string());
+        }
+    }
+
+    /// Invalidate several surrogate keys at once
+    pub async fn invalidate_tags<I, T>(&self, tags: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        for tag in tags {
+            self.invalidate_tag(tag.as_ref()).await;
+        }
+    }
+}
+
+/// Invalidate every page showing a library asset: projects with it in their
+/// gallery or a `figure` block, and every page when it fills a settings slot.
+pub async fn invalidate_media(cache: &IsrCache, pool: &sqlx::PgPool, asset_id: uuid::Uuid) {
+    let references = match crate::db::get_media_references(pool, asset_id).await {
+        Ok(references) => references,
+        Err(err) => {
+            tracing::warn!(error = %err, asset_id = %asset_id, "Failed to load media references");
+            return;
+        }
+    };
+
+    let mut affected: Vec<String> = references
+        .projects
+        .iter()
+        .chain(&references.figures)
+        .filter_map(|project| uuid::Uuid::parse_str(&project.id).ok())
+        .map(tags::project)
+        .collect();
+    if !references.settings.is_empty() {
+        affected.push(tags::SETTINGS.to_string());
+    }
+    cache.invalidate_tags(affected).await;
+}
+
+/// Drop an evicted entry's keys from the tag index, unless a newer insert under
+/// the same cache key has re-registered them.
+fn unindex(index: &TagIndex, key: &str, value: &CachedResponse) {
+    for tag in &value.tags {
+        let emptied = index.get_mut(tag).is_some_and(|mut entries| {
+            if entries.get(key) == Some(&value.generation) {
+                entries.remove(key);
+            }
+            entries.is_empty()
+        });
+        if emptied {
+            index.remove_if(tag, |_, entries| entries.is_empty());
+        }
+    }
+}
+
+/// Whether a request target is `path` itself or `path` with a query string.
+fn target_matches_path(target: &str, path: &str) -> bool {
+    target
+        .strip_prefix(path)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('?'))
 }
 
 /// Excludes admin, API, internal, and static asset paths.
@@ -256,6 +1000,14 @@ pub fn cache_key_for_target(host: &str, target: &str) -> String {
     format!("{host}{target}")
 }
 
+/// Split a cache key back into its host and request target, dropping any
+/// `Vary` variant (see [`cache_policy::variant_key`]).
+pub fn split_cache_key(key: &str) -> Option<(&str, &str)> {
+    let (base, _) = cache_policy::split_variant(key);
+    let idx = base.find('/')?;
+    Some((&base[..idx], &base[idx..]))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -300,6 +1052,10 @@ mod tests {
             cache_key("xevion.dev", "/", None),
             cache_key("walters.to", "/", None)
         );
+        assert_eq!(
+            split_cache_key("xevion.dev/?tag=rust"),
+            Some(("xevion.dev", "/?tag=rust"))
+        );
     }
 
     #[tokio::test]
@@ -317,10 +1073,19 @@ mod tests {
         cache
             .insert(cache_key("xevion.dev", "/projects/foo", None), resp())
             .await;
+        cache
+            .insert(cache_key("xevion.dev", "/", Some("tag=rust")), resp())
+            .await;
 
         cache.invalidate("/").await;
 
-        // Both hosts' homepage variants are gone.
+        // Both hosts' homepage variants are gone, query variants included.
+        assert!(
+            cache
+                .get(&cache_key("xevion.dev", "/", Some("tag=rust")))
+                .await
+                .is_none()
+        );
         assert!(
             cache
                 .get(&cache_key("xevion.dev", "/", None))
@@ -342,33 +1107,373 @@ mod tests {
         );
     }
 
+    #[test]
+    fn target_matching_includes_query_variants() {
+        assert!(target_matches_path("/", "/"));
+        assert!(target_matches_path("/?tag=rust", "/"));
+        assert!(!target_matches_path("/projects/foo", "/"));
+        assert!(!target_matches_path("/projects/foobar", "/projects/foo"));
+    }
+
+    #[test]
+    fn take_cache_tags_strips_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CACHE_TAGS_HEADER,
+            axum::http::HeaderValue::from_static("tag:b, settings,,tag:b,project:a"),
+        );
+        assert_eq!(
+            take_cache_tags(&mut headers),
+            ["project:a", "settings", "tag:b"]
+        );
+        assert!(headers.get(CACHE_TAGS_HEADER).is_none());
+        assert!(take_cache_tags(&mut headers).is_empty());
+    }
+
+    fn tagged(tags: &[&str]) -> CachedResponse {
+        CachedResponse::new(
+            StatusCode::OK,
+            HeaderMap::new(),
+            axum::body::Bytes::from_static(b"x"),
+        )
+        .with_tags(tags.iter().map(ToString::to_string).collect())
+    }
+
+    #[tokio::test]
+    async fn invalidate_tag_drops_every_page_carrying_it() {
+        let cache = IsrCache::new(IsrCacheConfig::default());
+        let home = cache_key("xevion.dev", "/", None);
+        let filtered = cache_key("walters.to", "/", Some("tag=rust"));
+        let project = cache_key("xevion.dev", "/projects/foo", None);
+        let other = cache_key("xevion.dev", "/projects/bar", None);
+
+        cache
+            .insert(home.clone(), tagged(&["projects", "tag:rust"]))
+            .await;
+        cache
+            .insert(filtered.clone(), tagged(&["projects", "tag:rust"]))
+            .await;
+        cache
+            .insert(project.clone(), tagged(&["project:foo", "tag:rust"]))
+            .await;
+        cache.insert(other.clone(), tagged(&["project:bar"])).await;
+
+        assert_eq!(cache.invalidate_tag("tag:rust").await, 3);
+        for key in [&home, &filtered, &project] {
+            assert!(cache.get(key).await.is_none());
+        }
+        assert!(cache.get(&other).await.is_some());
+        assert_eq!(cache.invalidate_tag("tag:rust").await, 0);
+    }
+
+    #[tokio::test]
+    async fn invalidate_tag_spares_replaced_entries_without_the_tag() {
+        let cache = IsrCache::new(IsrCacheConfig::default());
+        let key = cache_key("xevion.dev", "/projects/foo", None);
+
+        cache.insert(key.clone(), tagged(&["tag:old"])).await;
+        cache.insert(key.clone(), tagged(&["tag:new"])).await;
+
+        assert_eq!(cache.invalidate_tag("tag:old").await, 0);
+        assert!(cache.get(&key).await.is_some());
+        assert_eq!(cache.invalidate_tag("tag:new").await, 1);
+    }
+
+    #[tokio::test]
+    async fn untagged_entries_are_purged_by_any_tag() {
+        let cache = IsrCache::new(IsrCacheConfig::default());
+        let key = cache_key("xevion.dev", "/", None);
+
+        cache.insert(key.clone(), tagged(&[])).await;
+        assert_eq!(cache.invalidate_tag("settings").await, 1);
+        assert!(cache.get(&key).await.is_none());
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_matches("/projects/*", "/projects/foo"));
+        assert!(glob_matches("*", "/anything?q=1"));
+        assert!(glob_matches("/*?tag=*", "/?tag=rust"));
+        assert!(glob_matches("/pgp", "/pgp"));
+        assert!(!glob_matches("/pgp", "/pgp?x"));
+        assert!(!glob_matches("/projects/*", "/"));
+        assert!(!glob_matches("/a*a", "/a"));
+    }
+
+    #[tokio::test]
+    async fn purge_selects_by_target_and_host() {
+        let cache = IsrCache::new(IsrCacheConfig::default());
+        for key in [
+            cache_key("xevion.dev", "/", None),
+            cache_key("xevion.dev", "/projects/foo", None),
+            cache_key("walters.to", "/projects/foo", None),
+            cache_key("walters.to", "/projects/bar", Some("tab=media")),
+        ] {
+            cache.insert(key, tagged(&[])).await;
+        }
+
+        let purged = cache
+            .purge(
+                Some("walters.to"),
+                &PurgeSelector::Prefix("/projects/".into()),
+            )
+            .await;
+        assert_eq!(
+            purged,
+            [
+                "walters.to/projects/bar?tab=media",
+                "walters.to/projects/foo"
+            ]
+        );
+        assert!(
+            cache
+                .purge(None, &PurgeSelector::Target("/projects/bar".into()))
+                .await
+                .is_empty()
+        );
+        assert_eq!(
+            cache
+                .purge(None, &PurgeSelector::Glob("/projects/*".into()))
+                .await,
+            ["xevion.dev/projects/foo"]
+        );
+        assert_eq!(cache.purge(None, &PurgeSelector::All).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stats_count_outcomes_and_entries() {
+        let cache = IsrCache::new(IsrCacheConfig::default());
+        cache
+            .insert(cache_key("xevion.dev", "/", None), tagged(&[]))
+            .await;
+        cache.cache.run_pending_tasks().await;
+        cache.record(CacheOutcome::Hit);
+        cache.record(CacheOutcome::Hit);
+        cache.record(CacheOutcome::Bypass);
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.fresh_entries, 1);
+        assert_eq!(stats.bytes, 1);
+        assert_eq!((stats.hits, stats.misses, stats.bypasses), (2, 0, 1));
+        assert_eq!(cache.entries()[0].encodings, ["identity"]);
+    }
+
+    #[tokio::test]
+    async fn lookups_follow_the_latest_vary() {
+        let cache = IsrCache::new(IsrCacheConfig::default());
+        let base = cache_key("xevion.dev", "/", None);
+        let vary = [axum::http::HeaderName::from_static("accept-language")];
+        let mut german = HeaderMap::new();
+        german.insert("accept-language", "de".parse().unwrap());
+
+        let variant = cache_policy::variant_key(&base, &vary, &german);
+        cache.insert(variant.clone(), tagged(&[])).await;
+        assert_eq!(cache.lookup_key(&base, &german), variant);
+        assert!(cache.get(&cache.lookup_key(&base, &german)).await.is_some());
+        assert!(
+            cache
+                .get(&cache.lookup_key(&base, &HeaderMap::new()))
+                .await
+                .is_none()
+        );
+        assert_eq!(split_cache_key(&variant), Some(("xevion.dev", "/")));
+
+        // The page stopped varying
+        cache.insert(base.clone(), tagged(&[])).await;
+        assert_eq!(cache.lookup_key(&base, &german), base);
+    }
+
+    #[tokio::test]
+    async fn invalidated_keys_are_published() {
+        let cache = IsrCache::new(IsrCacheConfig::default());
+        let mut invalidations = cache.subscribe_invalidations();
+        let home = cache_key("xevion.dev", "/", None);
+        let project = cache_key("walters.to", "/projects/foo", None);
+
+        cache.insert(home.clone(), tagged(&["settings"])).await;
+        cache
+            .insert(project.clone(), tagged(&["project:foo"]))
+            .await;
+        cache.invalidate_tag("project:foo").await;
+        cache.invalidate("/").await;
+
+        assert_eq!(invalidations.try_recv().unwrap(), project);
+        assert_eq!(invalidations.try_recv().unwrap(), home);
+        assert!(invalidations.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn evicted_entries_leave_the_tag_index() {
+        let cache = IsrCache::new(IsrCacheConfig::default());
+        let key = cache_key("xevion.dev", "/", None);
+
+        cache.insert(key.clone(), tagged(&["settings"])).await;
+        cache.invalidate("/").await;
+        cache.cache.run_pending_tasks().await;
+
+        assert!(cache.tag_index.get("settings").is_none());
+    }
+
+    /// Wait for background disk writes to settle
+    async fn wait_for_files(dir: &std::path::Path, count: usize) {
+        for _ in 0..200 {
+            let files = std::fs::read_dir(dir).map_or(0, |entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter(|e| e.path().extension().is_some_and(|ext| ext == "isr"))
+                    .count()
+            });
+            if files == count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {count} persisted entries in {}", dir.display());
+    }
+
+    #[tokio::test]
+    async fn persisted_entries_survive_restart_with_their_age() {
+        let dir = std::env::temp_dir().join(format!("xevion-isr-{}", ulid::Ulid::new()));
+        let config = IsrCacheConfig {
+            persist_dir: Some(dir.clone()),
+            ..IsrCacheConfig::default()
+        };
+        let home = cache_key("xevion.dev", "/", None);
+        let project = cache_key("xevion.dev", "/projects/foo", None);
+        let body = axum::body::Bytes::from(vec![b'x'; 4096]);
+
+        let before = IsrCache::new(config.clone());
+        let mut page = CachedResponse::new(StatusCode::OK, HeaderMap::new(), body.clone())
+            .with_tags(vec!["settings".to_string()]);
+        page.cached_at -= Duration::from_secs(20);
+        page.rendered_at -= Duration::from_secs(20);
+        before.insert(home.clone(), page).await;
+        before
+            .insert(project.clone(), tagged(&["project:foo"]))
+            .await;
+        wait_for_files(&dir, 2).await;
+
+        // Invalidated entries are removed from disk too
+        before.invalidate_tag("project:foo").await;
+        before.cache.run_pending_tasks().await;
+        wait_for_files(&dir, 1).await;
+
+        let after = IsrCache::new(config);
+        assert_eq!(after.load_persisted().await, 1);
+        let restored = after.get(&home).await.unwrap();
+        assert_eq!(restored.body, body);
+        assert!(restored.age() >= Duration::from_secs(20));
+        assert_eq!(restored.etag, conditional::etag(&body));
+        assert_eq!(
+            restored.get_body(ContentEncoding::Brotli).1,
+            ContentEncoding::Brotli
+        );
+        assert!(after.get(&project).await.is_none());
+        assert_eq!(after.invalidate_tag("settings").await, 1);
+    }
+
     #[tokio::test]
     async fn test_cached_response_freshness() {
         let response = CachedResponse::new(
             StatusCode::OK,
             HeaderMap::new(),
             axum::body::Bytes::from_static(b"test"),
-        );
-
-        let fresh = Duration::from_millis(100);
-        let stale = Duration::from_millis(200);
+        )
+        .with_ttl(CacheTtl {
+            fresh: Duration::from_millis(100),
+            stale: Duration::from_millis(200),
+            stale_if_error: Duration::from_millis(300),
+        });
 
         // Should be fresh immediately
-        assert!(response.is_fresh(fresh));
-        assert!(!response.is_stale_but_usable(fresh, stale));
+        assert!(response.is_fresh());
+        assert!(!response.is_stale_but_usable());
 
         // Wait a bit
         tokio::time::sleep(Duration::from_millis(110)).await;
 
         // Should be stale but usable
-        assert!(!response.is_fresh(fresh));
-        assert!(response.is_stale_but_usable(fresh, stale));
+        assert!(!response.is_fresh());
+        assert!(response.is_stale_but_usable());
 
         // Wait more
         tokio::time::sleep(Duration::from_millis(100)).await;
 
-        // Should be neither fresh nor usable
-        assert!(!response.is_fresh(fresh));
-        assert!(!response.is_stale_but_usable(fresh, stale));
+        // Should be neither fresh nor usable, but kept for errors
+        assert!(!response.is_fresh());
+        assert!(!response.is_stale_but_usable());
+        assert!(response.is_usable_on_error());
+
+        // Past the stale-if-error window too
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!response.is_usable_on_error());
+    }
+
+    #[tokio::test]
+    async fn concurrent_misses_share_one_render() {
+        let cache = Arc::new(IsrCache::new(IsrCacheConfig::default()));
+        let key = cache_key("xevion.dev", "/", None);
+
+        let RenderFlight::Leader(leader) = cache.join_render(&key) else {
+            panic!("first miss should lead");
+        };
+        let RenderFlight::Follower(follower) = cache.join_render(&key) else {
+            panic!("concurrent miss should follow");
+        };
+        let waiting = tokio::spawn(follower.wait(Duration::from_secs(5)));
+
+        let response = cache
+            .insert(key.clone(), tagged(&["settings"]))
+            .await
+            .unwrap();
+        leader.finish(Some(SharedRender {
+            key: key.clone(),
+            response,
+        }));
+
+        let shared = waiting.await.unwrap().unwrap();
+        assert_eq!(shared.key, key);
+        assert!(shared.matches(&key, &HeaderMap::new()));
+        // The flight is over, so the next miss leads again
+        assert!(matches!(cache.join_render(&key), RenderFlight::Leader(_)));
+    }
+
+    #[tokio::test]
+    async fn followers_are_released_when_nothing_is_shared() {
+        let cache = Arc::new(IsrCache::new(IsrCacheConfig::default()));
+        let key = cache_key("xevion.dev", "/", None);
+
+        // Leader gave up without a render
+        let leader = cache.join_render(&key);
+        let RenderFlight::Follower(follower) = cache.join_render(&key) else {
+            panic!("concurrent miss should follow");
+        };
+        drop(leader);
+        assert!(follower.wait(Duration::from_secs(5)).await.is_none());
+
+        // Leader is still rendering when the follower's patience runs out
+        let _leader = cache.join_render(&key);
+        let RenderFlight::Follower(follower) = cache.join_render(&key) else {
+            panic!("concurrent miss should follow");
+        };
+        assert!(follower.wait(Duration::from_millis(10)).await.is_none());
+    }
+
+    #[test]
+    fn shared_renders_only_match_their_variant() {
+        let base = cache_key("xevion.dev", "/", None);
+        let vary = [axum::http::HeaderName::from_static("accept-language")];
+        let mut english = HeaderMap::new();
+        english.insert("accept-language", "en".parse().unwrap());
+        let mut german = HeaderMap::new();
+        german.insert("accept-language", "de".parse().unwrap());
+
+        let shared = SharedRender {
+            key: cache_policy::variant_key(&base, &vary, &english),
+            response: Arc::new(tagged(&[])),
+        };
+        assert!(shared.matches(&base, &english));
+        assert!(!shared.matches(&base, &german));
     }
 }
@@ -0,0 +1,342 @@
+//! Per-response ISR cache policy, read from Bun's response headers
+//!
+//! `SvelteKit` routes control their own caching through standard headers:
+//! - `Cache-Control: s-maxage` (or `max-age`) sets how long a render is fresh,
+//!   and `stale-while-revalidate` how much longer it may be served while a
+//!   background re-render runs. `stale-if-error` sets how much longer still it
+//!   is kept to stand in for a failed render. Without them the
+//!   [`IsrCacheConfig`] windows apply.
+//! - `no-store`, `no-cache`, `private`, or any `Set-Cookie` keeps a response out
+//!   of the cache entirely.
+//! - `Vary` on one of [`VARY_HEADERS`] caches a variant per value of that
+//!   request header. The host is always part of the key and `Accept-Encoding`
+//!   is negotiated from the stored body, so both are ignored here; varying on
+//!   `Cookie`, `Authorization`, or `*` makes the response uncacheable.
+//!
+//! [`IsrCacheConfig`]: crate::cache::IsrCacheConfig
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue, header};
+use std::{fmt::Write, time::Duration};
+
+/// Client request headers forwarded to Bun, and so the only ones a render can
+/// vary on. A `Vary` naming any other header is ignored: Bun never saw it, so
+/// the render cannot depend on it.
+pub const VARY_HEADERS: &[&str] = &[
+    "accept-language",
+    "sec-ch-prefers-color-scheme",
+    "sec-ch-prefers-reduced-motion",
+];
+
+/// Separates a cache key's request target from its `Vary` variant. Request
+/// targets never contain `#` (fragments are not sent to servers).
+const VARIANT_SEPARATOR: char = '#';
+
+/// Freshness windows for one cached response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheTtl {
+    /// Served without a re-render for this long after rendering
+    pub fresh: Duration,
+    /// Served stale (while re-rendering) until this age
+    pub stale: Duration,
+    /// Kept this much longer than `stale`, served only when Bun can't render
+    pub stale_if_error: Duration,
+}
+
+impl CacheTtl {
+    /// Total lifetime: how long the entry is kept at all
+    pub const fn retain(&self) -> Duration {
+        self.stale.saturating_add(self.stale_if_error)
+    }
+}
+
+/// How a downstream response may be cached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachePolicy {
+    pub ttl: CacheTtl,
+    /// Request headers the render varies on, lowercased and sorted
+    pub vary: Vec<HeaderName>,
+}
+
+/// Decide whether and for how long a successful downstream response may be
+/// cached. `None` means it must not be cached.
+pub fn cache_policy(headers: &HeaderMap, default: CacheTtl) -> Option<CachePolicy> {
+    if headers.contains_key(header::SET_COOKIE) {
+        return None;
+    }
+
+    let mut fresh = None;
+    let mut shared_fresh = None;
+    let mut swr = None;
+    let mut sie = None;
+    for directive in headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+    {
+        let (name, value) = directive
+            .split_once('=')
+            .map_or((directive, None), |(name, value)| {
+                (name, Some(value.trim().trim_matches('"')))
+            });
+        let seconds = || value?.parse::<u64>().ok().map(Duration::from_secs);
+        match name.trim().to_ascii_lowercase().as_str() {
+            "no-store" | "no-cache" | "private" => return None,
+            "s-maxage" => shared_fresh = seconds(),
+            "max-age" => fresh = seconds(),
+            "stale-while-revalidate" => swr = seconds(),
+            "stale-if-error" => sie = seconds(),
+            _ => {}
+        }
+    }
+
+    let stale_if_error = sie.unwrap_or(default.stale_if_error);
+    let ttl = match shared_fresh.or(fresh) {
+        // An explicit zero lifetime without a stale window means "don't share"
+        Some(fresh) if fresh.is_zero() && swr.is_none() => return None,
+        Some(fresh) => CacheTtl {
+            fresh,
+            stale: fresh + swr.unwrap_or_else(|| default.stale.saturating_sub(default.fresh)),
+            stale_if_error,
+        },
+        None => CacheTtl {
+            fresh: default.fresh,
+            stale: swr.map_or(default.stale, |swr| default.fresh + swr),
+            stale_if_error,
+        },
+    };
+    if ttl.stale.is_zero() {
+        return None;
+    }
+
+    Some(CachePolicy {
+        ttl,
+        vary: vary_headers(headers)?,
+    })
+}
+
+/// The forwarded request headers named by `Vary`, or `None` if the response
+/// varies on something that can't be keyed (`*`, cookies, credentials).
+fn vary_headers(headers: &HeaderMap) -> Option<Vec<HeaderName>> {
+    let mut vary = Vec::new();
+    for name in headers
+        .get_all(header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+    {
+        match name.as_str() {
+            "*" | "cookie" | "authorization" => return None,
+            _ if VARY_HEADERS.contains(&name.as_str()) => {
+                vary.push(HeaderName::from_bytes(name.as_bytes()).ok()?);
+            }
+            // Accept-Encoding, the host, and unforwarded headers
+            _ => {}
+        }
+    }
+    vary.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+    vary.dedup();
+    Some(vary)
+}
+
+/// Cache key for the variant of `base_key` selected by `request`'s values of
+/// the `vary` headers: `{base_key}#{name}={value}&…`, percent-encoded. Without
+/// `vary` this is `base_key` itself.
+pub fn variant_key(base_key: &str, vary: &[HeaderName], request: &HeaderMap) -> String {
+    if vary.is_empty() {
+        return base_key.to_string();
+    }
+    let pairs: Vec<String> = vary
+        .iter()
+        .map(|name| {
+            let value = request
+                .get(name)
+                .map(HeaderValue::as_bytes)
+                .unwrap_or_default();
+            format!("{name}={}", percent_encode(value))
+        })
+        .collect();
+    format!("{base_key}{VARIANT_SEPARATOR}{}", pairs.join("&"))
+}
+
+/// Split a cache key into its base key and variant, if any
+pub fn split_variant(key: &str) -> (&str, Option<&str>) {
+    key.split_once(VARIANT_SEPARATOR)
+        .map_or((key, None), |(base, variant)| (base, Some(variant)))
+}
+
+/// Request headers encoded in a cache key's variant, to replay when
+/// re-rendering it. Empty values (the header was absent) are skipped.
+pub fn variant_headers(key: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let Some(variant) = split_variant(key).1 else {
+        return headers;
+    };
+    for (name, value) in variant.split('&').filter_map(|pair| pair.split_once('=')) {
+        if let Ok(name) = HeaderName::from_bytes(name.as_bytes())
+            && let Some(value) = percent_decode(value).filter(|value| !value.is_empty())
+            && let Ok(value) = HeaderValue::from_bytes(&value)
+        {
+            headers.insert(name, value);
+        }
+    }
+    headers
+}
+
+/// Header names a cache key's variant is keyed on
+pub fn variant_names(key: &str) -> Option<Vec<HeaderName>> {
+    split_variant(key).1.map(|variant| {
+        variant
+            .split('&')
+            .filter_map(|pair| HeaderName::from_bytes(pair.split('=').next()?.as_bytes()).ok())
+            .collect()
+    })
+}
+
+fn percent_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            out.push(byte as char);
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+    }
+    out
+}
+
+fn percent_decode(text: &str) -> Option<Vec<u8>> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULT: CacheTtl = CacheTtl {
+        fresh: Duration::from_mins(1),
+        stale: Duration::from_mins(5),
+        stale_if_error: Duration::from_hours(1),
+    };
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        headers
+    }
+
+    fn ttl(pairs: &[(&str, &str)]) -> Option<CacheTtl> {
+        cache_policy(&headers(pairs), DEFAULT).map(|policy| policy.ttl)
+    }
+
+    #[test]
+    fn defaults_apply_without_directives() {
+        assert_eq!(ttl(&[]), Some(DEFAULT));
+        assert_eq!(ttl(&[("cache-control", "public")]), Some(DEFAULT));
+    }
+
+    #[test]
+    fn shared_max_age_and_swr_set_the_windows() {
+        assert_eq!(
+            ttl(&[(
+                "cache-control",
+                "public, max-age=0, s-maxage=600, stale-while-revalidate=\"3600\""
+            )]),
+            Some(CacheTtl {
+                fresh: Duration::from_mins(10),
+                stale: Duration::from_mins(70),
+                ..DEFAULT
+            })
+        );
+        // max-age alone keeps the default stale window length
+        assert_eq!(
+            ttl(&[("cache-control", "max-age=10")]),
+            Some(CacheTtl {
+                fresh: Duration::from_secs(10),
+                stale: Duration::from_secs(250),
+                ..DEFAULT
+            })
+        );
+        assert_eq!(
+            ttl(&[("cache-control", "s-maxage=0, stale-while-revalidate=30")]),
+            Some(CacheTtl {
+                fresh: Duration::ZERO,
+                stale: Duration::from_secs(30),
+                ..DEFAULT
+            })
+        );
+        assert_eq!(ttl(&[("cache-control", "s-maxage=0")]), None);
+        assert_eq!(
+            ttl(&[("cache-control", "max-age=60, stale-if-error=86400")]),
+            Some(CacheTtl {
+                stale_if_error: Duration::from_hours(24),
+                ..DEFAULT
+            })
+        );
+    }
+
+    #[test]
+    fn private_responses_are_not_cached() {
+        assert_eq!(ttl(&[("cache-control", "no-store")]), None);
+        assert_eq!(ttl(&[("cache-control", "private, max-age=60")]), None);
+        assert_eq!(ttl(&[("set-cookie", "theme=dark; Path=/")]), None);
+        assert_eq!(ttl(&[("vary", "Cookie")]), None);
+        assert_eq!(ttl(&[("vary", "*")]), None);
+    }
+
+    #[test]
+    fn vary_keeps_forwarded_headers_only() {
+        let policy = cache_policy(
+            &headers(&[
+                ("vary", "Accept-Encoding, Sec-CH-Prefers-Color-Scheme"),
+                ("vary", "accept-language, X-Unforwarded"),
+            ]),
+            DEFAULT,
+        )
+        .unwrap();
+        assert_eq!(
+            policy.vary,
+            ["accept-language", "sec-ch-prefers-color-scheme"]
+        );
+    }
+
+    #[test]
+    fn variant_keys_round_trip() {
+        let vary = [
+            HeaderName::from_static("accept-language"),
+            HeaderName::from_static("sec-ch-prefers-color-scheme"),
+        ];
+        let request = headers(&[("accept-language", "en-US,en;q=0.9")]);
+        let key = variant_key("xevion.dev/?tag=rust", &vary, &request);
+        assert_eq!(
+            key,
+            "xevion.dev/?tag=rust#accept-language=en-US%2Cen%3Bq%3D0.9&sec-ch-prefers-color-scheme="
+        );
+        assert_eq!(split_variant(&key).0, "xevion.dev/?tag=rust");
+        assert_eq!(variant_headers(&key), request);
+        assert_eq!(variant_names(&key).unwrap(), vary);
+
+        assert_eq!(variant_key("xevion.dev/", &[], &request), "xevion.dev/");
+        assert!(variant_names("xevion.dev/").is_none());
+    }
+}
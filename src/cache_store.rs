@@ -0,0 +1,573 @@
+//! On-disk tier for the ISR cache
+//!
+//! Mirrors [`IsrCache`](crate::cache::IsrCache) entries into a directory so a
+//! restart begins warm instead of sending every first visitor to Bun. Each
+//! entry is one file, named by the SHA-256 of its cache key, holding the
+//! response head, its surrogate keys and freshness windows, the wall-clock time
+//! it was rendered, the identity body, and every compressed variant.
+//!
+//! Files are written to a temp name and renamed into place, and the directory
+//! is held under a byte budget by deleting the oldest renders first. Entries
+//! leaving memory (expiry, capacity, invalidation) are deleted here as well, so
+//! a restart never resurrects a purged page.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::body::Bytes;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::cache_policy::CacheTtl;
+use crate::encoding::ContentEncoding;
+
+/// File header; bump the version when the layout changes so old files are
+/// discarded rather than misread.
+const MAGIC: &[u8] = b"XISR2\n";
+const ENTRY_EXTENSION: &str = "isr";
+const TEMP_EXTENSION: &str = "tmp";
+
+/// A response as written to (or read back from) disk
+pub struct StoredEntry {
+    pub key: String,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    pub variants: HashMap<ContentEncoding, Bytes>,
+    pub tags: Vec<String>,
+    pub ttl: CacheTtl,
+    /// Wall-clock time the response was rendered
+    pub rendered_at: SystemTime,
+}
+
+impl StoredEntry {
+    /// Age of the render, measured against the wall clock
+    pub fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.rendered_at)
+            .unwrap_or_default()
+    }
+}
+
+/// JSON header preceding the body sections of an entry file
+#[derive(Serialize, Deserialize)]
+struct Meta {
+    key: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    tags: Vec<String>,
+    fresh_ms: u64,
+    stale_ms: u64,
+    #[serde(default)]
+    stale_if_error_ms: u64,
+    rendered_at_ms: u64,
+    body_len: usize,
+    /// Compressed variants in file order, as (`Content-Encoding`, length)
+    variants: Vec<(String, usize)>,
+}
+
+struct TrackedFile {
+    generation: u64,
+    size: u64,
+    rendered_at: SystemTime,
+}
+
+#[derive(Default)]
+struct Index {
+    /// Files on disk, by file stem
+    files: HashMap<String, TrackedFile>,
+    /// Generation each key's next write must carry to be committed. Set when an
+    /// entry is inserted and cleared by its write or its eviction, so a write
+    /// that loses a race with a newer insert or an invalidation is discarded.
+    pending: HashMap<String, u64>,
+    total_bytes: u64,
+}
+
+/// Size-bounded directory of persisted ISR entries
+pub struct DiskStore {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<Index>,
+}
+
+impl DiskStore {
+    /// Open (creating if needed) the store directory
+    pub fn open(dir: impl Into<PathBuf>, max_bytes: u64) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            max_bytes,
+            index: Mutex::new(Index::default()),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Read every entry still within its retention window, newest first and within
+    /// the byte budget. Expired, corrupt, over-budget, and leftover temp files
+    /// are deleted. Loaded entries are not tracked until [`Self::track`].
+    pub fn load(&self) -> io::Result<Vec<StoredEntry>> {
+        let mut loaded = Vec::new();
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some(ENTRY_EXTENSION) => {}
+                Some(TEMP_EXTENSION) => {
+                    remove_file(&path);
+                    continue;
+                }
+                _ => continue,
+            }
+
+            match read_entry(&path) {
+                Ok(entry)
+                    if entry.age() < entry.ttl.retain() && path == self.entry_path(&entry.key) =>
+                {
+                    let size = fs::metadata(&path).map_or(0, |meta| meta.len());
+                    loaded.push((entry, size));
+                }
+                Ok(_) => remove_file(&path),
+                Err(err) => {
+                    tracing::warn!(path = %path.display(), error = %err, "Discarding unreadable ISR cache file");
+                    remove_file(&path);
+                }
+            }
+        }
+
+        loaded.sort_by_key(|(entry, _)| std::cmp::Reverse(entry.rendered_at));
+        let mut budget = self.max_bytes;
+        let mut kept = Vec::with_capacity(loaded.len());
+        for (entry, size) in loaded {
+            if size <= budget {
+                budget -= size;
+                kept.push(entry);
+            } else {
+                remove_file(&self.entry_path(&entry.key));
+            }
+        }
+        Ok(kept)
+    }
+
+    /// Record a loaded entry as the on-disk copy of `generation`
+    pub fn track(&self, entry: &StoredEntry, generation: u64) {
+        let path = self.entry_path(&entry.key);
+        let size = fs::metadata(&path).map_or(0, |meta| meta.len());
+        let mut index = self.index.lock();
+        index.total_bytes += size;
+        if let Some(previous) = index.files.insert(
+            file_stem(&entry.key),
+            TrackedFile {
+                generation,
+                size,
+                rendered_at: entry.rendered_at,
+            },
+        ) {
+            index.total_bytes -= previous.size;
+        }
+    }
+
+    /// Announce that `generation` of `key` is about to be written; any
+    /// in-flight write of an older generation will be discarded.
+    pub fn expect(&self, key: &str, generation: u64) {
+        self.index.lock().pending.insert(file_stem(key), generation);
+    }
+
+    /// Write an entry, unless a newer insert or an eviction has superseded it.
+    /// Returns whether the file was committed.
+    pub fn write(&self, entry: &StoredEntry, generation: u64) -> io::Result<bool> {
+        let stem = file_stem(&entry.key);
+        if !self.is_expected(&stem, generation) {
+            return Ok(false);
+        }
+
+        let encoded = encode_entry(entry)?;
+        let size = encoded.len() as u64;
+        if size > self.max_bytes {
+            self.abandon(&stem, generation);
+            return Ok(false);
+        }
+
+        let temp = self
+            .dir
+            .join(format!("{stem}.{generation}.{TEMP_EXTENSION}"));
+        if let Err(err) = write_file(&temp, &encoded) {
+            remove_file(&temp);
+            self.abandon(&stem, generation);
+            return Err(err);
+        }
+
+        // Commit under the lock, so an eviction can't interleave between the
+        // generation check and the rename (removals also delete under it).
+        let mut index = self.index.lock();
+        if index.pending.get(&stem) != Some(&generation) {
+            drop(index);
+            remove_file(&temp);
+            return Ok(false);
+        }
+        index.pending.remove(&stem);
+        if let Err(err) = fs::rename(&temp, self.dir.join(format!("{stem}.{ENTRY_EXTENSION}"))) {
+            drop(index);
+            remove_file(&temp);
+            return Err(err);
+        }
+
+        if let Some(previous) = index.files.insert(
+            stem,
+            TrackedFile {
+                generation,
+                size,
+                rendered_at: entry.rendered_at,
+            },
+        ) {
+            index.total_bytes -= previous.size;
+        }
+        index.total_bytes += size;
+        self.enforce_budget(&mut index);
+        drop(index);
+        Ok(true)
+    }
+
+    /// Forget `generation` of `key`: cancel its pending write, or delete its
+    /// file if that generation is the one on disk.
+    pub fn remove(&self, key: &str, generation: u64) {
+        let stem = file_stem(key);
+        let mut index = self.index.lock();
+        if index.pending.get(&stem) == Some(&generation) {
+            index.pending.remove(&stem);
+        }
+        if index
+            .files
+            .get(&stem)
+            .is_some_and(|file| file.generation == generation)
+            && let Some(file) = index.files.remove(&stem)
+        {
+            index.total_bytes -= file.size;
+            remove_file(&self.dir.join(format!("{stem}.{ENTRY_EXTENSION}")));
+        }
+        drop(index);
+    }
+
+    /// Total bytes of committed entry files
+    pub fn total_bytes(&self) -> u64 {
+        self.index.lock().total_bytes
+    }
+
+    fn is_expected(&self, stem: &str, generation: u64) -> bool {
+        self.index.lock().pending.get(stem) == Some(&generation)
+    }
+
+    fn abandon(&self, stem: &str, generation: u64) {
+        let mut index = self.index.lock();
+        if index.pending.get(stem) == Some(&generation) {
+            index.pending.remove(stem);
+        }
+    }
+
+    /// Delete the oldest renders until the directory fits the budget
+    fn enforce_budget(&self, index: &mut Index) {
+        if index.total_bytes <= self.max_bytes {
+            return;
+        }
+        let mut by_age: Vec<(String, SystemTime, u64)> = index
+            .files
+            .iter()
+            .map(|(stem, file)| (stem.clone(), file.rendered_at, file.size))
+            .collect();
+        by_age.sort_by_key(|(_, rendered_at, _)| *rendered_at);
+
+        for (stem, _, size) in by_age {
+            if index.total_bytes <= self.max_bytes {
+                break;
+            }
+            index.files.remove(&stem);
+            index.total_bytes -= size;
+            remove_file(&self.dir.join(format!("{stem}.{ENTRY_EXTENSION}")));
+        }
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.{ENTRY_EXTENSION}", file_stem(key)))
+    }
+}
+
+fn file_stem(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+fn remove_file(path: &Path) {
+    if let Err(err) = fs::remove_file(path)
+        && err.kind() != io::ErrorKind::NotFound
+    {
+        tracing::warn!(path = %path.display(), error = %err, "Failed to remove ISR cache file");
+    }
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_data()
+}
+
+fn encode_entry(entry: &StoredEntry) -> io::Result<Vec<u8>> {
+    let variants: Vec<(ContentEncoding, &Bytes)> = entry
+        .variants
+        .iter()
+        .map(|(encoding, bytes)| (*encoding, bytes))
+        .collect();
+    let meta = Meta {
+        key: entry.key.clone(),
+        status: entry.status.as_u16(),
+        // Non-UTF-8 header values are dropped rather than mangled
+        headers: entry
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect(),
+        tags: entry.tags.clone(),
+        fresh_ms: entry.ttl.fresh.as_millis() as u64,
+        stale_ms: entry.ttl.stale.as_millis() as u64,
+        stale_if_error_ms: entry.ttl.stale_if_error.as_millis() as u64,
+        rendered_at_ms: entry
+            .rendered_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+            .try_into()
+            .unwrap_or(u64::MAX),
+        body_len: entry.body.len(),
+        variants: variants
+            .iter()
+            .filter_map(|(encoding, bytes)| {
+                Some((encoding_name(*encoding)?.to_string(), bytes.len()))
+            })
+            .collect(),
+    };
+    let meta = serde_json::to_vec(&meta)?;
+    let meta_len = u32::try_from(meta.len()).map_err(io::Error::other)?;
+
+    let mut out = Vec::with_capacity(
+        MAGIC.len()
+            + 4
+            + meta.len()
+            + entry.body.len()
+            + variants.iter().map(|(_, bytes)| bytes.len()).sum::<usize>(),
+    );
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&meta_len.to_le_bytes());
+    out.extend_from_slice(&meta);
+    out.extend_from_slice(&entry.body);
+    for (encoding, bytes) in &variants {
+        if encoding_name(*encoding).is_some() {
+            out.extend_from_slice(bytes);
+        }
+    }
+    Ok(out)
+}
+
+fn read_entry(path: &Path) -> io::Result<StoredEntry> {
+    let data = Bytes::from(fs::read(path)?);
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+    let rest = data
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| invalid("bad magic"))?;
+    let (len, rest) = rest
+        .split_first_chunk::<4>()
+        .ok_or_else(|| invalid("truncated header"))?;
+    let meta_len = u32::from_le_bytes(*len) as usize;
+    let meta_bytes = rest
+        .get(..meta_len)
+        .ok_or_else(|| invalid("truncated header"))?;
+    let meta: Meta = serde_json::from_slice(meta_bytes)?;
+
+    // Slice sections out of the shared buffer rather than copying them
+    let mut offset = MAGIC.len() + 4 + meta_len;
+    let mut section = |len: usize| {
+        let end = offset.checked_add(len).filter(|end| *end <= data.len());
+        let bytes = end.map(|end| data.slice(offset..end));
+        offset += len;
+        bytes.ok_or_else(|| invalid("truncated body"))
+    };
+
+    let body = section(meta.body_len)?;
+    let mut variants = HashMap::with_capacity(meta.variants.len());
+    for (name, len) in &meta.variants {
+        let bytes = section(*len)?;
+        let encoding = parse_encoding(name).ok_or_else(|| invalid("unknown encoding"))?;
+        variants.insert(encoding, bytes);
+    }
+    if offset != data.len() {
+        return Err(invalid("trailing data"));
+    }
+
+    let mut headers = HeaderMap::with_capacity(meta.headers.len());
+    for (name, value) in meta.headers {
+        let name = HeaderName::try_from(name).map_err(io::Error::other)?;
+        let value = HeaderValue::try_from(value).map_err(io::Error::other)?;
+        headers.append(name, value);
+    }
+
+    Ok(StoredEntry {
+        key: meta.key,
+        status: StatusCode::from_u16(meta.status).map_err(io::Error::other)?,
+        headers,
+        body,
+        variants,
+        tags: meta.tags,
+        ttl: CacheTtl {
+            fresh: Duration::from_millis(meta.fresh_ms),
+            stale: Duration::from_millis(meta.stale_ms),
+            stale_if_error: Duration::from_millis(meta.stale_if_error_ms),
+        },
+        rendered_at: UNIX_EPOCH + Duration::from_millis(meta.rendered_at_ms),
+    })
+}
+
+/// Name of a compressed variant; the identity body is stored separately
+fn encoding_name(encoding: ContentEncoding) -> Option<&'static str> {
+    (encoding != ContentEncoding::Identity).then(|| encoding.name())
+}
+
+fn parse_encoding(name: &str) -> Option<ContentEncoding> {
+    ContentEncoding::from_name(name).filter(|encoding| *encoding != ContentEncoding::Identity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(max_bytes: u64) -> DiskStore {
+        let dir = std::env::temp_dir().join(format!("xevion-isr-{}", ulid::Ulid::new()));
+        DiskStore::open(dir, max_bytes).unwrap()
+    }
+
+    fn entry(key: &str, age: Duration) -> StoredEntry {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", HeaderValue::from_static("text/html"));
+        StoredEntry {
+            key: key.to_string(),
+            status: StatusCode::OK,
+            headers,
+            body: Bytes::from(vec![b'a'; 1000]),
+            variants: HashMap::from([(ContentEncoding::Gzip, Bytes::from_static(b"gz"))]),
+            tags: vec!["projects".to_string()],
+            ttl: CacheTtl {
+                fresh: Duration::from_mins(1),
+                stale: Duration::from_mins(5),
+                stale_if_error: Duration::ZERO,
+            },
+            rendered_at: SystemTime::now() - age,
+        }
+    }
+
+    fn write(store: &DiskStore, entry: &StoredEntry, generation: u64) -> bool {
+        store.expect(&entry.key, generation);
+        store.write(entry, generation).unwrap()
+    }
+
+    #[test]
+    fn entries_round_trip_with_their_age() {
+        let store = temp_store(1 << 20);
+        let original = entry("xevion.dev/", Duration::from_secs(30));
+        assert!(write(&store, &original, 1));
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.len(), 1);
+        let restored = &loaded[0];
+        assert_eq!(restored.key, "xevion.dev/");
+        assert_eq!(restored.status, StatusCode::OK);
+        assert_eq!(restored.headers["content-type"], "text/html");
+        assert_eq!(restored.body, original.body);
+        assert_eq!(restored.variants[&ContentEncoding::Gzip], "gz");
+        assert_eq!(restored.tags, ["projects"]);
+        assert_eq!(restored.ttl, original.ttl);
+        assert!(restored.age() >= Duration::from_secs(30));
+        assert!(restored.age() < Duration::from_secs(40));
+    }
+
+    #[test]
+    fn expired_and_corrupt_files_are_discarded() {
+        let store = temp_store(1 << 20);
+        assert!(write(
+            &store,
+            &entry("xevion.dev/old", Duration::from_mins(10)),
+            1
+        ));
+        fs::write(store.dir().join("garbage.isr"), b"not an entry").unwrap();
+        fs::write(store.dir().join("partial.1.tmp"), b"half").unwrap();
+
+        assert!(store.load().unwrap().is_empty());
+        assert_eq!(fs::read_dir(store.dir()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn superseded_and_removed_writes_are_not_committed() {
+        let store = temp_store(1 << 20);
+        let page = entry("xevion.dev/", Duration::ZERO);
+
+        // A newer insert claims the key before the older write lands
+        store.expect(&page.key, 1);
+        store.expect(&page.key, 2);
+        assert!(!store.write(&page, 1).unwrap());
+        assert!(store.write(&page, 2).unwrap());
+
+        // Evicting an older generation leaves the newer file alone
+        store.remove(&page.key, 1);
+        assert_eq!(store.load().unwrap().len(), 1);
+
+        // Invalidation before the write lands cancels it
+        store.expect(&page.key, 3);
+        store.remove(&page.key, 3);
+        assert!(!store.write(&page, 3).unwrap());
+
+        store.remove(&page.key, 2);
+        assert!(store.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn oldest_renders_are_evicted_over_budget() {
+        let one = encode_entry(&entry("xevion.dev/a", Duration::ZERO))
+            .unwrap()
+            .len() as u64;
+        let store = temp_store(one * 2 + one / 2);
+
+        assert!(write(
+            &store,
+            &entry("xevion.dev/a", Duration::from_secs(30)),
+            1
+        ));
+        assert!(write(
+            &store,
+            &entry("xevion.dev/b", Duration::from_secs(20)),
+            2
+        ));
+        assert!(write(
+            &store,
+            &entry("xevion.dev/c", Duration::from_secs(10)),
+            3
+        ));
+        assert!(store.total_bytes() <= one * 2 + one / 2);
+
+        let mut keys: Vec<String> = store
+            .load()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect();
+        keys.sort();
+        assert_eq!(keys, ["xevion.dev/b", "xevion.dev/c"]);
+    }
+}
@@ -0,0 +1,225 @@
+//! ISR cache warming: render pages into the cache before a visitor asks.
+//!
+//! On startup every public page (`/`, `/pgp`, and each public project) is
+//! rendered for every public host, skipping entries that are already fresh
+//! (e.g. restored from disk). Afterwards, every key dropped by an explicit
+//! invalidation is re-rendered once the burst of invalidations from a mutation
+//! settles. Renders go through [`proxy::refresh_cache_entry`] with bounded
+//! concurrency, and progress is reported in the admin stats.
+
+use axum::http::HeaderMap;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use tokio::sync::mpsc;
+
+use crate::{
+    cache,
+    db::{self, CacheWarmStatus, CacheWarmTrigger},
+    proxy,
+    state::AppState,
+};
+
+/// Pages rendered for every host besides the project pages
+const STATIC_PATHS: &[&str] = &["/", "/pgp"];
+
+/// How long to wait for Bun to report healthy before the startup warm
+const STARTUP_HEALTH_TIMEOUT: Duration = Duration::from_mins(1);
+
+#[derive(Debug, Clone)]
+pub struct CacheWarmerConfig {
+    pub enabled: bool,
+    /// Renders in flight at once
+    pub concurrency: usize,
+    /// Quiet period that ends a burst of invalidations
+    pub debounce: Duration,
+}
+
+impl CacheWarmerConfig {
+    pub fn from_env() -> Self {
+        let enabled = std::env::var("ISR_WARM_ENABLED").map_or(true, |v| v != "false" && v != "0");
+
+        let concurrency = std::env::var("ISR_WARM_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(4);
+
+        let debounce_ms = std::env::var("ISR_WARM_DEBOUNCE_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(500);
+
+        Self {
+            enabled,
+            concurrency,
+            debounce: Duration::from_millis(debounce_ms),
+        }
+    }
+}
+
+/// Progress of the current (or last) warm, shared with the stats handler
+#[derive(Default)]
+pub struct CacheWarmer {
+    status: Mutex<CacheWarmStatus>,
+}
+
+impl CacheWarmer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot of the current (or last) warm
+    pub fn status(&self) -> CacheWarmStatus {
+        self.status.lock().clone()
+    }
+
+    fn begin(&self, trigger: CacheWarmTrigger, total: usize) {
+        *self.status.lock() = CacheWarmStatus {
+            running: true,
+            trigger: Some(trigger),
+            total: u32::try_from(total).unwrap_or(u32::MAX),
+            completed: 0,
+            failed: 0,
+            started_at: now_rfc3339(),
+            finished_at: None,
+        };
+    }
+
+    fn record(&self, ok: bool) {
+        let mut status = self.status.lock();
+        status.completed += 1;
+        if !ok {
+            status.failed += 1;
+        }
+    }
+
+    fn finish(&self) -> CacheWarmStatus {
+        let mut status = self.status.lock();
+        status.running = false;
+        status.finished_at = now_rfc3339();
+        status.clone()
+    }
+}
+
+fn now_rfc3339() -> Option<String> {
+    OffsetDateTime::now_utc().format(&Rfc3339).ok()
+}
+
+/// Warm every public page, then re-render invalidated keys until the
+/// invalidation stream closes.
+pub async fn run(
+    state: Arc<AppState>,
+    mut invalidations: mpsc::UnboundedReceiver<String>,
+    config: CacheWarmerConfig,
+) {
+    warm_startup(&state, &config).await;
+
+    while let Some(key) = invalidations.recv().await {
+        let mut keys = BTreeSet::from([key]);
+        // One mutation purges several tags in sequence; collect them all
+        while let Ok(Some(key)) = tokio::time::timeout(config.debounce, invalidations.recv()).await
+        {
+            keys.insert(key);
+        }
+        warm(
+            &state,
+            CacheWarmTrigger::Invalidation,
+            keys,
+            config.concurrency,
+        )
+        .await;
+    }
+}
+
+/// Render every public page for every host, skipping fresh entries
+async fn warm_startup(state: &Arc<AppState>, config: &CacheWarmerConfig) {
+    if !wait_for_downstream(state).await {
+        tracing::warn!("Bun not healthy, skipping startup cache warm");
+        return;
+    }
+
+    let entries = match db::list_sitemap_entries(&state.pool).await {
+        Ok(entries) => entries,
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to list pages for cache warm");
+            return;
+        }
+    };
+
+    let project_paths: Vec<String> = entries
+        .iter()
+        .map(|entry| format!("/projects/{}", entry.slug))
+        .collect();
+    let paths = STATIC_PATHS
+        .iter()
+        .copied()
+        .chain(project_paths.iter().map(String::as_str));
+
+    let mut keys = BTreeSet::new();
+    for path in paths {
+        for host in state.host_config.public_hosts() {
+            // Pages varying on request headers are warmed as their
+            // header-less variant
+            let base_key = cache::cache_key_for_target(&host, path);
+            let key = state.isr_cache.lookup_key(&base_key, &HeaderMap::new());
+            let fresh = state
+                .isr_cache
+                .get(&key)
+                .await
+                .is_some_and(|entry| entry.is_fresh());
+            if !fresh {
+                keys.insert(key);
+            }
+        }
+    }
+
+    warm(state, CacheWarmTrigger::Startup, keys, config.concurrency).await;
+}
+
+/// Poll the health check until Bun answers, up to [`STARTUP_HEALTH_TIMEOUT`]
+async fn wait_for_downstream(state: &AppState) -> bool {
+    let deadline = tokio::time::Instant::now() + STARTUP_HEALTH_TIMEOUT;
+    loop {
+        if state.health_checker.check().await {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+/// Render `keys` into the cache, `concurrency` at a time
+async fn warm(
+    state: &Arc<AppState>,
+    trigger: CacheWarmTrigger,
+    keys: BTreeSet<String>,
+    concurrency: usize,
+) {
+    if keys.is_empty() {
+        return;
+    }
+
+    let warmer = &state.cache_warmer;
+    warmer.begin(trigger, keys.len());
+
+    futures::stream::iter(keys)
+        .for_each_concurrent(concurrency, |key| async move {
+            // A request-triggered refresh already has this key in flight
+            let ok = !state.isr_cache.start_refresh(&key)
+                || proxy::refresh_cache_entry(state.clone(), key.clone()).await;
+            warmer.record(ok);
+        })
+        .await;
+
+    let status = warmer.finish();
+    tracing::info!(
+        trigger = ?trigger,
+        total = status.total,
+        failed = status.failed,
+        "ISR cache warmed"
+    );
+}
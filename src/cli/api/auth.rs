@@ -1,19 +1,23 @@
 //! CLI authentication: a browser device-authorization flow, plus target and
 //! config management.
 //!
-//! `login` starts a request against the target API, opens the approval page in a
-//! browser, and waits on a server-sent-events stream for the admin to confirm.
-//! On approval the server returns a long-lived bearer token, which is saved into
-//! the named target in the config.
+//! `login` starts a request against the target API, opens the approval page
+//! in a browser, and waits on a server-sent-events stream for the admin to
+//! confirm. If the stream fails or stays silent (e.g. a proxy buffers it), it
+//! polls the token endpoint instead. On approval the server returns a
+//! long-lived bearer token, which is saved into the named target in the config.
 
 use futures::StreamExt;
 use time::OffsetDateTime;
 
-use crate::cli::client::{ApiClient, check_response, json as decode_json};
+use crate::cli::client::{ApiClient, check_response, http_client, json as decode_json};
 use crate::cli::config::Config;
 use crate::cli::error::CliError;
 use crate::cli::{TargetsCommand, output};
-use crate::cli_auth::{CliAuthStatus, StartRequest, StartResponse};
+use crate::cli_auth::{
+    CliAuthStatus, StartRequest, StartResponse, TokenError, TokenErrorCode, TokenRequest,
+};
+use crate::handlers::AdminRole;
 
 /// Run the browser device-auth flow and persist the resulting token.
 pub async fn login(
@@ -45,7 +49,7 @@ pub async fn login(
 
     let label = label.or_else(|| gethostname::gethostname().into_string().ok());
 
-    let http = reqwest::Client::new();
+    let http = http_client();
 
     // 1. Start the request.
     let start: StartResponse = decode_json(
@@ -84,12 +88,19 @@ pub async fn login(
         ));
     }
 
-    // 3. Wait on the SSE stream for approval/denial.
+    // 3. Wait for approval/denial.
     let event = wait_for_approval(&http, &base_url, &start).await?;
 
     match event {
-        CliAuthStatus::Approved { token, username } => {
-            config.set_target(&name, base_url.clone(), Some(token));
+        CliAuthStatus::Approved {
+            token,
+            username,
+            expires_at,
+        } => {
+            config.set_target(&name, base_url.clone(), Some(token.clone()));
+            if let Some(expires_at) = expires_at {
+                config.set_token_expiry(&token, expires_at);
+            }
             config.save()?;
             if json {
                 output::print_json(&serde_json::json!({
@@ -116,13 +127,17 @@ pub async fn login(
     }
 }
 
-/// Read the SSE stream until a terminal event arrives or the request expires.
+/// How long the event stream may stay silent before it's assumed to be
+/// buffered. The server sends `pending` as soon as it connects.
+const FIRST_EVENT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Wait on the event stream, or poll the token endpoint if the stream doesn't
+/// work, until a terminal status arrives or the request expires.
 async fn wait_for_approval(
     http: &reqwest::Client,
     base_url: &str,
     start: &StartResponse,
 ) -> Result<CliAuthStatus, CliError> {
-    let events_url = format!("{base_url}/api/auth/device/events/{}", start.request_id);
     // Fall back to the request TTL if the clock skew makes the diff negative.
     #[allow(clippy::duration_suboptimal_units)]
     let timeout = (start.expires_at - OffsetDateTime::now_utc())
@@ -132,45 +147,13 @@ async fn wait_for_approval(
     output::info("Waiting for approval…");
 
     let result = tokio::time::timeout(timeout, async {
-        let response = check_response(
-            http.get(&events_url)
-                .send()
-                .await
-                .map_err(|e| CliError::from_send(events_url.clone(), e))?,
-        )
-        .await?;
-        let mut stream = response.bytes_stream();
-        let mut buffer = String::new();
-
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(|e| CliError::from_send(events_url.clone(), e))?;
-            buffer.push_str(&String::from_utf8_lossy(&chunk));
-
-            // SSE frames are separated by a blank line.
-            while let Some(idx) = buffer.find("\n\n") {
-                let frame = buffer[..idx].to_string();
-                buffer.drain(..idx + 2);
-
-                let data: String = frame
-                    .lines()
-                    .filter_map(|line| line.strip_prefix("data:"))
-                    .map(str::trim_start)
-                    .collect::<Vec<_>>()
-                    .join("\n");
-
-                if data.is_empty() {
-                    continue;
-                }
-
-                let event: CliAuthStatus =
-                    serde_json::from_str(&data).map_err(CliError::invalid)?;
-                if !matches!(event, CliAuthStatus::Pending) {
-                    return Ok::<_, CliError>(event);
-                }
+        match stream_events(http, base_url, start).await {
+            Ok(status) => Ok(status),
+            Err(e) => {
+                output::info(&format!("Event stream unavailable ({e}); polling instead"));
+                poll_token(http, base_url, start).await
             }
         }
-
-        Ok(CliAuthStatus::Pending)
     })
     .await;
 
@@ -178,11 +161,114 @@ async fn wait_for_approval(
     result.unwrap_or(Ok(CliAuthStatus::Pending))
 }
 
+/// Read the SSE stream until a terminal event arrives. Any failure, including
+/// silence past [`FIRST_EVENT_TIMEOUT`] or the stream ending early, is an error
+/// so the caller can fall back to polling.
+async fn stream_events(
+    http: &reqwest::Client,
+    base_url: &str,
+    start: &StartResponse,
+) -> Result<CliAuthStatus, CliError> {
+    let events_url = format!("{base_url}/api/auth/device/events/{}", start.request_id);
+    let response = check_response(
+        http.get(&events_url)
+            .send()
+            .await
+            .map_err(|e| CliError::from_send(events_url.clone(), e))?,
+    )
+    .await?;
+    let mut stream = response.bytes_stream();
+    let mut buffer = String::new();
+    let mut heard = false;
+
+    loop {
+        let next = if heard {
+            stream.next().await
+        } else {
+            tokio::time::timeout(FIRST_EVENT_TIMEOUT, stream.next())
+                .await
+                .map_err(|_| CliError::invalid("no events received"))?
+        };
+        let Some(chunk) = next else {
+            return Err(CliError::invalid("stream ended before a decision"));
+        };
+        let chunk = chunk.map_err(|e| CliError::from_send(events_url.clone(), e))?;
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+        // SSE frames are separated by a blank line.
+        while let Some(idx) = buffer.find("\n\n") {
+            let frame = buffer[..idx].to_string();
+            buffer.drain(..idx + 2);
+
+            let data: String = frame
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(str::trim_start)
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            if data.is_empty() {
+                continue;
+            }
+
+            let event: CliAuthStatus = serde_json::from_str(&data).map_err(CliError::invalid)?;
+            heard = true;
+            if !matches!(event, CliAuthStatus::Pending) {
+                return Ok(event);
+            }
+        }
+    }
+}
+
+/// Poll the token endpoint until the request is approved, denied, or expires
+/// (which reads as `Pending`).
+async fn poll_token(
+    http: &reqwest::Client,
+    base_url: &str,
+    start: &StartResponse,
+) -> Result<CliAuthStatus, CliError> {
+    let token_url = format!("{base_url}/api/auth/device/token");
+    let request = TokenRequest {
+        request_id: start.request_id.clone(),
+    };
+    let mut interval = std::time::Duration::from_secs(start.interval.max(1));
+
+    loop {
+        tokio::time::sleep(interval).await;
+        let response = http
+            .post(&token_url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| CliError::from_send(token_url.clone(), e))?;
+
+        if response.status() != reqwest::StatusCode::BAD_REQUEST {
+            return decode_json(check_response(response).await?).await;
+        }
+        let error: TokenError = decode_json(response).await?;
+        match error.error {
+            TokenErrorCode::AuthorizationPending => {}
+            // RFC 8628: back off by five seconds unless told otherwise
+            TokenErrorCode::SlowDown => {
+                interval = error.interval.map_or(
+                    interval + std::time::Duration::from_secs(5),
+                    std::time::Duration::from_secs,
+                );
+            }
+            TokenErrorCode::AccessDenied => return Ok(CliAuthStatus::Denied),
+            TokenErrorCode::ExpiredToken => return Ok(CliAuthStatus::Pending),
+        }
+    }
+}
+
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SessionResponse {
     authenticated: bool,
     username: String,
+    // Absent on servers that predate roles
+    #[serde(default)]
+    role: Option<AdminRole>,
     session_type: String,
     expires_at: String,
 }
@@ -278,6 +364,7 @@ fn list_targets(config: &Config, json: bool) -> Result<(), CliError> {
                     "url": config.url_for(name),
                     "default": config.default.as_deref() == Some(name),
                     "authorized": config.is_authorized(name),
+                    "expiresAt": config.api.get(name).and_then(|e| e.expires_at.as_deref()),
                 })
             })
             .collect();
@@ -297,8 +384,16 @@ fn list_targets(config: &Config, json: bool) -> Result<(), CliError> {
     for name in config.names() {
         let is_default = config.default.as_deref() == Some(name);
         let marker = if is_default { "*" } else { " " };
+        let expires = config
+            .api
+            .get(name)
+            .and_then(|e| e.expires_at.as_deref())
+            .and_then(|at| at.get(..10));
         let auth = if config.is_authorized(name) {
-            nu_ansi_term::Color::Green.paint("authorized")
+            nu_ansi_term::Color::Green.paint(match expires {
+                Some(date) => format!("authorized until {date}"),
+                None => "authorized".to_string(),
+            })
         } else {
             dim.paint("no token")
         };
@@ -334,6 +429,7 @@ pub async fn session(client: ApiClient, name: &str, json: bool) -> Result<(), Cl
         output::print_json(&serde_json::json!({
             "authenticated": session.authenticated,
             "username": session.username,
+            "role": session.role,
             "sessionType": session.session_type,
             "expiresAt": session.expires_at,
             "target": name,
@@ -343,6 +439,9 @@ pub async fn session(client: ApiClient, name: &str, json: bool) -> Result<(), Cl
         output::print_session(&session.username, &client.api_url);
         let dim = nu_ansi_term::Style::new().dimmed();
         eprintln!("  {} {name}", dim.paint("Target:"));
+        if let Some(role) = session.role {
+            eprintln!("  {} {role}", dim.paint("Role:"));
+        }
         eprintln!("  {} {}", dim.paint("Type:"), session.session_type);
         eprintln!("  {} {}", dim.paint("Expires:"), session.expires_at);
     } else {
@@ -0,0 +1,77 @@
+use crate::cli::CacheCommand;
+use crate::cli::client::{ApiClient, check_response, json as decode_json};
+use crate::cli::error::CliError;
+use crate::cli::output;
+use crate::handlers::{CacheEntryInfo, CachePurgeReport, CachePurgeRequest, CacheStats};
+
+/// Run a cache subcommand
+pub async fn run(client: ApiClient, command: CacheCommand, json: bool) -> Result<(), CliError> {
+    match command {
+        CacheCommand::Ls { host } => list(client, host.as_deref(), json).await,
+        CacheCommand::Purge {
+            target,
+            prefix,
+            glob,
+            all,
+            host,
+        } => {
+            let request = CachePurgeRequest {
+                target,
+                prefix,
+                glob,
+                all,
+                host,
+            };
+            purge(client, &request, json).await
+        }
+        CacheCommand::Stats => stats(client, json).await,
+    }
+}
+
+/// List cached pages, optionally for one host
+async fn list(client: ApiClient, host: Option<&str>, json: bool) -> Result<(), CliError> {
+    let mut entries: Vec<CacheEntryInfo> =
+        decode_json(check_response(client.get("/api/cache").await?).await?).await?;
+    if let Some(host) = host {
+        entries.retain(|entry| entry.host.eq_ignore_ascii_case(host));
+    }
+
+    if json {
+        output::print_json(&entries)?;
+    } else {
+        output::print_cache_table(&entries);
+    }
+
+    Ok(())
+}
+
+/// Drop the selected pages
+async fn purge(client: ApiClient, request: &CachePurgeRequest, json: bool) -> Result<(), CliError> {
+    let report: CachePurgeReport =
+        decode_json(check_response(client.post("/api/cache/purge", request).await?).await?).await?;
+
+    if json {
+        output::print_json(&report)?;
+    } else {
+        for key in &report.purged {
+            println!("{key}");
+        }
+        output::success(&format!("Purged {} cached page(s)", report.purged.len()));
+    }
+
+    Ok(())
+}
+
+/// Show occupancy and counters
+async fn stats(client: ApiClient, json: bool) -> Result<(), CliError> {
+    let stats: CacheStats =
+        decode_json(check_response(client.get("/api/cache/stats").await?).await?).await?;
+
+    if json {
+        output::print_json(&stats)?;
+    } else {
+        output::print_cache_stats(&stats);
+    }
+
+    Ok(())
+}
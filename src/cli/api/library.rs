@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use crate::cli::LibraryCommand;
+use crate::cli::client::{ApiClient, check_response, json as decode_json};
+use crate::cli::error::CliError;
+use crate::cli::output;
+use crate::db::ApiMediaAsset;
+
+use super::media::file_name;
+
+/// Run a library subcommand
+pub async fn run(client: ApiClient, command: LibraryCommand, json: bool) -> Result<(), CliError> {
+    match command {
+        LibraryCommand::List => list(client, json).await,
+        LibraryCommand::Upload { files } => upload(client, files, json).await,
+        LibraryCommand::Show { id } => show(client, &id, json).await,
+        LibraryCommand::Delete { id } => delete(client, &id, json).await,
+    }
+}
+
+/// List every library asset
+async fn list(client: ApiClient, json: bool) -> Result<(), CliError> {
+    let assets: Vec<ApiMediaAsset> =
+        decode_json(check_response(client.get("/api/media").await?).await?).await?;
+
+    if json {
+        output::print_json(&assets)?;
+    } else {
+        output::print_library_table(&assets);
+    }
+
+    Ok(())
+}
+
+/// Upload files to the library, one multipart request each
+async fn upload(client: ApiClient, files: Vec<PathBuf>, json: bool) -> Result<(), CliError> {
+    let mut uploaded = Vec::with_capacity(files.len());
+    for path in &files {
+        let data = tokio::fs::read(path).await.map_err(|source| CliError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        let content_type = mime_guess::from_path(path).first_or_octet_stream();
+
+        let part = reqwest::multipart::Part::bytes(data)
+            .file_name(file_name(path))
+            .mime_str(content_type.essence_str())
+            .map_err(CliError::invalid)?;
+        let form = reqwest::multipart::Form::new().part("file", part);
+
+        let asset: ApiMediaAsset =
+            decode_json(check_response(client.post_multipart("/api/media", form).await?).await?)
+                .await?;
+
+        if !json {
+            output::success(&format!("Uploaded {} ({})", path.display(), asset.id));
+        }
+        uploaded.push(asset);
+    }
+
+    if json {
+        output::print_json(&uploaded)?;
+    }
+
+    Ok(())
+}
+
+/// Show one asset with its references
+async fn show(client: ApiClient, id: &str, json: bool) -> Result<(), CliError> {
+    let asset: ApiMediaAsset =
+        decode_json(check_response(client.get(&format!("/api/media/{id}")).await?).await?).await?;
+
+    if json {
+        output::print_json(&asset)?;
+    } else {
+        output::print_library_asset(&asset);
+    }
+
+    Ok(())
+}
+
+/// Delete an unreferenced asset
+async fn delete(client: ApiClient, id: &str, json: bool) -> Result<(), CliError> {
+    let asset: ApiMediaAsset =
+        decode_json(check_response(client.delete(&format!("/api/media/{id}")).await?).await?)
+            .await?;
+
+    if json {
+        output::print_json(&asset)?;
+    } else {
+        output::success(&format!("Deleted asset {}", asset.id));
+    }
+
+    Ok(())
+}
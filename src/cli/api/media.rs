@@ -0,0 +1,391 @@
+use std::path::{Path, PathBuf};
+
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::cli::MediaCommand;
+use crate::cli::client::{ApiClient, check_response, json as decode_json};
+use crate::cli::error::CliError;
+use crate::cli::output;
+use crate::db::ApiProjectMedia;
+use crate::handlers::{
+    CONTENT_SHA256_HEADER, CreateMediaUploadRequest, MediaUploadStatus, UPLOAD_OFFSET_HEADER,
+};
+
+/// Files at least this large go through the resumable chunked protocol.
+const CHUNKED_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// Attempts per chunk before giving up (and printing a `--resume` hint).
+const MAX_CHUNK_ATTEMPTS: u32 = 4;
+
+/// Run a media subcommand
+pub async fn run(client: ApiClient, command: MediaCommand, json: bool) -> Result<(), CliError> {
+    match command {
+        MediaCommand::List { reference } => list(client, &reference, json).await,
+        MediaCommand::Upload {
+            reference,
+            files,
+            chunked,
+            resume,
+        } => upload(client, &reference, files, chunked, resume, json).await,
+        MediaCommand::Attach {
+            reference,
+            asset_id,
+        } => attach(client, &reference, &asset_id, json).await,
+        MediaCommand::Delete {
+            reference,
+            media_id,
+        } => delete(client, &reference, &media_id, json).await,
+    }
+}
+
+/// List a project's media
+async fn list(client: ApiClient, reference: &str, json: bool) -> Result<(), CliError> {
+    let media: Vec<ApiProjectMedia> = decode_json(
+        check_response(
+            client
+                .get(&format!("/api/projects/{reference}/media"))
+                .await?,
+        )
+        .await?,
+    )
+    .await?;
+
+    if json {
+        output::print_json(&media)?;
+    } else {
+        output::print_media_table(&media);
+    }
+
+    Ok(())
+}
+
+/// Upload one or more files, choosing the chunked protocol for large files
+async fn upload(
+    client: ApiClient,
+    reference: &str,
+    files: Vec<PathBuf>,
+    chunked: bool,
+    resume: Option<String>,
+    json: bool,
+) -> Result<(), CliError> {
+    if resume.is_some() && files.len() != 1 {
+        return Err(CliError::invalid("--resume takes exactly one file"));
+    }
+
+    let mut uploaded = Vec::with_capacity(files.len());
+    for path in &files {
+        let size = tokio::fs::metadata(path)
+            .await
+            .map_err(|source| CliError::Io {
+                path: path.clone(),
+                source,
+            })?
+            .len();
+        let content_type = mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .essence_str()
+            .to_string();
+
+        let media = if chunked || resume.is_some() || size >= CHUNKED_THRESHOLD {
+            upload_chunked(
+                &client,
+                reference,
+                path,
+                size,
+                &content_type,
+                resume.as_deref(),
+                json,
+            )
+            .await?
+        } else {
+            upload_single(&client, reference, path, &content_type).await?
+        };
+
+        if !json {
+            output::success(&format!(
+                "Uploaded {} ({}, {})",
+                path.display(),
+                media.id,
+                status_label(&media)
+            ));
+        }
+        uploaded.push(media);
+    }
+
+    if json {
+        output::print_json(&uploaded)?;
+    }
+
+    Ok(())
+}
+
+/// Human label for a freshly uploaded item's processing state
+const fn status_label(media: &ApiProjectMedia) -> &'static str {
+    match media.status {
+        crate::db::MediaStatus::Ready => "ready",
+        crate::db::MediaStatus::Failed => "failed",
+        crate::db::MediaStatus::Pending | crate::db::MediaStatus::Processing => "processing",
+    }
+}
+
+/// Upload a small file in a single multipart request
+async fn upload_single(
+    client: &ApiClient,
+    reference: &str,
+    path: &Path,
+    content_type: &str,
+) -> Result<ApiProjectMedia, CliError> {
+    let data = tokio::fs::read(path).await.map_err(|source| CliError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let part = reqwest::multipart::Part::bytes(data)
+        .file_name(file_name(path))
+        .mime_str(content_type)
+        .map_err(CliError::invalid)?;
+    let form = reqwest::multipart::Form::new().part("file", part);
+
+    decode_json(
+        check_response(
+            client
+                .post_multipart(&format!("/api/projects/{reference}/media"), form)
+                .await?,
+        )
+        .await?,
+    )
+    .await
+}
+
+/// Upload a file chunk by chunk, retrying dropped chunks from the server's offset
+async fn upload_chunked(
+    client: &ApiClient,
+    reference: &str,
+    path: &Path,
+    size: u64,
+    content_type: &str,
+    resume: Option<&str>,
+    quiet: bool,
+) -> Result<ApiProjectMedia, CliError> {
+    let io_err = |source| CliError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let uploads_path = format!("/api/projects/{reference}/media/uploads");
+
+    let mut status: MediaUploadStatus = if let Some(upload_id) = resume {
+        decode_json(
+            check_response(client.get(&format!("{uploads_path}/{upload_id}")).await?).await?,
+        )
+        .await?
+    } else {
+        let request = CreateMediaUploadRequest {
+            filename: file_name(path),
+            content_type: content_type.to_string(),
+            size,
+            sha256: file_sha256(path).await.map_err(io_err)?,
+        };
+        decode_json(check_response(client.post(&uploads_path, &request).await?).await?).await?
+    };
+
+    if status.size != size {
+        return Err(CliError::invalid(format!(
+            "upload {} expects {} bytes but {} is {size} bytes",
+            status.upload_id,
+            status.size,
+            path.display()
+        )));
+    }
+
+    let upload_path = format!("{uploads_path}/{}", status.upload_id);
+    let mut file = tokio::fs::File::open(path).await.map_err(io_err)?;
+    let mut attempts = 0;
+
+    while status.offset < status.size {
+        let len = status.chunk_size.min(status.size - status.offset);
+        let mut chunk = vec![0; len as usize];
+        file.seek(std::io::SeekFrom::Start(status.offset))
+            .await
+            .map_err(io_err)?;
+        file.read_exact(&mut chunk).await.map_err(io_err)?;
+
+        let headers = [
+            (UPLOAD_OFFSET_HEADER, status.offset.to_string()),
+            (CONTENT_SHA256_HEADER, hex::encode(Sha256::digest(&chunk))),
+        ];
+
+        let result = match client.patch_bytes(&upload_path, chunk, &headers).await {
+            Ok(response) => check_response(response).await,
+            Err(err) => Err(err),
+        };
+
+        match result {
+            Ok(response) => {
+                status = decode_json(response).await?;
+                attempts = 0;
+                if !quiet {
+                    output::info(&format!(
+                        "{}: {}% ({} / {} bytes)",
+                        path.display(),
+                        status.offset * 100 / status.size,
+                        status.offset,
+                        status.size
+                    ));
+                }
+            }
+            Err(err) if is_retryable(&err) && attempts + 1 < MAX_CHUNK_ATTEMPTS => {
+                attempts += 1;
+                output::info(&format!(
+                    "chunk at offset {} failed ({err}); retrying",
+                    status.offset
+                ));
+                tokio::time::sleep(std::time::Duration::from_secs(u64::from(attempts))).await;
+                // Re-sync: the server may have committed the chunk before the
+                // connection dropped.
+                if let Ok(response) = client.get(&upload_path).await
+                    && let Ok(response) = check_response(response).await
+                {
+                    status = decode_json(response).await?;
+                }
+            }
+            Err(err) => {
+                output::error(&format!(
+                    "upload interrupted; resume with: xevion media upload {reference} {} --resume {}",
+                    path.display(),
+                    status.upload_id
+                ));
+                return Err(err);
+            }
+        }
+    }
+
+    decode_json(
+        check_response(
+            client
+                .post(&format!("{upload_path}/complete"), &serde_json::json!({}))
+                .await?,
+        )
+        .await?,
+    )
+    .await
+}
+
+/// Transport failures, server errors, and offset conflicts are worth retrying
+fn is_retryable(err: &CliError) -> bool {
+    match err {
+        CliError::Connect { .. } | CliError::Request { .. } => true,
+        CliError::Http { status, .. } => {
+            status.is_server_error() || *status == StatusCode::CONFLICT
+        }
+        _ => false,
+    }
+}
+
+/// Hex SHA-256 of a file, streamed
+async fn file_sha256(path: &Path) -> std::io::Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+pub(super) fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || "upload".to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    )
+}
+
+/// Attach an existing library asset to a project
+async fn attach(
+    client: ApiClient,
+    reference: &str,
+    asset_id: &str,
+    json: bool,
+) -> Result<(), CliError> {
+    let media: ApiProjectMedia = decode_json(
+        check_response(
+            client
+                .post(
+                    &format!("/api/projects/{reference}/media/attach"),
+                    &serde_json::json!({ "assetId": asset_id }),
+                )
+                .await?,
+        )
+        .await?,
+    )
+    .await?;
+
+    if json {
+        output::print_json(&media)?;
+    } else {
+        output::success(&format!("Attached asset {asset_id} as media {}", media.id));
+    }
+
+    Ok(())
+}
+
+/// Detach a media item from a project
+async fn delete(
+    client: ApiClient,
+    reference: &str,
+    media_id: &str,
+    json: bool,
+) -> Result<(), CliError> {
+    let media: ApiProjectMedia = decode_json(
+        check_response(
+            client
+                .delete(&format!("/api/projects/{reference}/media/{media_id}"))
+                .await?,
+        )
+        .await?,
+    )
+    .await?;
+
+    if json {
+        output::print_json(&media)?;
+    } else {
+        output::success(&format!("Detached media {}", media.id));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_transient_http_errors_only() {
+        let http = |status| CliError::Http {
+            status,
+            code: None,
+            message: String::new(),
+        };
+        assert!(is_retryable(&http(StatusCode::BAD_GATEWAY)));
+        assert!(is_retryable(&http(StatusCode::CONFLICT)));
+        assert!(!is_retryable(&http(StatusCode::BAD_REQUEST)));
+        assert!(!is_retryable(&CliError::Unauthorized));
+    }
+
+    #[tokio::test]
+    async fn file_sha256_matches_in_memory_digest() {
+        let path = std::env::temp_dir().join(format!("xevion-sha-{}", std::process::id()));
+        let data = vec![7u8; 3 * 1024 * 1024 + 5];
+        std::fs::write(&path, &data).unwrap();
+
+        let digest = file_sha256(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(digest, hex::encode(Sha256::digest(&data)));
+    }
+}
@@ -1,8 +1,15 @@
 pub mod auth;
+pub mod cache;
 pub mod content;
+pub mod library;
+pub mod media;
 pub mod projects;
+pub mod sessions;
 pub mod settings;
+pub mod storage;
 pub mod tags;
+pub mod tokens;
+pub mod users;
 
 use crate::cli::client::ApiClient;
 use crate::cli::config::Config;
@@ -40,20 +47,91 @@ pub async fn run(args: ApiArgs) -> Result<(), CliError> {
         ApiCommand::Session => {
             let (name, entry) = config.resolve(api)?;
             let client = ApiClient::new(entry.url, entry.token);
-            auth::session(client, &name, json).await
+            with_client(config, client, |client| async move {
+                auth::session(client, &name, json).await
+            })
+            .await
         }
 
         // Data commands all need an authenticated client.
-        ApiCommand::Projects(cmd) => projects::run(authed_client(&config, api)?, cmd, json).await,
-        ApiCommand::Tags(cmd) => tags::run(authed_client(&config, api)?, cmd, json).await,
-        ApiCommand::Settings(cmd) => settings::run(authed_client(&config, api)?, cmd, json).await,
+        ApiCommand::Projects(cmd) => {
+            let client = authed_client(&config, api)?;
+            with_client(config, client, |c| projects::run(c, cmd, json)).await
+        }
+        ApiCommand::Tags(cmd) => {
+            let client = authed_client(&config, api)?;
+            with_client(config, client, |c| tags::run(c, cmd, json)).await
+        }
+        ApiCommand::Settings(cmd) => {
+            let client = authed_client(&config, api)?;
+            with_client(config, client, |c| settings::run(c, cmd, json)).await
+        }
+        ApiCommand::Media(cmd) => {
+            let client = authed_client(&config, api)?;
+            with_client(config, client, |c| media::run(c, cmd, json)).await
+        }
+        ApiCommand::Library(cmd) => {
+            let client = authed_client(&config, api)?;
+            with_client(config, client, |c| library::run(c, cmd, json)).await
+        }
+        ApiCommand::Storage(cmd) => {
+            let client = authed_client(&config, api)?;
+            with_client(config, client, |c| storage::run(c, cmd, json)).await
+        }
+        ApiCommand::Cache(cmd) => {
+            let client = authed_client(&config, api)?;
+            with_client(config, client, |c| cache::run(c, cmd, json)).await
+        }
+        ApiCommand::Users(cmd) => {
+            let client = authed_client(&config, api)?;
+            with_client(config, client, |c| users::run(c, cmd, json)).await
+        }
+        ApiCommand::Tokens(cmd) => {
+            let client = authed_client(&config, api)?;
+            with_client(config, client, |c| tokens::run(c, cmd, json)).await
+        }
+        ApiCommand::Sessions(cmd) => {
+            let client = authed_client(&config, api)?;
+            with_client(config, client, |c| sessions::run(c, cmd, json)).await
+        }
     }
 }
 
+/// Run a command with `client`, then save the new expiry if the server renewed
+/// the session along the way, so the config shows when the token lapses.
+async fn with_client<F, Fut>(
+    mut config: Config,
+    client: ApiClient,
+    command: F,
+) -> Result<(), CliError>
+where
+    F: FnOnce(ApiClient) -> Fut,
+    Fut: Future<Output = Result<(), CliError>>,
+{
+    let token = client.token().map(str::to_string);
+    let renewed = client.renewed_expiry();
+    let result = command(client).await;
+    if let (Some(token), Some(expires_at)) = (token, renewed.take())
+        && config.set_token_expiry(&token, expires_at)
+    {
+        config.save()?;
+    }
+    result
+}
+
 /// Resolve the selected target and build an authenticated client. `config.resolve`
 /// guarantees a token, so the resulting client is always authorized — there is no
 /// second client-side auth check.
+///
+/// Without `--api`, `XEVION_URL` plus `XEVION_TOKEN` (an API token from
+/// `xevion tokens create`) stand in for a configured target, so CI jobs need
+/// no config file.
 pub fn authed_client(config: &Config, api: Option<&str>) -> Result<ApiClient, CliError> {
+    if api.is_none()
+        && let (Ok(url), Ok(token)) = (std::env::var("XEVION_URL"), std::env::var("XEVION_TOKEN"))
+    {
+        return Ok(ApiClient::new(url, Some(token)));
+    }
     let (_, entry) = config.resolve(api)?;
     Ok(ApiClient::new(entry.url, entry.token))
 }
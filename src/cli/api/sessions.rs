@@ -0,0 +1,57 @@
+use crate::cli::SessionsCommand;
+use crate::cli::client::{ApiClient, check_response, json as decode_json};
+use crate::cli::error::CliError;
+use crate::cli::output;
+use crate::handlers::ApiSession;
+
+/// Run a sessions subcommand
+pub async fn run(client: ApiClient, command: SessionsCommand, json: bool) -> Result<(), CliError> {
+    match command {
+        SessionsCommand::List => list(client, json).await,
+        SessionsCommand::Revoke { id } => revoke(client, &id, json).await,
+        SessionsCommand::RevokeOthers => revoke_others(client, json).await,
+    }
+}
+
+async fn list(client: ApiClient, json: bool) -> Result<(), CliError> {
+    let sessions: Vec<ApiSession> =
+        decode_json(check_response(client.get("/api/sessions").await?).await?).await?;
+
+    if json {
+        output::print_json(&sessions)?;
+    } else {
+        output::print_sessions_table(&sessions);
+    }
+
+    Ok(())
+}
+
+async fn revoke(client: ApiClient, id: &str, json: bool) -> Result<(), CliError> {
+    check_response(client.delete(&format!("/api/sessions/{id}")).await?).await?;
+
+    if json {
+        output::print_json(&serde_json::json!({ "id": id, "revoked": true }))?;
+    } else {
+        output::success(&format!("Revoked session {id}"));
+    }
+
+    Ok(())
+}
+
+#[derive(serde::Deserialize)]
+struct RevokeOthersResponse {
+    revoked: u64,
+}
+
+async fn revoke_others(client: ApiClient, json: bool) -> Result<(), CliError> {
+    let response: RevokeOthersResponse =
+        decode_json(check_response(client.delete("/api/sessions").await?).await?).await?;
+
+    if json {
+        output::print_json(&serde_json::json!({ "revoked": response.revoked }))?;
+    } else {
+        output::success(&format!("Revoked {} other session(s)", response.revoked));
+    }
+
+    Ok(())
+}
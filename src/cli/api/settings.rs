@@ -13,7 +13,21 @@ pub async fn run(client: ApiClient, command: SettingsCommand, json: bool) -> Res
             occupation,
             bio,
             site_title,
-        } => update(client, display_name, occupation, bio, site_title, json).await,
+            avatar,
+            favicon,
+        } => {
+            update(
+                client,
+                display_name,
+                occupation,
+                bio,
+                site_title,
+                avatar,
+                favicon,
+                json,
+            )
+            .await
+        }
     }
 }
 
@@ -32,12 +46,15 @@ async fn get(client: ApiClient, json: bool) -> Result<(), CliError> {
 }
 
 /// Update site settings
+#[allow(clippy::too_many_arguments)]
 async fn update(
     client: ApiClient,
     display_name: Option<String>,
     occupation: Option<String>,
     bio: Option<String>,
     site_title: Option<String>,
+    avatar: Option<String>,
+    favicon: Option<String>,
     json: bool,
 ) -> Result<(), CliError> {
     // First fetch current settings
@@ -51,6 +68,8 @@ async fn update(
             occupation: occupation.unwrap_or(current.identity.occupation),
             bio: bio.unwrap_or(current.identity.bio),
             site_title: site_title.unwrap_or(current.identity.site_title),
+            avatar_media_id: media_slot(avatar, current.identity.avatar_media_id)?,
+            favicon_media_id: media_slot(favicon, current.identity.favicon_media_id)?,
         },
         // Keep existing social links unchanged
         social_links: current
@@ -80,3 +99,18 @@ async fn update(
 
     Ok(())
 }
+
+/// Resolve a settings media slot: the flag if given ("none" clears it),
+/// otherwise the current value echoed back unchanged
+fn media_slot(
+    flag: Option<String>,
+    current: Option<String>,
+) -> Result<Option<uuid::Uuid>, CliError> {
+    match flag.or(current).as_deref() {
+        None | Some("none") => Ok(None),
+        Some(id) => id
+            .parse()
+            .map(Some)
+            .map_err(|_| CliError::invalid(format!("invalid media ID '{id}'"))),
+    }
+}
@@ -0,0 +1,33 @@
+use crate::cli::StorageCommand;
+use crate::cli::client::{ApiClient, check_response, json as decode_json};
+use crate::cli::error::CliError;
+use crate::cli::output;
+use crate::handlers::{StorageGcReport, StorageGcRequest};
+
+/// Run a storage subcommand
+pub async fn run(client: ApiClient, command: StorageCommand, json: bool) -> Result<(), CliError> {
+    match command {
+        StorageCommand::Gc { apply } => gc(client, apply, json).await,
+    }
+}
+
+/// Reconcile storage against the database
+async fn gc(client: ApiClient, apply: bool, json: bool) -> Result<(), CliError> {
+    let report: StorageGcReport = decode_json(
+        check_response(
+            client
+                .post("/api/storage/gc", &StorageGcRequest { apply })
+                .await?,
+        )
+        .await?,
+    )
+    .await?;
+
+    if json {
+        output::print_json(&report)?;
+    } else {
+        output::print_storage_gc_report(&report);
+    }
+
+    Ok(())
+}
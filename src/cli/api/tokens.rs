@@ -0,0 +1,74 @@
+use crate::api_tokens::{ApiToken, CreateApiTokenRequest, CreatedApiToken};
+use crate::cli::TokensCommand;
+use crate::cli::client::{ApiClient, check_response, json as decode_json};
+use crate::cli::error::CliError;
+use crate::cli::output;
+
+/// Run a tokens subcommand
+pub async fn run(client: ApiClient, command: TokensCommand, json: bool) -> Result<(), CliError> {
+    match command {
+        TokensCommand::List => list(client, json).await,
+        TokensCommand::Create {
+            name,
+            scopes,
+            projects,
+            days,
+        } => {
+            let request = CreateApiTokenRequest {
+                name,
+                scopes,
+                projects,
+                expires_in_days: Some(days),
+            };
+            create(client, &request, json).await
+        }
+        TokensCommand::Revoke { id } => revoke(client, &id, json).await,
+    }
+}
+
+async fn list(client: ApiClient, json: bool) -> Result<(), CliError> {
+    let tokens: Vec<ApiToken> =
+        decode_json(check_response(client.get("/api/tokens").await?).await?).await?;
+
+    if json {
+        output::print_json(&tokens)?;
+    } else {
+        output::print_tokens_table(&tokens);
+    }
+
+    Ok(())
+}
+
+/// Create a token. The secret goes to stdout alone so it can be piped straight
+/// into a CI secret store; everything else is stderr.
+async fn create(
+    client: ApiClient,
+    request: &CreateApiTokenRequest,
+    json: bool,
+) -> Result<(), CliError> {
+    let created: CreatedApiToken =
+        decode_json(check_response(client.post("/api/tokens", request).await?).await?).await?;
+
+    if json {
+        output::print_json(&created)?;
+    } else {
+        output::success(&format!("Created API token {}", created.info.name));
+        output::print_token(&created.info);
+        output::info("Copy the token now; it won't be shown again");
+        println!("{}", created.token);
+    }
+
+    Ok(())
+}
+
+async fn revoke(client: ApiClient, id: &str, json: bool) -> Result<(), CliError> {
+    check_response(client.delete(&format!("/api/tokens/{id}")).await?).await?;
+
+    if json {
+        output::print_json(&serde_json::json!({ "id": id, "revoked": true }))?;
+    } else {
+        output::success(&format!("Revoked API token {id}"));
+    }
+
+    Ok(())
+}
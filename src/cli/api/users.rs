@@ -0,0 +1,130 @@
+use std::io::Read;
+
+use crate::cli::UsersCommand;
+use crate::cli::client::{ApiClient, check_response, json as decode_json};
+use crate::cli::error::CliError;
+use crate::cli::output;
+use crate::handlers::{ApiAdminUser, CreateAdminUserRequest, UpdateAdminUserRequest};
+
+/// Run a users subcommand
+pub async fn run(client: ApiClient, command: UsersCommand, json: bool) -> Result<(), CliError> {
+    match command {
+        UsersCommand::List => list(client, json).await,
+        UsersCommand::Create {
+            username,
+            role,
+            password,
+            password_stdin,
+        } => {
+            let password = if password_stdin {
+                read_password()?
+            } else {
+                password.unwrap_or_default()
+            };
+            let request = CreateAdminUserRequest {
+                username,
+                password,
+                role: role.into(),
+            };
+            create(client, &request, json).await
+        }
+        UsersCommand::SetRole { user, role } => {
+            let request = UpdateAdminUserRequest { role: role.into() };
+            set_role(client, &user, &request, json).await
+        }
+        UsersCommand::Delete { user } => delete(client, &user, json).await,
+    }
+}
+
+/// Read a password from stdin, dropping the trailing newline `echo` adds.
+pub fn read_password() -> Result<String, CliError> {
+    let mut buf = String::new();
+    std::io::stdin()
+        .read_to_string(&mut buf)
+        .map_err(|source| CliError::Io {
+            path: "<stdin>".into(),
+            source,
+        })?;
+    Ok(buf.trim_end_matches(['\r', '\n']).to_string())
+}
+
+async fn fetch_users(client: &ApiClient) -> Result<Vec<ApiAdminUser>, CliError> {
+    decode_json(check_response(client.get("/api/users").await?).await?).await
+}
+
+/// Resolve a username or numeric ID to a user.
+async fn resolve(client: &ApiClient, user: &str) -> Result<ApiAdminUser, CliError> {
+    let users = fetch_users(client).await?;
+    users
+        .into_iter()
+        .find(|u| u.username == user || user.parse() == Ok(u.id))
+        .ok_or_else(|| CliError::invalid(format!("no user '{user}'")))
+}
+
+async fn list(client: ApiClient, json: bool) -> Result<(), CliError> {
+    let users = fetch_users(&client).await?;
+
+    if json {
+        output::print_json(&users)?;
+    } else {
+        output::print_users_table(&users);
+    }
+
+    Ok(())
+}
+
+async fn create(
+    client: ApiClient,
+    request: &CreateAdminUserRequest,
+    json: bool,
+) -> Result<(), CliError> {
+    let user: ApiAdminUser =
+        decode_json(check_response(client.post("/api/users", request).await?).await?).await?;
+
+    if json {
+        output::print_json(&user)?;
+    } else {
+        output::success(&format!("Created {} {}", user.role, user.username));
+    }
+
+    Ok(())
+}
+
+async fn set_role(
+    client: ApiClient,
+    user: &str,
+    request: &UpdateAdminUserRequest,
+    json: bool,
+) -> Result<(), CliError> {
+    let target = resolve(&client, user).await?;
+    let updated: ApiAdminUser = decode_json(
+        check_response(
+            client
+                .put(&format!("/api/users/{}", target.id), request)
+                .await?,
+        )
+        .await?,
+    )
+    .await?;
+
+    if json {
+        output::print_json(&updated)?;
+    } else {
+        output::success(&format!("{} is now {}", updated.username, updated.role));
+    }
+
+    Ok(())
+}
+
+async fn delete(client: ApiClient, user: &str, json: bool) -> Result<(), CliError> {
+    let target = resolve(&client, user).await?;
+    check_response(client.delete(&format!("/api/users/{}", target.id)).await?).await?;
+
+    if json {
+        output::print_json(&target)?;
+    } else {
+        output::success(&format!("Deleted user: {}", target.username));
+    }
+
+    Ok(())
+}
@@ -1,8 +1,20 @@
+use parking_lot::Mutex;
 use reqwest::{Client, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
+use std::sync::Arc;
 
 use crate::cli::error::{CliError, DecodeSnafu};
+use crate::handlers::SESSION_EXPIRES_HEADER;
+
+/// An HTTP client that identifies itself as `xevion/<version>`, which the
+/// server's sessions list shows as the device.
+pub(crate) fn http_client() -> Client {
+    Client::builder()
+        .user_agent(concat!("xevion/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default()
+}
 
 /// API client that authenticates with a long-lived CLI bearer token.
 ///
@@ -14,6 +26,19 @@ pub struct ApiClient {
     pub api_url: String,
     token: Option<String>,
     client: Client,
+    renewed: RenewedExpiry,
+}
+
+/// The new expiry a server announced for the client's session (see
+/// [`SESSION_EXPIRES_HEADER`]). Shared, so it can be read once the client that
+/// saw it is gone.
+#[derive(Debug, Clone, Default)]
+pub struct RenewedExpiry(Arc<Mutex<Option<String>>>);
+
+impl RenewedExpiry {
+    pub fn take(&self) -> Option<String> {
+        self.0.lock().take()
+    }
 }
 
 impl ApiClient {
@@ -24,10 +49,20 @@ impl ApiClient {
             // Normalize away a trailing slash so `url()` joins cleanly.
             api_url: api_url.trim_end_matches('/').to_string(),
             token,
-            client: Client::new(),
+            client: http_client(),
+            renewed: RenewedExpiry::default(),
         }
     }
 
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Handle to the session expiry the server reports when it renews the token.
+    pub fn renewed_expiry(&self) -> RenewedExpiry {
+        self.renewed.clone()
+    }
+
     /// Build the full URL for an endpoint.
     fn url(&self, path: &str) -> String {
         format!("{}{}", self.api_url, path)
@@ -43,16 +78,24 @@ impl ApiClient {
     }
 
     /// Send a request, mapping transport failures to a connect/request diagnostic
-    /// that names the URL.
+    /// that names the URL, and noting any session renewal.
     async fn send(
         &self,
         request: reqwest::RequestBuilder,
         url: String,
     ) -> Result<Response, CliError> {
-        request
+        let response = request
             .send()
             .await
-            .map_err(|e| CliError::from_send(url, e))
+            .map_err(|e| CliError::from_send(url, e))?;
+        if let Some(expires_at) = response
+            .headers()
+            .get(SESSION_EXPIRES_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.renewed.0.lock() = Some(expires_at.to_string());
+        }
+        Ok(response)
     }
 
     pub async fn get(&self, path: &str) -> Result<Response, CliError> {
@@ -78,6 +121,35 @@ impl ApiClient {
             .await
     }
 
+    pub async fn post_multipart(
+        &self,
+        path: &str,
+        form: reqwest::multipart::Form,
+    ) -> Result<Response, CliError> {
+        let url = self.url(path);
+        self.send(self.authed(self.client.post(&url).multipart(form)), url)
+            .await
+    }
+
+    /// PATCH a raw binary body with extra headers (chunked upload parts).
+    pub async fn patch_bytes(
+        &self,
+        path: &str,
+        body: Vec<u8>,
+        headers: &[(&str, String)],
+    ) -> Result<Response, CliError> {
+        let url = self.url(path);
+        let mut request = self
+            .client
+            .patch(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .body(body);
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        self.send(self.authed(request), url).await
+    }
+
     pub async fn delete(&self, path: &str) -> Result<Response, CliError> {
         let url = self.url(path);
         self.send(self.authed(self.client.delete(&url)), url).await
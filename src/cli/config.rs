@@ -80,6 +80,9 @@ pub struct ApiEntry {
     pub url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token: Option<String>,
+    /// When the token expires (RFC 3339), as last reported by the server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -237,7 +240,14 @@ impl Config {
 
     /// Upsert a target's URL and token, making it the default if none is set.
     pub fn set_target(&mut self, name: &str, url: String, token: Option<String>) {
-        self.api.insert(name.to_string(), ApiEntry { url, token });
+        self.api.insert(
+            name.to_string(),
+            ApiEntry {
+                url,
+                token,
+                expires_at: None,
+            },
+        );
         if self.default.is_none() {
             self.default = Some(name.to_string());
         }
@@ -296,8 +306,23 @@ impl Config {
     pub fn clear_token(&mut self, name: &str) {
         if let Some(entry) = self.api.get_mut(name) {
             entry.token = None;
+            entry.expires_at = None;
         }
     }
+
+    /// Record a new expiry for `token` on whichever target holds it. Returns
+    /// `false` when none does (e.g. the token came from `XEVION_TOKEN`).
+    pub fn set_token_expiry(&mut self, token: &str, expires_at: String) -> bool {
+        let Some(entry) = self
+            .api
+            .values_mut()
+            .find(|e| e.token.as_deref() == Some(token))
+        else {
+            return false;
+        };
+        entry.expires_at = Some(expires_at);
+        true
+    }
 }
 
 #[cfg(test)]
@@ -372,6 +397,24 @@ mod tests {
         assert!(config.resolve(Some("local")).is_err());
     }
 
+    #[test]
+    fn token_expiry_lands_on_the_matching_target() {
+        let mut config = Config::default();
+        config.set_target("local", "http://localhost:10237".into(), Some("t1".into()));
+        config.set_target("prod", "https://xevion.dev".into(), Some("t2".into()));
+
+        assert!(config.set_token_expiry("t2", "2027-01-01T00:00:00Z".into()));
+        assert_eq!(
+            config.api["prod"].expires_at.as_deref(),
+            Some("2027-01-01T00:00:00Z")
+        );
+        assert!(config.api["local"].expires_at.is_none());
+        assert!(!config.set_token_expiry("other", "2027-01-01T00:00:00Z".into()));
+
+        config.clear_token("prod");
+        assert!(config.api["prod"].expires_at.is_none());
+    }
+
     #[test]
     fn set_default_requires_existing_target() {
         let mut config = Config::default();
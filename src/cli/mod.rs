@@ -7,8 +7,11 @@ pub mod client;
 pub mod config;
 pub mod error;
 pub mod output;
-// `serve` (the web server) and `seed` (DB seeding) are the server entry points,
-// invoked from the `xevion-server` binary; the client CLI omits them.
+// `serve` (the web server), `seed` (DB seeding), and `reset_password` (account
+// recovery) are the server entry points, invoked from the `xevion-server`
+// binary; the client CLI omits them.
+#[cfg(feature = "server")]
+pub mod reset_password;
 #[cfg(feature = "server")]
 pub mod seed;
 #[cfg(feature = "server")]
@@ -16,7 +19,9 @@ pub mod serve;
 
 use clap::{Parser, Subcommand, ValueEnum};
 
+use crate::api_tokens::{ApiScope, DEFAULT_TOKEN_DAYS};
 use crate::db::ProjectStatus;
+use crate::handlers::AdminRole;
 
 /// `xevion` — API client for managing xevion.dev content remotely.
 #[derive(Parser, Debug)]
@@ -85,6 +90,34 @@ pub enum ApiCommand {
     /// Site settings management
     #[command(subcommand)]
     Settings(SettingsCommand),
+
+    /// Project media (images and videos)
+    #[command(subcommand)]
+    Media(MediaCommand),
+
+    /// Shared media library
+    #[command(subcommand)]
+    Library(LibraryCommand),
+
+    /// Media storage maintenance
+    #[command(subcommand)]
+    Storage(StorageCommand),
+
+    /// Inspect and purge the ISR page cache
+    #[command(subcommand)]
+    Cache(CacheCommand),
+
+    /// Admin users and their roles (owners only)
+    #[command(subcommand)]
+    Users(UsersCommand),
+
+    /// Scoped API tokens for CI and other automation
+    #[command(subcommand)]
+    Tokens(TokensCommand),
+
+    /// Your signed-in sessions, CLI logins, and API tokens (owners only)
+    #[command(subcommand)]
+    Sessions(SessionsCommand),
 }
 
 #[derive(Subcommand, Debug)]
@@ -147,6 +180,29 @@ impl From<StatusArg> for ProjectStatus {
     }
 }
 
+/// Admin role, validated at parse time and mirrored to
+/// [`AdminRole`](crate::handlers::AdminRole).
+#[derive(Copy, Clone, Debug, ValueEnum)]
+#[value(rename_all = "lowercase")]
+pub enum RoleArg {
+    /// Everything, including settings, sessions, and users
+    Owner,
+    /// Projects, tags, media, and content
+    Editor,
+    /// Read-only
+    Viewer,
+}
+
+impl From<RoleArg> for AdminRole {
+    fn from(arg: RoleArg) -> Self {
+        match arg {
+            RoleArg::Owner => Self::Owner,
+            RoleArg::Editor => Self::Editor,
+            RoleArg::Viewer => Self::Viewer,
+        }
+    }
+}
+
 /// clap `value_parser` for hex colors: accepts `#6366F1` or `6366F1`, returns the
 /// canonical bare lowercase form. Shared by `--color` (tags) and `--accent`
 /// (projects) so both flags behave identically.
@@ -493,7 +549,232 @@ pub enum SettingsCommand {
         /// Site title
         #[arg(long)]
         site_title: Option<String>,
+
+        /// Library media UUID to use as the avatar ("none" to clear)
+        #[arg(long)]
+        avatar: Option<String>,
+
+        /// Library media UUID to use as the favicon ("none" to clear)
+        #[arg(long)]
+        favicon: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MediaCommand {
+    /// List a project's media
+    List {
+        /// Project slug or UUID
+        #[arg(name = "ref")]
+        reference: String,
+    },
+
+    /// Upload image/video files; large files use resumable chunked uploads
+    Upload {
+        /// Project slug or UUID
+        #[arg(name = "ref")]
+        reference: String,
+
+        /// Files to upload
+        #[arg(required = true)]
+        files: Vec<std::path::PathBuf>,
+
+        /// Always use the chunked protocol, regardless of file size
+        #[arg(long)]
+        chunked: bool,
+
+        /// Resume an interrupted chunked upload by ID (single file only)
+        #[arg(long, conflicts_with = "chunked")]
+        resume: Option<String>,
+    },
+
+    /// Attach an existing library asset to a project
+    Attach {
+        /// Project slug or UUID
+        #[arg(name = "ref")]
+        reference: String,
+
+        /// Library asset UUID
+        asset_id: String,
+    },
+
+    /// Detach a media item (its asset is deleted once nothing else uses it)
+    Delete {
+        /// Project slug or UUID
+        #[arg(name = "ref")]
+        reference: String,
+
+        /// Media UUID
+        media_id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LibraryCommand {
+    /// List library assets with their reference counts
+    List,
+
+    /// Upload image/video files to the library without attaching them
+    Upload {
+        /// Files to upload
+        #[arg(required = true)]
+        files: Vec<std::path::PathBuf>,
     },
+
+    /// Show an asset and everything that references it
+    Show {
+        /// Library asset UUID
+        id: String,
+    },
+
+    /// Delete an asset (refused while anything references it)
+    Delete {
+        /// Library asset UUID
+        id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StorageCommand {
+    /// Find orphaned objects and media with missing files (dry run by default)
+    Gc {
+        /// Delete orphans and repair media instead of only reporting
+        #[arg(long)]
+        apply: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    /// List cached pages
+    Ls {
+        /// Only pages cached for this host
+        #[arg(long)]
+        host: Option<String>,
+    },
+
+    /// Drop cached pages (they re-render in the background)
+    #[command(group(
+        clap::ArgGroup::new("selector")
+            .required(true)
+            .args(["target", "prefix", "glob", "all"]),
+    ))]
+    Purge {
+        /// Exact request target, query included (e.g. /projects/foo)
+        target: Option<String>,
+
+        /// Every target starting with this
+        #[arg(long)]
+        prefix: Option<String>,
+
+        /// Targets matching a pattern; `*` matches anything
+        #[arg(long)]
+        glob: Option<String>,
+
+        /// Every cached page
+        #[arg(long)]
+        all: bool,
+
+        /// Only pages cached for this host
+        #[arg(long)]
+        host: Option<String>,
+    },
+
+    /// Show occupancy and hit/miss counters
+    Stats,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UsersCommand {
+    /// List admin users
+    List,
+
+    /// Create an admin user
+    #[command(group(
+        clap::ArgGroup::new("secret")
+            .required(true)
+            .args(["password", "password_stdin"]),
+    ))]
+    Create {
+        /// Login name (letters, digits, '.', '_', '-')
+        username: String,
+
+        /// Role to grant
+        #[arg(long, value_enum, default_value = "viewer")]
+        role: RoleArg,
+
+        /// Initial password (prefer --password-stdin; flags show up in `ps`)
+        #[arg(long, env = "XEVION_USER_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+
+        /// Read the initial password from stdin
+        #[arg(long)]
+        password_stdin: bool,
+    },
+
+    /// Change a user's role (applies to their signed-in sessions at once)
+    SetRole {
+        /// Username or numeric ID
+        user: String,
+
+        /// New role
+        #[arg(value_enum)]
+        role: RoleArg,
+    },
+
+    /// Delete a user and sign out their sessions
+    Delete {
+        /// Username or numeric ID
+        user: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TokensCommand {
+    /// List your API tokens
+    List,
+
+    /// Create an API token; the secret is printed once, to stdout
+    Create {
+        /// Name to recognize the token by (e.g. "github-actions")
+        name: String,
+
+        /// Permission to grant; repeat for several. Without any, the token
+        /// is read-only. One of: projects:write, content:write, media:upload,
+        /// media:write, tags:write, cache:purge
+        #[arg(long = "scope", value_name = "SCOPE")]
+        scopes: Vec<ApiScope>,
+
+        /// Limit the token to this project (slug or UUID); repeat for several.
+        /// A limited token can't write tags or library media
+        #[arg(long = "project", value_name = "REF")]
+        projects: Vec<String>,
+
+        /// Days until the token expires (at most 365)
+        #[arg(long, default_value_t = DEFAULT_TOKEN_DAYS)]
+        days: u32,
+    },
+
+    /// Revoke an API token
+    Revoke {
+        /// Token ID (from `tokens list`)
+        id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SessionsCommand {
+    /// List active sessions with where they were last used from
+    List,
+
+    /// Revoke a session
+    Revoke {
+        /// Session ID (from `sessions list`)
+        id: String,
+    },
+
+    /// Revoke every session and API token except the one this CLI uses
+    RevokeOthers,
 }
 
 /// Tag operation for updates
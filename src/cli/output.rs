@@ -2,8 +2,16 @@ use nu_ansi_term::{Color, Style};
 use serde::Serialize;
 use snafu::ResultExt;
 
+use crate::api_tokens::ApiToken;
 use crate::cli::error::{CliError, SerializeSnafu};
-use crate::db::{ApiAdminProject, ApiSiteSettings, ApiTag, ApiTagWithCount};
+use crate::db::{
+    ApiAdminProject, ApiMediaAsset, ApiProjectMedia, ApiSiteSettings, ApiTag, ApiTagWithCount,
+    MediaStatus, MediaType,
+};
+use crate::handlers::{
+    AdminRole, ApiAdminUser, ApiSession, CacheEntryInfo, CacheEntryState, CacheStats, OrphanReason,
+    StorageGcReport,
+};
 use crate::pm::{Doc, Node};
 
 // Status, progress, and confirmation lines are diagnostics: they go to stderr so
@@ -321,6 +329,486 @@ pub fn print_tags_table(tags: &[ApiTagWithCount]) {
     info(&format!("{} tag(s)", tags.len()));
 }
 
+/// Print admin users in table format
+pub fn print_users_table(users: &[ApiAdminUser]) {
+    if users.is_empty() {
+        info("No users found");
+        return;
+    }
+
+    let header = Style::new().bold().underline();
+    let dim = Style::new().dimmed();
+
+    let name_width = users
+        .iter()
+        .map(|u| u.username.len())
+        .max()
+        .unwrap_or(8)
+        .max(8);
+
+    println!(
+        "{:>4}  {:name_width$}  {:6}  {:3}  {:8}  {}",
+        header.paint("ID"),
+        header.paint("USERNAME"),
+        header.paint("ROLE"),
+        header.paint("2FA"),
+        header.paint("PASSKEYS"),
+        header.paint("CREATED"),
+    );
+
+    for user in users {
+        let role = match user.role {
+            AdminRole::Owner => Color::Purple.paint("owner"),
+            AdminRole::Editor => Color::Blue.paint("editor"),
+            AdminRole::Viewer => Color::Default.paint("viewer"),
+        };
+        let totp = if user.totp_enabled { "yes" } else { "-" };
+        println!(
+            "{:>4}  {:name_width$}  {:6}  {:3}  {:8}  {}",
+            user.id,
+            user.username,
+            role,
+            totp,
+            user.passkeys,
+            dim.paint(&user.created_at),
+        );
+    }
+
+    println!();
+    info(&format!("{} user(s)", users.len()));
+}
+
+/// Print sessions in table format; the one making the request is starred
+pub fn print_sessions_table(sessions: &[ApiSession]) {
+    if sessions.is_empty() {
+        info("No active sessions");
+        return;
+    }
+
+    let header = Style::new().bold().underline();
+    let dim = Style::new().dimmed();
+
+    let describe = |session: &ApiSession| {
+        let device = session.device.as_deref().unwrap_or("unknown device");
+        match &session.label {
+            Some(label) => format!("{device} ({label})"),
+            None => device.to_string(),
+        }
+    };
+    let device_width = column_width(sessions.iter().map(|s| describe(s).chars().count()), 6);
+    let ip_width = column_width(
+        sessions
+            .iter()
+            .map(|s| s.last_ip.as_deref().map_or(1, str::len)),
+        7,
+    );
+
+    println!(
+        "  {:26}  {:7}  {}  {}  {}",
+        header.paint("ID"),
+        header.paint("TYPE"),
+        padded("DEVICE", header, device_width),
+        padded("LAST IP", header, ip_width),
+        header.paint("LAST ACTIVE"),
+    );
+
+    for session in sessions {
+        let marker = if session.current { "*" } else { " " };
+        println!(
+            "{marker} {:26}  {:7}  {:device_width$}  {:ip_width$}  {}",
+            dim.paint(&session.id),
+            session.session_type.as_str(),
+            describe(session),
+            session.last_ip.as_deref().unwrap_or("-"),
+            dim.paint(&session.last_active_at),
+        );
+    }
+
+    println!();
+    info(&format!("{} session(s); * marks this one", sessions.len()));
+}
+
+fn token_scopes(token: &ApiToken) -> String {
+    if token.scopes.is_empty() {
+        "read-only".to_string()
+    } else {
+        token
+            .scopes
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Print API tokens in table format
+pub fn print_tokens_table(tokens: &[ApiToken]) {
+    if tokens.is_empty() {
+        info("No API tokens");
+        return;
+    }
+
+    let header = Style::new().bold().underline();
+    let dim = Style::new().dimmed();
+
+    let name_width = tokens
+        .iter()
+        .map(|t| t.name.len())
+        .max()
+        .unwrap_or(4)
+        .max(4);
+
+    println!(
+        "{:26}  {:name_width$}  {:8}  {:20}  {:20}  {}",
+        header.paint("ID"),
+        header.paint("NAME"),
+        header.paint("PROJECTS"),
+        header.paint("LAST USED"),
+        header.paint("EXPIRES"),
+        header.paint("SCOPES"),
+    );
+
+    for token in tokens {
+        let projects = token
+            .project_ids
+            .as_ref()
+            .map_or_else(|| "all".to_string(), |ids| ids.len().to_string());
+        println!(
+            "{:26}  {:name_width$}  {:8}  {:20}  {:20}  {}",
+            dim.paint(&token.id),
+            token.name,
+            projects,
+            dim.paint(&token.last_used_at),
+            dim.paint(&token.expires_at),
+            token_scopes(token),
+        );
+    }
+
+    println!();
+    info(&format!("{} token(s)", tokens.len()));
+}
+
+/// Print an API token's details (stderr, so stdout can carry just the secret)
+pub fn print_token(token: &ApiToken) {
+    let dim = Style::new().dimmed();
+
+    eprintln!("  {} {}", dim.paint("ID:"), token.id);
+    eprintln!("  {} {}", dim.paint("Scopes:"), token_scopes(token));
+    if let Some(ids) = &token.project_ids {
+        for id in ids {
+            eprintln!("  {} {id}", dim.paint("Project:"));
+        }
+    }
+    eprintln!("  {} {}", dim.paint("Expires:"), token.expires_at);
+}
+
+/// Print a project's media in table format
+pub fn print_media_table(media: &[ApiProjectMedia]) {
+    if media.is_empty() {
+        info("No media found");
+        return;
+    }
+
+    let header = Style::new().bold().underline();
+    let dim = Style::new().dimmed();
+
+    println!(
+        "{:5}  {:36}  {:5}  {:10}  {}",
+        header.paint("ORDER"),
+        header.paint("ID"),
+        header.paint("TYPE"),
+        header.paint("STATUS"),
+        header.paint("URL"),
+    );
+
+    for item in media {
+        let kind = match item.media_type {
+            MediaType::Image => "image",
+            MediaType::Video => "video",
+        };
+        let status = match item.status {
+            MediaStatus::Pending => Color::Yellow.paint("pending"),
+            MediaStatus::Processing => Color::Yellow.paint("processing"),
+            MediaStatus::Ready => Color::Green.paint("ready"),
+            MediaStatus::Failed => Color::Red.paint("failed"),
+        };
+        let url = item
+            .variants
+            .original
+            .as_ref()
+            .map(|v| v.url.as_str())
+            .or_else(|| item.variants.video.as_ref().map(|v| v.url.as_str()))
+            .unwrap_or("-");
+
+        println!(
+            "{:5}  {:36}  {:5}  {:10}  {}",
+            item.display_order,
+            item.id,
+            kind,
+            status,
+            dim.paint(url),
+        );
+        if let Some(ref error) = item.processing_error {
+            println!("       {}", Color::Red.paint(error));
+        }
+    }
+
+    println!();
+    info(&format!("{} media item(s)", media.len()));
+}
+
+/// Print cached pages as a table
+pub fn print_cache_table(entries: &[CacheEntryInfo]) {
+    if entries.is_empty() {
+        info("Cache is empty");
+        return;
+    }
+
+    let header = Style::new().bold().underline();
+    let dim = Style::new().dimmed();
+
+    println!(
+        "{:5}  {:3}  {:>6}  {:>9}  {:18}  {}",
+        header.paint("STATE"),
+        header.paint("ST"),
+        header.paint("AGE"),
+        header.paint("SIZE"),
+        header.paint("ENCODINGS"),
+        header.paint("PAGE"),
+    );
+
+    for entry in entries {
+        let state = match entry.state {
+            CacheEntryState::Fresh => Color::Green.paint("fresh"),
+            CacheEntryState::Stale => Color::Yellow.paint("stale"),
+        };
+        let variant = entry
+            .variant
+            .as_ref()
+            .map(|variant| format!(" #{variant}"))
+            .unwrap_or_default();
+        println!(
+            "{:5}  {:3}  {:>6}  {:>9}  {:18}  {}{}{}",
+            state,
+            entry.status,
+            format!("{}s", entry.age_secs),
+            format_bytes(entry.size as u64),
+            entry.encodings.join(","),
+            dim.paint(&entry.host),
+            entry.target,
+            dim.paint(variant),
+        );
+    }
+
+    println!();
+    info(&format!("{} cached page(s)", entries.len()));
+}
+
+/// Print cache occupancy and counters
+pub fn print_cache_stats(stats: &CacheStats) {
+    let label = Style::new().bold();
+
+    if !stats.enabled {
+        info("ISR cache is disabled on this server");
+    }
+    println!(
+        "{} {} ({} fresh, {} stale), {}",
+        label.paint("Entries:"),
+        stats.entries,
+        stats.fresh_entries,
+        stats.stale_entries,
+        format_bytes(stats.bytes)
+    );
+    if let Some(persisted) = stats.persisted_bytes {
+        println!("{} {}", label.paint("On disk:"), format_bytes(persisted));
+    }
+
+    let served = stats.hits + stats.stale_hits + stats.misses + stats.coalesced;
+    println!(
+        "{} {} hit, {} stale, {} miss, {} coalesced, {} bypass",
+        label.paint("Requests:"),
+        stats.hits,
+        stats.stale_hits,
+        stats.misses,
+        stats.coalesced,
+        stats.bypasses
+    );
+    if stats.stale_if_error > 0 {
+        println!(
+            "{} {} (served expired pages while Bun was failing)",
+            label.paint("Stale-if-error:"),
+            stats.stale_if_error
+        );
+    }
+    if served > 0 {
+        let ratio = (stats.hits + stats.stale_hits) as f64 / served as f64 * 100.0;
+        println!("{} {ratio:.1}%", label.paint("Hit ratio:"));
+    }
+}
+
+/// Human-readable byte count (binary units)
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Print the media library as a table
+pub fn print_library_table(assets: &[ApiMediaAsset]) {
+    if assets.is_empty() {
+        info("Media library is empty");
+        return;
+    }
+
+    let header = Style::new().bold().underline();
+    let dim = Style::new().dimmed();
+
+    println!(
+        "{:36}  {:5}  {:10}  {:4}  {}",
+        header.paint("ID"),
+        header.paint("TYPE"),
+        header.paint("STATUS"),
+        header.paint("REFS"),
+        header.paint("FILENAME"),
+    );
+
+    for asset in assets {
+        let refs = if asset.ref_count == 0 {
+            dim.paint("0").to_string()
+        } else {
+            asset.ref_count.to_string()
+        };
+        println!(
+            "{:36}  {:5}  {:10}  {:4}  {}",
+            asset.id,
+            media_kind(asset.media_type),
+            format_media_status(asset.status),
+            refs,
+            asset.original_filename,
+        );
+    }
+
+    println!();
+    info(&format!("{} asset(s)", assets.len()));
+}
+
+/// Print one library asset with its references
+pub fn print_library_asset(asset: &ApiMediaAsset) {
+    let header = Style::new().bold();
+    let dim = Style::new().dimmed();
+
+    println!("{}", header.paint(&asset.original_filename));
+    println!("  {} {}", dim.paint("ID:"), asset.id);
+    println!("  {} {}", dim.paint("Type:"), media_kind(asset.media_type));
+    println!(
+        "  {} {}",
+        dim.paint("Status:"),
+        format_media_status(asset.status)
+    );
+    println!("  {} {} bytes", dim.paint("Size:"), asset.size_bytes);
+    println!("  {} {}", dim.paint("Uploaded:"), asset.created_at);
+    if let Some(ref error) = asset.processing_error {
+        println!("  {} {}", dim.paint("Error:"), Color::Red.paint(error));
+    }
+
+    let Some(ref references) = asset.references else {
+        return;
+    };
+    println!();
+    if references.count() == 0 {
+        info("Not referenced anywhere; safe to delete");
+        return;
+    }
+    println!("{}", header.paint("Referenced by"));
+    for project in &references.projects {
+        println!("  {} {}", dim.paint("gallery:"), project.slug);
+    }
+    for project in &references.figures {
+        println!("  {} {}", dim.paint("figure: "), project.slug);
+    }
+    for slot in &references.settings {
+        println!("  {} {slot}", dim.paint("setting:"));
+    }
+}
+
+const fn media_kind(media_type: MediaType) -> &'static str {
+    match media_type {
+        MediaType::Image => "image",
+        MediaType::Video => "video",
+    }
+}
+
+fn format_media_status(status: MediaStatus) -> String {
+    match status {
+        MediaStatus::Pending => Color::Yellow.paint("pending"),
+        MediaStatus::Processing => Color::Yellow.paint("processing"),
+        MediaStatus::Ready => Color::Green.paint("ready"),
+        MediaStatus::Failed => Color::Red.paint("failed"),
+    }
+    .to_string()
+}
+
+/// Print a storage reconciliation report
+pub fn print_storage_gc_report(report: &StorageGcReport) {
+    let header = Style::new().bold().underline();
+    let dim = Style::new().dimmed();
+
+    if !report.orphans.is_empty() {
+        println!("{:18}  {}", header.paint("ORPHAN"), header.paint("KEY"));
+        for orphan in &report.orphans {
+            let reason = match orphan.reason {
+                OrphanReason::DeletedProject => "deleted project",
+                OrphanReason::UnreferencedMedia => "unreferenced",
+                OrphanReason::StaleOgImage => "stale og image",
+            };
+            println!("{:18}  {}", Color::Yellow.paint(reason), orphan.key);
+        }
+        println!();
+    }
+
+    if !report.missing.is_empty() {
+        println!(
+            "{:36}  {}",
+            header.paint("MISSING (MEDIA)"),
+            header.paint("KEY")
+        );
+        for missing in &report.missing {
+            println!(
+                "{:36}  {}",
+                dim.paint(missing.media_id.to_string()),
+                missing.key
+            );
+        }
+        println!();
+    }
+
+    info(&format!(
+        "Scanned {} object(s): {} orphaned, {} missing, {} too recent to judge",
+        report.scanned,
+        report.orphans.len(),
+        report.missing.len(),
+        report.skipped_recent
+    ));
+
+    if report.applied {
+        success(&format!(
+            "Deleted {} orphan(s), re-queued {} media, marked {} media failed",
+            report.deleted, report.requeued, report.failed
+        ));
+    } else if !report.orphans.is_empty() || !report.missing.is_empty() {
+        info("Dry run; re-run with --apply to clean up");
+    }
+}
+
 /// Print site settings in formatted output
 pub fn print_settings(settings: &ApiSiteSettings) {
     let header = Style::new().bold();
@@ -343,6 +831,12 @@ pub fn print_settings(settings: &ApiSiteSettings) {
         dim.paint("Site Title:"),
         settings.identity.site_title
     );
+    if let Some(ref id) = settings.identity.avatar_media_id {
+        println!("  {} {id}", dim.paint("Avatar:"));
+    }
+    if let Some(ref id) = settings.identity.favicon_media_id {
+        println!("  {} {id}", dim.paint("Favicon:"));
+    }
 
     if !settings.social_links.is_empty() {
         println!();
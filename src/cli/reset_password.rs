@@ -0,0 +1,52 @@
+use sqlx::PgPool;
+
+use crate::auth;
+
+/// What else a reset clears besides the password.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResetOptions {
+    /// Leave the user's sessions and API tokens signed in.
+    pub keep_sessions: bool,
+    /// Turn off TOTP and drop the recovery codes.
+    pub disable_2fa: bool,
+    /// Remove every registered passkey.
+    pub clear_passkeys: bool,
+}
+
+/// Set a user's password directly in the database, for when nobody can sign
+/// in. A lost authenticator needs `disable_2fa` too. Unless `keep_sessions`,
+/// the user's sessions and API tokens are deleted; a running server drops them
+/// within a minute (see [`auth::SessionManager::forget_revoked`]).
+pub async fn run(
+    pool: &PgPool,
+    username: &str,
+    password: &str,
+    options: ResetOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    auth::validate_password(password)?;
+    let user = auth::get_admin_user(pool, username)
+        .await?
+        .ok_or_else(|| format!("No user '{username}'"))?;
+
+    let password_hash =
+        auth::hash_password(password).map_err(|e| format!("Failed to hash password: {e}"))?;
+    auth::set_admin_password(pool, user.id, &password_hash).await?;
+    println!("Password reset for {username}");
+
+    if options.disable_2fa {
+        auth::disable_totp(pool, user.id).await?;
+        println!("  Two-factor authentication disabled");
+    }
+
+    if options.clear_passkeys {
+        let removed = auth::delete_passkeys(pool, user.id).await?;
+        println!("  Removed {removed} passkeys");
+    }
+
+    if !options.keep_sessions {
+        let revoked = auth::revoke_user_sessions(pool, user.id).await?;
+        println!("  Revoked {revoked} sessions (a running server signs them out within a minute)");
+    }
+
+    Ok(())
+}
@@ -12,9 +12,12 @@ use crate::config::ListenAddr;
 use crate::encoding::COMPRESSION_MIN_SIZE;
 use crate::github;
 use crate::icon_cache::IconCache;
-use crate::middleware::RequestIdLayer;
+use crate::login_throttle::{LoginThrottle, LoginThrottleConfig};
+use crate::middleware::{RequestIdLayer, SessionRenewalLayer};
+use crate::session_policy::SessionPolicies;
 use crate::state::AppState;
 use crate::tarpit::{TarpitConfig, TarpitState};
+use crate::webauthn::{Webauthn, WebauthnConfig};
 use crate::{auth, db, health, http, og, proxy, routes};
 
 /// Run the web server
@@ -76,8 +79,22 @@ pub async fn run(
         .expect("Failed to ensure admin user exists");
 
     // Initialize session manager
+    let session_policies = SessionPolicies::from_env();
+    tracing::info!(
+        browser_lifetime_sec = session_policies.browser.lifetime.whole_seconds(),
+        browser_idle_sec = session_policies
+            .browser
+            .idle_timeout
+            .map(time::Duration::whole_seconds),
+        cli_lifetime_sec = session_policies.cli.lifetime.whole_seconds(),
+        cli_idle_sec = session_policies
+            .cli
+            .idle_timeout
+            .map(time::Duration::whole_seconds),
+        "Session policies loaded"
+    );
     let session_manager = Arc::new(
-        auth::SessionManager::new(pool.clone())
+        auth::SessionManager::new(pool.clone(), session_policies)
             .await
             .expect("Failed to initialize session manager"),
     );
@@ -96,6 +113,22 @@ pub async fn run(
         }
     });
 
+    // Pick up sessions revoked outside the server (`reset-password`)
+    tokio::spawn({
+        let session_manager = session_manager.clone();
+        async move {
+            let mut interval = tokio::time::interval(Duration::from_mins(1));
+            loop {
+                interval.tick().await;
+                match session_manager.forget_revoked().await {
+                    Ok(0) => {}
+                    Ok(revoked) => tracing::info!(revoked, "Dropped sessions revoked elsewhere"),
+                    Err(e) => tracing::error!(error = %e, "Failed to check for revoked sessions"),
+                }
+            }
+        }
+    });
+
     if listen.is_empty() {
         eprintln!("Error: At least one --listen address is required");
         std::process::exit(1);
@@ -127,7 +160,12 @@ pub async fn run(
         "Tarpit initialized"
     );
 
+    let login_throttle = Arc::new(LoginThrottle::new(LoginThrottleConfig::from_env()));
+
     // Initialize ISR cache
+    let tagged_assets = crate::assets::precompute_etags();
+    tracing::debug!(files = tagged_assets, "Embedded asset ETags computed");
+
     let isr_cache_config = IsrCacheConfig::from_env();
     let isr_cache = Arc::new(IsrCache::new(isr_cache_config.clone()));
 
@@ -136,9 +174,20 @@ pub async fn run(
         max_entries = isr_cache_config.max_entries,
         fresh_sec = isr_cache_config.fresh_duration.as_secs(),
         stale_sec = isr_cache_config.stale_duration.as_secs(),
+        stale_if_error_sec = isr_cache_config.stale_if_error.as_secs(),
         "ISR cache initialized"
     );
 
+    if isr_cache.is_persistent() {
+        let restored = isr_cache.load_persisted().await;
+        tracing::info!(
+            dir = ?isr_cache_config.persist_dir,
+            max_mb = isr_cache_config.persist_max_bytes / (1024 * 1024),
+            restored,
+            "ISR disk cache loaded"
+        );
+    }
+
     // Resolve the public-host allowlist used for per-domain ISR caching/origin
     let host_config = crate::host::HostConfig::from_env();
     tracing::info!(
@@ -146,6 +195,28 @@ pub async fn run(
         "Public host config initialized"
     );
 
+    let webauthn = Arc::new(Webauthn::new(WebauthnConfig::from_env(
+        host_config.canonical(),
+    )));
+    tracing::info!(
+        rp_id = %webauthn.config.rp_id,
+        origins = ?webauthn.config.origins,
+        "Passkey relying party initialized"
+    );
+
+    // Resolve media/OG storage; the client itself is created lazily on first use
+    let storage_backend = crate::r2::StorageBackend::from_env().unwrap_or_else(|e| {
+        tracing::error!(error = %e, "Invalid storage configuration");
+        std::process::exit(1);
+    });
+    crate::db::set_media_base_url(storage_backend.public_base_url());
+    match &storage_backend {
+        crate::r2::StorageBackend::R2 => tracing::info!("Media storage: R2"),
+        crate::r2::StorageBackend::Fs { root } => {
+            tracing::info!(root = %root.display(), "Media storage: local directory");
+        }
+    }
+
     // Initialize icon cache
     let icon_cache = Arc::new(IconCache::new());
     tracing::debug!("Icon cache initialized");
@@ -157,6 +228,9 @@ pub async fn run(
         pool.clone(),
     ));
 
+    // Create media processing queue; workers are spawned once state exists
+    let (media_queue, media_receiver) = crate::media_queue::MediaQueue::new();
+
     let state = Arc::new(AppState {
         client,
         health_checker,
@@ -164,12 +238,61 @@ pub async fn run(
         pool: pool.clone(),
         session_manager: session_manager.clone(),
         isr_cache,
+        cache_warmer: Arc::new(crate::cache_warmer::CacheWarmer::new()),
         icon_cache,
         event_sender,
+        login_throttle,
         cli_auth: crate::cli_auth::CliAuthRegistry::new(),
         host_config,
+        webauthn,
+        media_queue,
+        media_uploads: crate::media_upload::MediaUploadRegistry::new(
+            crate::media_upload::MediaUploadConfig::from_env(),
+        ),
+    });
+
+    // Abort chunked uploads that have gone idle
+    tokio::spawn({
+        let media_uploads = state.media_uploads.clone();
+        async move {
+            let mut interval = tokio::time::interval(Duration::from_hours(1));
+            loop {
+                interval.tick().await;
+                let removed = media_uploads.cleanup_expired().await;
+                if removed > 0 {
+                    tracing::info!(removed, "Expired chunked uploads aborted");
+                }
+            }
+        }
     });
 
+    // Spawn media processing workers (also resumes unfinished uploads)
+    let media_queue_config = crate::media_queue::MediaQueueConfig::from_env();
+    tracing::info!(
+        workers = media_queue_config.workers,
+        "Media queue initialized"
+    );
+    tokio::spawn(crate::media_queue::run_workers(
+        state.clone(),
+        media_receiver,
+        media_queue_config,
+    ));
+
+    // Warm the ISR cache now and after every invalidation
+    let cache_warmer_config = crate::cache_warmer::CacheWarmerConfig::from_env();
+    if cache_warmer_config.enabled && state.isr_cache.config.enabled {
+        tracing::info!(
+            concurrency = cache_warmer_config.concurrency,
+            debounce_ms = cache_warmer_config.debounce.as_millis() as u64,
+            "Cache warmer initialized"
+        );
+        tokio::spawn(crate::cache_warmer::run(
+            state.clone(),
+            state.isr_cache.subscribe_invalidations(),
+            cache_warmer_config,
+        ));
+    }
+
     // Regenerate common OGP images on startup
     tokio::spawn({
         let state = state.clone();
@@ -205,7 +328,19 @@ pub async fn run(
             .and(NotForContentType::new("application/octet-stream"))
             // SSE must stream unbuffered (CLI device-auth waits on it); compressing
             // a Content-Length-less stream would buffer it and stall delivery.
-            .and(NotForContentType::new("text/event-stream"));
+            .and(NotForContentType::new("text/event-stream"))
+            // A strong ETag names exact bytes (an encoding we already chose);
+            // recompressing would serve different bytes under the same tag.
+            // Streamed SSR renders are fine: the encoder flushes whenever Bun
+            // pauses, so early chunks aren't held back.
+            .and(
+                |_: axum::http::StatusCode,
+                 _: axum::http::Version,
+                 headers: &axum::http::HeaderMap,
+                 _: &axum::http::Extensions| {
+                    !crate::conditional::has_strong_etag(headers)
+                },
+            );
 
         // Compression layer with all algorithms at fastest levels
         // This handles runtime compression for all responses (API, SSR pages, etc.)
@@ -220,6 +355,7 @@ pub async fn run(
 
         router
             .layer(compression_layer)
+            .layer(SessionRenewalLayer)
             .layer(RequestIdLayer::new(trust_request_id))
             .layer(CorsLayer::permissive())
             // 50 MiB limit for media uploads
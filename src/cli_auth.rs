@@ -3,12 +3,17 @@
 //! The CLI starts a request and is handed a short, human-readable user code. It
 //! opens the approval page in a browser and waits on an SSE stream. An
 //! authenticated admin confirms the code matches, which mints a long-lived CLI
-//! token and pushes it back over the stream. Pending requests live in memory
+//! token and pushes it back over the stream. Where a proxy buffers the stream,
+//! the CLI instead polls `POST /api/auth/device/token` every `interval`
+//! seconds, as in RFC 8628's device access token request, and gets
+//! `authorization_pending`, `slow_down`, `access_denied`, or `expired_token`
+//! until the request resolves. Pending requests live in memory
 //! only — they're short-lived and losing them on restart just means re-running
 //! `xevion login`.
 
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use std::sync::Arc;
 use time::{Duration, OffsetDateTime};
 use tokio::sync::watch;
@@ -35,6 +40,40 @@ pub struct StartResponse {
     pub verification_path: String,
     #[serde(with = "time::serde::rfc3339")]
     pub expires_at: OffsetDateTime,
+    /// Seconds to wait between token polls. Absent from older servers.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+const fn default_interval() -> u64 {
+    POLL_INTERVAL.whole_seconds().unsigned_abs()
+}
+
+/// `POST /api/auth/device/token` request body.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRequest {
+    pub request_id: String,
+}
+
+/// Why a token poll didn't return a token (the RFC 8628 error codes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenErrorCode {
+    AuthorizationPending,
+    SlowDown,
+    AccessDenied,
+    ExpiredToken,
+}
+
+/// `POST /api/auth/device/token` error body, sent with a 400. An approved
+/// poll gets [`CliAuthStatus::Approved`] instead, as on the event stream.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenError {
+    pub error: TokenErrorCode,
+    /// Seconds to wait before polling again, when still pending
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
 }
 
 /// `POST /api/auth/device/approve` request body.
@@ -43,6 +82,9 @@ pub struct StartResponse {
 pub struct ApproveRequest {
     pub request_id: String,
     pub user_code: String,
+    /// Fresh TOTP or recovery code; required when the approving admin has 2FA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp_code: Option<String>,
 }
 
 /// `POST /api/auth/device/deny` request body.
@@ -55,6 +97,12 @@ pub struct DenyRequest {
 /// How long an unconfirmed request stays valid.
 const REQUEST_TTL: Duration = Duration::minutes(10);
 
+/// How often the CLI may poll for its token to begin with.
+pub const POLL_INTERVAL: Duration = Duration::seconds(5);
+
+/// How much each `slow_down` lengthens a request's poll interval.
+const SLOW_DOWN_STEP: Duration = Duration::seconds(5);
+
 /// Unambiguous alphabet for the user code (no 0/O/1/I/L).
 const CODE_ALPHABET: [char; 31] = [
     '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'M',
@@ -67,7 +115,13 @@ const CODE_ALPHABET: [char; 31] = [
 #[serde(rename_all = "camelCase", tag = "status")]
 pub enum CliAuthStatus {
     Pending,
-    Approved { token: String, username: String },
+    Approved {
+        token: String,
+        username: String,
+        /// When the token expires (RFC 3339). Absent from older servers.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_at: Option<String>,
+    },
     Denied,
 }
 
@@ -75,7 +129,11 @@ struct PendingAuth {
     user_code: String,
     label: Option<String>,
     expires_at: OffsetDateTime,
+    device_ip: Option<IpAddr>,
+    device_user_agent: Option<String>,
     tx: watch::Sender<CliAuthStatus>,
+    interval: Duration,
+    last_polled: Option<OffsetDateTime>,
 }
 
 /// Public view of a pending request, for the approval page.
@@ -84,6 +142,23 @@ pub struct PendingInfo {
     pub user_code: String,
     pub label: Option<String>,
     pub expires_at: OffsetDateTime,
+    /// Address and user agent of the CLI that started the request, recorded on
+    /// the session it gets.
+    pub device_ip: Option<IpAddr>,
+    pub device_user_agent: Option<String>,
+}
+
+/// Outcome of a token poll.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PollOutcome {
+    /// Not decided yet; poll again after `interval`.
+    Pending { interval: Duration },
+    /// Polled before `interval` had passed, which has now grown.
+    SlowDown { interval: Duration },
+    /// Approved or denied. The request is gone once this is returned.
+    Resolved(CliAuthStatus),
+    /// Unknown or expired.
+    Expired,
 }
 
 /// Outcome of starting a new request.
@@ -111,8 +186,13 @@ impl CliAuthRegistry {
         format!("{}-{}", &chars[..4], &chars[4..])
     }
 
-    /// Register a new pending request.
-    pub fn start(&self, label: Option<String>) -> StartedRequest {
+    /// Register a new pending request from the CLI at `device_ip`.
+    pub fn start(
+        &self,
+        label: Option<String>,
+        device_ip: Option<IpAddr>,
+        device_user_agent: Option<String>,
+    ) -> StartedRequest {
         self.sweep_expired();
 
         let request_id = Ulid::new();
@@ -126,7 +206,11 @@ impl CliAuthRegistry {
                 user_code: user_code.clone(),
                 label,
                 expires_at,
+                device_ip,
+                device_user_agent,
                 tx,
+                interval: POLL_INTERVAL,
+                last_polled: None,
             },
         );
 
@@ -147,6 +231,8 @@ impl CliAuthRegistry {
             user_code: entry.user_code.clone(),
             label: entry.label.clone(),
             expires_at: entry.expires_at,
+            device_ip: entry.device_ip,
+            device_user_agent: entry.device_user_agent.clone(),
         })
     }
 
@@ -159,15 +245,52 @@ impl CliAuthRegistry {
         Some(entry.tx.subscribe())
     }
 
-    /// Approve a request, pushing the minted token to the waiter. The supplied
-    /// `user_code` must match (anti-phishing). Returns `false` if the request is
-    /// unknown, expired, or the code mismatches.
+    /// Check on a request for a polling CLI. A resolved request is handed out
+    /// once and removed; polling faster than the interval lengthens it.
+    pub fn poll(&self, request_id: Ulid) -> PollOutcome {
+        let now = OffsetDateTime::now_utc();
+        let Some(mut entry) = self.requests.get_mut(&request_id) else {
+            return PollOutcome::Expired;
+        };
+        if entry.expires_at < now {
+            drop(entry);
+            self.requests.remove(&request_id);
+            return PollOutcome::Expired;
+        }
+
+        let status = entry.tx.borrow().clone();
+        if status != CliAuthStatus::Pending {
+            drop(entry);
+            self.requests.remove(&request_id);
+            return PollOutcome::Resolved(status);
+        }
+
+        let too_soon = entry
+            .last_polled
+            .is_some_and(|last| now - last < entry.interval);
+        entry.last_polled = Some(now);
+        if too_soon {
+            entry.interval += SLOW_DOWN_STEP;
+            PollOutcome::SlowDown {
+                interval: entry.interval,
+            }
+        } else {
+            PollOutcome::Pending {
+                interval: entry.interval,
+            }
+        }
+    }
+
+    /// Approve a request, pushing the minted token and its expiry to the
+    /// waiter. The supplied `user_code` must match (anti-phishing). Returns
+    /// `false` if the request is unknown, expired, or the code mismatches.
     pub fn approve(
         &self,
         request_id: Ulid,
         user_code: &str,
         token: String,
         username: String,
+        token_expires_at: OffsetDateTime,
     ) -> bool {
         let Some(entry) = self.requests.get(&request_id) else {
             return false;
@@ -177,7 +300,14 @@ impl CliAuthRegistry {
         {
             return false;
         }
-        let _ = entry.tx.send(CliAuthStatus::Approved { token, username });
+        let expires_at = token_expires_at
+            .format(&time::format_description::well_known::Rfc3339)
+            .ok();
+        entry.tx.send_replace(CliAuthStatus::Approved {
+            token,
+            username,
+            expires_at,
+        });
         true
     }
 
@@ -186,7 +316,7 @@ impl CliAuthRegistry {
         let Some(entry) = self.requests.get(&request_id) else {
             return false;
         };
-        let _ = entry.tx.send(CliAuthStatus::Denied);
+        entry.tx.send_replace(CliAuthStatus::Denied);
         true
     }
 
@@ -215,14 +345,16 @@ mod tests {
     #[test]
     fn approve_requires_matching_code() {
         let registry = CliAuthRegistry::new();
-        let started = registry.start(Some("laptop".into()));
+        let started = registry.start(Some("laptop".into()), None, None);
 
         // Wrong code is rejected.
+        let expires_at = time::macros::datetime!(2027-01-01 00:00 UTC);
         assert!(!registry.approve(
             started.request_id,
             "WRONG-CODE",
             "tok".into(),
-            "admin".into()
+            "admin".into(),
+            expires_at,
         ));
 
         // Correct code (case-insensitive) approves and pushes the token.
@@ -232,12 +364,14 @@ mod tests {
             &started.user_code.to_lowercase(),
             "tok".into(),
             "admin".into(),
+            expires_at,
         ));
         assert_eq!(
             *rx.borrow_and_update(),
             CliAuthStatus::Approved {
                 token: "tok".into(),
-                username: "admin".into()
+                username: "admin".into(),
+                expires_at: Some("2027-01-01T00:00:00Z".into()),
             }
         );
     }
@@ -245,7 +379,7 @@ mod tests {
     #[test]
     fn deny_sets_denied_status() {
         let registry = CliAuthRegistry::new();
-        let started = registry.start(None);
+        let started = registry.start(None, None, None);
         let rx = registry.subscribe(started.request_id).unwrap();
         assert!(registry.deny(started.request_id));
         assert_eq!(*rx.borrow(), CliAuthStatus::Denied);
@@ -258,5 +392,36 @@ mod tests {
         assert!(registry.info(missing).is_none());
         assert!(registry.subscribe(missing).is_none());
         assert!(!registry.deny(missing));
+        assert_eq!(registry.poll(missing), PollOutcome::Expired);
+    }
+
+    #[test]
+    fn fast_polling_slows_down() {
+        let registry = CliAuthRegistry::new();
+        let started = registry.start(None, None, None);
+        assert_eq!(
+            registry.poll(started.request_id),
+            PollOutcome::Pending {
+                interval: POLL_INTERVAL
+            }
+        );
+        assert_eq!(
+            registry.poll(started.request_id),
+            PollOutcome::SlowDown {
+                interval: POLL_INTERVAL + SLOW_DOWN_STEP
+            }
+        );
+    }
+
+    #[test]
+    fn resolved_request_is_handed_out_once() {
+        let registry = CliAuthRegistry::new();
+        let started = registry.start(None, None, None);
+        assert!(registry.deny(started.request_id));
+        assert_eq!(
+            registry.poll(started.request_id),
+            PollOutcome::Resolved(CliAuthStatus::Denied)
+        );
+        assert_eq!(registry.poll(started.request_id), PollOutcome::Expired);
     }
 }
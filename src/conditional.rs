@@ -0,0 +1,220 @@
+//! Conditional requests: validators and `304 Not Modified`
+//!
+//! ISR pages and embedded assets carry a strong `ETag` (a content hash, per
+//! content encoding) and, where a render time is known, `Last-Modified`.
+//! Requests repeating them in `If-None-Match` / `If-Modified-Since` get an
+//! empty 304 instead of the body (RFC 9110 §13).
+
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use std::time::SystemTime;
+
+use crate::encoding::ContentEncoding;
+
+/// Response headers a 304 repeats from the full response (RFC 9110 §15.4.5)
+const NOT_MODIFIED_HEADERS: &[header::HeaderName] = &[
+    header::CACHE_CONTROL,
+    header::CONTENT_LOCATION,
+    header::ETAG,
+    header::EXPIRES,
+    header::LAST_MODIFIED,
+    header::VARY,
+];
+
+/// Strong entity tag for `bytes`: the first 16 bytes of its SHA-256, quoted
+pub fn etag(bytes: &[u8]) -> HeaderValue {
+    let digest = Sha256::digest(bytes);
+    HeaderValue::from_str(&format!("\"{}\"", hex::encode(&digest[..16])))
+        .expect("hex digest is a valid header value")
+}
+
+/// Tag for `encoding` of the representation tagged `etag`. Compressed bytes
+/// differ from the identity body, so a strong tag must too.
+pub fn encoded_etag(etag: &HeaderValue, encoding: ContentEncoding) -> HeaderValue {
+    if encoding == ContentEncoding::Identity {
+        return etag.clone();
+    }
+    let tag = etag.to_str().unwrap_or_default().trim_end_matches('"');
+    HeaderValue::from_str(&format!("{tag}-{}\"", encoding.name())).unwrap_or_else(|_| etag.clone())
+}
+
+/// Weak form of `etag`, for a body that may still be re-encoded on the way
+/// out (see the compression layer in `serve`)
+pub fn weak_etag(etag: &HeaderValue) -> HeaderValue {
+    let tag = etag.to_str().unwrap_or_default();
+    if tag.starts_with("W/") {
+        return etag.clone();
+    }
+    HeaderValue::from_str(&format!("W/{tag}")).unwrap_or_else(|_| etag.clone())
+}
+
+/// Whether the request carries a validator that could earn it a 304
+pub fn has_preconditions(request: &HeaderMap) -> bool {
+    request.contains_key(header::IF_NONE_MATCH) || request.contains_key(header::IF_MODIFIED_SINCE)
+}
+
+/// Whether `headers` carry a strong `ETag`
+pub fn has_strong_etag(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .is_some_and(|etag| !etag.starts_with("W/"))
+}
+
+/// `Last-Modified` value for `time`
+pub fn http_date(time: SystemTime) -> HeaderValue {
+    HeaderValue::from_str(&httpdate::fmt_http_date(time))
+        .expect("HTTP dates are valid header values")
+}
+
+/// Whether a GET/HEAD with `request` headers may be answered with a 304 for a
+/// representation with these validators. `If-None-Match` takes precedence;
+/// `If-Modified-Since` is only consulted without it.
+pub fn is_not_modified(
+    request: &HeaderMap,
+    etag: Option<&HeaderValue>,
+    last_modified: Option<SystemTime>,
+) -> bool {
+    if request.contains_key(header::IF_NONE_MATCH) {
+        let Some(etag) = etag.and_then(|etag| etag.to_str().ok()) else {
+            return false;
+        };
+        return request
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .any(|candidate| candidate == "*" || weak_eq(candidate, etag));
+    }
+
+    let (Some(last_modified), Some(since)) = (
+        last_modified,
+        request
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| httpdate::parse_http_date(value).ok()),
+    ) else {
+        return false;
+    };
+    // HTTP dates have whole-second precision
+    last_modified
+        .duration_since(since)
+        .map_or(true, |newer_by| newer_by.as_secs() == 0)
+}
+
+/// Weak comparison (RFC 9110 §8.8.3.2): opaque tags match, ignoring `W/`
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+/// Empty 304 carrying the validator and caching headers of the full response
+pub fn not_modified(headers: &HeaderMap) -> Response {
+    let mut kept = HeaderMap::new();
+    for name in NOT_MODIFIED_HEADERS {
+        for value in headers.get_all(name) {
+            kept.append(name.clone(), value.clone());
+        }
+    }
+    (StatusCode::NOT_MODIFIED, kept).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn request(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn etags_are_stable_and_per_encoding() {
+        let tag = etag(b"<html></html>");
+        assert_eq!(tag, etag(b"<html></html>"));
+        assert_ne!(tag, etag(b"<html> </html>"));
+        assert_eq!(tag.len(), 34);
+
+        let weak = weak_etag(&tag);
+        assert_eq!(
+            weak.to_str().unwrap(),
+            format!("W/{}", tag.to_str().unwrap())
+        );
+        assert_eq!(weak_etag(&weak), weak);
+
+        let br = encoded_etag(&tag, ContentEncoding::Brotli);
+        assert!(br.to_str().unwrap().ends_with("-br\""));
+        assert_eq!(encoded_etag(&tag, ContentEncoding::Identity), tag);
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        let tag = etag(b"body");
+        let quoted = tag.to_str().unwrap();
+
+        assert!(is_not_modified(
+            &request(header::IF_NONE_MATCH, &format!("\"other\", W/{quoted}")),
+            Some(&tag),
+            None
+        ));
+        assert!(is_not_modified(
+            &request(header::IF_NONE_MATCH, "*"),
+            Some(&tag),
+            None
+        ));
+        assert!(!is_not_modified(
+            &request(header::IF_NONE_MATCH, "\"other\""),
+            Some(&tag),
+            None
+        ));
+    }
+
+    #[test]
+    fn if_modified_since_is_ignored_when_if_none_match_is_present() {
+        let rendered = SystemTime::now() - Duration::from_mins(1);
+        let mut headers = request(header::IF_NONE_MATCH, "\"other\"");
+        headers.insert(header::IF_MODIFIED_SINCE, http_date(SystemTime::now()));
+        assert!(!is_not_modified(
+            &headers,
+            Some(&etag(b"body")),
+            Some(rendered)
+        ));
+    }
+
+    #[test]
+    fn if_modified_since_compares_whole_seconds() {
+        let rendered = SystemTime::now() - Duration::from_mins(1);
+        let same = request(
+            header::IF_MODIFIED_SINCE,
+            &httpdate::fmt_http_date(rendered),
+        );
+        assert!(is_not_modified(&same, None, Some(rendered)));
+
+        let earlier = request(
+            header::IF_MODIFIED_SINCE,
+            &httpdate::fmt_http_date(rendered - Duration::from_mins(5)),
+        );
+        assert!(!is_not_modified(&earlier, None, Some(rendered)));
+        assert!(!is_not_modified(&same, None, None));
+    }
+
+    #[test]
+    fn not_modified_keeps_only_validators_and_caching_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, etag(b"body"));
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("4"));
+
+        let response = not_modified(&headers);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(response.headers().contains_key(header::ETAG));
+        assert!(response.headers().contains_key(header::CACHE_CONTROL));
+        assert!(!response.headers().contains_key(header::CONTENT_TYPE));
+    }
+}
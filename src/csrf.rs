@@ -0,0 +1,169 @@
+//! Cross-site request forgery protection for cookie-authenticated writes.
+//!
+//! A browser attaches the `admin_session` cookie to requests other pages
+//! start, within `SameSite=Lax` rules (which still let a sibling subdomain
+//! through). So a write authenticated by that cookie must come from one of our
+//! own origins: `Sec-Fetch-Site` has to be `same-origin` (or `none`, for a
+//! request the user started), and where a browser doesn't send it, `Origin`
+//! has to be ours per [`HostConfig::is_own_origin`]. A request with neither
+//! header isn't from a browser, and so can't be carrying someone else's
+//! cookie.
+//!
+//! Bearer tokens are exempt: another site can't make a browser attach one.
+
+use axum::http::{HeaderMap, Method, header};
+
+use crate::auth::{Session, SessionType};
+use crate::host::HostConfig;
+use crate::state::AppError;
+
+/// Reject a write by a browser session that didn't come from our own pages.
+pub fn check(
+    session: &Session,
+    method: &Method,
+    headers: &HeaderMap,
+    hosts: &HostConfig,
+) -> Result<(), AppError> {
+    if session.session_type != SessionType::Browser
+        || method.is_safe()
+        || is_same_origin(headers, hosts)
+    {
+        return Ok(());
+    }
+    tracing::warn!(
+        %method,
+        origin = ?headers.get(header::ORIGIN),
+        fetch_site = ?headers.get("sec-fetch-site"),
+        "Rejected cross-site write"
+    );
+    Err(AppError::CrossSite)
+}
+
+fn is_same_origin(headers: &HeaderMap, hosts: &HostConfig) -> bool {
+    if let Some(site) = headers.get("sec-fetch-site") {
+        return matches!(site.as_bytes(), b"same-origin" | b"none");
+    }
+    match headers.get(header::ORIGIN).map(|v| v.to_str()) {
+        Some(Ok(origin)) => hosts.is_own_origin(origin, headers),
+        Some(Err(_)) => false,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::AdminRole;
+    use time::OffsetDateTime;
+    use ulid::Ulid;
+
+    fn session(session_type: SessionType) -> Session {
+        let now = OffsetDateTime::now_utc();
+        Session {
+            id: Ulid::new(),
+            user_id: 1,
+            username: "admin".into(),
+            created_at: now,
+            last_active_at: now,
+            expires_at: now,
+            session_type,
+            label: None,
+            role: AdminRole::Owner,
+            grant: None,
+            created_ip: None,
+            last_ip: None,
+            user_agent: None,
+        }
+    }
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(
+                axum::http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        headers
+    }
+
+    fn allows(session_type: SessionType, method: &Method, pairs: &[(&str, &str)]) -> bool {
+        let hosts = HostConfig::new(&["xevion.dev", "walters.to"], "xevion.dev");
+        check(&session(session_type), method, &headers(pairs), &hosts).is_ok()
+    }
+
+    #[test]
+    fn fetch_metadata_decides_when_present() {
+        let post = Method::POST;
+        assert!(allows(
+            SessionType::Browser,
+            &post,
+            &[("sec-fetch-site", "same-origin")]
+        ));
+        assert!(allows(
+            SessionType::Browser,
+            &post,
+            &[("sec-fetch-site", "none")]
+        ));
+        assert!(!allows(
+            SessionType::Browser,
+            &post,
+            &[("sec-fetch-site", "same-site")]
+        ));
+        // Fetch metadata takes precedence over Origin
+        assert!(!allows(
+            SessionType::Browser,
+            &post,
+            &[
+                ("sec-fetch-site", "cross-site"),
+                ("origin", "https://xevion.dev")
+            ]
+        ));
+    }
+
+    #[test]
+    fn origin_must_be_a_public_host_over_https() {
+        let put = Method::PUT;
+        assert!(allows(
+            SessionType::Browser,
+            &put,
+            &[("origin", "https://walters.to")]
+        ));
+        assert!(!allows(
+            SessionType::Browser,
+            &put,
+            &[("origin", "http://xevion.dev")]
+        ));
+        assert!(!allows(
+            SessionType::Browser,
+            &put,
+            &[("origin", "https://evil.example")]
+        ));
+        assert!(!allows(SessionType::Browser, &put, &[("origin", "null")]));
+    }
+
+    #[test]
+    fn reads_bearer_sessions_and_non_browsers_pass() {
+        let cross = [("sec-fetch-site", "cross-site")];
+        assert!(allows(SessionType::Browser, &Method::GET, &cross));
+        assert!(allows(SessionType::Cli, &Method::DELETE, &cross));
+        assert!(allows(SessionType::Token, &Method::POST, &cross));
+        assert!(allows(SessionType::Browser, &Method::POST, &[]));
+    }
+
+    #[test]
+    fn dev_mode_matches_the_request_authority() {
+        let hosts = HostConfig::new(&[], "localhost");
+        let browser = session(SessionType::Browser);
+        let same = headers(&[
+            ("host", "localhost:10237"),
+            ("origin", "http://localhost:10237"),
+        ]);
+        assert!(check(&browser, &Method::POST, &same, &hosts).is_ok());
+        let other_port = headers(&[
+            ("host", "localhost:10237"),
+            ("origin", "http://localhost:5173"),
+        ]);
+        assert!(check(&browser, &Method::POST, &other_port, &hosts).is_err());
+    }
+}
@@ -62,6 +62,8 @@ pub struct EventFilters {
     pub level: Option<EventLevel>,
     pub entity_type: Option<String>,
     pub event_type: Option<String>,
+    /// Username that triggered the event.
+    pub actor: Option<String>,
 }
 
 /// Fetch events with optional filters, ordered by `created_at` DESC
@@ -86,6 +88,7 @@ pub async fn get_events(
         WHERE ($1::event_level IS NULL OR level = $1)
           AND ($2::text IS NULL OR entity_type = $2)
           AND ($3::text IS NULL OR event_type = $3)
+          AND ($6::text IS NULL OR actor = $6)
         ORDER BY created_at DESC
         LIMIT $4 OFFSET $5
         "#,
@@ -94,6 +97,7 @@ pub async fn get_events(
         filters.event_type,
         filters.limit,
         filters.offset,
+        filters.actor,
     )
     .fetch_all(pool)
     .await?;
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
 use ts_rs::TS;
 use uuid::Uuid;
 
@@ -13,17 +14,58 @@ pub enum MediaType {
     Video,
 }
 
-/// Database model for project media
+/// Processing lifecycle for uploaded media, matching the `PostgreSQL` enum.
+///
+/// Images are stored as `pending` and advanced by the background media queue;
+/// videos need no processing and are created `ready`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, TS)]
+#[sqlx(type_name = "media_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum MediaStatus {
+    Pending,
+    Processing,
+    Ready,
+    Failed,
+}
+
+impl MediaStatus {
+    /// Whether the media has finished processing successfully.
+    pub const fn is_ready(self) -> bool {
+        matches!(self, Self::Ready)
+    }
+}
+
+/// Database model for project media: an attachment joined with its library asset
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct DbProjectMedia {
     pub id: Uuid,
     pub project_id: Uuid,
     pub display_order: i32,
+    pub asset_id: Uuid,
+    pub media_type: MediaType,
+    pub r2_base_path: String,
+    pub variants: serde_json::Value,
+    pub blurhash: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+    pub status: MediaStatus,
+    pub processing_error: Option<String>,
+}
+
+/// Database model for a media library asset (the stored file and its processing state)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DbMediaAsset {
+    pub id: Uuid,
     pub media_type: MediaType,
+    pub original_filename: String,
     pub r2_base_path: String,
     pub variants: serde_json::Value,
+    pub size_bytes: i64,
     pub blurhash: Option<String>,
     pub metadata: Option<serde_json::Value>,
+    pub status: MediaStatus,
+    pub processing_error: Option<String>,
+    pub created_at: OffsetDateTime,
 }
 
 /// Variant info for images
@@ -130,6 +172,8 @@ pub struct FocalPoint {
 #[ts(export)]
 pub struct ApiProjectMedia {
     pub id: String,
+    /// The library asset this attachment points at.
+    pub asset_id: String,
     pub display_order: i32,
     pub media_type: MediaType,
     pub variants: ApiMediaVariants,
@@ -139,116 +183,252 @@ pub struct ApiProjectMedia {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[ts(optional)]
     pub metadata: Option<MediaMetadata>,
+    /// Processing state; only `ready` media has its resized variants.
+    pub status: MediaStatus,
+    /// Last processing failure, present while `failed` (or between retries).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub processing_error: Option<String>,
 }
 
-/// Base URL for R2 media storage
-const R2_BASE_URL: &str = "https://media.xevion.dev";
+/// API response type for a media library asset
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiMediaAsset {
+    pub id: String,
+    pub media_type: MediaType,
+    pub original_filename: String,
+    pub variants: ApiMediaVariants,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub blurhash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub metadata: Option<MediaMetadata>,
+    pub size_bytes: i64,
+    pub status: MediaStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub processing_error: Option<String>,
+    pub created_at: String,
+    /// Project attachments + figure blocks + settings slots using this asset.
+    pub ref_count: i64,
+    /// Where the asset is used; only included when fetching a single asset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub references: Option<ApiMediaReferences>,
+}
+
+/// Everything that keeps a library asset alive
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiMediaReferences {
+    /// Projects with the asset in their media gallery
+    pub projects: Vec<ApiMediaProjectRef>,
+    /// Projects whose detail page has a `figure` block using the asset
+    pub figures: Vec<ApiMediaProjectRef>,
+    /// Site settings slots (`avatar`, `favicon`)
+    pub settings: Vec<String>,
+}
+
+impl ApiMediaReferences {
+    pub const fn count(&self) -> i64 {
+        (self.projects.len() + self.figures.len() + self.settings.len()) as i64
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiMediaProjectRef {
+    pub id: String,
+    pub slug: String,
+    pub name: String,
+}
+
+/// Public base URL for stored media, set once at startup from the storage backend
+static MEDIA_BASE_URL: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+/// Used until [`set_media_base_url`] is called (the R2 bucket's public domain)
+const DEFAULT_MEDIA_BASE_URL: &str = "https://media.xevion.dev";
+
+/// Set the base URL media variant URLs are built from. Only the first call wins.
+pub fn set_media_base_url(url: String) {
+    let _ = MEDIA_BASE_URL.set(url);
+}
+
+fn media_base_url() -> &'static str {
+    MEDIA_BASE_URL
+        .get()
+        .map_or(DEFAULT_MEDIA_BASE_URL, String::as_str)
+}
 
 impl DbProjectMedia {
     /// Convert database media to API response format
     pub fn to_api_media(&self) -> ApiProjectMedia {
-        let variants = self.build_api_variants();
-        let metadata = self
-            .metadata
-            .as_ref()
-            .and_then(|m| serde_json::from_value(m.clone()).ok());
-
         ApiProjectMedia {
             id: self.id.to_string(),
+            asset_id: self.asset_id.to_string(),
             display_order: self.display_order,
             media_type: self.media_type,
-            variants,
+            variants: build_api_variants(self.media_type, &self.r2_base_path, &self.variants),
             blurhash: self.blurhash.clone(),
-            metadata,
+            metadata: parse_metadata(self.metadata.as_ref()),
+            status: self.status,
+            processing_error: self.processing_error.clone(),
         }
     }
+}
 
-    fn build_api_variants(&self) -> ApiMediaVariants {
-        let base_url = format!(
-            "{}/{}",
-            R2_BASE_URL,
-            self.r2_base_path.trim_end_matches('/')
-        );
-
-        let mut variants = ApiMediaVariants {
-            thumb: None,
-            medium: None,
-            full: None,
-            original: None,
-            poster: None,
-            video: None,
-        };
-
-        // Parse the JSONB variants
-        if let Some(obj) = self.variants.as_object() {
-            // Handle image variants
-            if let Some(thumb) = obj.get("thumb")
-                && let Ok(v) = serde_json::from_value::<ImageVariant>(thumb.clone())
-            {
-                variants.thumb = Some(ApiMediaVariant {
-                    url: format!("{}/{}", base_url, v.key),
-                    width: v.width,
-                    height: v.height,
-                });
-            }
+impl DbMediaAsset {
+    /// Whether the asset was uploaded for a project (`projects/{id}/...`) rather
+    /// than straight into the library (`media/...`). Only those are deleted
+    /// once nothing references them; library uploads stay until deleted.
+    pub fn uploaded_for_project(&self) -> bool {
+        self.r2_base_path.starts_with("projects/")
+    }
 
-            if let Some(medium) = obj.get("medium")
-                && let Ok(v) = serde_json::from_value::<ImageVariant>(medium.clone())
-            {
-                variants.medium = Some(ApiMediaVariant {
-                    url: format!("{}/{}", base_url, v.key),
-                    width: v.width,
-                    height: v.height,
-                });
-            }
+    /// Convert a library asset to API response format
+    pub fn to_api_asset(&self, ref_count: i64) -> ApiMediaAsset {
+        ApiMediaAsset {
+            id: self.id.to_string(),
+            media_type: self.media_type,
+            original_filename: self.original_filename.clone(),
+            variants: build_api_variants(self.media_type, &self.r2_base_path, &self.variants),
+            blurhash: self.blurhash.clone(),
+            metadata: parse_metadata(self.metadata.as_ref()),
+            size_bytes: self.size_bytes,
+            status: self.status,
+            processing_error: self.processing_error.clone(),
+            created_at: self.created_at.format(&Rfc3339).unwrap_or_default(),
+            ref_count,
+            references: None,
+        }
+    }
 
-            if let Some(full) = obj.get("full")
-                && let Ok(v) = serde_json::from_value::<ImageVariant>(full.clone())
-            {
-                variants.full = Some(ApiMediaVariant {
-                    url: format!("{}/{}", base_url, v.key),
-                    width: v.width,
-                    height: v.height,
-                });
+    /// Public URL of the stored file a `figure` block (or settings slot) should
+    /// show: the full-size image variant, or the video itself. Images that aren't
+    /// `ready` show the original, since their variants are missing or being
+    /// rebuilt.
+    pub fn display_url(&self) -> Option<String> {
+        let variants = build_api_variants(self.media_type, &self.r2_base_path, &self.variants);
+        match self.media_type {
+            MediaType::Image if self.status == MediaStatus::Ready => {
+                variants.full.or(variants.original).map(|v| v.url)
             }
+            MediaType::Image => variants.original.map(|v| v.url),
+            MediaType::Video => variants.video.map(|v| v.url),
+        }
+    }
 
-            // Handle original - could be image or video
-            if let Some(original) = obj.get("original") {
-                if self.media_type == MediaType::Video {
-                    // Video original has different structure
-                    if let Ok(v) = serde_json::from_value::<VideoOriginal>(original.clone()) {
-                        variants.video = Some(ApiVideoOriginal {
-                            url: format!("{}/{}", base_url, v.key),
-                            mime: v.mime,
-                            duration: v.duration,
-                        });
-                    }
-                } else {
-                    // Image original
-                    if let Ok(v) = serde_json::from_value::<ImageVariant>(original.clone()) {
-                        variants.original = Some(ApiMediaVariant {
-                            url: format!("{}/{}", base_url, v.key),
-                            width: v.width,
-                            height: v.height,
-                        });
-                    }
-                }
-            }
+    /// Public URL of the smallest image variant (thumb, falling back to larger ones)
+    pub fn thumb_url(&self) -> Option<String> {
+        let variants = build_api_variants(self.media_type, &self.r2_base_path, &self.variants);
+        variants
+            .thumb
+            .or(variants.medium)
+            .or(variants.full)
+            .or(variants.original)
+            .map(|v| v.url)
+    }
+}
+
+fn parse_metadata(metadata: Option<&serde_json::Value>) -> Option<MediaMetadata> {
+    metadata.and_then(|m| serde_json::from_value(m.clone()).ok())
+}
+
+/// Build public URLs for each stored variant
+fn build_api_variants(
+    media_type: MediaType,
+    r2_base_path: &str,
+    stored: &serde_json::Value,
+) -> ApiMediaVariants {
+    let base_url = format!(
+        "{}/{}",
+        media_base_url(),
+        r2_base_path.trim_end_matches('/')
+    );
+
+    let mut variants = ApiMediaVariants {
+        thumb: None,
+        medium: None,
+        full: None,
+        original: None,
+        poster: None,
+        video: None,
+    };
+
+    // Parse the JSONB variants
+    if let Some(obj) = stored.as_object() {
+        // Handle image variants
+        if let Some(thumb) = obj.get("thumb")
+            && let Ok(v) = serde_json::from_value::<ImageVariant>(thumb.clone())
+        {
+            variants.thumb = Some(ApiMediaVariant {
+                url: format!("{}/{}", base_url, v.key),
+                width: v.width,
+                height: v.height,
+            });
+        }
 
-            // Handle video poster
-            if let Some(poster) = obj.get("poster")
-                && let Ok(v) = serde_json::from_value::<ImageVariant>(poster.clone())
-            {
-                variants.poster = Some(ApiMediaVariant {
-                    url: format!("{}/{}", base_url, v.key),
-                    width: v.width,
-                    height: v.height,
-                });
+        if let Some(medium) = obj.get("medium")
+            && let Ok(v) = serde_json::from_value::<ImageVariant>(medium.clone())
+        {
+            variants.medium = Some(ApiMediaVariant {
+                url: format!("{}/{}", base_url, v.key),
+                width: v.width,
+                height: v.height,
+            });
+        }
+
+        if let Some(full) = obj.get("full")
+            && let Ok(v) = serde_json::from_value::<ImageVariant>(full.clone())
+        {
+            variants.full = Some(ApiMediaVariant {
+                url: format!("{}/{}", base_url, v.key),
+                width: v.width,
+                height: v.height,
+            });
+        }
+
+        // Handle original - could be image or video
+        if let Some(original) = obj.get("original") {
+            if media_type == MediaType::Video {
+                // Video original has different structure
+                if let Ok(v) = serde_json::from_value::<VideoOriginal>(original.clone()) {
+                    variants.video = Some(ApiVideoOriginal {
+                        url: format!("{}/{}", base_url, v.key),
+                        mime: v.mime,
+                        duration: v.duration,
+                    });
+                }
+            } else {
+                // Image original
+                if let Ok(v) = serde_json::from_value::<ImageVariant>(original.clone()) {
+                    variants.original = Some(ApiMediaVariant {
+                        url: format!("{}/{}", base_url, v.key),
+                        width: v.width,
+                        height: v.height,
+                    });
+                }
             }
         }
 
-        variants
+        // Handle video poster
+        if let Some(poster) = obj.get("poster")
+            && let Ok(v) = serde_json::from_value::<ImageVariant>(poster.clone())
+        {
+            variants.poster = Some(ApiMediaVariant {
+                url: format!("{}/{}", base_url, v.key),
+                width: v.width,
+                height: v.height,
+            });
+        }
     }
+
+    variants
 }
 
 // Database query functions
@@ -261,18 +441,22 @@ pub async fn get_media_for_project(
     sqlx::query_as!(
         DbProjectMedia,
         r#"
-        SELECT 
-            id,
-            project_id,
-            display_order,
-            media_type as "media_type: MediaType",
-            r2_base_path,
-            variants,
-            blurhash,
-            metadata
-        FROM project_media
-        WHERE project_id = $1
-        ORDER BY display_order ASC
+        SELECT
+            pm.id,
+            pm.project_id,
+            pm.display_order,
+            pm.asset_id,
+            a.media_type as "media_type: MediaType",
+            a.r2_base_path,
+            a.variants,
+            a.blurhash,
+            a.metadata,
+            a.status as "status: MediaStatus",
+            a.processing_error
+        FROM project_media pm
+        JOIN media_assets a ON a.id = pm.asset_id
+        WHERE pm.project_id = $1
+        ORDER BY pm.display_order ASC
         "#,
         project_id
     )
@@ -295,18 +479,22 @@ pub async fn get_media_for_projects(
     let rows = sqlx::query_as!(
         DbProjectMedia,
         r#"
-        SELECT 
-            id,
-            project_id,
-            display_order,
-            media_type as "media_type: MediaType",
-            r2_base_path,
-            variants,
-            blurhash,
-            metadata
-        FROM project_media
-        WHERE project_id = ANY($1)
-        ORDER BY project_id, display_order ASC
+        SELECT
+            pm.id,
+            pm.project_id,
+            pm.display_order,
+            pm.asset_id,
+            a.media_type as "media_type: MediaType",
+            a.r2_base_path,
+            a.variants,
+            a.blurhash,
+            a.metadata,
+            a.status as "status: MediaStatus",
+            a.processing_error
+        FROM project_media pm
+        JOIN media_assets a ON a.id = pm.asset_id
+        WHERE pm.project_id = ANY($1)
+        ORDER BY pm.project_id, pm.display_order ASC
         "#,
         project_ids
     )
@@ -327,7 +515,7 @@ pub async fn get_media_for_projects(
     Ok(result)
 }
 
-/// Get single media item by ID
+/// Get a single project media attachment by its ID
 pub async fn get_media_by_id(
     pool: &PgPool,
     id: Uuid,
@@ -335,17 +523,21 @@ pub async fn get_media_by_id(
     sqlx::query_as!(
         DbProjectMedia,
         r#"
-        SELECT 
-            id,
-            project_id,
-            display_order,
-            media_type as "media_type: MediaType",
-            r2_base_path,
-            variants,
-            blurhash,
-            metadata
-        FROM project_media
-        WHERE id = $1
+        SELECT
+            pm.id,
+            pm.project_id,
+            pm.display_order,
+            pm.asset_id,
+            a.media_type as "media_type: MediaType",
+            a.r2_base_path,
+            a.variants,
+            a.blurhash,
+            a.metadata,
+            a.status as "status: MediaStatus",
+            a.processing_error
+        FROM project_media pm
+        JOIN media_assets a ON a.id = pm.asset_id
+        WHERE pm.id = $1
         "#,
         id
     )
@@ -369,69 +561,408 @@ pub async fn get_next_display_order(pool: &PgPool, project_id: Uuid) -> Result<i
     Ok(result.next_order)
 }
 
-/// Create a new media record
-#[allow(clippy::too_many_arguments)]
-pub async fn create_media(
+/// Add a library asset to the end of a project's media gallery.
+///
+/// Fails with a unique violation if the asset is already attached.
+pub async fn attach_media(
     pool: &PgPool,
     project_id: Uuid,
+    asset_id: Uuid,
+) -> Result<DbProjectMedia, sqlx::Error> {
+    let display_order = get_next_display_order(pool, project_id).await?;
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO project_media (id, project_id, asset_id, display_order)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        project_id,
+        asset_id,
+        display_order
+    )
+    .fetch_one(pool)
+    .await?;
+
+    get_media_by_id(pool, id)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Remove a media attachment from its project (the asset stays in the library)
+pub async fn detach_media(pool: &PgPool, id: Uuid) -> Result<Option<DbProjectMedia>, sqlx::Error> {
+    let media = get_media_by_id(pool, id).await?;
+
+    if media.is_some() {
+        sqlx::query!("DELETE FROM project_media WHERE id = $1", id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(media)
+}
+
+/// Create a new library asset
+#[allow(clippy::too_many_arguments)]
+pub async fn create_media_asset(
+    pool: &PgPool,
+    id: Uuid,
     media_type: MediaType,
     original_filename: &str,
     r2_base_path: &str,
     variants: serde_json::Value,
-    width: Option<i32>,
-    height: Option<i32>,
     size_bytes: i64,
-    blurhash: Option<&str>,
     metadata: Option<serde_json::Value>,
-) -> Result<DbProjectMedia, sqlx::Error> {
-    let display_order = get_next_display_order(pool, project_id).await?;
-
+    status: MediaStatus,
+) -> Result<DbMediaAsset, sqlx::Error> {
     sqlx::query_as!(
-        DbProjectMedia,
+        DbMediaAsset,
         r#"
-        INSERT INTO project_media (
-            project_id, display_order, media_type, original_filename,
-            r2_base_path, variants, width, height, size_bytes, blurhash, metadata
+        INSERT INTO media_assets (
+            id, media_type, original_filename, r2_base_path, variants, size_bytes, metadata, status
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-        RETURNING 
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING
             id,
-            project_id,
-            display_order,
             media_type as "media_type: MediaType",
+            original_filename,
             r2_base_path,
             variants,
+            size_bytes,
             blurhash,
-            metadata
+            metadata,
+            status as "status: MediaStatus",
+            processing_error,
+            created_at
         "#,
-        project_id,
-        display_order,
+        id,
         media_type as MediaType,
         original_filename,
         r2_base_path,
         variants,
-        width,
-        height,
         size_bytes,
-        blurhash,
-        metadata
+        metadata,
+        status as MediaStatus
     )
     .fetch_one(pool)
     .await
 }
 
-/// Delete a media record
-pub async fn delete_media(pool: &PgPool, id: Uuid) -> Result<Option<DbProjectMedia>, sqlx::Error> {
-    // First get the media to return it
-    let media = get_media_by_id(pool, id).await?;
+/// Get a library asset by ID
+pub async fn get_media_asset(pool: &PgPool, id: Uuid) -> Result<Option<DbMediaAsset>, sqlx::Error> {
+    sqlx::query_as!(
+        DbMediaAsset,
+        r#"
+        SELECT
+            id,
+            media_type as "media_type: MediaType",
+            original_filename,
+            r2_base_path,
+            variants,
+            size_bytes,
+            blurhash,
+            metadata,
+            status as "status: MediaStatus",
+            processing_error,
+            created_at
+        FROM media_assets
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await
+}
 
-    if media.is_some() {
-        sqlx::query!("DELETE FROM project_media WHERE id = $1", id)
+/// Fetch several library assets at once (missing IDs are simply absent)
+pub async fn get_media_assets(
+    pool: &PgPool,
+    ids: &[Uuid],
+) -> Result<Vec<DbMediaAsset>, sqlx::Error> {
+    sqlx::query_as!(
+        DbMediaAsset,
+        r#"
+        SELECT
+            id,
+            media_type as "media_type: MediaType",
+            original_filename,
+            r2_base_path,
+            variants,
+            size_bytes,
+            blurhash,
+            metadata,
+            status as "status: MediaStatus",
+            processing_error,
+            created_at
+        FROM media_assets
+        WHERE id = ANY($1)
+        "#,
+        ids
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Every library asset, newest first
+pub async fn get_all_media_assets(pool: &PgPool) -> Result<Vec<DbMediaAsset>, sqlx::Error> {
+    sqlx::query_as!(
+        DbMediaAsset,
+        r#"
+        SELECT
+            id,
+            media_type as "media_type: MediaType",
+            original_filename,
+            r2_base_path,
+            variants,
+            size_bytes,
+            blurhash,
+            metadata,
+            status as "status: MediaStatus",
+            processing_error,
+            created_at
+        FROM media_assets
+        ORDER BY created_at DESC
+        "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Reference count of every library asset: project attachments, projects with
+/// `figure` blocks naming it in `attrs.mediaId`, and site settings slots. Matches
+/// [`ApiMediaReferences::count`].
+pub async fn get_media_ref_counts(
+    pool: &PgPool,
+) -> Result<std::collections::HashMap<Uuid, i64>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            a.id,
+            (SELECT COUNT(*) FROM project_media pm WHERE pm.asset_id = a.id)
+            + (
+                SELECT COUNT(*)
+                FROM projects p
+                WHERE jsonb_path_exists(
+                    p.detail_content,
+                    '$.** ? (@.type == "figure" && @.attrs.mediaId == $id)',
+                    jsonb_build_object('id', a.id::text)
+                )
+            )
+            + (SELECT COUNT(*) FROM site_identity s WHERE s.avatar_media_id = a.id)
+            + (SELECT COUNT(*) FROM site_identity s WHERE s.favicon_media_id = a.id)
+            AS "ref_count!"
+        FROM media_assets a
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| (r.id, r.ref_count)).collect())
+}
+
+/// Everything referencing a library asset
+pub async fn get_media_references(
+    pool: &PgPool,
+    asset_id: Uuid,
+) -> Result<ApiMediaReferences, sqlx::Error> {
+    let projects = sqlx::query!(
+        r#"
+        SELECT p.id, p.slug, p.name
+        FROM project_media pm
+        JOIN projects p ON p.id = pm.project_id
+        WHERE pm.asset_id = $1
+        ORDER BY p.name
+        "#,
+        asset_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let figures = sqlx::query!(
+        r#"
+        SELECT p.id, p.slug, p.name
+        FROM projects p
+        WHERE jsonb_path_exists(
+            p.detail_content,
+            '$.** ? (@.type == "figure" && @.attrs.mediaId == $id)',
+            jsonb_build_object('id', $1::uuid::text)
+        )
+        ORDER BY p.name
+        "#,
+        asset_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let settings = sqlx::query!(
+        r#"
+        SELECT avatar_media_id, favicon_media_id
+        FROM site_identity
+        WHERE id = 1
+        "#
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let mut slots = Vec::new();
+    if let Some(row) = settings {
+        if row.avatar_media_id == Some(asset_id) {
+            slots.push("avatar".to_string());
+        }
+        if row.favicon_media_id == Some(asset_id) {
+            slots.push("favicon".to_string());
+        }
+    }
+
+    Ok(ApiMediaReferences {
+        projects: projects
+            .into_iter()
+            .map(|p| ApiMediaProjectRef {
+                id: p.id.to_string(),
+                slug: p.slug,
+                name: p.name,
+            })
+            .collect(),
+        figures: figures
+            .into_iter()
+            .map(|p| ApiMediaProjectRef {
+                id: p.id.to_string(),
+                slug: p.slug,
+                name: p.name,
+            })
+            .collect(),
+        settings: slots,
+    })
+}
+
+/// Delete a library asset row. Attachments and settings slots hold `RESTRICT`
+/// foreign keys, so this fails with a foreign-key violation while those remain;
+/// callers check [`get_media_references`] first for a friendlier error.
+pub async fn delete_media_asset(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<DbMediaAsset>, sqlx::Error> {
+    let asset = get_media_asset(pool, id).await?;
+
+    if asset.is_some() {
+        sqlx::query!("DELETE FROM media_assets WHERE id = $1", id)
             .execute(pool)
             .await?;
     }
 
-    Ok(media)
+    Ok(asset)
+}
+
+/// Claim an asset for processing: mark it `processing` and bump the attempt counter.
+///
+/// Returns the new attempt count, or `None` if the asset no longer needs it:
+/// deleted while queued, already `ready`, or given up on as `failed`.
+pub async fn start_media_processing(pool: &PgPool, id: Uuid) -> Result<Option<i32>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        UPDATE media_assets
+        SET status = 'processing', processing_attempts = processing_attempts + 1
+        WHERE id = $1 AND status IN ('pending', 'processing')
+        RETURNING processing_attempts
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| r.processing_attempts))
+}
+
+/// Store processed variants and mark the asset `ready`
+pub async fn complete_media_processing(
+    pool: &PgPool,
+    id: Uuid,
+    variants: serde_json::Value,
+    width: i32,
+    height: i32,
+    blurhash: &str,
+) -> Result<Option<DbMediaAsset>, sqlx::Error> {
+    sqlx::query_as!(
+        DbMediaAsset,
+        r#"
+        UPDATE media_assets
+        SET status = 'ready', variants = $2, width = $3, height = $4, blurhash = $5,
+            processing_error = NULL
+        WHERE id = $1
+        RETURNING
+            id,
+            media_type as "media_type: MediaType",
+            original_filename,
+            r2_base_path,
+            variants,
+            size_bytes,
+            blurhash,
+            metadata,
+            status as "status: MediaStatus",
+            processing_error,
+            created_at
+        "#,
+        id,
+        variants,
+        width,
+        height,
+        blurhash
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Record a processing failure.
+///
+/// `status` is `pending` when a retry is scheduled and `failed` once retries are
+/// exhausted (or the error is permanent).
+pub async fn record_media_processing_error(
+    pool: &PgPool,
+    id: Uuid,
+    status: MediaStatus,
+    error: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE media_assets SET status = $2, processing_error = $3 WHERE id = $1",
+        id,
+        status as MediaStatus,
+        error
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// IDs of assets left `pending`/`processing` (e.g. by a restart), oldest first
+pub async fn get_unfinished_media_ids(pool: &PgPool) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM media_assets
+        WHERE status IN ('pending', 'processing')
+        ORDER BY created_at ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Put an asset back in the queue from scratch: `pending`, attempts and error cleared
+pub async fn reset_media_processing(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE media_assets
+        SET status = 'pending', processing_attempts = 0, processing_error = NULL
+        WHERE id = $1
+        "#,
+        id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
 }
 
 /// Reorder media for a project
@@ -274,6 +274,43 @@ pub struct AdminStats {
     #[ts(type = "Record<string, number>")]
     pub projects_by_status: serde_json::Value,
     pub total_tags: i32,
+    /// Progress of the most recent ISR cache warm; filled in by the handler
+    pub cache_warm: CacheWarmStatus,
+}
+
+/// What started a cache warm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum CacheWarmTrigger {
+    /// Every public page, rendered after the server starts
+    Startup,
+    /// Pages dropped by an invalidation, rendered once the burst settles
+    Invalidation,
+}
+
+/// Progress of the current (or last) cache warm.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct CacheWarmStatus {
+    pub running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub trigger: Option<CacheWarmTrigger>,
+    /// Pages in the current (or last) warm
+    pub total: u32,
+    /// Pages rendered so far, failures included
+    pub completed: u32,
+    pub failed: u32,
+    /// RFC 3339
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub started_at: Option<String>,
+    /// RFC 3339; absent while running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub finished_at: Option<String>,
 }
 
 pub async fn get_public_projects(pool: &PgPool) -> Result<Vec<DbProject>, sqlx::Error> {
@@ -670,6 +707,25 @@ pub async fn update_project_content(
     Ok(())
 }
 
+/// Like [`update_project_content`], but only if the stored document is still
+/// `expected`. Returns `false` when another save got there first.
+pub async fn replace_project_content(
+    pool: &PgPool,
+    id: Uuid,
+    expected: &serde_json::Value,
+    content: Option<&serde_json::Value>,
+) -> Result<bool, sqlx::Error> {
+    let result = query!(
+        "UPDATE projects SET detail_content = $1 WHERE id = $2 AND detail_content = $3",
+        content as Option<&serde_json::Value>,
+        id,
+        expected
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
 /// Delete project (CASCADE will handle tags)
 pub async fn delete_project(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
     query!("DELETE FROM projects WHERE id = $1", id)
@@ -678,6 +734,13 @@ pub async fn delete_project(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error>
     Ok(())
 }
 
+/// IDs of every project, public or not
+pub async fn get_all_project_ids(pool: &PgPool) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!("SELECT id FROM projects")
+        .fetch_all(pool)
+        .await
+}
+
 /// Get admin stats
 pub async fn get_admin_stats(pool: &PgPool) -> Result<AdminStats, sqlx::Error> {
     // Get project counts by status
@@ -715,6 +778,7 @@ pub async fn get_admin_stats(pool: &PgPool) -> Result<AdminStats, sqlx::Error> {
         total_projects,
         projects_by_status,
         total_tags: tag_count.count,
+        cache_warm: CacheWarmStatus::default(),
     })
 }
 
@@ -3,6 +3,8 @@ use sqlx::PgPool;
 use ts_rs::TS;
 use uuid::Uuid;
 
+use super::media::{DbMediaAsset, get_media_assets};
+
 // Site settings models
 
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -11,6 +13,8 @@ pub struct DbSiteIdentity {
     pub occupation: String,
     pub bio: String,
     pub site_title: String,
+    pub avatar_media_id: Option<Uuid>,
+    pub favicon_media_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -33,6 +37,20 @@ pub struct ApiSiteIdentity {
     pub occupation: String,
     pub bio: String,
     pub site_title: String,
+    /// Media library asset used as the profile avatar
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub avatar_media_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub avatar_url: Option<String>,
+    /// Media library asset used as the favicon
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub favicon_media_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub favicon_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -64,6 +82,12 @@ pub struct UpdateSiteIdentityRequest {
     pub occupation: String,
     pub bio: String,
     pub site_title: String,
+    /// Library image for the avatar. PUT replace (absent/null clears it).
+    #[serde(default)]
+    pub avatar_media_id: Option<Uuid>,
+    /// Library image for the favicon. PUT replace (absent/null clears it).
+    #[serde(default)]
+    pub favicon_media_id: Option<Uuid>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -87,14 +111,31 @@ pub struct UpdateSiteSettingsRequest {
 
 // Conversion implementations
 impl DbSiteIdentity {
-    pub fn to_api(&self) -> ApiSiteIdentity {
+    /// Convert to the API shape; `assets` supplies the avatar/favicon URLs.
+    pub fn to_api(&self, assets: &[DbMediaAsset]) -> ApiSiteIdentity {
+        let url_of = |id: Option<Uuid>| {
+            id.and_then(|id| assets.iter().find(|a| a.id == id))
+                .and_then(DbMediaAsset::thumb_url)
+        };
+
         ApiSiteIdentity {
             display_name: self.display_name.clone(),
             occupation: self.occupation.clone(),
             bio: self.bio.clone(),
             site_title: self.site_title.clone(),
+            avatar_media_id: self.avatar_media_id.map(|id| id.to_string()),
+            avatar_url: url_of(self.avatar_media_id),
+            favicon_media_id: self.favicon_media_id.map(|id| id.to_string()),
+            favicon_url: url_of(self.favicon_media_id),
         }
     }
+
+    fn media_ids(&self) -> Vec<Uuid> {
+        self.avatar_media_id
+            .into_iter()
+            .chain(self.favicon_media_id)
+            .collect()
+    }
 }
 
 impl DbSocialLink {
@@ -117,13 +158,14 @@ pub async fn get_site_settings(pool: &PgPool) -> Result<ApiSiteSettings, sqlx::E
     let identity = sqlx::query_as!(
         DbSiteIdentity,
         r#"
-        SELECT display_name, occupation, bio, site_title
+        SELECT display_name, occupation, bio, site_title, avatar_media_id, favicon_media_id
         FROM site_identity
         WHERE id = 1
         "#
     )
     .fetch_one(pool)
     .await?;
+    let assets = get_media_assets(pool, &identity.media_ids()).await?;
 
     // Get social links (ordered)
     let social_links = sqlx::query_as!(
@@ -138,7 +180,7 @@ pub async fn get_site_settings(pool: &PgPool) -> Result<ApiSiteSettings, sqlx::E
     .await?;
 
     Ok(ApiSiteSettings {
-        identity: identity.to_api(),
+        identity: identity.to_api(&assets),
         social_links: social_links.into_iter().map(|sl| sl.to_api()).collect(),
     })
 }
@@ -151,14 +193,17 @@ pub async fn update_site_identity(
         DbSiteIdentity,
         r#"
         UPDATE site_identity
-        SET display_name = $1, occupation = $2, bio = $3, site_title = $4
+        SET display_name = $1, occupation = $2, bio = $3, site_title = $4,
+            avatar_media_id = $5, favicon_media_id = $6
         WHERE id = 1
-        RETURNING display_name, occupation, bio, site_title
+        RETURNING display_name, occupation, bio, site_title, avatar_media_id, favicon_media_id
         "#,
         req.display_name,
         req.occupation,
         req.bio,
-        req.site_title
+        req.site_title,
+        req.avatar_media_id,
+        req.favicon_media_id
     )
     .fetch_one(pool)
     .await
@@ -209,8 +254,10 @@ pub async fn update_site_settings(
         updated_links.push(link);
     }
 
+    let assets = get_media_assets(pool, &identity.media_ids()).await?;
+
     Ok(ApiSiteSettings {
-        identity: identity.to_api(),
+        identity: identity.to_api(&assets),
         social_links: updated_links.into_iter().map(|sl| sl.to_api()).collect(),
     })
 }
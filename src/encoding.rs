@@ -1,7 +1,7 @@
 //! Content encoding negotiation and compression utilities
 //!
 //! Handles Accept-Encoding header parsing with quality values
-//! and provides compression helpers for ISR cache.
+//! and provides compression helpers for the ISR cache and embedded assets.
 
 use axum::http::{HeaderMap, HeaderValue, header};
 use std::io::Write;
@@ -33,6 +33,24 @@ impl ContentEncoding {
         }
     }
 
+    /// Token used for this encoding in `Accept-Encoding`/`Content-Encoding`
+    #[inline]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Zstd => "zstd",
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+            Self::Identity => "identity",
+        }
+    }
+
+    /// Parse a token produced by [`Self::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Zstd, Self::Brotli, Self::Gzip, Self::Identity]
+            .into_iter()
+            .find(|encoding| encoding.name() == name)
+    }
+
     /// Content-Encoding header value
     #[inline]
     pub const fn header_value(self) -> Option<HeaderValue> {
@@ -133,7 +151,6 @@ pub fn negotiate_encoding(headers: &HeaderMap) -> ContentEncoding {
 }
 
 /// Check if content type should be compressed
-#[cfg(test)]
 #[inline]
 pub fn is_compressible_content_type(content_type: &str) -> bool {
     let ct = content_type.to_lowercase();
@@ -147,6 +164,16 @@ pub fn is_compressible_content_type(content_type: &str) -> bool {
         || (ct.contains("font") && !ct.contains("woff"))
 }
 
+/// Compress data with `encoding`; `None` for identity or on failure
+pub fn compress(data: &[u8], encoding: ContentEncoding) -> Option<Vec<u8>> {
+    match encoding {
+        ContentEncoding::Zstd => compress_zstd(data),
+        ContentEncoding::Brotli => compress_brotli(data),
+        ContentEncoding::Gzip => compress_gzip(data),
+        ContentEncoding::Identity => None,
+    }
+}
+
 /// Compress data with zstd at fast level (level 3)
 pub fn compress_zstd(data: &[u8]) -> Option<Vec<u8>> {
     match zstd::encode_all(std::io::Cursor::new(data), 3) {
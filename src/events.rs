@@ -50,6 +50,44 @@ pub enum EventType {
     OgImageFailed,
     #[serde(rename = "cache.invalidated")]
     CacheInvalidated,
+    #[serde(rename = "media.uploaded")]
+    MediaUploaded,
+    #[serde(rename = "media.processing")]
+    MediaProcessing,
+    #[serde(rename = "media.processed")]
+    MediaProcessed,
+    #[serde(rename = "media.failed")]
+    MediaFailed,
+    #[serde(rename = "storage.gc")]
+    StorageGc,
+    #[serde(rename = "auth.login_failed")]
+    AuthLoginFailed,
+    #[serde(rename = "auth.locked_out")]
+    AuthLockedOut,
+    #[serde(rename = "auth.lockout_cleared")]
+    AuthLockoutCleared,
+    #[serde(rename = "auth.totp_enabled")]
+    AuthTotpEnabled,
+    #[serde(rename = "auth.totp_disabled")]
+    AuthTotpDisabled,
+    #[serde(rename = "auth.recovery_code_used")]
+    AuthRecoveryCodeUsed,
+    #[serde(rename = "auth.passkey_added")]
+    AuthPasskeyAdded,
+    #[serde(rename = "auth.passkey_removed")]
+    AuthPasskeyRemoved,
+    #[serde(rename = "auth.password_changed")]
+    AuthPasswordChanged,
+    #[serde(rename = "auth.token_created")]
+    AuthTokenCreated,
+    #[serde(rename = "auth.token_revoked")]
+    AuthTokenRevoked,
+    #[serde(rename = "user.created")]
+    UserCreated,
+    #[serde(rename = "user.role_changed")]
+    UserRoleChanged,
+    #[serde(rename = "user.deleted")]
+    UserDeleted,
 }
 
 impl EventType {
@@ -71,6 +109,25 @@ impl EventType {
             Self::OgImageGenerated => "og.generated",
             Self::OgImageFailed => "og.failed",
             Self::CacheInvalidated => "cache.invalidated",
+            Self::MediaUploaded => "media.uploaded",
+            Self::MediaProcessing => "media.processing",
+            Self::MediaProcessed => "media.processed",
+            Self::MediaFailed => "media.failed",
+            Self::StorageGc => "storage.gc",
+            Self::AuthLoginFailed => "auth.login_failed",
+            Self::AuthLockedOut => "auth.locked_out",
+            Self::AuthLockoutCleared => "auth.lockout_cleared",
+            Self::AuthTotpEnabled => "auth.totp_enabled",
+            Self::AuthTotpDisabled => "auth.totp_disabled",
+            Self::AuthRecoveryCodeUsed => "auth.recovery_code_used",
+            Self::AuthPasskeyAdded => "auth.passkey_added",
+            Self::AuthPasskeyRemoved => "auth.passkey_removed",
+            Self::AuthPasswordChanged => "auth.password_changed",
+            Self::AuthTokenCreated => "auth.token_created",
+            Self::AuthTokenRevoked => "auth.token_revoked",
+            Self::UserCreated => "user.created",
+            Self::UserRoleChanged => "user.role_changed",
+            Self::UserDeleted => "user.deleted",
         }
     }
 }
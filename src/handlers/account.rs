@@ -0,0 +1,302 @@
+//! The signed-in admin's own account: password changes and TOTP two-factor
+//! enrollment.
+//!
+//! Enrollment is two steps: `setup` stores a pending secret and returns it as
+//! an `otpauth://` URI, and `confirm` enables it once the authenticator app
+//! produces a matching code, returning one-time recovery codes. Disabling 2FA
+//! or replacing recovery codes requires a current code.
+
+use axum::{
+    Json,
+    extract::{ConnectInfo, Extension, State},
+    http::HeaderMap,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{net::SocketAddr, sync::Arc};
+use ts_rs::TS;
+
+use crate::{
+    auth::{self, AdminUser},
+    events::{self, EventLevel, EventType},
+    handlers::auth::{
+        begin_attempt, client_ip, log_failed_login, require_second_factor, verify_code,
+    },
+    state::{AdminSession, AppError, AppResult, AppState},
+    totp,
+};
+
+/// 2FA state of the signed-in admin.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiTotpStatus {
+    pub enabled: bool,
+    /// A secret has been issued but not confirmed yet.
+    pub pending: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+/// A freshly issued, unconfirmed TOTP secret.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiTotpSetup {
+    /// Base32 secret, for manual entry.
+    pub secret: String,
+    /// `otpauth://` URI, for a QR code.
+    pub otpauth_uri: String,
+}
+
+/// Recovery codes, shown exactly once.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiRecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Body of the endpoints that need a current TOTP or recovery code.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpCodeRequest {
+    #[serde(default)]
+    pub totp_code: Option<String>,
+}
+
+/// `PUT /api/account/password` request body.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+    /// Also sign out every other browser session, CLI login, and API token.
+    #[serde(default)]
+    pub revoke_other_sessions: bool,
+}
+
+async fn current_user(state: &AppState, session: &AdminSession) -> AppResult<AdminUser> {
+    auth::get_admin_user(&state.pool, &session.0.username)
+        .await?
+        .ok_or(AppError::Unauthorized)
+}
+
+fn log_totp_event(state: &AppState, event_type: EventType, username: &str, message: &str) {
+    events::log_event(
+        &state.event_sender,
+        event_type,
+        EventLevel::Info,
+        Some("auth"),
+        None,
+        Some(username),
+        message.to_string(),
+        None,
+    );
+}
+
+/// Change the signed-in admin's password. The current password is checked
+/// through the login throttle, so this can't be used to guess it.
+#[tracing::instrument(skip_all)]
+pub async fn change_password_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> AppResult<impl IntoResponse> {
+    auth::validate_password(&payload.new_password)
+        .map_err(|msg| AppError::field("newPassword", msg))?;
+    if payload.new_password == payload.current_password {
+        return Err(AppError::field(
+            "newPassword",
+            "Must differ from the current password",
+        ));
+    }
+
+    let user = current_user(&state, &session).await?;
+    let client_ip = client_ip(peer, &headers);
+    let attempt = begin_attempt(&state, client_ip, &user.username).await?;
+    let valid = auth::verify_password(&payload.current_password, &user.password_hash)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    if !valid {
+        log_failed_login(&state, &attempt, &user.username, client_ip, "password");
+        return Err(AppError::field("currentPassword", "Incorrect password"));
+    }
+    attempt.succeeded();
+
+    let password_hash = auth::hash_password(&payload.new_password)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    if !auth::set_admin_password(&state.pool, user.id, &password_hash).await? {
+        return Err(AppError::Unauthorized);
+    }
+    let revoked = if payload.revoke_other_sessions {
+        state
+            .session_manager
+            .revoke_other_sessions(user.id, session.0.id)
+            .await?
+    } else {
+        0
+    };
+
+    tracing::info!(username = %user.username, revoked, "Password changed");
+    events::log_event(
+        &state.event_sender,
+        EventType::AuthPasswordChanged,
+        EventLevel::Info,
+        Some("auth"),
+        None,
+        Some(&user.username),
+        if revoked > 0 {
+            format!("Password changed, {revoked} other sessions revoked")
+        } else {
+            "Password changed".to_string()
+        },
+        Some(json!({ "ip": client_ip.to_string(), "revokedSessions": revoked })),
+    );
+
+    Ok(Json(json!({ "success": true, "revokedSessions": revoked })))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn totp_status_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+) -> AppResult<impl IntoResponse> {
+    let user = current_user(&state, &session).await?;
+    let recovery_codes_remaining = if user.totp_enabled {
+        auth::recovery_codes_remaining(&state.pool, user.id).await?
+    } else {
+        0
+    };
+    Ok(Json(ApiTotpStatus {
+        enabled: user.totp_enabled,
+        pending: !user.totp_enabled && user.totp_secret.is_some(),
+        recovery_codes_remaining,
+    }))
+}
+
+/// Issue a new pending secret. Calling it again replaces the pending secret;
+/// once 2FA is enabled it has to be disabled first.
+#[tracing::instrument(skip_all)]
+pub async fn totp_setup_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+) -> AppResult<impl IntoResponse> {
+    let user = current_user(&state, &session).await?;
+    let secret = totp::generate_secret();
+    if !auth::begin_totp_enrollment(&state.pool, user.id, &secret).await? {
+        return Err(AppError::Conflict(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    tracing::info!(username = %user.username, "TOTP enrollment started");
+    Ok(Json(ApiTotpSetup {
+        otpauth_uri: totp::otpauth_uri(&user.username, &secret),
+        secret,
+    }))
+}
+
+/// Enable the pending secret with a code from the authenticator app.
+#[tracing::instrument(skip_all)]
+pub async fn totp_confirm_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    Json(payload): Json<TotpCodeRequest>,
+) -> AppResult<impl IntoResponse> {
+    let user = current_user(&state, &session).await?;
+    if user.totp_enabled {
+        return Err(AppError::Conflict(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+    if user.totp_secret.is_none() {
+        return Err(AppError::validation("Start two-factor setup first"));
+    }
+    verify_code(
+        &state,
+        &user,
+        payload.totp_code.as_deref(),
+        client_ip(peer, &headers),
+    )
+    .await?;
+
+    let recovery_codes = totp::generate_recovery_codes();
+    if !auth::confirm_totp_enrollment(&state.pool, user.id, &recovery_codes).await? {
+        return Err(AppError::Conflict(
+            "Two-factor setup changed concurrently; start again".to_string(),
+        ));
+    }
+
+    tracing::info!(username = %user.username, "TOTP enabled");
+    log_totp_event(
+        &state,
+        EventType::AuthTotpEnabled,
+        &user.username,
+        "Two-factor authentication enabled",
+    );
+    Ok(Json(ApiRecoveryCodes { recovery_codes }))
+}
+
+/// Turn 2FA off. Requires a current code.
+#[tracing::instrument(skip_all)]
+pub async fn totp_disable_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    Json(payload): Json<TotpCodeRequest>,
+) -> AppResult<impl IntoResponse> {
+    let user = current_user(&state, &session).await?;
+    if !user.totp_enabled {
+        return Err(AppError::NotFound);
+    }
+    require_second_factor(
+        &state,
+        &user,
+        payload.totp_code.as_deref(),
+        client_ip(peer, &headers),
+    )
+    .await?;
+    auth::disable_totp(&state.pool, user.id).await?;
+
+    tracing::info!(username = %user.username, "TOTP disabled");
+    log_totp_event(
+        &state,
+        EventType::AuthTotpDisabled,
+        &user.username,
+        "Two-factor authentication disabled",
+    );
+    Ok(Json(json!({ "success": true })))
+}
+
+/// Replace the recovery codes. Requires a current code.
+#[tracing::instrument(skip_all)]
+pub async fn recovery_codes_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    Json(payload): Json<TotpCodeRequest>,
+) -> AppResult<impl IntoResponse> {
+    let user = current_user(&state, &session).await?;
+    if !user.totp_enabled {
+        return Err(AppError::NotFound);
+    }
+    require_second_factor(
+        &state,
+        &user,
+        payload.totp_code.as_deref(),
+        client_ip(peer, &headers),
+    )
+    .await?;
+
+    let recovery_codes = totp::generate_recovery_codes();
+    auth::regenerate_recovery_codes(&state.pool, user.id, &recovery_codes).await?;
+
+    tracing::info!(username = %user.username, "Recovery codes regenerated");
+    Ok(Json(ApiRecoveryCodes { recovery_codes }))
+}
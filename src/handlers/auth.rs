@@ -1,9 +1,23 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
-use std::sync::Arc;
+use axum::{
+    Json,
+    extract::{ConnectInfo, Extension, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use serde_json::json;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
 
 use crate::{
-    auth,
+    auth::{self, AdminUser, ClientInfo, SecondFactor, Session},
+    events::{self, EventLevel, EventType},
+    handlers::AdminRole,
+    login_throttle::LoginAttempt,
     state::{AdminSession, AppError, AppResult, AppState},
+    tarpit,
 };
 
 #[derive(serde::Deserialize)]
@@ -11,6 +25,9 @@ use crate::{
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    /// TOTP or recovery code; required once the account has 2FA enabled.
+    #[serde(default)]
+    pub totp_code: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -25,7 +42,8 @@ pub struct LoginResponse {
 pub struct SessionResponse {
     pub authenticated: bool,
     pub username: String,
-    /// "browser" or "cli".
+    pub role: AdminRole,
+    /// "browser", "cli", or "token".
     pub session_type: String,
     pub expires_at: String,
 }
@@ -33,37 +51,52 @@ pub struct SessionResponse {
 #[tracing::instrument(skip_all)]
 pub async fn api_login_handler(
     State(state): State<Arc<AppState>>,
+    // Absent on the Unix socket listener
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
     jar: axum_extra::extract::CookieJar,
     Json(payload): Json<LoginRequest>,
 ) -> AppResult<impl IntoResponse> {
-    let user = auth::get_admin_user(&state.pool, &payload.username)
-        .await?
-        .ok_or(AppError::InvalidCredentials)?;
+    let client_ip = client_ip(peer, &headers);
+    let attempt = begin_attempt(&state, client_ip, &payload.username).await?;
+
+    let Some(user) = auth::get_admin_user(&state.pool, &payload.username).await? else {
+        log_failed_login(&state, &attempt, &payload.username, client_ip, "password");
+        return Err(AppError::InvalidCredentials);
+    };
 
     let password_valid = auth::verify_password(&payload.password, &user.password_hash)
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
     if !password_valid {
+        log_failed_login(&state, &attempt, &payload.username, client_ip, "password");
         return Err(AppError::InvalidCredentials);
     }
 
+    if user.totp_enabled {
+        // A missing code leaves the attempt counted: the password is only
+        // half of the credentials
+        let code = non_empty(payload.totp_code.as_deref()).ok_or(AppError::SecondFactorRequired)?;
+        if !check_second_factor(&state, &attempt, &user, code, client_ip).await? {
+            return Err(AppError::InvalidCredentials);
+        }
+    }
+    attempt.succeeded();
+
     let session = state
         .session_manager
-        .create_session(user.id, user.username.clone())
+        .create_session(
+            user.id,
+            user.username.clone(),
+            user.role,
+            ClientInfo::from_headers(&headers, Some(client_ip)),
+        )
         .await?;
 
-    let cookie =
-        axum_extra::extract::cookie::Cookie::build(("admin_session", session.id.to_string()))
-            .path("/")
-            .http_only(true)
-            .same_site(axum_extra::extract::cookie::SameSite::Lax)
-            .max_age(time::Duration::days(7))
-            .build();
-
     tracing::info!(username = %user.username, "User logged in");
 
     Ok((
-        jar.add(cookie),
+        jar.add(session_cookie(&session)),
         Json(LoginResponse {
             success: true,
             username: user.username,
@@ -71,18 +104,196 @@ pub async fn api_login_handler(
     ))
 }
 
+/// The `admin_session` cookie for a browser session, lasting as long as the
+/// session does
+pub(crate) fn session_cookie(session: &Session) -> Cookie<'static> {
+    Cookie::build(("admin_session", session.id.to_string()))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(session.expires_at - time::OffsetDateTime::now_utc())
+        .build()
+}
+
+/// Client address of a request, preferring the reverse proxy's headers
+pub(crate) fn client_ip(
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: &HeaderMap,
+) -> IpAddr {
+    tarpit::extract_client_ip(headers, peer.map(|Extension(ConnectInfo(addr))| addr))
+}
+
+fn non_empty(code: Option<&str>) -> Option<&str> {
+    code.map(str::trim).filter(|code| !code.is_empty())
+}
+
+/// Admit a credential check through the login throttle, holding it for the
+/// username's backoff first
+pub(crate) async fn begin_attempt<'a>(
+    state: &'a AppState,
+    client_ip: IpAddr,
+    username: &str,
+) -> AppResult<LoginAttempt<'a>> {
+    let attempt = state
+        .login_throttle
+        .begin(client_ip, username)
+        .map_err(|retry_after| {
+            tracing::info!(
+                client_ip = %client_ip,
+                retry_after_secs = retry_after.as_secs(),
+                "Login attempt throttled"
+            );
+            AppError::TooManyRequests(retry_after)
+        })?;
+    if !attempt.delay.is_zero() {
+        tracing::info!(
+            client_ip = %client_ip,
+            delay_ms = attempt.delay.as_millis() as u64,
+            "Login attempt delayed"
+        );
+        tokio::time::sleep(attempt.delay).await;
+    }
+    Ok(attempt)
+}
+
+/// Verify `code` as the second factor of `user` within a throttled `attempt`,
+/// logging failures and spent recovery codes
+async fn check_second_factor(
+    state: &AppState,
+    attempt: &LoginAttempt<'_>,
+    user: &AdminUser,
+    code: &str,
+    client_ip: IpAddr,
+) -> AppResult<bool> {
+    match auth::verify_second_factor(&state.pool, user, code).await? {
+        Some(SecondFactor::Totp) => Ok(true),
+        Some(SecondFactor::RecoveryCode) => {
+            let remaining = auth::recovery_codes_remaining(&state.pool, user.id).await?;
+            tracing::warn!(username = %user.username, remaining, "Recovery code used");
+            events::log_event(
+                &state.event_sender,
+                EventType::AuthRecoveryCodeUsed,
+                EventLevel::Warning,
+                Some("auth"),
+                None,
+                Some(&user.username),
+                format!("Recovery code used from {client_ip}, {remaining} left"),
+                Some(json!({ "ip": client_ip.to_string(), "remaining": remaining })),
+            );
+            Ok(true)
+        }
+        None => {
+            log_failed_login(state, attempt, &user.username, client_ip, "second factor");
+            Ok(false)
+        }
+    }
+}
+
+/// Require a fresh second factor from a signed-in `user` before a sensitive
+/// action. A no-op unless the account has 2FA enabled; codes are throttled
+/// like logins.
+pub(crate) async fn require_second_factor(
+    state: &AppState,
+    user: &AdminUser,
+    code: Option<&str>,
+    client_ip: IpAddr,
+) -> AppResult<()> {
+    if !user.totp_enabled {
+        return Ok(());
+    }
+    verify_code(state, user, code, client_ip).await
+}
+
+/// Verify `code` against the TOTP secret or recovery codes of `user`, whether
+/// or not 2FA is enabled yet; throttled like logins.
+pub(crate) async fn verify_code(
+    state: &AppState,
+    user: &AdminUser,
+    code: Option<&str>,
+    client_ip: IpAddr,
+) -> AppResult<()> {
+    let code = non_empty(code).ok_or(AppError::SecondFactorRequired)?;
+    let attempt = begin_attempt(state, client_ip, &user.username).await?;
+    if !check_second_factor(state, &attempt, user, code, client_ip).await? {
+        return Err(AppError::field("totpCode", "Invalid or already used code"));
+    }
+    attempt.succeeded();
+    Ok(())
+}
+
+/// Record a failed login, and any lockouts it caused, as events. `factor` is
+/// the credential that was rejected.
+pub(crate) fn log_failed_login(
+    state: &AppState,
+    attempt: &LoginAttempt<'_>,
+    username: &str,
+    client_ip: IpAddr,
+    factor: &str,
+) {
+    tracing::warn!(
+        client_ip = %client_ip,
+        failures = attempt.failures,
+        factor,
+        "Failed login attempt"
+    );
+    events::log_event(
+        &state.event_sender,
+        EventType::AuthLoginFailed,
+        EventLevel::Warning,
+        Some("auth"),
+        None,
+        None,
+        format!("Failed login for {username:?} from {client_ip} ({factor})"),
+        Some(json!({
+            "username": username,
+            "ip": client_ip.to_string(),
+            "failures": attempt.failures,
+            "factor": factor,
+        })),
+    );
+
+    let lockout_secs = state.login_throttle.config.lockout.as_secs();
+    for key in &attempt.locks_out {
+        tracing::warn!(key = %key, lockout_secs, "Login locked out");
+        events::log_event(
+            &state.event_sender,
+            EventType::AuthLockedOut,
+            EventLevel::Warning,
+            Some("auth"),
+            None,
+            None,
+            format!(
+                "Locked out {} {} for {lockout_secs}s",
+                key.kind(),
+                key.value()
+            ),
+            Some(json!({
+                "key": key.to_string(),
+                "username": username,
+                "ip": client_ip.to_string(),
+                "failures": attempt.failures,
+                "lockoutSecs": lockout_secs,
+            })),
+        );
+    }
+}
+
 /// Logout handler - revokes the session that authenticated the request, whether
 /// that's a browser cookie or a CLI bearer token.
 #[tracing::instrument(skip_all)]
 pub async fn api_logout_handler(
     State(state): State<Arc<AppState>>,
+    method: axum::http::Method,
     headers: axum::http::HeaderMap,
+    extensions: axum::http::Extensions,
     jar: axum_extra::extract::CookieJar,
-) -> (axum_extra::extract::CookieJar, StatusCode) {
-    if let Some(session) = auth::authenticate(&state, &headers).await
-        && let Err(e) = state.session_manager.delete_session(session.id).await
-    {
-        tracing::error!(error = %e, "Failed to delete session during logout");
+) -> AppResult<(axum_extra::extract::CookieJar, StatusCode)> {
+    if let Some(session) = auth::authenticate(&state, &headers, &extensions).await {
+        // Another site can't sign the admin out either
+        crate::csrf::check(&session, &method, &headers, &state.host_config)?;
+        if let Err(e) = state.session_manager.delete_session(session.id).await {
+            tracing::error!(error = %e, "Failed to delete session during logout");
+        }
     }
 
     let cookie = axum_extra::extract::cookie::Cookie::build(("admin_session", ""))
@@ -90,7 +301,7 @@ pub async fn api_logout_handler(
         .max_age(time::Duration::ZERO)
         .build();
 
-    (jar.add(cookie), StatusCode::OK)
+    Ok((jar.add(cookie), StatusCode::OK))
 }
 
 /// Session check handler - returns current session status
@@ -99,11 +310,8 @@ pub async fn api_session_handler(session: AdminSession) -> Json<SessionResponse>
     Json(SessionResponse {
         authenticated: true,
         username: session.0.username,
-        session_type: match session.0.session_type {
-            crate::auth::SessionType::Browser => "browser",
-            crate::auth::SessionType::Cli => "cli",
-        }
-        .to_string(),
+        role: session.0.role,
+        session_type: session.0.session_type.to_string(),
         expires_at: session
             .0
             .expires_at
@@ -0,0 +1,111 @@
+//! ISR cache inspection and purging for admins.
+
+use axum::{Json, extract::State, response::IntoResponse};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::{
+    cache::PurgeSelector,
+    events::{self, EventLevel, EventType},
+    handlers::{CachePurgeReport, CachePurgeRequest},
+    state::{AdminSession, AppError, AppResult, AppState, EditorSession},
+};
+
+/// List every cached page (requires auth)
+#[tracing::instrument(skip_all)]
+pub async fn list_cache_entries_handler(
+    State(state): State<Arc<AppState>>,
+    _: AdminSession,
+) -> impl IntoResponse {
+    Json(state.isr_cache.entries())
+}
+
+/// Cache occupancy and hit/miss counters (requires auth)
+#[tracing::instrument(skip_all)]
+pub async fn cache_stats_handler(
+    State(state): State<Arc<AppState>>,
+    _: AdminSession,
+) -> impl IntoResponse {
+    Json(state.isr_cache.stats())
+}
+
+/// Drop cached pages by target, prefix, glob, or all of them (requires auth)
+#[tracing::instrument(skip_all)]
+pub async fn purge_cache_handler(
+    State(state): State<Arc<AppState>>,
+    session: EditorSession,
+    Json(req): Json<CachePurgeRequest>,
+) -> AppResult<impl IntoResponse> {
+    let selector = purge_selector(&req)?;
+    let host = req.host.as_deref().map(str::to_ascii_lowercase);
+
+    let purged = state.isr_cache.purge(host.as_deref(), &selector).await;
+    tracing::info!(selector = ?selector, host = ?host, purged = purged.len(), "ISR cache purged");
+    events::log_event(
+        &state.event_sender,
+        EventType::CacheInvalidated,
+        EventLevel::Info,
+        Some("cache"),
+        None,
+        Some(&session.0.username),
+        format!("Purged {} cached page(s)", purged.len()),
+        Some(json!({
+            "selector": describe(&selector),
+            "host": host,
+            "purged": purged.len(),
+        })),
+    );
+
+    Ok(Json(CachePurgeReport { purged }))
+}
+
+/// Exactly one of `target`, `prefix`, `glob`, and `all` must be given
+fn purge_selector(req: &CachePurgeRequest) -> AppResult<PurgeSelector> {
+    let mut selectors = Vec::new();
+    if let Some(target) = &req.target {
+        selectors.push(PurgeSelector::Target(target.clone()));
+    }
+    if let Some(prefix) = &req.prefix {
+        selectors.push(PurgeSelector::Prefix(prefix.clone()));
+    }
+    if let Some(glob) = &req.glob {
+        selectors.push(PurgeSelector::Glob(glob.clone()));
+    }
+    if req.all {
+        selectors.push(PurgeSelector::All);
+    }
+
+    let selector = match selectors.len() {
+        0 => {
+            return Err(AppError::validation(
+                "Specify one of target, prefix, glob, or all",
+            ));
+        }
+        1 => selectors.remove(0),
+        _ => {
+            return Err(AppError::validation(
+                "Only one of target, prefix, glob, or all may be given",
+            ));
+        }
+    };
+
+    match &selector {
+        PurgeSelector::Target(value)
+        | PurgeSelector::Prefix(value)
+        | PurgeSelector::Glob(value)
+            if !value.starts_with('/') && !value.starts_with('*') =>
+        {
+            Err(AppError::validation("Targets start with '/'"))
+        }
+        _ => Ok(selector),
+    }
+}
+
+fn describe(selector: &PurgeSelector) -> String {
+    match selector {
+        PurgeSelector::All => "all".to_string(),
+        PurgeSelector::Target(target) => format!("target:{target}"),
+        PurgeSelector::Prefix(prefix) => format!("prefix:{prefix}"),
+        PurgeSelector::Glob(glob) => format!("glob:{glob}"),
+    }
+}
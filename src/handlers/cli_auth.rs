@@ -2,22 +2,27 @@
 
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{ConnectInfo, Extension, Path, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
     response::{
-        IntoResponse, Sse,
+        IntoResponse, Response, Sse,
         sse::{Event, KeepAlive},
     },
 };
 use futures::StreamExt;
 use std::convert::Infallible;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio_stream::wrappers::WatchStream;
 use ulid::Ulid;
 
 use crate::{
+    auth::{self, ClientInfo},
     cli_auth::{
-        ApproveRequest, CliAuthStatus, DenyRequest, StartRequest, StartResponse, VERIFICATION_PATH,
+        ApproveRequest, CliAuthStatus, DenyRequest, POLL_INTERVAL, PollOutcome, StartRequest,
+        StartResponse, TokenError, TokenErrorCode, TokenRequest, VERIFICATION_PATH,
     },
+    handlers::auth::{client_ip, require_second_factor},
     state::{AdminSession, AppError, AppResult, AppState},
 };
 
@@ -26,9 +31,14 @@ use crate::{
 #[tracing::instrument(skip_all)]
 pub async fn cli_auth_start_handler(
     State(state): State<Arc<AppState>>,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
     Json(payload): Json<StartRequest>,
 ) -> AppResult<impl IntoResponse> {
-    let started = state.cli_auth.start(payload.label);
+    let device = ClientInfo::from_headers(&headers, Some(client_ip(peer, &headers)));
+    let started = state
+        .cli_auth
+        .start(payload.label, device.ip, device.user_agent);
     tracing::info!(request_id = %started.request_id, "CLI auth request started");
 
     Ok(Json(StartResponse {
@@ -36,9 +46,44 @@ pub async fn cli_auth_start_handler(
         user_code: started.user_code,
         verification_path: VERIFICATION_PATH.to_string(),
         expires_at: started.expires_at,
+        interval: POLL_INTERVAL.whole_seconds().unsigned_abs(),
     }))
 }
 
+/// Polling alternative to the event stream, for when a proxy buffers it.
+/// Answers like an RFC 8628 token endpoint: a 400 with an error code until the
+/// request resolves, then the `approved` status (once) with the token.
+#[tracing::instrument(skip_all)]
+pub async fn cli_auth_token_handler(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<TokenRequest>,
+) -> Response {
+    let outcome = Ulid::from_string(&payload.request_id)
+        .map_or(PollOutcome::Expired, |id| state.cli_auth.poll(id));
+    let error = |error, interval: Option<time::Duration>| {
+        let body = TokenError {
+            error,
+            interval: interval.map(|i| i.whole_seconds().unsigned_abs()),
+        };
+        (StatusCode::BAD_REQUEST, Json(body)).into_response()
+    };
+
+    let mut response = match outcome {
+        PollOutcome::Pending { interval } => {
+            error(TokenErrorCode::AuthorizationPending, Some(interval))
+        }
+        PollOutcome::SlowDown { interval } => error(TokenErrorCode::SlowDown, Some(interval)),
+        PollOutcome::Resolved(CliAuthStatus::Denied) => error(TokenErrorCode::AccessDenied, None),
+        PollOutcome::Resolved(status) => Json(status).into_response(),
+        PollOutcome::Expired => error(TokenErrorCode::ExpiredToken, None),
+    };
+    // Holds a token, or a status the client must re-ask for
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
+
 /// SSE stream the CLI waits on. Emits `pending` immediately, then the terminal
 /// `approved`/`denied` event, after which the stream closes.
 #[tracing::instrument(skip_all, fields(request_id = %request_id))]
@@ -70,12 +115,15 @@ pub async fn cli_auth_events_handler(
     Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
 
-/// Approve a pending request. Requires an authenticated admin; mints a
-/// long-lived CLI token bound to that admin and pushes it to the waiter.
+/// Approve a pending request. Requires an authenticated admin, plus a fresh
+/// second factor when they have 2FA enabled; mints a long-lived CLI token bound
+/// to that admin and pushes it to the waiter.
 #[tracing::instrument(skip_all)]
 pub async fn cli_auth_approve_handler(
     State(state): State<Arc<AppState>>,
     session: AdminSession,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
     Json(payload): Json<ApproveRequest>,
 ) -> AppResult<impl IntoResponse> {
     let request_id = Ulid::from_string(&payload.request_id).map_err(|_| AppError::NotFound)?;
@@ -88,12 +136,29 @@ pub async fn cli_auth_approve_handler(
         return Err(AppError::validation("User code does not match"));
     }
 
+    let user = auth::get_admin_user(&state.pool, &session.0.username)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    require_second_factor(
+        &state,
+        &user,
+        payload.totp_code.as_deref(),
+        client_ip(peer, &headers),
+    )
+    .await?;
+
     let (cli_session, token) = state
         .session_manager
         .create_cli_session(
             session.0.user_id,
             session.0.username.clone(),
+            user.role,
             info.label.clone(),
+            // The CLI's own address, not the approving browser's
+            ClientInfo {
+                ip: info.device_ip,
+                user_agent: info.device_user_agent.clone(),
+            },
         )
         .await?;
 
@@ -102,6 +167,7 @@ pub async fn cli_auth_approve_handler(
         &payload.user_code,
         token,
         session.0.username.clone(),
+        cli_session.expires_at,
     ) {
         // The request expired between the lookup and approval; roll back the token.
         let _ = state.session_manager.delete_session(cli_session.id).await;
@@ -3,13 +3,16 @@ use axum::{
     extract::{Path, State},
     response::IntoResponse,
 };
+use serde_json::Value;
+use sqlx::PgPool;
 use std::sync::Arc;
+use uuid::Uuid;
 
 use crate::{
-    auth, db,
+    auth, cache, db,
     events::{self, EventLevel, EventType},
     pm::{Doc, DocOp, OpError, PmError, generate_block_id},
-    state::{AdminSession, AppError, AppResult, AppState, OptionNotFoundExt},
+    state::{AppError, AppResult, AppState, EditorSession, OptionNotFoundExt},
 };
 
 // A schema failure is about the submitted document, not the resolved project
@@ -41,8 +44,11 @@ pub async fn get_project_content_handler(
     State(state): State<Arc<AppState>>,
     Path(ref_str): Path<String>,
     headers: axum::http::HeaderMap,
+    extensions: axum::http::Extensions,
 ) -> AppResult<impl IntoResponse> {
-    let is_admin = auth::authenticate(&state, &headers).await.is_some();
+    let is_admin = auth::authenticate(&state, &headers, &extensions)
+        .await
+        .is_some();
     let project = db::get_project_by_ref(&state.pool, &ref_str)
         .await?
         .or_not_found()?;
@@ -61,7 +67,7 @@ pub async fn get_project_content_handler(
 pub async fn patch_project_content_handler(
     State(state): State<Arc<AppState>>,
     Path(ref_str): Path<String>,
-    session: AdminSession,
+    session: EditorSession,
     Json(ops): Json<Vec<DocOp>>,
 ) -> AppResult<impl IntoResponse> {
     let project = db::get_project_by_ref(&state.pool, &ref_str)
@@ -70,7 +76,7 @@ pub async fn patch_project_content_handler(
 
     let mut doc = Doc::from_stored(project.detail_content.as_ref());
     doc.apply_all(ops, generate_block_id)?;
-    persist_content(&state, &project, &doc, &session.0.username).await?;
+    persist_content(&state, &project, &mut doc, &session.0.username).await?;
 
     Ok(Json(doc.into_inner()))
 }
@@ -83,7 +89,7 @@ pub async fn patch_project_content_handler(
 pub async fn put_project_content_handler(
     State(state): State<Arc<AppState>>,
     Path(ref_str): Path<String>,
-    session: AdminSession,
+    session: EditorSession,
     Json(value): Json<serde_json::Value>,
 ) -> AppResult<impl IntoResponse> {
     let project = db::get_project_by_ref(&state.pool, &ref_str)
@@ -92,20 +98,97 @@ pub async fn put_project_content_handler(
 
     let mut doc = Doc::parse(&value)?;
     doc.ensure_block_ids(generate_block_id);
-    persist_content(&state, &project, &doc, &session.0.username).await?;
+    persist_content(&state, &project, &mut doc, &session.0.username).await?;
 
     Ok(Json(doc.into_inner()))
 }
 
-/// Persist a fully-formed document, then log the update and invalidate the
-/// affected ISR cache entries. Shared by the ops (PATCH) and whole-document
-/// (PUT) paths so neither drifts on event or cache bookkeeping.
+/// Resolve media library references in `figure` blocks. Every `attrs.mediaId`
+/// must name an existing asset; its `src` and `kind` are rewritten from that
+/// asset so the renderer never needs a lookup. Figures without a `mediaId` keep
+/// whatever `src` they were given.
+pub(super) async fn resolve_figure_media(pool: &PgPool, doc: &mut Doc) -> AppResult<()> {
+    let ids = doc
+        .figure_media_ids()
+        .iter()
+        .map(|id| {
+            Uuid::parse_str(id)
+                .map_err(|_| AppError::validation(format!("figure mediaId \"{id}\" is not a UUID")))
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let assets = db::get_media_assets(pool, &ids).await?;
+    if let Some(missing) = ids.iter().find(|id| !assets.iter().any(|a| a.id == **id)) {
+        return Err(AppError::validation(format!(
+            "figure mediaId \"{missing}\" is not in the media library"
+        )));
+    }
+
+    doc.for_each_figure_mut(|node| {
+        let Some(asset) = node
+            .attrs
+            .get("mediaId")
+            .and_then(Value::as_str)
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .and_then(|id| assets.iter().find(|a| a.id == id))
+        else {
+            return;
+        };
+        // Canonical form, so reference lookups can match it textually
+        node.attrs
+            .insert("mediaId".into(), Value::String(asset.id.to_string()));
+        if let Some(src) = asset.display_url() {
+            node.attrs.insert("src".into(), Value::String(src));
+        }
+        let kind = match asset.media_type {
+            db::MediaType::Image => "image",
+            db::MediaType::Video => "video",
+        };
+        node.attrs.insert("kind".into(), kind.into());
+    });
+
+    Ok(())
+}
+
+/// Re-resolve the `figure` blocks showing `asset_id` after the asset changed:
+/// its variants finished processing, or storage GC sent it back to be
+/// reprocessed. A document that was saved in the meantime is left alone, since
+/// that save resolved its figures itself. Returns how many projects changed.
+pub(crate) async fn refresh_figure_media(pool: &PgPool, asset_id: Uuid) -> AppResult<usize> {
+    let references = db::get_media_references(pool, asset_id).await?;
+    let mut refreshed = 0;
+    for project in &references.figures {
+        let Some(project) = db::get_project_by_ref(pool, &project.id).await? else {
+            continue;
+        };
+        let Some(stored) = project.detail_content else {
+            continue;
+        };
+        let mut doc = Doc::from_stored(Some(&stored));
+        resolve_figure_media(pool, &mut doc).await?;
+        let content = doc.to_stored();
+        if content.as_ref() != Some(&stored)
+            && db::replace_project_content(pool, project.id, &stored, content.as_ref()).await?
+        {
+            refreshed += 1;
+        }
+    }
+    Ok(refreshed)
+}
+
+/// Resolve figure media, persist the document, then log the update and
+/// invalidate the affected ISR cache entries. Shared by the ops (PATCH) and
+/// whole-document (PUT) paths so neither drifts on event or cache bookkeeping.
 async fn persist_content(
     state: &AppState,
     project: &db::DbProject,
-    doc: &Doc,
+    doc: &mut Doc,
     username: &str,
 ) -> AppResult<()> {
+    resolve_figure_media(&state.pool, doc).await?;
     db::update_project_content(&state.pool, project.id, doc.to_stored().as_ref()).await?;
 
     tracing::info!(project_id = %project.id, "Project content updated");
@@ -120,11 +203,83 @@ async fn persist_content(
         None,
     );
 
-    state.isr_cache.invalidate("/").await;
     state
         .isr_cache
-        .invalidate(&format!("/projects/{}", project.slug))
+        .invalidate_tag(&cache::tags::project(project.id))
         .await;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn figure_src(pool: &PgPool, project_id: Uuid) -> String {
+        let project = db::get_project_by_id(pool, project_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let doc = project.detail_content.unwrap();
+        doc["content"][0]["attrs"]["src"]
+            .as_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[sqlx::test]
+    async fn figures_follow_their_media_through_processing(pool: PgPool) {
+        let asset_id = Uuid::new_v4();
+        let original = json!({ "key": "original.png", "width": 4, "height": 4 });
+        db::create_media_asset(
+            &pool,
+            asset_id,
+            db::MediaType::Image,
+            "logo.png",
+            &format!("media/{}", ulid::Ulid::new()),
+            json!({ "original": original }),
+            4,
+            None,
+            db::MediaStatus::Pending,
+        )
+        .await
+        .unwrap();
+
+        let mut doc = Doc::from_stored(Some(&json!({
+            "type": "doc",
+            "content": [{ "type": "figure", "attrs": { "mediaId": asset_id.to_string() } }]
+        })));
+        resolve_figure_media(&pool, &mut doc).await.unwrap();
+        let project_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO projects (slug, name, detail_content) VALUES ('logo', 'Logo', $1) RETURNING id",
+        )
+        .bind(doc.to_stored())
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(
+            figure_src(&pool, project_id)
+                .await
+                .ends_with("/original.png")
+        );
+
+        let full = json!({ "key": "full.webp", "width": 4, "height": 4 });
+        let variants = json!({ "original": original, "full": full });
+        db::complete_media_processing(&pool, asset_id, variants, 4, 4, "L00000")
+            .await
+            .unwrap();
+        assert_eq!(refresh_figure_media(&pool, asset_id).await.unwrap(), 1);
+        assert!(figure_src(&pool, project_id).await.ends_with("/full.webp"));
+
+        // Sent back for reprocessing: the variants can't be trusted until rebuilt
+        db::reset_media_processing(&pool, asset_id).await.unwrap();
+        assert_eq!(refresh_figure_media(&pool, asset_id).await.unwrap(), 1);
+        assert!(
+            figure_src(&pool, project_id)
+                .await
+                .ends_with("/original.png")
+        );
+        assert_eq!(refresh_figure_media(&pool, asset_id).await.unwrap(), 0);
+    }
+}
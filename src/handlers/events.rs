@@ -15,6 +15,7 @@ pub struct ListEventsQuery {
     pub level: Option<EventLevel>,
     pub entity_type: Option<String>,
     pub event_type: Option<String>,
+    pub actor: Option<String>,
 }
 
 #[tracing::instrument(skip_all)]
@@ -29,6 +30,7 @@ pub async fn list_events_handler(
         level: params.level,
         entity_type: params.entity_type,
         event_type: params.event_type,
+        actor: params.actor,
     };
 
     let events = db::events::get_events(&state.pool, filters).await?;
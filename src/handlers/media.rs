@@ -1,17 +1,25 @@
 use axum::{
     Json,
+    body::Bytes,
     extract::{Multipart, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
+use sqlx::PgPool;
 use std::sync::Arc;
 use ulid::Ulid;
 use uuid::Uuid;
 
 use crate::{
-    db, media_processing,
+    cache, db,
+    events::{self, EventLevel, EventType},
+    handlers::{CONTENT_SHA256_HEADER, CreateMediaUploadRequest, UPLOAD_OFFSET_HEADER},
+    media_processing,
     r2::R2Client,
-    state::{AdminSession, AppError, AppResult, AppState, OptionNotFoundExt},
+    state::{
+        AdminSession, AppError, AppResult, AppState, EditorSession, OptionNotFoundExt,
+        SqlxResultExt,
+    },
 };
 
 #[derive(Debug, serde::Deserialize)]
@@ -20,12 +28,22 @@ pub struct ReorderMediaRequest {
     pub media_ids: Vec<String>,
 }
 
-/// Accepts multipart/form-data; processes images into variants and uploads to R2.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachMediaRequest {
+    pub asset_id: String,
+}
+
+/// Accepts multipart/form-data, stores the original in R2 as a new library
+/// asset, and attaches it to the project.
+///
+/// Images are inserted as `pending` and handed to the media queue, returning
+/// `202 Accepted` immediately; videos need no processing and return `201 Created`.
 #[tracing::instrument(skip_all, fields(ref_str))]
 pub async fn upload_media_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(ref_str): axum::extract::Path<String>,
-    _session: AdminSession,
+    session: EditorSession,
     mut multipart: Multipart,
 ) -> AppResult<impl IntoResponse> {
     let project = db::get_project_by_ref(&state.pool, &ref_str)
@@ -33,11 +51,63 @@ pub async fn upload_media_handler(
         .or_not_found()?;
     let project_id = project.id;
 
+    let stored = store_multipart_original(
+        &mut multipart,
+        &format!("projects/{project_id}/{}", Ulid::new()),
+    )
+    .await?;
+    let (status, asset) = register_media(&state, stored.as_upload(), &session.0.username).await?;
+    attach_to_project(&state, project_id, status, &asset).await
+}
+
+/// Accepts multipart/form-data and stores the original in R2 as a library
+/// asset that is not attached to any project.
+#[tracing::instrument(skip_all)]
+pub async fn upload_library_media_handler(
+    State(state): State<Arc<AppState>>,
+    session: EditorSession,
+    mut multipart: Multipart,
+) -> AppResult<impl IntoResponse> {
+    let stored =
+        store_multipart_original(&mut multipart, &format!("media/{}", Ulid::new())).await?;
+    let (status, asset) = register_media(&state, stored.as_upload(), &session.0.username).await?;
+    Ok((status, Json(asset.to_api_asset(0))))
+}
+
+/// An original read from a multipart form and stored in R2.
+struct StoredOriginal {
+    media_type: db::MediaType,
+    filename: String,
+    mime: String,
+    r2_base_path: String,
+    original_key: String,
+    size_bytes: i64,
+}
+
+impl StoredOriginal {
+    fn as_upload(&self) -> NewMediaUpload<'_> {
+        NewMediaUpload {
+            media_type: self.media_type,
+            filename: &self.filename,
+            mime: &self.mime,
+            r2_base_path: &self.r2_base_path,
+            original_key: &self.original_key,
+            size_bytes: self.size_bytes,
+        }
+    }
+}
+
+/// Read the `file` field of a multipart form, validate its type, and store it
+/// as the original under `r2_base_path`.
+async fn store_multipart_original(
+    multipart: &mut Multipart,
+    r2_base_path: &str,
+) -> AppResult<StoredOriginal> {
     let r2 = R2Client::get()
         .await
         .ok_or_else(|| AppError::ServiceUnavailable("Media storage is not configured".into()))?;
 
-    let (filename, content_type, data) = extract_file(&mut multipart)
+    let (filename, content_type, data) = extract_file(multipart)
         .await
         .map_err(AppError::validation)?
         .ok_or_else(|| AppError::validation("No file provided"))?;
@@ -51,110 +121,280 @@ pub async fn upload_media_handler(
         )));
     }
 
-    let asset_id = Ulid::new();
-    let r2_base_path = format!("projects/{project_id}/{asset_id}");
-
-    if is_image {
-        let processed = media_processing::process_image(&data, &filename)
+    let (media_type, mime, original_key) = if is_image {
+        // Reject undecodable formats up front; the heavy lifting happens in the queue
+        let (_, mime) = media_processing::detect_format(&data, &filename)
             .map_err(|e| AppError::validation(format!("Failed to process image: {e}")))?;
+        let key = format!("original.{}", media_processing::image_extension(mime));
+        (db::MediaType::Image, mime.to_string(), key)
+    } else {
+        let key = format!(
+            "original.{}",
+            media_processing::video_extension(&content_type)
+        );
+        (db::MediaType::Video, content_type, key)
+    };
 
-        upload_image_variants(&r2, &r2_base_path, &processed)
-            .await
-            .map_err(|e| AppError::Internal(format!("Failed to upload media to storage: {e}")))?;
-
-        let original_ext = filename.rsplit('.').next().unwrap_or("jpg");
-        let variants = serde_json::json!({
-            "thumb": {
-                "key": "thumb.webp",
-                "width": processed.thumb.width,
-                "height": processed.thumb.height
-            },
-            "medium": {
-                "key": "medium.webp",
-                "width": processed.medium.width,
-                "height": processed.medium.height
-            },
-            "full": {
-                "key": "full.webp",
-                "width": processed.full.width,
-                "height": processed.full.height
-            },
-            "original": {
-                "key": format!("original.{original_ext}"),
-                "width": processed.original.width,
-                "height": processed.original.height,
-                "mime": processed.original.mime
-            }
-        });
-
-        let media = db::create_media(
-            &state.pool,
-            project_id,
-            db::MediaType::Image,
-            &filename,
-            &r2_base_path,
-            variants,
-            Some(processed.original.width as i32),
-            Some(processed.original.height as i32),
-            data.len() as i64,
-            Some(&processed.blurhash),
-            None,
-        )
-        .await?;
+    let size_bytes = data.len() as i64;
+    r2.put_object(&format!("{r2_base_path}/{original_key}"), data, &mime, None)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to upload media to storage: {e}")))?;
+
+    Ok(StoredOriginal {
+        media_type,
+        filename,
+        mime,
+        r2_base_path: r2_base_path.to_string(),
+        original_key,
+        size_bytes,
+    })
+}
 
-        tracing::info!(
-            media_id = %media.id,
-            project_id = %project_id,
-            filename = %filename,
-            "Image uploaded successfully"
-        );
-        state.isr_cache.invalidate("/").await;
+/// An original that has been stored in R2 and needs a library asset row.
+struct NewMediaUpload<'a> {
+    media_type: db::MediaType,
+    filename: &'a str,
+    mime: &'a str,
+    r2_base_path: &'a str,
+    original_key: &'a str,
+    size_bytes: i64,
+}
+
+/// Create the library asset for a stored original.
+///
+/// Images are inserted `pending` and queued for processing (`202 Accepted`);
+/// videos are usable as-is and inserted `ready` (`201 Created`).
+async fn register_media(
+    state: &AppState,
+    upload: NewMediaUpload<'_>,
+    actor: &str,
+) -> AppResult<(StatusCode, db::DbMediaAsset)> {
+    let is_image = upload.media_type == db::MediaType::Image;
+    let variants = serde_json::json!({
+        "original": {
+            "key": upload.original_key,
+            "mime": upload.mime
+        }
+    });
+
+    let asset = db::create_media_asset(
+        &state.pool,
+        Uuid::new_v4(),
+        upload.media_type,
+        upload.filename,
+        upload.r2_base_path,
+        variants,
+        upload.size_bytes,
+        None,
+        if is_image {
+            db::MediaStatus::Pending
+        } else {
+            db::MediaStatus::Ready
+        },
+    )
+    .await?;
+
+    tracing::info!(
+        media_id = %asset.id,
+        filename = %upload.filename,
+        size_bytes = upload.size_bytes,
+        "Media uploaded"
+    );
+    events::log_event(
+        &state.event_sender,
+        EventType::MediaUploaded,
+        EventLevel::Info,
+        Some("media"),
+        Some(asset.id),
+        Some(actor),
+        format!("Media uploaded: {}", upload.filename),
+        Some(serde_json::json!({
+            "sizeBytes": upload.size_bytes,
+        })),
+    );
 
-        Ok((StatusCode::CREATED, Json(media.to_api_media())))
+    if is_image {
+        state.media_queue.enqueue(asset.id);
+        Ok((StatusCode::ACCEPTED, asset))
     } else {
-        let original_ext = match content_type.as_str() {
-            "video/webm" => "webm",
-            "video/quicktime" => "mov",
-            _ => "mp4",
-        };
+        Ok((StatusCode::CREATED, asset))
+    }
+}
 
-        let video_key = format!("{r2_base_path}/original.{original_ext}");
-        r2.put_object(&video_key, data.clone(), &content_type, None)
-            .await
-            .map_err(|e| AppError::Internal(format!("Failed to upload video to storage: {e}")))?;
-
-        let variants = serde_json::json!({
-            "original": {
-                "key": format!("original.{original_ext}"),
-                "mime": content_type
-            }
-        });
-
-        let media = db::create_media(
-            &state.pool,
-            project_id,
-            db::MediaType::Video,
-            &filename,
-            &r2_base_path,
-            variants,
-            None,
-            None,
-            data.len() as i64,
-            None,
-            None,
-        )
-        .await?;
+/// Attach a freshly registered asset to a project's gallery.
+async fn attach_to_project(
+    state: &AppState,
+    project_id: Uuid,
+    status: StatusCode,
+    asset: &db::DbMediaAsset,
+) -> AppResult<(StatusCode, Json<db::ApiProjectMedia>)> {
+    let media = db::attach_media(&state.pool, project_id, asset.id).await?;
+    if asset.status == db::MediaStatus::Ready {
+        state
+            .isr_cache
+            .invalidate_tag(&cache::tags::project(project_id))
+            .await;
+    }
+    Ok((status, Json(media.to_api_media())))
+}
 
-        tracing::info!(
-            media_id = %media.id,
-            project_id = %project_id,
-            filename = %filename,
-            "Video uploaded successfully"
-        );
-        state.isr_cache.invalidate("/").await;
+/// Start a resumable chunked upload (requires authentication).
+///
+/// See [`crate::media_upload`] for the protocol.
+#[tracing::instrument(skip_all, fields(ref_str))]
+pub async fn create_media_upload_handler(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(ref_str): axum::extract::Path<String>,
+    _session: EditorSession,
+    Json(payload): Json<CreateMediaUploadRequest>,
+) -> AppResult<impl IntoResponse> {
+    let project = db::get_project_by_ref(&state.pool, &ref_str)
+        .await?
+        .or_not_found()?;
 
-        Ok((StatusCode::CREATED, Json(media.to_api_media())))
+    if R2Client::get().await.is_none() {
+        return Err(AppError::ServiceUnavailable(
+            "Media storage is not configured".into(),
+        ));
     }
+
+    let status = state.media_uploads.create(project.id, &payload)?;
+    tracing::info!(
+        upload_id = %status.upload_id,
+        project_id = %project.id,
+        size = payload.size,
+        "Chunked upload started"
+    );
+    Ok((StatusCode::CREATED, Json(status)))
+}
+
+/// Current offset of a chunked upload, for resuming after a dropped connection.
+#[tracing::instrument(skip_all, fields(ref_str, upload_id))]
+pub async fn get_media_upload_handler(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path((ref_str, upload_id)): axum::extract::Path<(String, String)>,
+    _session: AdminSession,
+) -> AppResult<impl IntoResponse> {
+    let project = db::get_project_by_ref(&state.pool, &ref_str)
+        .await?
+        .or_not_found()?;
+
+    let upload = state.media_uploads.get(project.id, &upload_id).await?;
+    let status = upload.lock().await.status();
+    Ok(Json(status))
+}
+
+/// Append one chunk to a chunked upload.
+///
+/// The raw chunk is the request body; `Upload-Offset` must equal the upload's
+/// current offset and `X-Content-Sha256` carries the chunk's hex SHA-256.
+#[tracing::instrument(skip_all, fields(ref_str, upload_id))]
+pub async fn append_media_upload_handler(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path((ref_str, upload_id)): axum::extract::Path<(String, String)>,
+    _session: EditorSession,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<impl IntoResponse> {
+    let project = db::get_project_by_ref(&state.pool, &ref_str)
+        .await?
+        .or_not_found()?;
+
+    let offset = headers
+        .get(UPLOAD_OFFSET_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .ok_or_else(|| AppError::validation("Missing or invalid Upload-Offset header"))?;
+    let checksum = headers
+        .get(CONTENT_SHA256_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::validation("Missing X-Content-Sha256 header"))?;
+
+    let r2 = R2Client::get()
+        .await
+        .ok_or_else(|| AppError::ServiceUnavailable("Media storage is not configured".into()))?;
+
+    let upload = state.media_uploads.get(project.id, &upload_id).await?;
+    let mut upload = upload.lock().await;
+
+    if let Err(err) = upload.append(&r2, offset, &body, checksum).await {
+        // A storage failure leaves the multipart upload in an unknown state;
+        // discard it rather than risk assembling a corrupt file.
+        if matches!(err, AppError::Internal(_)) {
+            upload.abort().await;
+            state.media_uploads.remove(upload.id);
+        }
+        return Err(err);
+    }
+
+    Ok(Json(upload.status()))
+}
+
+/// Verify and finalize a chunked upload, creating the media record.
+#[tracing::instrument(skip_all, fields(ref_str, upload_id))]
+pub async fn complete_media_upload_handler(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path((ref_str, upload_id)): axum::extract::Path<(String, String)>,
+    session: EditorSession,
+) -> AppResult<impl IntoResponse> {
+    let project = db::get_project_by_ref(&state.pool, &ref_str)
+        .await?
+        .or_not_found()?;
+
+    let upload = state.media_uploads.get(project.id, &upload_id).await?;
+    let mut upload = upload.lock().await;
+
+    match upload.finish().await {
+        Ok(()) => {}
+        Err(AppError::Validation(err)) if upload.offset == upload.size => {
+            // Checksum mismatch: the upload was aborted and cannot be resumed
+            state.media_uploads.remove(upload.id);
+            return Err(AppError::Validation(err));
+        }
+        Err(err) => return Err(err),
+    }
+    state.media_uploads.remove(upload.id);
+
+    let original_key = upload
+        .original_key
+        .clone()
+        .ok_or_else(|| AppError::Internal("Upload has no stored original".into()))?;
+
+    let (status, asset) = register_media(
+        &state,
+        NewMediaUpload {
+            media_type: upload.media_type,
+            filename: &upload.filename,
+            mime: &upload.mime,
+            r2_base_path: &upload.r2_base_path,
+            original_key: &original_key,
+            size_bytes: upload.size as i64,
+        },
+        &session.0.username,
+    )
+    .await?;
+    drop(upload);
+    attach_to_project(&state, project.id, status, &asset).await
+}
+
+/// Abort a chunked upload, discarding any uploaded parts.
+#[tracing::instrument(skip_all, fields(ref_str, upload_id))]
+pub async fn abort_media_upload_handler(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path((ref_str, upload_id)): axum::extract::Path<(String, String)>,
+    _session: EditorSession,
+) -> AppResult<impl IntoResponse> {
+    let project = db::get_project_by_ref(&state.pool, &ref_str)
+        .await?
+        .or_not_found()?;
+
+    let upload = state.media_uploads.get(project.id, &upload_id).await?;
+    let mut upload = upload.lock().await;
+    upload.abort().await;
+    state.media_uploads.remove(upload.id);
+    drop(upload);
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
 /// Extract file from multipart form data
@@ -185,55 +425,6 @@ async fn extract_file(
     Ok(None)
 }
 
-/// Upload all image variants to R2
-async fn upload_image_variants(
-    r2: &R2Client,
-    base_path: &str,
-    processed: &media_processing::ProcessedImage,
-) -> Result<(), String> {
-    r2.put_object(
-        &format!("{base_path}/thumb.webp"),
-        processed.thumb.data.clone(),
-        "image/webp",
-        None,
-    )
-    .await?;
-
-    r2.put_object(
-        &format!("{base_path}/medium.webp"),
-        processed.medium.data.clone(),
-        "image/webp",
-        None,
-    )
-    .await?;
-
-    r2.put_object(
-        &format!("{base_path}/full.webp"),
-        processed.full.data.clone(),
-        "image/webp",
-        None,
-    )
-    .await?;
-
-    let original_ext = match processed.original.mime.as_str() {
-        "image/png" => "png",
-        "image/gif" => "gif",
-        "image/webp" => "webp",
-        "image/avif" => "avif",
-        _ => "jpg",
-    };
-
-    r2.put_object(
-        &format!("{base_path}/original.{original_ext}"),
-        processed.original.data.clone(),
-        &processed.original.mime,
-        None,
-    )
-    .await?;
-
-    Ok(())
-}
-
 /// Get all media for a project
 #[tracing::instrument(skip_all, fields(ref_str))]
 pub async fn get_project_media_handler(
@@ -249,12 +440,16 @@ pub async fn get_project_media_handler(
     Ok(Json(response))
 }
 
-/// Delete a media item (requires authentication)
+/// Detach a media item from a project (requires authentication).
+///
+/// An asset uploaded for the project is deleted along with its stored files
+/// once nothing else references it, so single-use uploads clean up as before.
+/// Assets uploaded to the library stay in it.
 #[tracing::instrument(skip_all, fields(ref_str, media_id))]
 pub async fn delete_media_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path((ref_str, media_id)): axum::extract::Path<(String, String)>,
-    _session: AdminSession,
+    _session: EditorSession,
 ) -> AppResult<impl IntoResponse> {
     let project = db::get_project_by_ref(&state.pool, &ref_str)
         .await?
@@ -271,43 +466,178 @@ pub async fn delete_media_handler(
         return Err(AppError::NotFound);
     }
 
-    // Delete files from R2 storage
-    let r2_base_path = media.r2_base_path.clone();
-    if let Some(r2) = R2Client::get().await {
-        let prefix = format!("{}/", r2_base_path.trim_end_matches('/'));
-        match r2.delete_prefix(&prefix).await {
-            Ok(count) => {
-                tracing::info!(
-                    media_id = %media_id,
-                    r2_prefix = %prefix,
-                    deleted_count = count,
-                    "Deleted R2 objects"
-                );
-            }
-            Err(err) => {
-                tracing::warn!(
-                    error = %err,
-                    media_id = %media_id,
-                    r2_prefix = %prefix,
-                    "Failed to delete R2 objects (will be orphaned)"
-                );
-            }
-        }
-    }
-
-    let deleted = db::delete_media(&state.pool, media_id)
+    let detached = db::detach_media(&state.pool, media_id)
         .await?
         .or_not_found()?;
 
     tracing::info!(
         media_id = %media_id,
+        asset_id = %detached.asset_id,
         project_id = %project.id,
-        r2_base_path = %deleted.r2_base_path,
-        "Media deleted from database"
+        "Media detached from project"
     );
-    state.isr_cache.invalidate("/").await;
 
-    Ok(Json(deleted.to_api_media()))
+    release_media_asset(&state.pool, detached.asset_id).await?;
+    state
+        .isr_cache
+        .invalidate_tag(&cache::tags::project(project.id))
+        .await;
+
+    Ok(Json(detached.to_api_media()))
+}
+
+/// Attach an existing library asset to a project (requires authentication)
+#[tracing::instrument(skip_all, fields(ref_str))]
+pub async fn attach_media_handler(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(ref_str): axum::extract::Path<String>,
+    _session: EditorSession,
+    Json(payload): Json<AttachMediaRequest>,
+) -> AppResult<impl IntoResponse> {
+    let project = db::get_project_by_ref(&state.pool, &ref_str)
+        .await?
+        .or_not_found()?;
+
+    let asset_id = Uuid::parse_str(&payload.asset_id)
+        .map_err(|_| AppError::field("assetId", "Must be a valid UUID"))?;
+    db::get_media_asset(&state.pool, asset_id)
+        .await?
+        .ok_or_else(|| AppError::field("assetId", "Media not found in the library"))?;
+
+    let media = db::attach_media(&state.pool, project.id, asset_id)
+        .await
+        .conflict_on_unique("Media is already attached to this project")?;
+
+    tracing::info!(asset_id = %asset_id, project_id = %project.id, "Media attached");
+    state
+        .isr_cache
+        .invalidate_tag(&cache::tags::project(project.id))
+        .await;
+
+    Ok((StatusCode::CREATED, Json(media.to_api_media())))
+}
+
+/// List every library asset with its reference count (requires authentication)
+#[tracing::instrument(skip_all)]
+pub async fn list_library_media_handler(
+    State(state): State<Arc<AppState>>,
+    _session: AdminSession,
+) -> AppResult<impl IntoResponse> {
+    let assets = db::get_all_media_assets(&state.pool).await?;
+    let counts = db::get_media_ref_counts(&state.pool).await?;
+
+    let response: Vec<db::ApiMediaAsset> = assets
+        .iter()
+        .map(|a| a.to_api_asset(counts.get(&a.id).copied().unwrap_or(0)))
+        .collect();
+    Ok(Json(response))
+}
+
+/// Get one library asset with everything that references it (requires authentication)
+#[tracing::instrument(skip_all, fields(asset_id))]
+pub async fn get_library_media_handler(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(asset_id): axum::extract::Path<String>,
+    _session: AdminSession,
+) -> AppResult<impl IntoResponse> {
+    let asset_id = parse_asset_id(&asset_id)?;
+    let asset = db::get_media_asset(&state.pool, asset_id)
+        .await?
+        .or_not_found()?;
+
+    let references = db::get_media_references(&state.pool, asset_id).await?;
+    let mut response = asset.to_api_asset(references.count());
+    response.references = Some(references);
+    Ok(Json(response))
+}
+
+/// Delete a library asset and its stored files (requires authentication).
+///
+/// Refused with `409 Conflict` while any project, figure, or settings slot
+/// still references the asset.
+#[tracing::instrument(skip_all, fields(asset_id))]
+pub async fn delete_library_media_handler(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(asset_id): axum::extract::Path<String>,
+    _session: EditorSession,
+) -> AppResult<impl IntoResponse> {
+    let asset_id = parse_asset_id(&asset_id)?;
+    db::get_media_asset(&state.pool, asset_id)
+        .await?
+        .or_not_found()?;
+
+    let references = db::get_media_references(&state.pool, asset_id).await?;
+    let count = references.count();
+    if count > 0 {
+        return Err(AppError::Conflict(format!(
+            "Media is still referenced {count} time(s); detach it first"
+        )));
+    }
+
+    let asset = db::delete_media_asset(&state.pool, asset_id)
+        .await?
+        .or_not_found()?;
+    delete_asset_files(&asset).await;
+
+    tracing::info!(
+        asset_id = %asset.id,
+        r2_base_path = %asset.r2_base_path,
+        "Media asset deleted"
+    );
+
+    Ok(Json(asset.to_api_asset(0)))
+}
+
+/// Delete an asset uploaded for a project, and its stored files, once nothing
+/// references it any more. Called after an attachment goes away, so single-use
+/// uploads are cleaned up with it. Assets uploaded to the library stay there
+/// until `DELETE /api/media/{id}`.
+pub(super) async fn release_media_asset(pool: &PgPool, asset_id: Uuid) -> AppResult<()> {
+    let Some(asset) = db::get_media_asset(pool, asset_id).await? else {
+        return Ok(());
+    };
+    if !asset.uploaded_for_project() {
+        return Ok(());
+    }
+    let references = db::get_media_references(pool, asset_id).await?;
+    if references.count() == 0
+        && let Some(asset) = db::delete_media_asset(pool, asset_id).await?
+    {
+        delete_asset_files(&asset).await;
+        tracing::info!(asset_id = %asset.id, "Unreferenced media asset deleted");
+    }
+    Ok(())
+}
+
+fn parse_asset_id(raw: &str) -> AppResult<Uuid> {
+    Uuid::parse_str(raw).map_err(|_| AppError::validation("Media ID must be a valid UUID"))
+}
+
+/// Best-effort removal of an asset's files from R2; failures only orphan objects
+/// (which storage GC later reclaims), so they are logged rather than surfaced.
+async fn delete_asset_files(asset: &db::DbMediaAsset) {
+    let Some(r2) = R2Client::get().await else {
+        return;
+    };
+    let prefix = format!("{}/", asset.r2_base_path.trim_end_matches('/'));
+    match r2.delete_prefix(&prefix).await {
+        Ok(count) => {
+            tracing::info!(
+                asset_id = %asset.id,
+                r2_prefix = %prefix,
+                deleted_count = count,
+                "Deleted R2 objects"
+            );
+        }
+        Err(err) => {
+            tracing::warn!(
+                error = %err,
+                asset_id = %asset.id,
+                r2_prefix = %prefix,
+                "Failed to delete R2 objects (will be orphaned)"
+            );
+        }
+    }
 }
 
 /// Reorder media items for a project (requires authentication)
@@ -315,7 +645,7 @@ pub async fn delete_media_handler(
 pub async fn reorder_media_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(ref_str): axum::extract::Path<String>,
-    _session: AdminSession,
+    _session: EditorSession,
     Json(payload): Json<ReorderMediaRequest>,
 ) -> AppResult<impl IntoResponse> {
     let project = db::get_project_by_ref(&state.pool, &ref_str)
@@ -332,8 +662,75 @@ pub async fn reorder_media_handler(
     db::reorder_media(&state.pool, project.id, &media_ids).await?;
 
     let media = db::get_media_for_project(&state.pool, project.id).await?;
-    state.isr_cache.invalidate("/").await;
+    state
+        .isr_cache
+        .invalidate_tag(&cache::tags::project(project.id))
+        .await;
 
     let response: Vec<db::ApiProjectMedia> = media.into_iter().map(|m| m.to_api_media()).collect();
     Ok(Json(response))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn create_project(pool: &PgPool) -> Uuid {
+        let input = db::ProjectInput {
+            name: "Portfolio",
+            slug_override: None,
+            short_description: "",
+            status: db::ProjectStatus::Active,
+            hidden: false,
+            github_repo: None,
+            github_repo_id: None,
+            demo_url: None,
+            detail_content: None,
+            project_type: None,
+            private: false,
+            terminal_cast: None,
+            accent_color: None,
+        };
+        db::create_project(pool, input).await.unwrap().id
+    }
+
+    async fn create_asset(pool: &PgPool, r2_base_path: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        db::create_media_asset(
+            pool,
+            id,
+            db::MediaType::Image,
+            "logo.png",
+            r2_base_path,
+            serde_json::json!({}),
+            4,
+            None,
+            db::MediaStatus::Ready,
+        )
+        .await
+        .unwrap();
+        id
+    }
+
+    /// Attach `asset_id` to a project, detach it again, and report whether the
+    /// asset survived.
+    async fn survives_attach_and_detach(pool: &PgPool, asset_id: Uuid) -> bool {
+        let project_id = create_project(pool).await;
+        let attached = db::attach_media(pool, project_id, asset_id).await.unwrap();
+        db::detach_media(pool, attached.id).await.unwrap().unwrap();
+        release_media_asset(pool, asset_id).await.unwrap();
+        db::get_media_asset(pool, asset_id).await.unwrap().is_some()
+    }
+
+    #[sqlx::test]
+    async fn library_upload_survives_its_last_detach(pool: PgPool) {
+        let asset_id = create_asset(&pool, &format!("media/{}", Ulid::new())).await;
+        assert!(survives_attach_and_detach(&pool, asset_id).await);
+    }
+
+    #[sqlx::test]
+    async fn project_upload_goes_with_its_last_detach(pool: PgPool) {
+        let asset_id = create_asset(&pool, &format!("projects/{}", Ulid::new())).await;
+        assert!(!survives_attach_and_detach(&pool, asset_id).await);
+    }
+}
@@ -2,10 +2,14 @@
 // serving, the proxy, …). The request DTOs further down are shared with the
 // `xevion` CLI and stay ungated.
 #[cfg(feature = "server")]
+pub mod account;
+#[cfg(feature = "server")]
 pub mod assets;
 #[cfg(feature = "server")]
 pub mod auth;
 #[cfg(feature = "server")]
+pub mod cache;
+#[cfg(feature = "server")]
 pub mod cli_auth;
 #[cfg(feature = "server")]
 pub mod content;
@@ -18,6 +22,8 @@ pub mod icons;
 #[cfg(feature = "server")]
 pub mod media;
 #[cfg(feature = "server")]
+pub mod passkeys;
+#[cfg(feature = "server")]
 pub mod projects;
 #[cfg(feature = "server")]
 pub mod seo;
@@ -26,13 +32,23 @@ pub mod sessions;
 #[cfg(feature = "server")]
 pub mod settings;
 #[cfg(feature = "server")]
+pub mod storage;
+#[cfg(feature = "server")]
 pub mod tags;
+#[cfg(feature = "server")]
+pub mod tokens;
+#[cfg(feature = "server")]
+pub mod users;
 
+#[cfg(feature = "server")]
+pub use account::*;
 #[cfg(feature = "server")]
 pub use assets::*;
 #[cfg(feature = "server")]
 pub use auth::*;
 #[cfg(feature = "server")]
+pub use cache::*;
+#[cfg(feature = "server")]
 pub use cli_auth::*;
 #[cfg(feature = "server")]
 pub use content::*;
@@ -45,6 +61,8 @@ pub use icons::*;
 #[cfg(feature = "server")]
 pub use media::*;
 #[cfg(feature = "server")]
+pub use passkeys::*;
+#[cfg(feature = "server")]
 pub use projects::*;
 #[cfg(feature = "server")]
 pub use seo::*;
@@ -53,7 +71,183 @@ pub use sessions::*;
 #[cfg(feature = "server")]
 pub use settings::*;
 #[cfg(feature = "server")]
+pub use storage::*;
+#[cfg(feature = "server")]
 pub use tags::*;
+#[cfg(feature = "server")]
+pub use tokens::*;
+#[cfg(feature = "server")]
+pub use users::*;
+
+/// What an admin user may do. Owners manage the site (settings, sessions,
+/// users), editors manage content (projects, tags, media), viewers can only
+/// read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ts_rs::TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum AdminRole {
+    Owner,
+    Editor,
+    Viewer,
+}
+
+impl AdminRole {
+    /// Canonical wire/storage string.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Owner => "owner",
+            Self::Editor => "editor",
+            Self::Viewer => "viewer",
+        }
+    }
+
+    /// Create, change, and delete content.
+    pub const fn can_edit_content(self) -> bool {
+        matches!(self, Self::Owner | Self::Editor)
+    }
+
+    /// Site settings, sessions and lockouts, and user management.
+    pub const fn can_manage_site(self) -> bool {
+        matches!(self, Self::Owner)
+    }
+}
+
+impl std::fmt::Display for AdminRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for AdminRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(Self::Owner),
+            "editor" => Ok(Self::Editor),
+            "viewer" => Ok(Self::Viewer),
+            other => Err(format!("unknown role '{other}'")),
+        }
+    }
+}
+
+impl TryFrom<String> for AdminRole {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Response header carrying a CLI session's new expiry (RFC 3339) after a
+/// request renewed it.
+pub const SESSION_EXPIRES_HEADER: &str = "x-session-expires";
+
+/// How a session authenticates: an interactive browser cookie, a long-lived
+/// CLI bearer token, or a scoped API token for automation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, ts_rs::TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum SessionType {
+    Browser,
+    Cli,
+    Token,
+}
+
+impl SessionType {
+    /// Canonical wire/storage string. Single source of truth for the column
+    /// values and the `Display` impl.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Browser => "browser",
+            Self::Cli => "cli",
+            Self::Token => "token",
+        }
+    }
+
+    /// Unknown values read as browser sessions, the least capable kind.
+    #[cfg(feature = "server")]
+    pub(crate) fn from_db(s: &str) -> Self {
+        match s {
+            "cli" => Self::Cli,
+            "token" => Self::Token,
+            _ => Self::Browser,
+        }
+    }
+
+    /// Authenticates with an `Authorization: Bearer` token rather than a cookie.
+    pub const fn is_bearer(self) -> bool {
+        matches!(self, Self::Cli | Self::Token)
+    }
+}
+
+impl std::fmt::Display for SessionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A session as shown in the admin sessions list and `xevion sessions list`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, ts_rs::TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiSession {
+    pub id: String,
+    pub username: String,
+    pub session_type: SessionType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub label: Option<String>,
+    pub created_at: String,
+    pub last_active_at: String,
+    pub expires_at: String,
+    /// Address the session was created from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub created_ip: Option<String>,
+    /// Address of its latest recorded request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub last_ip: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub user_agent: Option<String>,
+    /// Browser and OS read from the user agent, e.g. "Firefox on Linux".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub device: Option<String>,
+    /// True for the session making this request (don't let the user lock themselves out blindly).
+    pub current: bool,
+}
+
+/// An admin user, as listed by `GET /api/users`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, ts_rs::TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiAdminUser {
+    pub id: i32,
+    pub username: String,
+    pub role: AdminRole,
+    pub totp_enabled: bool,
+    pub passkeys: i64,
+    pub created_at: String,
+}
+
+/// `POST /api/users` request body.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAdminUserRequest {
+    pub username: String,
+    pub password: String,
+    pub role: AdminRole,
+}
+
+/// `PUT /api/users/{id}` request body.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAdminUserRequest {
+    pub role: AdminRole,
+}
 
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -148,3 +342,178 @@ pub struct UpdateProjectRequest {
     #[serde(default)]
     pub related_ids: Vec<String>,
 }
+
+/// Chunked-upload request header: byte offset the chunk starts at.
+pub const UPLOAD_OFFSET_HEADER: &str = "upload-offset";
+/// Chunked-upload request header: hex SHA-256 of the chunk body.
+pub const CONTENT_SHA256_HEADER: &str = "x-content-sha256";
+
+/// `POST /api/projects/{ref}/media/uploads` request body: start a chunked upload.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMediaUploadRequest {
+    pub filename: String,
+    pub content_type: String,
+    /// Total size in bytes; every chunk is checked against it.
+    pub size: u64,
+    /// Hex SHA-256 of the whole file, verified when the upload completes.
+    pub sha256: String,
+}
+
+/// State of a chunked upload, returned by every `/media/uploads` endpoint.
+///
+/// Clients resume by sending the next chunk at `offset`. Every chunk except the
+/// last must be exactly `chunkSize` bytes.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaUploadStatus {
+    pub upload_id: String,
+    pub offset: u64,
+    pub size: u64,
+    pub chunk_size: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: time::OffsetDateTime,
+}
+
+/// `POST /api/storage/gc` request body. Without `apply` the run is a dry run.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageGcRequest {
+    #[serde(default)]
+    pub apply: bool,
+}
+
+/// Why an R2 object is considered orphaned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanReason {
+    /// Under a project that no longer exists
+    DeletedProject,
+    /// Under an existing project or the library, but no media asset references it
+    UnreferencedMedia,
+    /// OG card for a project that no longer exists
+    StaleOgImage,
+}
+
+/// An object in storage with nothing in the database pointing at it.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedObject {
+    pub key: String,
+    pub reason: OrphanReason,
+}
+
+/// A key referenced by a media asset that is absent from storage.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingObject {
+    pub media_id: uuid::Uuid,
+    pub key: String,
+}
+
+/// Result of reconciling storage against the database.
+///
+/// On a dry run (`applied: false`) the counters of actions taken are all zero.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageGcReport {
+    pub applied: bool,
+    /// Objects listed in storage
+    pub scanned: usize,
+    /// Unreferenced objects left alone because they are too new to judge
+    pub skipped_recent: usize,
+    pub orphans: Vec<OrphanedObject>,
+    pub missing: Vec<MissingObject>,
+    /// Orphans deleted
+    pub deleted: usize,
+    /// Media with missing variants sent back through processing
+    pub requeued: usize,
+    /// Media marked `failed` because their original is gone
+    pub failed: usize,
+}
+
+/// Whether a cached page is served as-is or served while re-rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheEntryState {
+    Fresh,
+    Stale,
+}
+
+/// One ISR cache entry, as listed by `GET /api/cache`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEntryInfo {
+    pub host: String,
+    /// Request target (path and query)
+    pub target: String,
+    /// Request header values this variant was rendered for (`name=value&…`),
+    /// when the page sets `Vary`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    pub status: u16,
+    pub age_secs: u64,
+    /// Uncompressed body size in bytes
+    pub size: usize,
+    /// Encodings available without compressing on request, `identity` first
+    pub encodings: Vec<String>,
+    pub state: CacheEntryState,
+    pub tags: Vec<String>,
+}
+
+/// `POST /api/cache/purge` request body. Exactly one of `target`, `prefix`,
+/// `glob`, or `all` selects entries by request target; `host` narrows any of
+/// them to one public host.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachePurgeRequest {
+    /// Exact request target, query included (e.g. `/projects/foo`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Request targets starting with this (e.g. `/projects/`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// Request targets matching a pattern where `*` matches any run of characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glob: Option<String>,
+    #[serde(default)]
+    pub all: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+}
+
+/// Cache keys (`{host}{target}`) dropped by a purge.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachePurgeReport {
+    pub purged: Vec<String>,
+}
+
+/// ISR cache occupancy and request counters since the server started.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub enabled: bool,
+    pub entries: u64,
+    pub fresh_entries: u64,
+    pub stale_entries: u64,
+    /// Uncompressed bytes held in memory
+    pub bytes: u64,
+    /// Bytes in the on-disk tier; absent when the cache is memory-only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persisted_bytes: Option<u64>,
+    /// Served fresh from the cache
+    pub hits: u64,
+    /// Served stale while re-rendering in the background
+    pub stale_hits: u64,
+    /// Rendered by Bun because nothing usable was cached
+    pub misses: u64,
+    /// Rendered by Bun without consulting the cache (authenticated or uncacheable)
+    pub bypasses: u64,
+    /// Cold misses served from a concurrent request's render
+    #[serde(default)]
+    pub coalesced: u64,
+    /// Expired entries served because Bun was down or failed to render
+    #[serde(default)]
+    pub stale_if_error: u64,
+}
@@ -0,0 +1,261 @@
+//! Passkey (`WebAuthn`) login and passkey management for the signed-in admin.
+//!
+//! Each ceremony is two requests: fetch options (with a fresh challenge) for
+//! `navigator.credentials`, then post back what the authenticator produced.
+//! A passkey login ends exactly like a password login: a browser session from
+//! [`crate::auth::SessionManager::create_session`] and the `admin_session`
+//! cookie. Passkeys require user verification, so they stand in for both the
+//! password and the TOTP code.
+
+use axum::{
+    Json,
+    extract::{ConnectInfo, Extension, Path, State},
+    http::HeaderMap,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{net::SocketAddr, sync::Arc};
+use ts_rs::TS;
+
+use crate::{
+    auth::{self, AdminPasskey, ClientInfo},
+    events::{self, EventLevel, EventType},
+    handlers::auth::{
+        LoginResponse, begin_attempt, client_ip, log_failed_login, require_second_factor,
+        session_cookie,
+    },
+    state::{AdminSession, AppError, AppResult, AppState},
+    webauthn::{self, AuthenticationCredential, RegistrationCredential},
+};
+
+const MAX_NAME_LEN: usize = 64;
+
+/// A registered passkey, as shown on the account page.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiPasskey {
+    pub id: i32,
+    pub name: String,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub last_used_at: Option<String>,
+}
+
+impl From<AdminPasskey> for ApiPasskey {
+    fn from(passkey: AdminPasskey) -> Self {
+        let fmt = |dt: time::OffsetDateTime| {
+            dt.format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or_default()
+        };
+        Self {
+            id: passkey.id,
+            name: passkey.name,
+            created_at: fmt(passkey.created_at),
+            last_used_at: passkey.last_used_at.map(fmt),
+        }
+    }
+}
+
+/// Body of the registration finish request.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterPasskeyRequest {
+    pub credential: RegistrationCredential,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Current TOTP or recovery code; required once the account has 2FA.
+    #[serde(default)]
+    pub totp_code: Option<String>,
+}
+
+/// Options for a passkey login. Unauthenticated; any passkey registered for
+/// this site may answer.
+#[tracing::instrument(skip_all)]
+pub async fn passkey_login_options_handler(
+    State(state): State<Arc<AppState>>,
+) -> AppResult<impl IntoResponse> {
+    Ok(Json(state.webauthn.start_authentication()))
+}
+
+/// Sign in with a passkey assertion. Failures are throttled and logged like
+/// failed password logins.
+#[tracing::instrument(skip_all)]
+pub async fn passkey_login_handler(
+    State(state): State<Arc<AppState>>,
+    // Absent on the Unix socket listener
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    jar: axum_extra::extract::CookieJar,
+    Json(credential): Json<AuthenticationCredential>,
+) -> AppResult<impl IntoResponse> {
+    let client_ip = client_ip(peer, &headers);
+    let credential_id =
+        webauthn::decode(&credential.id).map_err(|_| AppError::InvalidCredentials)?;
+
+    // Credential IDs are random and unguessable, so an unknown one is a
+    // deleted passkey rather than a guess
+    let Some(stored) = auth::get_passkey_credential(&state.pool, &credential_id).await? else {
+        tracing::info!(client_ip = %client_ip, "Login with unknown passkey");
+        return Err(AppError::InvalidCredentials);
+    };
+    let Some(user) = auth::get_admin_user_by_id(&state.pool, stored.user_id).await? else {
+        return Err(AppError::InvalidCredentials);
+    };
+    let attempt = begin_attempt(&state, client_ip, &user.username).await?;
+
+    let sign_count = match state.webauthn.finish_authentication(&credential, &stored) {
+        Ok(sign_count) => sign_count,
+        Err(e) => {
+            tracing::warn!(username = %user.username, error = %e, "Passkey assertion rejected");
+            log_failed_login(&state, &attempt, &user.username, client_ip, "passkey");
+            return Err(AppError::InvalidCredentials);
+        }
+    };
+    if !auth::record_passkey_use(&state.pool, &credential_id, sign_count).await? {
+        log_failed_login(&state, &attempt, &user.username, client_ip, "passkey");
+        return Err(AppError::InvalidCredentials);
+    }
+    attempt.succeeded();
+
+    let session = state
+        .session_manager
+        .create_session(
+            user.id,
+            user.username.clone(),
+            user.role,
+            ClientInfo::from_headers(&headers, Some(client_ip)),
+        )
+        .await?;
+
+    tracing::info!(username = %user.username, "User logged in with passkey");
+
+    Ok((
+        jar.add(session_cookie(&session)),
+        Json(LoginResponse {
+            success: true,
+            username: user.username,
+        }),
+    ))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn list_passkeys_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+) -> AppResult<impl IntoResponse> {
+    let passkeys = auth::list_passkeys(&state.pool, session.0.user_id).await?;
+    Ok(Json(
+        passkeys
+            .into_iter()
+            .map(ApiPasskey::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Options for registering a new passkey to the signed-in admin.
+#[tracing::instrument(skip_all)]
+pub async fn passkey_registration_options_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+) -> AppResult<impl IntoResponse> {
+    let existing = auth::passkey_credential_ids(&state.pool, session.0.user_id).await?;
+    let options =
+        state
+            .webauthn
+            .start_registration(session.0.user_id, &session.0.username, &existing);
+    Ok(Json(options))
+}
+
+/// Store a new passkey. A passkey signs in on its own, so adding one needs a
+/// current code once 2FA is enabled.
+#[tracing::instrument(skip_all)]
+pub async fn register_passkey_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    Json(payload): Json<RegisterPasskeyRequest>,
+) -> AppResult<impl IntoResponse> {
+    let user = auth::get_admin_user_by_id(&state.pool, session.0.user_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    let name = payload
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or("Passkey");
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::field(
+            "name",
+            format!("Must be at most {MAX_NAME_LEN} characters"),
+        ));
+    }
+    require_second_factor(
+        &state,
+        &user,
+        payload.totp_code.as_deref(),
+        client_ip(peer, &headers),
+    )
+    .await?;
+
+    let credential = state
+        .webauthn
+        .finish_registration(&payload.credential)
+        .map_err(|err| AppError::validation(err.to_string()))?;
+    // The challenge was issued to whoever asked for the options
+    if credential.user_id != user.id {
+        return Err(AppError::Unauthorized);
+    }
+    let Some(id) = auth::add_passkey(&state.pool, &credential, name).await? else {
+        return Err(AppError::Conflict(
+            "This passkey is already registered".to_string(),
+        ));
+    };
+
+    tracing::info!(username = %user.username, passkey_id = id, "Passkey registered");
+    events::log_event(
+        &state.event_sender,
+        EventType::AuthPasskeyAdded,
+        EventLevel::Info,
+        Some("auth"),
+        None,
+        Some(&user.username),
+        format!("Passkey {name:?} added"),
+        Some(json!({ "passkeyId": id, "algorithm": credential.algorithm })),
+    );
+
+    let passkey = auth::list_passkeys(&state.pool, user.id)
+        .await?
+        .into_iter()
+        .find(|passkey| passkey.id == id)
+        .ok_or(AppError::NotFound)?;
+    Ok(Json(ApiPasskey::from(passkey)))
+}
+
+#[tracing::instrument(skip_all, fields(passkey_id = id))]
+pub async fn delete_passkey_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+    Path(id): Path<i32>,
+) -> AppResult<impl IntoResponse> {
+    if !auth::delete_passkey(&state.pool, session.0.user_id, id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    tracing::info!(username = %session.0.username, "Passkey removed");
+    events::log_event(
+        &state.event_sender,
+        EventType::AuthPasskeyRemoved,
+        EventLevel::Info,
+        Some("auth"),
+        None,
+        Some(&session.0.username),
+        "Passkey removed".to_string(),
+        Some(json!({ "passkeyId": id })),
+    );
+    Ok(Json(json!({ "success": true })))
+}
@@ -2,12 +2,15 @@ use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use std::sync::Arc;
 
 use crate::{
-    auth, db,
+    auth, cache, db,
     events::{self, EventLevel, EventType},
     github,
     handlers::{AddProjectTagRequest, CreateProjectRequest, UpdateProjectRequest},
     pm::Doc,
-    state::{AdminSession, AppError, AppResult, AppState, OptionNotFoundExt, SqlxResultExt},
+    state::{
+        AdminSession, AppError, AppResult, AppState, EditorSession, OptionNotFoundExt,
+        SqlxResultExt,
+    },
 };
 
 /// Normalize a submitted `github_repo` to canonical "owner/repo", mapping a bad
@@ -50,8 +53,11 @@ async fn resolve_repo(repo: &str) -> AppResult<Option<github::RepoMeta>> {
 pub async fn projects_handler(
     State(state): State<Arc<AppState>>,
     headers: axum::http::HeaderMap,
+    extensions: axum::http::Extensions,
 ) -> AppResult<impl IntoResponse> {
-    let is_admin = auth::authenticate(&state, &headers).await.is_some();
+    let is_admin = auth::authenticate(&state, &headers, &extensions)
+        .await
+        .is_some();
 
     let projects_with_tags = if is_admin {
         db::get_all_projects_with_tags_admin(&state.pool).await?
@@ -61,7 +67,13 @@ pub async fn projects_handler(
 
     let response: Vec<db::ApiAdminProject> = projects_with_tags
         .into_iter()
-        .map(|(project, tags, media)| project.to_api_admin_project(tags, media))
+        .map(|(project, tags, mut media)| {
+            // Unprocessed uploads have no variants to render publicly
+            if !is_admin {
+                media.retain(|m| m.status.is_ready());
+            }
+            project.to_api_admin_project(tags, media)
+        })
         .collect::<AppResult<Vec<_>>>()?;
     Ok(Json(response))
 }
@@ -72,15 +84,21 @@ pub async fn get_project_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(ref_str): axum::extract::Path<String>,
     headers: axum::http::HeaderMap,
+    extensions: axum::http::Extensions,
 ) -> AppResult<impl IntoResponse> {
-    let is_admin = auth::authenticate(&state, &headers).await.is_some();
-    let (project, tags, media) = db::get_project_by_ref_with_tags(&state.pool, &ref_str)
+    let is_admin = auth::authenticate(&state, &headers, &extensions)
+        .await
+        .is_some();
+    let (project, tags, mut media) = db::get_project_by_ref_with_tags(&state.pool, &ref_str)
         .await?
         .or_not_found()?;
 
     if project.hidden && !is_admin {
         return Err(AppError::NotFound);
     }
+    if !is_admin {
+        media.retain(|m| m.status.is_ready());
+    }
 
     let related = db::get_related_projects(&state.pool, project.id).await?;
 
@@ -91,7 +109,7 @@ pub async fn get_project_handler(
 #[tracing::instrument(skip_all)]
 pub async fn create_project_handler(
     State(state): State<Arc<AppState>>,
-    session: AdminSession,
+    session: EditorSession,
     Json(payload): Json<CreateProjectRequest>,
 ) -> AppResult<impl IntoResponse> {
     if payload.name.trim().is_empty() {
@@ -128,12 +146,15 @@ pub async fn create_project_handler(
     // Validate the submitted detail document against the schema (rejecting it
     // with a 400 if malformed) and store its canonical form — an empty body
     // normalizes to NULL, exactly like the /content ops path.
-    let detail_content = payload
+    let mut detail_doc = payload
         .detail_content
         .as_ref()
         .map(Doc::parse)
-        .transpose()?
-        .and_then(|doc| doc.to_stored());
+        .transpose()?;
+    if let Some(doc) = detail_doc.as_mut() {
+        super::content::resolve_figure_media(&state.pool, doc).await?;
+    }
+    let detail_content = detail_doc.and_then(|doc| doc.to_stored());
 
     // Resolve the repo to its canonical name + stable id at save time. A 404
     // rejects; an inconclusive result keeps the user's normalized name and leaves
@@ -199,7 +220,7 @@ pub async fn create_project_handler(
         tracing::debug!(project_id = %project.id, repo = %repo, "Added project to GitHub scheduler");
     }
 
-    state.isr_cache.invalidate("/").await;
+    state.isr_cache.invalidate_tag(cache::tags::PROJECTS).await;
     crate::og::spawn_project_og(state.clone(), project.id);
 
     Ok((
@@ -213,7 +234,7 @@ pub async fn create_project_handler(
 pub async fn update_project_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(ref_str): axum::extract::Path<String>,
-    session: AdminSession,
+    session: EditorSession,
     Json(payload): Json<UpdateProjectRequest>,
 ) -> AppResult<impl IntoResponse> {
     let existing_project = db::get_project_by_ref(&state.pool, &ref_str)
@@ -255,12 +276,15 @@ pub async fn update_project_handler(
     // Validate the submitted detail document against the schema (rejecting it
     // with a 400 if malformed) and store its canonical form — an empty body
     // normalizes to NULL, exactly like the /content ops path.
-    let detail_content = payload
+    let mut detail_doc = payload
         .detail_content
         .as_ref()
         .map(Doc::parse)
-        .transpose()?
-        .and_then(|doc| doc.to_stored());
+        .transpose()?;
+    if let Some(doc) = detail_doc.as_mut() {
+        super::content::resolve_figure_media(&state.pool, doc).await?;
+    }
+    let detail_content = detail_doc.and_then(|doc| doc.to_stored());
 
     let mut github_repo = normalize_repo_field(payload.github_repo.as_deref())?;
     // Only spend an API call when the repo actually changed, so editing unrelated
@@ -348,17 +372,14 @@ pub async fn update_project_handler(
         }
     }
 
-    state.isr_cache.invalidate("/").await;
+    // Visibility, status, and ordering changes move the project within listings
     state
         .isr_cache
-        .invalidate(&format!("/projects/{}", project.slug))
+        .invalidate_tags([
+            cache::tags::project(project.id),
+            cache::tags::PROJECTS.into(),
+        ])
         .await;
-    if existing_project.slug != project.slug {
-        state
-            .isr_cache
-            .invalidate(&format!("/projects/{}", existing_project.slug))
-            .await;
-    }
     crate::og::spawn_project_og(state.clone(), project.id);
 
     Ok(Json(project.to_api_admin_project(tags, media)?))
@@ -369,7 +390,7 @@ pub async fn update_project_handler(
 pub async fn delete_project_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(ref_str): axum::extract::Path<String>,
-    session: AdminSession,
+    session: EditorSession,
 ) -> AppResult<impl IntoResponse> {
     let (project, tags, media) = db::get_project_by_ref_with_tags(&state.pool, &ref_str)
         .await?
@@ -394,13 +415,20 @@ pub async fn delete_project_handler(
         scheduler.remove_project(project.id);
     }
 
-    state.isr_cache.invalidate("/").await;
     state
         .isr_cache
-        .invalidate(&format!("/projects/{}", project.slug))
+        .invalidate_tags([
+            cache::tags::project(project.id),
+            cache::tags::PROJECTS.into(),
+        ])
         .await;
     crate::og::spawn_delete_project_og(project.id);
 
+    // The attachments cascaded with the project; drop its uploads nothing else uses
+    for item in &media {
+        super::media::release_media_asset(&state.pool, item.asset_id).await?;
+    }
+
     Ok(Json(project.to_api_admin_project(tags, media)?))
 }
 
@@ -410,7 +438,7 @@ pub async fn delete_project_handler(
 pub async fn sync_project_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(ref_str): axum::extract::Path<String>,
-    _: AdminSession,
+    _: EditorSession,
 ) -> AppResult<impl IntoResponse> {
     let project = db::get_project_by_ref(&state.pool, &ref_str)
         .await?
@@ -453,10 +481,9 @@ pub async fn sync_project_handler(
         .or_not_found()?;
 
     tracing::info!(project_id = %project.id, repo = %repo, "Manual GitHub sync complete");
-    state.isr_cache.invalidate("/").await;
     state
         .isr_cache
-        .invalidate(&format!("/projects/{}", project.slug))
+        .invalidate_tag(&cache::tags::project(project.id))
         .await;
 
     Ok(Json(project.to_api_admin_project(tags, media)?))
@@ -464,7 +491,7 @@ pub async fn sync_project_handler(
 
 /// Enqueue every tracked project for an immediate GitHub sync (requires auth).
 #[tracing::instrument(skip_all)]
-pub async fn sync_all_projects_handler(_: AdminSession) -> AppResult<impl IntoResponse> {
+pub async fn sync_all_projects_handler(_: EditorSession) -> AppResult<impl IntoResponse> {
     let Some(scheduler) = github::get_scheduler() else {
         return Err(AppError::ServiceUnavailable(
             "GitHub sync is disabled".to_string(),
@@ -504,7 +531,8 @@ pub async fn get_admin_stats_handler(
     State(state): State<Arc<AppState>>,
     _: AdminSession,
 ) -> AppResult<impl IntoResponse> {
-    let admin_stats = db::get_admin_stats(&state.pool).await?;
+    let mut admin_stats = db::get_admin_stats(&state.pool).await?;
+    admin_stats.cache_warm = state.cache_warmer.status();
     Ok(Json(admin_stats))
 }
 
@@ -528,7 +556,7 @@ pub async fn get_project_tags_handler(
 pub async fn add_project_tag_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(ref_str): axum::extract::Path<String>,
-    session: AdminSession,
+    session: EditorSession,
     Json(payload): Json<AddProjectTagRequest>,
 ) -> AppResult<impl IntoResponse> {
     let project = db::get_project_by_ref(&state.pool, &ref_str)
@@ -553,7 +581,10 @@ pub async fn add_project_tag_handler(
         None,
     );
 
-    state.isr_cache.invalidate("/").await;
+    state
+        .isr_cache
+        .invalidate_tags([cache::tags::project(project.id), cache::tags::tag(tag_id)])
+        .await;
 
     Ok((
         StatusCode::CREATED,
@@ -566,7 +597,7 @@ pub async fn add_project_tag_handler(
 pub async fn remove_project_tag_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path((ref_str, tag_ref)): axum::extract::Path<(String, String)>,
-    session: AdminSession,
+    session: EditorSession,
 ) -> AppResult<impl IntoResponse> {
     let project = db::get_project_by_ref(&state.pool, &ref_str)
         .await?
@@ -589,7 +620,10 @@ pub async fn remove_project_tag_handler(
         None,
     );
 
-    state.isr_cache.invalidate("/").await;
+    state
+        .isr_cache
+        .invalidate_tags([cache::tags::project(project.id), cache::tags::tag(tag.id)])
+        .await;
 
     Ok(Json(
         serde_json::json!({ "message": "Tag removed from project" }),
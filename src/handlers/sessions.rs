@@ -1,4 +1,5 @@
-//! Session management for the admin UI: list active sessions and revoke them.
+//! Session management for the admin UI and `xevion sessions`: list active
+//! sessions and revoke them, and inspect or clear login lockouts.
 
 use axum::{
     Json,
@@ -6,32 +7,19 @@ use axum::{
     response::IntoResponse,
 };
 use serde::Serialize;
+use serde_json::json;
 use std::sync::Arc;
 use ts_rs::TS;
 use ulid::Ulid;
 
 use crate::{
-    auth::{Session, SessionType},
-    state::{AdminSession, AppError, AppResult, AppState},
+    auth::{self, Session},
+    events::{self, EventLevel, EventType},
+    handlers::ApiSession,
+    login_throttle::{ThrottleEntry, ThrottleKey},
+    state::{AppError, AppResult, AppState, OwnerSession},
 };
 
-/// A session as shown in the admin sessions list.
-#[derive(Debug, Clone, Serialize, TS)]
-#[serde(rename_all = "camelCase")]
-#[ts(export)]
-pub struct ApiSession {
-    pub id: String,
-    pub session_type: SessionType,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[ts(optional)]
-    pub label: Option<String>,
-    pub created_at: String,
-    pub last_active_at: String,
-    pub expires_at: String,
-    /// True for the session making this request (don't let the user lock themselves out blindly).
-    pub current: bool,
-}
-
 impl ApiSession {
     fn from_session(session: &Session, current_id: Ulid) -> Self {
         let fmt = |dt: time::OffsetDateTime| {
@@ -40,21 +28,69 @@ impl ApiSession {
         };
         Self {
             id: session.id.to_string(),
+            username: session.username.clone(),
             session_type: session.session_type,
             label: session.label.clone(),
             created_at: fmt(session.created_at),
             last_active_at: fmt(session.last_active_at),
             expires_at: fmt(session.expires_at),
+            created_ip: session.created_ip.map(|ip| ip.to_string()),
+            last_ip: session.last_ip.map(|ip| ip.to_string()),
+            user_agent: session.user_agent.clone(),
+            device: session
+                .user_agent
+                .as_deref()
+                .and_then(auth::describe_user_agent),
             current: session.id == current_id,
         }
     }
 }
 
+/// Failed-login throttle state of a client IP or username.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ApiLoginLockout {
+    /// `kind:value`, as accepted by `DELETE /api/sessions/lockouts/{key}`.
+    pub key: String,
+    /// "ip" or "username".
+    pub kind: String,
+    pub value: String,
+    pub failures: u32,
+    pub last_failure_at: String,
+    /// When attempts are accepted again, if they're currently blocked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub blocked_until: Option<String>,
+    /// True for a lockout, false for a backoff delay.
+    pub locked: bool,
+}
+
+impl From<ThrottleEntry> for ApiLoginLockout {
+    fn from(entry: ThrottleEntry) -> Self {
+        let fmt = |dt: time::OffsetDateTime| {
+            dt.format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or_default()
+        };
+        Self {
+            key: entry.key.to_string(),
+            kind: entry.key.kind().to_string(),
+            value: entry.key.value(),
+            failures: entry.failures,
+            last_failure_at: fmt(entry.last_failure_at.into()),
+            blocked_until: entry
+                .blocked_for
+                .map(|left| fmt(time::OffsetDateTime::now_utc() + left)),
+            locked: entry.locked,
+        }
+    }
+}
+
 /// List the authenticated admin's active sessions (browser + CLI), newest first.
 #[tracing::instrument(skip_all)]
 pub async fn list_sessions_handler(
     State(state): State<Arc<AppState>>,
-    session: AdminSession,
+    session: OwnerSession,
 ) -> AppResult<impl IntoResponse> {
     let current_id = session.0.id;
     let sessions: Vec<ApiSession> = state
@@ -71,7 +107,7 @@ pub async fn list_sessions_handler(
 #[tracing::instrument(skip_all, fields(target = %target_id))]
 pub async fn revoke_session_handler(
     State(state): State<Arc<AppState>>,
-    session: AdminSession,
+    session: OwnerSession,
     Path(target_id): Path<String>,
 ) -> AppResult<impl IntoResponse> {
     let target = Ulid::from_string(&target_id).map_err(|_| AppError::NotFound)?;
@@ -89,3 +125,86 @@ pub async fn revoke_session_handler(
 
     Ok(Json(serde_json::json!({ "success": true })))
 }
+
+/// Revoke every session and API token of the admin except the one making the
+/// request.
+#[tracing::instrument(skip_all)]
+pub async fn revoke_other_sessions_handler(
+    State(state): State<Arc<AppState>>,
+    session: OwnerSession,
+) -> AppResult<impl IntoResponse> {
+    let revoked = state
+        .session_manager
+        .revoke_other_sessions(session.0.user_id, session.0.id)
+        .await?;
+    tracing::info!(revoked, "Other sessions revoked");
+
+    Ok(Json(json!({ "success": true, "revoked": revoked })))
+}
+
+/// List client IPs and usernames with recent failed logins, most recent first.
+#[tracing::instrument(skip_all)]
+pub async fn list_lockouts_handler(
+    State(state): State<Arc<AppState>>,
+    _session: OwnerSession,
+) -> AppResult<impl IntoResponse> {
+    let lockouts: Vec<ApiLoginLockout> = state
+        .login_throttle
+        .entries()
+        .into_iter()
+        .map(ApiLoginLockout::from)
+        .collect();
+    Ok(Json(lockouts))
+}
+
+/// Clear the failed logins of one `kind:value` key, lifting any lockout.
+#[tracing::instrument(skip_all, fields(key = %key))]
+pub async fn clear_lockout_handler(
+    State(state): State<Arc<AppState>>,
+    session: OwnerSession,
+    Path(key): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let key: ThrottleKey = key
+        .parse()
+        .map_err(|()| AppError::validation("Expected ip:<address> or username:<name>"))?;
+    if !state.login_throttle.clear(&key) {
+        return Err(AppError::NotFound);
+    }
+
+    tracing::info!(key = %key, "Login lockout cleared");
+    events::log_event(
+        &state.event_sender,
+        EventType::AuthLockoutCleared,
+        EventLevel::Info,
+        Some("auth"),
+        None,
+        Some(&session.0.username),
+        format!("Cleared failed logins for {} {}", key.kind(), key.value()),
+        Some(json!({ "key": key.to_string() })),
+    );
+
+    Ok(Json(json!({ "success": true })))
+}
+
+/// Clear all failed logins, lifting every lockout.
+#[tracing::instrument(skip_all)]
+pub async fn clear_lockouts_handler(
+    State(state): State<Arc<AppState>>,
+    session: OwnerSession,
+) -> AppResult<impl IntoResponse> {
+    let cleared = state.login_throttle.clear_all();
+
+    tracing::info!(cleared, "Login lockouts cleared");
+    events::log_event(
+        &state.event_sender,
+        EventType::AuthLockoutCleared,
+        EventLevel::Info,
+        Some("auth"),
+        None,
+        Some(&session.0.username),
+        format!("Cleared failed logins for {cleared} key(s)"),
+        Some(json!({ "cleared": cleared })),
+    );
+
+    Ok(Json(json!({ "success": true, "cleared": cleared })))
+}
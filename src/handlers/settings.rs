@@ -2,9 +2,9 @@ use axum::{Json, extract::State, response::IntoResponse};
 use std::sync::Arc;
 
 use crate::{
-    db,
+    cache, db,
     events::{self, EventLevel, EventType},
-    state::{AdminSession, AppResult, AppState},
+    state::{AppError, AppResult, AppState, OwnerSession},
 };
 
 #[tracing::instrument(skip_all)]
@@ -18,9 +18,22 @@ pub async fn get_settings_handler(
 #[tracing::instrument(skip_all)]
 pub async fn update_settings_handler(
     State(state): State<Arc<AppState>>,
-    session: AdminSession,
+    session: OwnerSession,
     Json(payload): Json<db::UpdateSiteSettingsRequest>,
 ) -> AppResult<impl IntoResponse> {
+    for (field, id) in [
+        ("avatarMediaId", payload.identity.avatar_media_id),
+        ("faviconMediaId", payload.identity.favicon_media_id),
+    ] {
+        let Some(id) = id else { continue };
+        let asset = db::get_media_asset(&state.pool, id)
+            .await?
+            .ok_or_else(|| AppError::field(field, "Media not found in the library"))?;
+        if asset.media_type != db::MediaType::Image {
+            return Err(AppError::field(field, "Must be an image"));
+        }
+    }
+
     let settings = db::update_site_settings(&state.pool, &payload).await?;
     tracing::info!("Site settings updated");
     events::log_event(
@@ -33,5 +46,6 @@ pub async fn update_settings_handler(
         "Site settings updated".to_string(),
         None,
     );
+    state.isr_cache.invalidate_tag(cache::tags::SETTINGS).await;
     Ok(Json(settings))
 }
@@ -0,0 +1,173 @@
+//! Object storage: serving locally stored files and reconciling storage
+//! against the database.
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{Path, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::{
+    handlers::StorageGcRequest,
+    r2::R2Client,
+    state::{AppResult, AppState, OwnerSession},
+    storage_gc, utils,
+};
+
+/// Serve an object from the local `fs` storage backend, honoring single byte
+/// ranges so videos can seek. 404s when media lives in R2, which serves its
+/// own public URLs.
+#[tracing::instrument(skip(headers))]
+pub async fn serve_stored_object_handler(Path(key): Path<String>, headers: HeaderMap) -> Response {
+    let Some(storage) = R2Client::get().await.filter(|s| s.is_local()) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    serve_object(&storage, key, &headers).await
+}
+
+async fn serve_object(storage: &R2Client, key: String, headers: &HeaderMap) -> Response {
+    if !is_servable_key(&key) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let stat = match storage.stat_object(&key).await {
+        Ok(Some(stat)) => stat,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to stat stored object");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let range_header = headers.get(header::RANGE).and_then(|v| v.to_str().ok());
+    let (status, range) = match utils::parse_byte_range(range_header, stat.size) {
+        utils::ByteRange::Partial(range) => (StatusCode::PARTIAL_CONTENT, range),
+        utils::ByteRange::Full => (StatusCode::OK, 0..stat.size),
+        utils::ByteRange::Unsatisfiable => {
+            return (
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{}", stat.size))],
+            )
+                .into_response();
+        }
+    };
+
+    let stream = match storage.stream_object(&key, range.clone()).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to read stored object");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let mut response = Response::new(Body::from_stream(stream));
+    *response.status_mut() = status;
+    let response_headers = response.headers_mut();
+    let content_type = mime_guess::from_path(&key).first_or_octet_stream();
+    if let Ok(value) = HeaderValue::from_str(content_type.essence_str()) {
+        response_headers.insert(header::CONTENT_TYPE, value);
+    }
+    response_headers.insert(
+        header::CONTENT_LENGTH,
+        HeaderValue::from(range.end - range.start),
+    );
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    // Uploads are served from the site's origin; never let a browser sniff one
+    // into something other than its declared type
+    response_headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    response_headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control(&key)),
+    );
+    if status == StatusCode::PARTIAL_CONTENT
+        && let Ok(value) = HeaderValue::from_str(&format!(
+            "bytes {}-{}/{}",
+            range.start,
+            range.end - 1,
+            stat.size
+        ))
+    {
+        response_headers.insert(header::CONTENT_RANGE, value);
+    }
+
+    response
+}
+
+/// Only the public prefixes are served, and never anything path-like that could
+/// escape the storage root (`..`, empty segments, the `.tmp` staging area).
+fn is_servable_key(key: &str) -> bool {
+    ["projects/", "media/", "og/"]
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+        && key
+            .split('/')
+            .all(|segment| !segment.is_empty() && !segment.starts_with('.'))
+}
+
+/// Media assets and project OG cards never change under a given URL (new
+/// uploads get new keys; project cards are cache-busted with `?v=`); the shared
+/// OG cards are overwritten in place, so they only cache for a day.
+fn cache_control(key: &str) -> &'static str {
+    if key.starts_with("og/") && !key.starts_with("og/project/") {
+        "public, max-age=86400"
+    } else {
+        "public, max-age=31536000, immutable"
+    }
+}
+
+/// Report orphaned and missing media objects; delete/repair them with `apply`
+/// (requires auth).
+#[tracing::instrument(skip_all, fields(apply = req.apply))]
+pub async fn storage_gc_handler(
+    State(state): State<Arc<AppState>>,
+    session: OwnerSession,
+    Json(req): Json<StorageGcRequest>,
+) -> AppResult<impl IntoResponse> {
+    let report = storage_gc::run(&state, req.apply, &session.0.username).await?;
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opendal::{Operator, services::Memory};
+
+    async fn storage_with(key: &str) -> R2Client {
+        let storage = R2Client::from_operator(Operator::new(Memory::default()).unwrap().finish());
+        storage
+            .put_object(key, b"webp".to_vec(), "image/webp", None)
+            .await
+            .unwrap();
+        storage
+    }
+
+    #[tokio::test]
+    async fn serves_library_media() {
+        let key = format!("media/{}/full.webp", ulid::Ulid::new());
+        let storage = storage_with(&key).await;
+
+        let response = serve_object(&storage, key, &HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/webp");
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "4");
+        assert_eq!(
+            response.headers()[header::X_CONTENT_TYPE_OPTIONS],
+            "nosniff"
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_keys_outside_public_prefixes() {
+        let storage = storage_with("private/secret.txt").await;
+        let response = serve_object(&storage, "private/secret.txt".into(), &HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(!is_servable_key("media/../private/secret.txt"));
+        assert!(!is_servable_key("media/.tmp/upload"));
+    }
+}
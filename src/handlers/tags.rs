@@ -2,10 +2,10 @@ use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use std::sync::Arc;
 
 use crate::{
-    db,
+    cache, db,
     events::{self, EventLevel, EventType},
     handlers::{CreateTagRequest, UpdateTagRequest},
-    state::{AdminSession, AppError, AppResult, AppState, OptionNotFoundExt, SqlxResultExt},
+    state::{AppError, AppResult, AppState, EditorSession, OptionNotFoundExt, SqlxResultExt},
     utils,
 };
 
@@ -26,7 +26,7 @@ pub async fn list_tags_handler(State(state): State<Arc<AppState>>) -> AppResult<
 #[tracing::instrument(skip_all)]
 pub async fn create_tag_handler(
     State(state): State<Arc<AppState>>,
-    session: AdminSession,
+    session: EditorSession,
     Json(payload): Json<CreateTagRequest>,
 ) -> AppResult<impl IntoResponse> {
     if payload.name.trim().is_empty() {
@@ -62,7 +62,7 @@ pub async fn create_tag_handler(
         None,
     );
 
-    state.isr_cache.invalidate("/").await;
+    // A new tag is on no project yet, so no cached page shows it
     Ok((StatusCode::CREATED, Json(tag.to_api_tag())))
 }
 
@@ -88,7 +88,7 @@ pub async fn get_tag_handler(
 pub async fn update_tag_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(ref_str): axum::extract::Path<String>,
-    session: AdminSession,
+    session: EditorSession,
     Json(payload): Json<UpdateTagRequest>,
 ) -> AppResult<impl IntoResponse> {
     if payload.name.trim().is_empty() {
@@ -129,7 +129,10 @@ pub async fn update_tag_handler(
         None,
     );
 
-    state.isr_cache.invalidate("/").await;
+    state
+        .isr_cache
+        .invalidate_tag(&cache::tags::tag(updated_tag.id))
+        .await;
     Ok(Json(updated_tag.to_api_tag()))
 }
 
@@ -138,7 +141,7 @@ pub async fn update_tag_handler(
 pub async fn delete_tag_handler(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(ref_str): axum::extract::Path<String>,
-    session: AdminSession,
+    session: EditorSession,
 ) -> AppResult<impl IntoResponse> {
     let tag = db::get_tag_by_ref(&state.pool, &ref_str)
         .await?
@@ -157,7 +160,10 @@ pub async fn delete_tag_handler(
         None,
     );
 
-    state.isr_cache.invalidate("/").await;
+    state
+        .isr_cache
+        .invalidate_tag(&cache::tags::tag(tag.id))
+        .await;
     Ok(Json(tag.to_api_tag()))
 }
 
@@ -186,7 +192,7 @@ pub async fn get_related_tags_handler(
 #[tracing::instrument(skip_all)]
 pub async fn recalculate_cooccurrence_handler(
     State(state): State<Arc<AppState>>,
-    _: AdminSession,
+    _: EditorSession,
 ) -> AppResult<impl IntoResponse> {
     db::recalculate_tag_cooccurrence(&state.pool).await?;
     Ok(Json(serde_json::json!({
@@ -0,0 +1,178 @@
+//! Scoped API tokens for the signed-in admin: list, create, and revoke.
+//!
+//! Creating a token needs an interactive login (browser or CLI): `POST
+//! /api/tokens` has no scope, so API tokens can't mint more tokens.
+
+use axum::{
+    Json,
+    extract::{ConnectInfo, Extension, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use serde_json::json;
+use std::{net::SocketAddr, sync::Arc};
+use ulid::Ulid;
+
+use crate::{
+    api_tokens::{
+        ApiToken, CreateApiTokenRequest, CreatedApiToken, DEFAULT_TOKEN_DAYS, MAX_TOKEN_DAYS,
+    },
+    auth::{ClientInfo, Session, SessionType, TokenGrant},
+    db,
+    events::{self, EventLevel, EventType},
+    handlers::auth::client_ip,
+    state::{AdminSession, AppError, AppResult, AppState},
+};
+
+const MAX_NAME_LEN: usize = 64;
+
+fn api_token(session: &Session) -> ApiToken {
+    let fmt = |dt: time::OffsetDateTime| {
+        dt.format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_default()
+    };
+    let grant = session.grant.as_ref();
+    ApiToken {
+        id: session.id.to_string(),
+        name: session.label.clone().unwrap_or_default(),
+        scopes: grant.map(|g| g.scopes.clone()).unwrap_or_default(),
+        project_ids: grant.and_then(|g| g.project_ids.clone()),
+        created_at: fmt(session.created_at),
+        last_used_at: fmt(session.last_active_at),
+        expires_at: fmt(session.expires_at),
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn list_tokens_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+) -> AppResult<impl IntoResponse> {
+    let tokens: Vec<ApiToken> = state
+        .session_manager
+        .list_user_sessions(session.0.user_id)
+        .iter()
+        .filter(|s| s.session_type == SessionType::Token)
+        .map(api_token)
+        .collect();
+    Ok(Json(tokens))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn create_token_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateApiTokenRequest>,
+) -> AppResult<impl IntoResponse> {
+    let session = session.0;
+    let name = payload.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::field(
+            "name",
+            format!("Must be 1 to {MAX_NAME_LEN} characters"),
+        ));
+    }
+    // Every scope is a content write; a viewer can only mint read-only tokens
+    if !payload.scopes.is_empty() && !session.role.can_edit_content() {
+        return Err(AppError::Forbidden);
+    }
+    let days = payload.expires_in_days.unwrap_or(DEFAULT_TOKEN_DAYS);
+    if !(1..=MAX_TOKEN_DAYS).contains(&days) {
+        return Err(AppError::field(
+            "expiresInDays",
+            format!("Must be between 1 and {MAX_TOKEN_DAYS} days"),
+        ));
+    }
+
+    // Resolve refs now so the allowlist survives slug changes
+    let project_ids = if payload.projects.is_empty() {
+        None
+    } else {
+        let mut ids = Vec::with_capacity(payload.projects.len());
+        for project_ref in &payload.projects {
+            let project = db::get_project_by_ref(&state.pool, project_ref)
+                .await?
+                .ok_or_else(|| {
+                    AppError::field("projects", format!("No project '{project_ref}'"))
+                })?;
+            if !ids.contains(&project.id) {
+                ids.push(project.id);
+            }
+        }
+        Some(ids)
+    };
+
+    let mut scopes = payload.scopes;
+    scopes.sort_by_key(|s| s.as_str());
+    scopes.dedup();
+    let grant = TokenGrant {
+        scopes,
+        project_ids,
+    };
+
+    let (token_session, token) = state
+        .session_manager
+        .create_api_token(
+            &session,
+            name.to_string(),
+            grant,
+            time::Duration::days(i64::from(days)),
+            ClientInfo::from_headers(&headers, Some(client_ip(peer, &headers))),
+        )
+        .await?;
+    let info = api_token(&token_session);
+
+    tracing::info!(username = %session.username, token_id = %info.id, "API token created");
+    events::log_event(
+        &state.event_sender,
+        EventType::AuthTokenCreated,
+        EventLevel::Info,
+        Some("auth"),
+        None,
+        Some(&session.username),
+        format!("API token {name:?} created"),
+        Some(json!({
+            "tokenId": info.id,
+            "scopes": info.scopes,
+            "projectIds": info.project_ids,
+            "expiresAt": info.expires_at,
+        })),
+    );
+
+    Ok((StatusCode::CREATED, Json(CreatedApiToken { token, info })))
+}
+
+#[tracing::instrument(skip_all, fields(token_id = %id))]
+pub async fn revoke_token_handler(
+    State(state): State<Arc<AppState>>,
+    session: AdminSession,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let target = Ulid::from_string(&id).map_err(|_| AppError::NotFound)?;
+    let token = state
+        .session_manager
+        .get_session(target)
+        .filter(|s| s.user_id == session.0.user_id && s.session_type == SessionType::Token)
+        .ok_or(AppError::NotFound)?;
+
+    state.session_manager.delete_session(target).await?;
+
+    tracing::info!(username = %session.0.username, "API token revoked");
+    events::log_event(
+        &state.event_sender,
+        EventType::AuthTokenRevoked,
+        EventLevel::Info,
+        Some("auth"),
+        None,
+        Some(&session.0.username),
+        format!(
+            "API token {:?} revoked",
+            token.label.as_deref().unwrap_or_default()
+        ),
+        Some(json!({ "tokenId": id })),
+    );
+
+    Ok(Json(json!({ "success": true })))
+}
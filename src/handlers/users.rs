@@ -0,0 +1,154 @@
+//! Admin user management (owners only): list users, create them, change their
+//! role, and delete them. Changes apply to the target's live sessions at once.
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::{
+    auth::{self, AdminUserSummary, UserChange},
+    events::{self, EventLevel, EventType},
+    handlers::{ApiAdminUser, CreateAdminUserRequest, UpdateAdminUserRequest},
+    state::{AppError, AppResult, AppState, OwnerSession, SqlxResultExt},
+};
+
+const LAST_OWNER: &str = "At least one owner must remain";
+
+impl From<AdminUserSummary> for ApiAdminUser {
+    fn from(user: AdminUserSummary) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            role: user.role,
+            totp_enabled: user.totp_enabled,
+            passkeys: user.passkeys,
+            created_at: user
+                .created_at
+                .format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+async fn find_user(state: &AppState, id: i32) -> AppResult<ApiAdminUser> {
+    auth::list_admin_users(&state.pool)
+        .await?
+        .into_iter()
+        .find(|user| user.id == id)
+        .map(ApiAdminUser::from)
+        .ok_or(AppError::NotFound)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn list_users_handler(
+    State(state): State<Arc<AppState>>,
+    _: OwnerSession,
+) -> AppResult<impl IntoResponse> {
+    let users = auth::list_admin_users(&state.pool).await?;
+    Ok(Json(
+        users
+            .into_iter()
+            .map(ApiAdminUser::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn create_user_handler(
+    State(state): State<Arc<AppState>>,
+    session: OwnerSession,
+    Json(payload): Json<CreateAdminUserRequest>,
+) -> AppResult<impl IntoResponse> {
+    let username = payload.username.trim();
+    auth::validate_username(username).map_err(|msg| AppError::field("username", msg))?;
+    auth::validate_password(&payload.password).map_err(|msg| AppError::field("password", msg))?;
+
+    let password_hash =
+        auth::hash_password(&payload.password).map_err(|e| AppError::Internal(e.to_string()))?;
+    let id = auth::insert_admin_user(&state.pool, username, &password_hash, payload.role)
+        .await
+        .conflict_on_unique(format!("User '{username}' already exists"))?;
+
+    tracing::info!(username, role = %payload.role, "Admin user created");
+    events::log_event(
+        &state.event_sender,
+        EventType::UserCreated,
+        EventLevel::Info,
+        Some("user"),
+        None,
+        Some(&session.0.username),
+        format!("Created {} '{username}'", payload.role),
+        Some(json!({ "userId": id, "username": username, "role": payload.role })),
+    );
+
+    Ok((StatusCode::CREATED, Json(find_user(&state, id).await?)))
+}
+
+#[tracing::instrument(skip_all, fields(user_id = id))]
+pub async fn update_user_handler(
+    State(state): State<Arc<AppState>>,
+    session: OwnerSession,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateAdminUserRequest>,
+) -> AppResult<impl IntoResponse> {
+    match auth::set_admin_role(&state.pool, id, payload.role).await? {
+        UserChange::Done => {}
+        UserChange::NotFound => return Err(AppError::NotFound),
+        UserChange::LastOwner => return Err(AppError::Conflict(LAST_OWNER.to_string())),
+    }
+    state.session_manager.set_user_role(id, payload.role);
+
+    let user = find_user(&state, id).await?;
+    tracing::info!(username = %user.username, role = %payload.role, "Admin role changed");
+    events::log_event(
+        &state.event_sender,
+        EventType::UserRoleChanged,
+        EventLevel::Info,
+        Some("user"),
+        None,
+        Some(&session.0.username),
+        format!("Changed '{}' to {}", user.username, payload.role),
+        Some(json!({ "userId": id, "username": user.username, "role": payload.role })),
+    );
+
+    Ok(Json(user))
+}
+
+#[tracing::instrument(skip_all, fields(user_id = id))]
+pub async fn delete_user_handler(
+    State(state): State<Arc<AppState>>,
+    session: OwnerSession,
+    Path(id): Path<i32>,
+) -> AppResult<impl IntoResponse> {
+    if id == session.0.user_id {
+        return Err(AppError::Conflict(
+            "You can't delete your own account".to_string(),
+        ));
+    }
+    let user = find_user(&state, id).await?;
+    match auth::delete_admin_user(&state.pool, id).await? {
+        UserChange::Done => {}
+        UserChange::NotFound => return Err(AppError::NotFound),
+        UserChange::LastOwner => return Err(AppError::Conflict(LAST_OWNER.to_string())),
+    }
+    state.session_manager.forget_user_sessions(id);
+
+    tracing::info!(username = %user.username, "Admin user deleted");
+    events::log_event(
+        &state.event_sender,
+        EventType::UserDeleted,
+        EventLevel::Warning,
+        Some("user"),
+        None,
+        Some(&session.0.username),
+        format!("Deleted '{}'", user.username),
+        Some(json!({ "userId": id, "username": user.username })),
+    );
+
+    Ok(Json(json!({ "success": true })))
+}
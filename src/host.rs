@@ -79,6 +79,16 @@ impl HostConfig {
         &self.canonical
     }
 
+    /// Every host a page can be cached under: the allowlist plus the canonical
+    /// fallback, or just the canonical host in permissive mode.
+    pub fn public_hosts(&self) -> Vec<String> {
+        let mut hosts = self.allowed.clone();
+        if !hosts.contains(&self.canonical) {
+            hosts.push(self.canonical.clone());
+        }
+        hosts
+    }
+
     /// The public URL scheme. Allowlisted hosts are always served over HTTPS
     /// (TLS terminates at the edge); permissive dev mode stays on HTTP so
     /// `localhost` origins resolve to `http://localhost:<port>`.
@@ -86,6 +96,22 @@ impl HostConfig {
         if self.permissive { "http" } else { "https" }
     }
 
+    /// Whether `origin` (an `Origin` header value) is one of ours. In
+    /// permissive mode it must match the request's own authority; otherwise
+    /// it must be `https://` on a public host.
+    pub fn is_own_origin(&self, origin: &str, headers: &HeaderMap) -> bool {
+        let Some((scheme, authority)) = origin.split_once("://") else {
+            return false;
+        };
+        if self.permissive {
+            return candidate_authority(headers)
+                .is_some_and(|own| own.eq_ignore_ascii_case(authority));
+        }
+        let hostname = normalize_hostname(authority);
+        scheme == "https"
+            && (hostname == self.canonical || self.allowed.iter().any(|h| h == &hostname))
+    }
+
     /// Resolve the trusted public host for a request from its headers.
     ///
     /// Prefers `X-Forwarded-Host` (set by the proxy), falling back to `Host`.
@@ -166,6 +192,18 @@ mod tests {
         assert_eq!(config.resolve(&HeaderMap::new()), "xevion.dev");
     }
 
+    #[test]
+    fn public_hosts_include_canonical_once() {
+        let config = HostConfig::new(&["xevion.dev", "walters.to"], "xevion.dev");
+        assert_eq!(config.public_hosts(), ["xevion.dev", "walters.to"]);
+
+        let config = HostConfig::new(&["walters.to"], "xevion.dev");
+        assert_eq!(config.public_hosts(), ["walters.to", "xevion.dev"]);
+
+        let config = HostConfig::new(&[], "localhost");
+        assert_eq!(config.public_hosts(), ["localhost"]);
+    }
+
     #[test]
     fn forwarded_host_takes_precedence_over_host() {
         let config = HostConfig::new(&["xevion.dev", "walters.to"], "xevion.dev");
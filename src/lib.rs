@@ -6,8 +6,10 @@
 //! entry points.
 
 // Shared between the `xevion` CLI and `xevion-server`: the CLI dispatch, the
-// DTO types (db/handlers/pm/cli_auth), and the error types in `state`. These
-// compile without the `server` feature so the client builds with no frontend.
+// DTO types (db/handlers/pm/cli_auth/api_tokens), and the error types in
+// `state`. These compile without the `server` feature so the client builds
+// with no frontend.
+pub mod api_tokens;
 pub mod cli;
 pub mod cli_auth;
 pub mod db;
@@ -26,8 +28,18 @@ pub mod auth;
 #[cfg(feature = "server")]
 pub mod cache;
 #[cfg(feature = "server")]
+pub mod cache_policy;
+#[cfg(feature = "server")]
+pub mod cache_store;
+#[cfg(feature = "server")]
+pub mod cache_warmer;
+#[cfg(feature = "server")]
+pub mod conditional;
+#[cfg(feature = "server")]
 pub mod config;
 #[cfg(feature = "server")]
+pub mod csrf;
+#[cfg(feature = "server")]
 pub mod encoding;
 #[cfg(feature = "server")]
 pub mod events;
@@ -44,8 +56,14 @@ pub mod http;
 #[cfg(feature = "server")]
 pub mod icon_cache;
 #[cfg(feature = "server")]
+pub mod login_throttle;
+#[cfg(feature = "server")]
 pub mod media_processing;
 #[cfg(feature = "server")]
+pub mod media_queue;
+#[cfg(feature = "server")]
+pub mod media_upload;
+#[cfg(feature = "server")]
 pub mod middleware;
 #[cfg(feature = "server")]
 pub mod og;
@@ -56,6 +74,14 @@ pub mod r2;
 #[cfg(feature = "server")]
 pub mod routes;
 #[cfg(feature = "server")]
+pub mod session_policy;
+#[cfg(feature = "server")]
+pub mod storage_gc;
+#[cfg(feature = "server")]
 pub mod tarpit;
 #[cfg(feature = "server")]
+pub mod totp;
+#[cfg(feature = "server")]
 pub mod utils;
+#[cfg(feature = "server")]
+pub mod webauthn;
@@ -0,0 +1,561 @@
+//! Login brute-force protection
+//!
+//! Login attempts are counted per client IP and per username. After
+//! `free_attempts` failures each further attempt has to wait an exponentially
+//! growing delay. An IP is refused until its delay has passed, and after
+//! `lockout_after` failures it is locked out for `lockout`. A username is never
+//! refused, since anyone can fail logins for it: its delay is served by holding
+//! the next attempt before the credentials are checked, so the real owner still
+//! gets in. A successful login clears both counters; counters are forgotten
+//! after `failure_window` without failures, and at most `max_entries` keys are
+//! tracked.
+//!
+//! Client IPs come from `X-Real-IP` / `X-Forwarded-For` (see
+//! [`crate::tarpit::extract_client_ip`]), which a client can forge when the
+//! server is reachable without the proxy in front; the username delay still
+//! applies then.
+
+use dashmap::DashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Usernames are truncated to this many characters before being used as a key
+const MAX_USERNAME_KEY_LEN: usize = 64;
+
+/// Longest an attempt is held for its username's backoff, well inside the
+/// timeouts of a proxy in front
+const MAX_USERNAME_DELAY: Duration = Duration::from_secs(30);
+
+/// Entry count above which stale entries are swept while recording attempts
+const SWEEP_THRESHOLD: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct LoginThrottleConfig {
+    /// Failures allowed before attempts are delayed
+    pub free_attempts: u32,
+    /// Delay after the first failure beyond `free_attempts`; doubles per failure
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Failures after which the key is locked out
+    pub lockout_after: u32,
+    pub lockout: Duration,
+    /// Counters are forgotten after this long without a failure
+    pub failure_window: Duration,
+    /// Keys tracked at most; the least recently failed are dropped first
+    pub max_entries: usize,
+}
+
+impl LoginThrottleConfig {
+    pub fn from_env() -> Self {
+        Self {
+            free_attempts: env_or("LOGIN_FREE_ATTEMPTS", 3),
+            base_delay: Duration::from_millis(env_or("LOGIN_BACKOFF_BASE_MS", 1000)),
+            max_delay: Duration::from_secs(env_or("LOGIN_BACKOFF_MAX_SEC", 300)),
+            lockout_after: env_or("LOGIN_LOCKOUT_AFTER", 10),
+            lockout: Duration::from_secs(env_or("LOGIN_LOCKOUT_SEC", 900)),
+            failure_window: Duration::from_secs(env_or("LOGIN_FAILURE_WINDOW_SEC", 3600)),
+            max_entries: env_or("LOGIN_THROTTLE_MAX_ENTRIES", 10_000).max(2),
+        }
+    }
+
+    /// How long a key with `failures` recorded failures is blocked, and
+    /// whether that block is a lockout (only IPs are locked out)
+    fn block_for(&self, key: &ThrottleKey, failures: u32) -> Option<(Duration, bool)> {
+        if failures >= self.lockout_after && matches!(key, ThrottleKey::Ip(_)) {
+            return Some((self.lockout, true));
+        }
+        let excess = failures.checked_sub(self.free_attempts)?;
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(excess))
+            .min(self.max_delay);
+        Some((delay, false))
+    }
+}
+
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// What login attempts are counted against
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ThrottleKey {
+    Ip(IpAddr),
+    Username(String),
+}
+
+impl ThrottleKey {
+    /// Key for `username`, case-insensitive and length-capped so arbitrary
+    /// request bodies can't grow the table unboundedly per entry
+    pub fn username(username: &str) -> Self {
+        Self::Username(
+            username
+                .trim()
+                .to_lowercase()
+                .chars()
+                .take(MAX_USERNAME_KEY_LEN)
+                .collect(),
+        )
+    }
+
+    /// `"ip"` or `"username"`
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Ip(_) => "ip",
+            Self::Username(_) => "username",
+        }
+    }
+
+    pub fn value(&self) -> String {
+        match self {
+            Self::Ip(ip) => ip.to_string(),
+            Self::Username(name) => name.clone(),
+        }
+    }
+}
+
+impl fmt::Display for ThrottleKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.kind(), self.value())
+    }
+}
+
+impl FromStr for ThrottleKey {
+    type Err = ();
+
+    /// Parses the `kind:value` form produced by `Display`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':').ok_or(())? {
+            ("ip", ip) => ip.parse().map(Self::Ip).map_err(|_| ()),
+            ("username", name) => Ok(Self::username(name)),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Failures {
+    count: u32,
+    last_failure: Instant,
+    last_failure_at: SystemTime,
+    blocked_until: Option<Instant>,
+    locked: bool,
+}
+
+impl Failures {
+    fn remaining_block(&self, now: Instant) -> Option<Duration> {
+        self.blocked_until
+            .and_then(|until| until.checked_duration_since(now))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    fn is_stale(&self, now: Instant, window: Duration) -> bool {
+        self.remaining_block(now).is_none()
+            && now.saturating_duration_since(self.last_failure) >= window
+    }
+}
+
+/// Throttle state of one key, as listed for admins
+#[derive(Debug, Clone)]
+pub struct ThrottleEntry {
+    pub key: ThrottleKey,
+    pub failures: u32,
+    pub last_failure_at: SystemTime,
+    /// Time left until attempts are accepted again
+    pub blocked_for: Option<Duration>,
+    /// Whether the block is a lockout rather than a backoff delay
+    pub locked: bool,
+}
+
+/// An attempt admitted by [`LoginThrottle::begin`]. It is counted as a failure
+/// up front so concurrent guesses can't all slip in before the first one is
+/// verified; [`LoginAttempt::succeeded`] clears the counters again.
+///
+/// `delay` is the username's backoff: how long to hold the attempt before
+/// checking its credentials (at most 30 seconds).
+#[derive(Debug)]
+#[must_use]
+pub struct LoginAttempt<'a> {
+    throttle: &'a LoginThrottle,
+    keys: [ThrottleKey; 2],
+    /// Highest failure count among the keys, including this attempt
+    pub failures: u32,
+    pub delay: Duration,
+    /// Keys this attempt locks out if it fails
+    pub locks_out: Vec<ThrottleKey>,
+}
+
+impl LoginAttempt<'_> {
+    pub fn succeeded(self) {
+        for key in &self.keys {
+            self.throttle.entries.remove(key);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LoginThrottle {
+    entries: DashMap<ThrottleKey, Failures>,
+    pub config: LoginThrottleConfig,
+}
+
+impl LoginThrottle {
+    pub fn new(config: LoginThrottleConfig) -> Self {
+        Self {
+            entries: DashMap::new(),
+            config,
+        }
+    }
+
+    /// Admit a login attempt from `ip` for `username`, or return how long the
+    /// IP has to wait first. Rejected attempts are not counted.
+    pub fn begin(&self, ip: IpAddr, username: &str) -> Result<LoginAttempt<'_>, Duration> {
+        self.begin_at(ip, username, Instant::now(), SystemTime::now())
+    }
+
+    fn begin_at(
+        &self,
+        ip: IpAddr,
+        username: &str,
+        now: Instant,
+        wall: SystemTime,
+    ) -> Result<LoginAttempt<'_>, Duration> {
+        let keys = [ThrottleKey::Ip(ip), ThrottleKey::username(username)];
+
+        if let Some(retry_after) = self
+            .entries
+            .get(&keys[0])
+            .and_then(|entry| entry.remaining_block(now))
+        {
+            return Err(retry_after);
+        }
+        let delay = self
+            .entries
+            .get(&keys[1])
+            .and_then(|entry| entry.remaining_block(now))
+            .unwrap_or_default()
+            .min(MAX_USERNAME_DELAY);
+
+        if self.entries.len() > SWEEP_THRESHOLD {
+            self.sweep(now);
+        }
+        let new_keys = keys
+            .iter()
+            .filter(|key| !self.entries.contains_key(key))
+            .count();
+        if self.entries.len() + new_keys > self.config.max_entries {
+            self.make_room(now, new_keys);
+        }
+
+        let mut failures = 0;
+        let mut locks_out = Vec::new();
+        for key in &keys {
+            let mut entry = self.entries.entry(key.clone()).or_insert(Failures {
+                count: 0,
+                last_failure: now,
+                last_failure_at: wall,
+                blocked_until: None,
+                locked: false,
+            });
+            if entry.is_stale(now, self.config.failure_window) {
+                entry.count = 0;
+                entry.locked = false;
+            }
+            entry.count = entry.count.saturating_add(1);
+            entry.last_failure = now;
+            entry.last_failure_at = wall;
+            match self.config.block_for(key, entry.count) {
+                Some((block, locked)) => {
+                    entry.blocked_until = Some(now + block);
+                    if locked && !entry.locked {
+                        locks_out.push(key.clone());
+                    }
+                    entry.locked = locked;
+                }
+                None => entry.blocked_until = None,
+            }
+            failures = failures.max(entry.count);
+        }
+
+        Ok(LoginAttempt {
+            throttle: self,
+            keys,
+            failures,
+            delay,
+            locks_out,
+        })
+    }
+
+    /// Keys with recent failures, most recently failed first
+    pub fn entries(&self) -> Vec<ThrottleEntry> {
+        let now = Instant::now();
+        self.sweep(now);
+        let mut entries: Vec<ThrottleEntry> = self
+            .entries
+            .iter()
+            .map(|entry| ThrottleEntry {
+                key: entry.key().clone(),
+                failures: entry.count,
+                last_failure_at: entry.last_failure_at,
+                blocked_for: entry.remaining_block(now),
+                locked: entry.locked && entry.remaining_block(now).is_some(),
+            })
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_failure_at));
+        entries
+    }
+
+    /// Forget the failures of `key`. Returns whether it had any.
+    pub fn clear(&self, key: &ThrottleKey) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    /// Forget all failures. Returns how many keys had any.
+    pub fn clear_all(&self) -> usize {
+        let cleared = self.entries.len();
+        self.entries.clear();
+        cleared
+    }
+
+    /// Free up `needed` slots: drop stale keys, then the least recently failed,
+    /// keeping blocked keys for as long as possible
+    fn make_room(&self, now: Instant, needed: usize) {
+        self.sweep(now);
+        while self.entries.len() + needed > self.config.max_entries {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|entry| (entry.remaining_block(now).is_some(), entry.last_failure))
+                .map(|entry| entry.key().clone());
+            let Some(oldest) = oldest else { break };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn sweep(&self, now: Instant) {
+        let window = self.config.failure_window;
+        self.entries
+            .retain(|_, failures| !failures.is_stale(now, window));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle() -> LoginThrottle {
+        LoginThrottle::new(LoginThrottleConfig {
+            free_attempts: 2,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(8),
+            lockout_after: 6,
+            lockout: Duration::from_mins(15),
+            failure_window: Duration::from_hours(1),
+            max_entries: 100,
+        })
+    }
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([203, 0, 113, last])
+    }
+
+    /// Fail an attempt at `now`, panicking if it was throttled
+    fn fail(throttle: &LoginThrottle, ip: IpAddr, username: &str, now: Instant) -> u32 {
+        let attempt = throttle
+            .begin_at(ip, username, now, SystemTime::now())
+            .expect("attempt should be admitted");
+        attempt.failures
+    }
+
+    #[test]
+    fn backoff_doubles_after_free_attempts() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        fail(&throttle, ip(1), "admin", now);
+        assert!(
+            throttle
+                .begin_at(ip(1), "admin", now, SystemTime::now())
+                .is_ok()
+        );
+        // The second failure reached `free_attempts`: 1s, then 2s, 4s, …
+        assert_eq!(
+            throttle
+                .begin_at(ip(1), "admin", now, SystemTime::now())
+                .err(),
+            Some(Duration::from_secs(1))
+        );
+
+        let now = now + Duration::from_secs(1);
+        fail(&throttle, ip(1), "admin", now);
+        assert_eq!(
+            throttle
+                .begin_at(ip(1), "admin", now, SystemTime::now())
+                .err(),
+            Some(Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn counts_per_ip_and_per_username() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        // Rotating IPs still trips the username counter, which delays the
+        // next attempt rather than refusing it
+        for last in 0..2 {
+            fail(&throttle, ip(last), "admin", now);
+        }
+        let attempt = throttle
+            .begin_at(ip(9), "admin", now, SystemTime::now())
+            .unwrap();
+        assert_eq!(attempt.delay, Duration::from_secs(1));
+        drop(attempt);
+
+        // Rotating usernames still trips the IP counter
+        for name in ["a", "b"] {
+            fail(&throttle, ip(20), name, now);
+        }
+        assert!(
+            throttle
+                .begin_at(ip(20), "c", now, SystemTime::now())
+                .is_err()
+        );
+
+        // Case variants share a counter
+        let attempt = throttle
+            .begin_at(ip(30), "ADMIN", now, SystemTime::now())
+            .unwrap();
+        assert_eq!(attempt.delay, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn locks_out_after_threshold_and_reports_it_once() {
+        let throttle = throttle();
+        let mut now = Instant::now();
+
+        let mut locked = Vec::new();
+        for _ in 0..6 {
+            let attempt = throttle
+                .begin_at(ip(1), "admin", now, SystemTime::now())
+                .unwrap();
+            locked.extend(attempt.locks_out);
+            now += Duration::from_secs(8);
+        }
+        assert_eq!(locked, vec![ThrottleKey::Ip(ip(1))]);
+
+        let retry = throttle
+            .begin_at(ip(1), "admin", now, SystemTime::now())
+            .unwrap_err();
+        assert!(retry > Duration::from_mins(14));
+
+        let entries = throttle.entries();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.failures == 6));
+        assert!(entries.iter().all(|e| e.locked == (e.key.kind() == "ip")));
+    }
+
+    #[test]
+    fn username_failures_never_lock_out_the_owner() {
+        let throttle = throttle();
+        let mut now = Instant::now();
+
+        // Someone else fails the owner's username from many addresses
+        for last in 0..20 {
+            fail(&throttle, ip(last), "admin", now);
+            now += Duration::from_secs(8);
+        }
+
+        // The owner's attempt is admitted, held at most the maximum backoff
+        let attempt = throttle
+            .begin_at(ip(100), "admin", now, SystemTime::now())
+            .expect("the owner is never refused");
+        assert!(attempt.delay <= Duration::from_secs(8));
+        assert!(attempt.locks_out.is_empty());
+        attempt.succeeded();
+        assert_eq!(
+            throttle
+                .begin_at(ip(100), "admin", now, SystemTime::now())
+                .unwrap()
+                .delay,
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn table_is_capped() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        // A blocked address outlives the churn of one-off keys
+        fail(&throttle, ip(250), "a", now);
+        fail(&throttle, ip(250), "b", now);
+        for n in 0..200u8 {
+            fail(&throttle, ip(n), &format!("user{n}"), now);
+        }
+        assert!(throttle.entries.len() <= 100);
+        assert!(
+            throttle
+                .begin_at(ip(250), "c", now, SystemTime::now())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn success_and_clearing_reset_counters() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        fail(&throttle, ip(1), "admin", now);
+        throttle
+            .begin_at(ip(1), "admin", now, SystemTime::now())
+            .unwrap()
+            .succeeded();
+        assert!(throttle.entries().is_empty());
+
+        fail(&throttle, ip(1), "admin", now);
+        fail(&throttle, ip(1), "admin", now);
+        assert!(throttle.clear(&"username:Admin".parse().unwrap()));
+        assert!(!throttle.clear(&ThrottleKey::username("admin")));
+        // The IP is still blocked
+        assert!(
+            throttle
+                .begin_at(ip(1), "admin", now, SystemTime::now())
+                .is_err()
+        );
+        assert_eq!(throttle.clear_all(), 1);
+        assert!(
+            throttle
+                .begin_at(ip(1), "admin", now, SystemTime::now())
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn failures_are_forgotten_after_the_window() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        fail(&throttle, ip(1), "admin", now);
+        let later = now + Duration::from_hours(1);
+        assert_eq!(fail(&throttle, ip(1), "admin", later), 1);
+    }
+
+    #[test]
+    fn keys_round_trip_through_strings() {
+        for key in [
+            ThrottleKey::Ip(ip(1)),
+            ThrottleKey::Ip("2001:db8::1".parse().unwrap()),
+            ThrottleKey::username("admin"),
+        ] {
+            assert_eq!(key.to_string().parse::<ThrottleKey>(), Ok(key));
+        }
+        assert!("host:example".parse::<ThrottleKey>().is_err());
+        assert!("ip:nope".parse::<ThrottleKey>().is_err());
+    }
+}
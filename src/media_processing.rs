@@ -187,6 +187,26 @@ pub fn is_supported_video(mime: &str) -> bool {
     matches!(mime, "video/mp4" | "video/webm" | "video/quicktime")
 }
 
+/// File extension for a stored image original, keyed by MIME type
+pub fn image_extension(mime: &str) -> &'static str {
+    match mime {
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/avif" => "avif",
+        _ => "jpg",
+    }
+}
+
+/// File extension for a stored video original, keyed by MIME type
+pub fn video_extension(mime: &str) -> &'static str {
+    match mime {
+        "video/webm" => "webm",
+        "video/quicktime" => "mov",
+        _ => "mp4",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
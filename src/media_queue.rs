@@ -0,0 +1,436 @@
+//! Background processing queue for uploaded images.
+//!
+//! Uploads store the original in R2 and insert a `pending` media row; the
+//! workers here fetch the original, generate the resized variants and blurhash,
+//! upload them, and mark the row `ready`. Transient failures (storage, database)
+//! are retried with exponential backoff; decode errors fail immediately.
+//! Progress is reported through the events system as `media.*` events.
+
+use dashmap::DashSet;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, mpsc};
+use uuid::Uuid;
+
+use crate::{
+    cache,
+    db::{self, MediaStatus},
+    events::{self, EventLevel, EventType},
+    handlers, media_processing,
+    r2::R2Client,
+    state::AppState,
+};
+
+/// Attempts (including the first) before media is marked `failed`
+pub const MAX_ATTEMPTS: i32 = 3;
+
+/// Delay before the first retry; doubles with each subsequent attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(5);
+
+/// Number of images processed concurrently
+#[derive(Debug, Clone)]
+pub struct MediaQueueConfig {
+    pub workers: usize,
+}
+
+impl MediaQueueConfig {
+    pub fn from_env() -> Self {
+        let workers = std::env::var("MEDIA_WORKERS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(2);
+
+        Self { workers }
+    }
+}
+
+/// Handle for enqueueing media; cheap to clone.
+///
+/// IDs already queued or in flight are ignored, so enqueueing is idempotent.
+#[derive(Clone)]
+pub struct MediaQueue {
+    sender: mpsc::UnboundedSender<Uuid>,
+    queued: Arc<DashSet<Uuid>>,
+}
+
+impl MediaQueue {
+    /// Create the queue handle and the receiver consumed by [`run_workers`]
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<Uuid>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (
+            Self {
+                sender,
+                queued: Arc::new(DashSet::new()),
+            },
+            receiver,
+        )
+    }
+
+    /// Schedule media for processing. Returns `false` if it was already queued.
+    pub fn enqueue(&self, media_id: Uuid) -> bool {
+        if !self.queued.insert(media_id) {
+            return false;
+        }
+        if self.sender.send(media_id).is_err() {
+            // Workers are gone; the row stays pending and is recovered on restart.
+            self.queued.remove(&media_id);
+            tracing::warn!(media_id = %media_id, "Media queue closed, processing deferred");
+            return false;
+        }
+        true
+    }
+
+    /// Number of media queued or currently processing
+    pub fn len(&self) -> usize {
+        self.queued.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    fn finish(&self, media_id: Uuid) {
+        self.queued.remove(&media_id);
+    }
+}
+
+/// Failure from a single processing attempt
+#[derive(Debug)]
+enum JobError {
+    /// The media row was deleted, or became `ready` or `failed`, while
+    /// queued; nothing left to do
+    Gone,
+    /// Retrying cannot help (undecodable or unsupported image)
+    Permanent(String),
+    /// Storage or database hiccup; worth retrying
+    Transient(String),
+}
+
+impl JobError {
+    fn message(&self) -> &str {
+        match self {
+            Self::Gone => "media no longer exists",
+            Self::Permanent(msg) | Self::Transient(msg) => msg,
+        }
+    }
+}
+
+impl From<sqlx::Error> for JobError {
+    fn from(err: sqlx::Error) -> Self {
+        Self::Transient(format!("Database error: {err}"))
+    }
+}
+
+/// Backoff before retrying after the given (1-based) failed attempt
+fn retry_delay(attempt: i32) -> Duration {
+    let exponent = u32::try_from(attempt.saturating_sub(1)).unwrap_or(0).min(6);
+    RETRY_BASE_DELAY * 2u32.pow(exponent)
+}
+
+/// Dispatch queued media to a bounded pool of workers.
+///
+/// Re-enqueues media left `pending`/`processing` by a previous run first, then
+/// runs for the lifetime of the server.
+pub async fn run_workers(
+    state: Arc<AppState>,
+    mut receiver: mpsc::UnboundedReceiver<Uuid>,
+    config: MediaQueueConfig,
+) {
+    match db::get_unfinished_media_ids(&state.pool).await {
+        Ok(ids) => {
+            if !ids.is_empty() {
+                tracing::info!(count = ids.len(), "Resuming unfinished media processing");
+            }
+            for id in ids {
+                state.media_queue.enqueue(id);
+            }
+        }
+        Err(err) => {
+            tracing::error!(error = %err, "Failed to load unfinished media");
+        }
+    }
+
+    let semaphore = Arc::new(Semaphore::new(config.workers));
+
+    while let Some(media_id) = receiver.recv().await {
+        let Ok(permit) = semaphore.clone().acquire_owned().await else {
+            break;
+        };
+        let state = state.clone();
+        let semaphore = semaphore.clone();
+        tokio::spawn(async move {
+            process_with_retries(&state, &semaphore, permit, media_id).await;
+            state.media_queue.finish(media_id);
+        });
+    }
+}
+
+/// Process `media_id` until it succeeds or fails for good. `permit` is held
+/// for each attempt but not through the backoff, so a flaky item doesn't keep
+/// a worker idle.
+#[tracing::instrument(skip(state, semaphore, permit))]
+async fn process_with_retries(
+    state: &AppState,
+    semaphore: &Arc<Semaphore>,
+    mut permit: OwnedSemaphorePermit,
+    media_id: Uuid,
+) {
+    loop {
+        let claim = db::start_media_processing(&state.pool, media_id)
+            .await
+            .map_err(JobError::from)
+            .and_then(|attempt| attempt.ok_or(JobError::Gone));
+        let attempt = match claim {
+            Ok(attempt) => attempt,
+            Err(JobError::Gone) => {
+                tracing::debug!("Media no longer needs processing");
+                return;
+            }
+            Err(err) => {
+                tracing::error!(
+                    error = err.message(),
+                    "Failed to claim media for processing"
+                );
+                return;
+            }
+        };
+
+        match process_media(state, media_id, attempt).await {
+            Ok(()) => {
+                tracing::info!(attempt, "Media processed");
+                events::log_event(
+                    &state.event_sender,
+                    EventType::MediaProcessed,
+                    EventLevel::Info,
+                    Some("media"),
+                    Some(media_id),
+                    None,
+                    "Media processed".to_string(),
+                    Some(serde_json::json!({
+                        "attempt": attempt,
+                        "progress": 100,
+                    })),
+                );
+                if let Err(err) =
+                    handlers::content::refresh_figure_media(&state.pool, media_id).await
+                {
+                    tracing::warn!(error = %err, "Failed to point figures at processed media");
+                }
+                cache::invalidate_media(&state.isr_cache, &state.pool, media_id).await;
+                return;
+            }
+            Err(JobError::Gone) => return,
+            Err(err) => {
+                let retry = matches!(err, JobError::Transient(_)) && attempt < MAX_ATTEMPTS;
+                let status = if retry {
+                    MediaStatus::Pending
+                } else {
+                    MediaStatus::Failed
+                };
+
+                if let Err(db_err) =
+                    db::record_media_processing_error(&state.pool, media_id, status, err.message())
+                        .await
+                {
+                    tracing::error!(error = %db_err, "Failed to record media processing error");
+                }
+
+                events::log_event(
+                    &state.event_sender,
+                    if retry {
+                        EventType::MediaProcessing
+                    } else {
+                        EventType::MediaFailed
+                    },
+                    if retry {
+                        EventLevel::Warning
+                    } else {
+                        EventLevel::Error
+                    },
+                    Some("media"),
+                    Some(media_id),
+                    None,
+                    if retry {
+                        format!("Media processing attempt {attempt} failed, retrying")
+                    } else {
+                        format!("Media processing failed: {}", err.message())
+                    },
+                    Some(serde_json::json!({
+                        "attempt": attempt,
+                        "maxAttempts": MAX_ATTEMPTS,
+                        "error": err.message(),
+                    })),
+                );
+
+                if !retry {
+                    tracing::warn!(attempt, error = err.message(), "Media processing failed");
+                    return;
+                }
+
+                let delay = retry_delay(attempt);
+                tracing::warn!(
+                    attempt,
+                    retry_in_sec = delay.as_secs(),
+                    error = err.message(),
+                    "Media processing attempt failed"
+                );
+                drop(permit);
+                tokio::time::sleep(delay).await;
+                let Ok(next) = semaphore.clone().acquire_owned().await else {
+                    return;
+                };
+                permit = next;
+            }
+        }
+    }
+}
+
+/// Emit a `media.processing` progress event for the given stage
+fn report_progress(state: &AppState, media_id: Uuid, attempt: i32, step: &str, progress: u8) {
+    events::log_event(
+        &state.event_sender,
+        EventType::MediaProcessing,
+        EventLevel::Info,
+        Some("media"),
+        Some(media_id),
+        None,
+        format!("Media processing: {step}"),
+        Some(serde_json::json!({
+            "stage": step,
+            "progress": progress,
+            "attempt": attempt,
+        })),
+    );
+}
+
+/// Run a single processing attempt end to end
+async fn process_media(state: &AppState, media_id: Uuid, attempt: i32) -> Result<(), JobError> {
+    let media = db::get_media_asset(&state.pool, media_id)
+        .await?
+        .ok_or(JobError::Gone)?;
+
+    let original_key = media
+        .variants
+        .get("original")
+        .and_then(|o| o.get("key"))
+        .and_then(|k| k.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| JobError::Permanent("Media has no stored original".to_string()))?;
+
+    let r2 = R2Client::get()
+        .await
+        .ok_or_else(|| JobError::Transient("Media storage is not configured".to_string()))?;
+
+    let base_path = media.r2_base_path.trim_end_matches('/').to_string();
+
+    report_progress(state, media_id, attempt, "fetching", 10);
+    let data = r2
+        .get_object(&format!("{base_path}/{original_key}"))
+        .await
+        .map_err(JobError::Transient)?;
+
+    report_progress(state, media_id, attempt, "resizing", 30);
+    let filename = original_key.clone();
+    let processed =
+        tokio::task::spawn_blocking(move || media_processing::process_image(&data, &filename))
+            .await
+            .map_err(|e| JobError::Transient(format!("Processing task panicked: {e}")))?
+            .map_err(|e| JobError::Permanent(e.to_string()))?;
+
+    report_progress(state, media_id, attempt, "uploading", 70);
+    upload_image_variants(&r2, &base_path, &processed)
+        .await
+        .map_err(JobError::Transient)?;
+
+    let variants = serde_json::json!({
+        "thumb": {
+            "key": "thumb.webp",
+            "width": processed.thumb.width,
+            "height": processed.thumb.height
+        },
+        "medium": {
+            "key": "medium.webp",
+            "width": processed.medium.width,
+            "height": processed.medium.height
+        },
+        "full": {
+            "key": "full.webp",
+            "width": processed.full.width,
+            "height": processed.full.height
+        },
+        "original": {
+            "key": original_key,
+            "width": processed.original.width,
+            "height": processed.original.height,
+            "mime": processed.original.mime
+        }
+    });
+
+    db::complete_media_processing(
+        &state.pool,
+        media_id,
+        variants,
+        processed.original.width as i32,
+        processed.original.height as i32,
+        &processed.blurhash,
+    )
+    .await?
+    .ok_or(JobError::Gone)?;
+    Ok(())
+}
+
+/// Upload the resized variants to R2 (the original is stored at upload time)
+async fn upload_image_variants(
+    r2: &R2Client,
+    base_path: &str,
+    processed: &media_processing::ProcessedImage,
+) -> Result<(), String> {
+    for (name, variant) in [
+        ("thumb", &processed.thumb),
+        ("medium", &processed.medium),
+        ("full", &processed.full),
+    ] {
+        r2.put_object(
+            &format!("{base_path}/{name}.webp"),
+            variant.data.clone(),
+            "image/webp",
+            None,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_doubles() {
+        assert_eq!(retry_delay(1), Duration::from_secs(5));
+        assert_eq!(retry_delay(2), Duration::from_secs(10));
+        assert_eq!(retry_delay(3), Duration::from_secs(20));
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        assert_eq!(retry_delay(100), retry_delay(7));
+    }
+
+    #[test]
+    fn test_enqueue_deduplicates() {
+        let (queue, mut receiver) = MediaQueue::new();
+        let id = Uuid::new_v4();
+
+        assert!(queue.enqueue(id));
+        assert!(!queue.enqueue(id));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(receiver.try_recv().unwrap(), id);
+        assert!(receiver.try_recv().is_err());
+
+        queue.finish(id);
+        assert!(queue.is_empty());
+        assert!(queue.enqueue(id));
+    }
+}
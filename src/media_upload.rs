@@ -0,0 +1,431 @@
+//! Resumable chunked uploads for large media.
+//!
+//! A client starts an upload with the file's size and SHA-256, then sends the
+//! file as fixed-size chunks, each carrying its byte offset and its own SHA-256.
+//! Every chunk is streamed straight into an R2 multipart part, so the server
+//! never buffers more than one chunk. If the connection drops, the client asks
+//! for the current offset and continues from there. Completing the upload
+//! verifies the whole-file checksum before the media row is created.
+//!
+//! Sessions live in memory only: a restart loses in-progress uploads (the
+//! client starts over), which is acceptable for an admin-only upload path.
+
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use time::{Duration, OffsetDateTime};
+use tokio::sync::Mutex;
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{
+    db::MediaType,
+    handlers::{CreateMediaUploadRequest, MediaUploadStatus},
+    media_processing,
+    r2::{R2Client, R2MultipartUpload},
+    state::{AppError, AppResult},
+};
+
+/// Chunk size handed to clients; each chunk becomes one R2 multipart part, so it
+/// must stay above R2's 5 MiB minimum part size.
+pub const CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Images are decoded in memory by the media queue, so they keep the same cap
+/// as the single-request upload endpoint.
+pub const MAX_IMAGE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Default for `MEDIA_MAX_UPLOAD_BYTES`.
+const DEFAULT_MAX_UPLOAD_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Uploads with no chunk activity for this long are aborted.
+const IDLE_TTL: Duration = Duration::hours(24);
+
+/// Upload size limits
+#[derive(Debug, Clone)]
+pub struct MediaUploadConfig {
+    /// Largest file accepted through the chunked protocol
+    pub max_bytes: u64,
+}
+
+impl MediaUploadConfig {
+    pub fn from_env() -> Self {
+        let max_bytes = std::env::var("MEDIA_MAX_UPLOAD_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_UPLOAD_BYTES);
+
+        Self { max_bytes }
+    }
+}
+
+impl Default for MediaUploadConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_UPLOAD_BYTES,
+        }
+    }
+}
+
+/// An in-progress chunked upload.
+pub struct UploadSession {
+    pub id: Ulid,
+    pub project_id: Uuid,
+    pub filename: String,
+    pub media_type: MediaType,
+    pub size: u64,
+    pub offset: u64,
+    pub r2_base_path: String,
+    /// Key of the original under `r2_base_path`, chosen once the first chunk
+    /// reveals the real format.
+    pub original_key: Option<String>,
+    /// MIME type of the stored original (sniffed for images, declared for videos).
+    pub mime: String,
+    sha256: String,
+    hasher: Sha256,
+    upload: Option<R2MultipartUpload>,
+    expires_at: OffsetDateTime,
+}
+
+impl UploadSession {
+    pub fn status(&self) -> MediaUploadStatus {
+        MediaUploadStatus {
+            upload_id: self.id.to_string(),
+            offset: self.offset,
+            size: self.size,
+            chunk_size: CHUNK_SIZE,
+            expires_at: self.expires_at,
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        OffsetDateTime::now_utc() > self.expires_at
+    }
+
+    /// Validate and stream one chunk into the multipart upload.
+    pub async fn append(
+        &mut self,
+        r2: &R2Client,
+        offset: u64,
+        data: &[u8],
+        checksum: &str,
+    ) -> AppResult<()> {
+        if offset != self.offset {
+            return Err(AppError::Conflict(format!(
+                "Upload offset mismatch: expected {}, got {offset}",
+                self.offset
+            )));
+        }
+        check_chunk_len(self.offset, data.len() as u64, self.size)?;
+        if hex::encode(Sha256::digest(data)) != checksum.to_ascii_lowercase() {
+            return Err(AppError::validation("Chunk checksum mismatch"));
+        }
+
+        if self.upload.is_none() {
+            self.begin(r2, data).await?;
+        }
+        let upload = self
+            .upload
+            .as_mut()
+            .ok_or_else(|| AppError::Internal("Multipart upload not started".into()))?;
+
+        upload
+            .write(data.to_vec())
+            .await
+            .map_err(AppError::Internal)?;
+
+        self.hasher.update(data);
+        self.offset += data.len() as u64;
+        self.expires_at = OffsetDateTime::now_utc() + IDLE_TTL;
+        Ok(())
+    }
+
+    /// Pick the storage key from the first chunk and open the multipart upload.
+    async fn begin(&mut self, r2: &R2Client, first_chunk: &[u8]) -> AppResult<()> {
+        let ext = match self.media_type {
+            MediaType::Image => {
+                let (_, mime) = media_processing::detect_format(first_chunk, &self.filename)
+                    .map_err(|e| AppError::validation(format!("Failed to process image: {e}")))?;
+                self.mime = mime.to_string();
+                media_processing::image_extension(mime)
+            }
+            MediaType::Video => media_processing::video_extension(&self.mime),
+        };
+
+        let key = format!("original.{ext}");
+        let upload = r2
+            .start_multipart(
+                &format!("{}/{key}", self.r2_base_path),
+                &self.mime,
+                CHUNK_SIZE as usize,
+            )
+            .await
+            .map_err(AppError::Internal)?;
+
+        self.original_key = Some(key);
+        self.upload = Some(upload);
+        Ok(())
+    }
+
+    /// Verify the full file and finalize the multipart upload.
+    ///
+    /// On a checksum mismatch the upload is aborted; the client must start over.
+    pub async fn finish(&mut self) -> AppResult<()> {
+        if self.offset != self.size {
+            return Err(AppError::validation(format!(
+                "Upload incomplete: received {} of {} bytes",
+                self.offset, self.size
+            )));
+        }
+
+        let digest = hex::encode(self.hasher.clone().finalize());
+        if digest != self.sha256 {
+            self.abort().await;
+            return Err(AppError::validation(
+                "File checksum mismatch; the upload was discarded",
+            ));
+        }
+
+        let upload = self
+            .upload
+            .as_mut()
+            .ok_or_else(|| AppError::Internal("Multipart upload not started".into()))?;
+        upload.complete().await.map_err(AppError::Internal)
+    }
+
+    /// Best-effort release of any uploaded parts.
+    pub async fn abort(&mut self) {
+        if let Some(mut upload) = self.upload.take()
+            && let Err(err) = upload.abort().await
+        {
+            tracing::warn!(upload_id = %self.id, error = %err, "Failed to abort multipart upload");
+        }
+    }
+}
+
+/// Reject chunks that overrun the file or would produce an undersized part.
+fn check_chunk_len(offset: u64, len: u64, size: u64) -> AppResult<()> {
+    if len == 0 {
+        return Err(AppError::validation("Chunk is empty"));
+    }
+    let end = offset + len;
+    if end > size {
+        return Err(AppError::validation(format!(
+            "Chunk overruns the declared size ({size} bytes)"
+        )));
+    }
+    if len != CHUNK_SIZE && end != size {
+        return Err(AppError::validation(format!(
+            "Chunks must be exactly {CHUNK_SIZE} bytes except the last"
+        )));
+    }
+    Ok(())
+}
+
+/// Validate a create request, returning the media type it will produce.
+fn validate_request(
+    req: &CreateMediaUploadRequest,
+    config: &MediaUploadConfig,
+) -> AppResult<MediaType> {
+    let media_type = if media_processing::is_supported_image(&req.content_type) {
+        MediaType::Image
+    } else if media_processing::is_supported_video(&req.content_type) {
+        MediaType::Video
+    } else {
+        return Err(AppError::field(
+            "contentType",
+            format!(
+                "Content type '{}' is not supported. Supported: JPEG, PNG, GIF, WebP, AVIF, MP4, WebM",
+                req.content_type
+            ),
+        ));
+    };
+
+    if req.size == 0 {
+        return Err(AppError::field("size", "File is empty"));
+    }
+    let limit = match media_type {
+        MediaType::Image => MAX_IMAGE_BYTES.min(config.max_bytes),
+        MediaType::Video => config.max_bytes,
+    };
+    if req.size > limit {
+        return Err(AppError::field(
+            "size",
+            format!("File exceeds the {limit}-byte upload limit"),
+        ));
+    }
+
+    if req.sha256.len() != 64 || !req.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::field(
+            "sha256",
+            "Must be a hex-encoded SHA-256 digest",
+        ));
+    }
+
+    Ok(media_type)
+}
+
+/// In-memory registry of chunked uploads
+#[derive(Clone, Default)]
+pub struct MediaUploadRegistry {
+    uploads: Arc<DashMap<Ulid, Arc<Mutex<UploadSession>>>>,
+    config: MediaUploadConfig,
+}
+
+impl MediaUploadRegistry {
+    pub fn new(config: MediaUploadConfig) -> Self {
+        Self {
+            uploads: Arc::new(DashMap::new()),
+            config,
+        }
+    }
+
+    /// Register a new upload for `project_id`.
+    pub fn create(
+        &self,
+        project_id: Uuid,
+        req: &CreateMediaUploadRequest,
+    ) -> AppResult<MediaUploadStatus> {
+        let media_type = validate_request(req, &self.config)?;
+
+        let id = Ulid::new();
+        let session = UploadSession {
+            id,
+            project_id,
+            filename: req.filename.clone(),
+            media_type,
+            size: req.size,
+            offset: 0,
+            r2_base_path: format!("projects/{project_id}/{id}"),
+            original_key: None,
+            mime: req.content_type.clone(),
+            sha256: req.sha256.to_ascii_lowercase(),
+            hasher: Sha256::new(),
+            upload: None,
+            expires_at: OffsetDateTime::now_utc() + IDLE_TTL,
+        };
+        let status = session.status();
+        self.uploads.insert(id, Arc::new(Mutex::new(session)));
+        Ok(status)
+    }
+
+    /// Look up a live upload belonging to `project_id`.
+    pub async fn get(&self, project_id: Uuid, id: &str) -> AppResult<Arc<Mutex<UploadSession>>> {
+        let id: Ulid = id.parse().map_err(|_| AppError::NotFound)?;
+        let session = self
+            .uploads
+            .get(&id)
+            .map(|entry| entry.value().clone())
+            .ok_or(AppError::NotFound)?;
+
+        let guard = session.lock().await;
+        if guard.project_id != project_id || guard.is_expired() {
+            return Err(AppError::NotFound);
+        }
+        drop(guard);
+        Ok(session)
+    }
+
+    /// Drop an upload from the registry (after completion or abort).
+    pub fn remove(&self, id: Ulid) {
+        self.uploads.remove(&id);
+    }
+
+    /// R2 base paths of uploads still in progress, whose parts must not be
+    /// mistaken for orphans.
+    pub async fn active_base_paths(&self) -> Vec<String> {
+        let sessions: Vec<_> = self
+            .uploads
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+
+        let mut paths = Vec::with_capacity(sessions.len());
+        for session in sessions {
+            let guard = session.lock().await;
+            if !guard.is_expired() {
+                paths.push(guard.r2_base_path.clone());
+            }
+        }
+        paths
+    }
+
+    /// Abort and forget uploads idle past their TTL. Returns how many were removed.
+    pub async fn cleanup_expired(&self) -> usize {
+        let candidates: Vec<_> = self
+            .uploads
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+
+        let mut removed = 0;
+        for (id, session) in candidates {
+            let mut guard = session.lock().await;
+            if guard.is_expired() {
+                guard.abort().await;
+                drop(guard);
+                self.uploads.remove(&id);
+                removed += 1;
+            }
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(content_type: &str, size: u64) -> CreateMediaUploadRequest {
+        CreateMediaUploadRequest {
+            filename: "demo.mp4".to_string(),
+            content_type: content_type.to_string(),
+            size,
+            sha256: "a".repeat(64),
+        }
+    }
+
+    #[test]
+    fn test_chunk_len_accepts_full_and_final_chunks() {
+        let size = CHUNK_SIZE * 2 + 10;
+        assert!(check_chunk_len(0, CHUNK_SIZE, size).is_ok());
+        assert!(check_chunk_len(CHUNK_SIZE * 2, 10, size).is_ok());
+    }
+
+    #[test]
+    fn test_chunk_len_rejects_short_middle_chunk() {
+        assert!(check_chunk_len(0, 10, CHUNK_SIZE * 2).is_err());
+    }
+
+    #[test]
+    fn test_chunk_len_rejects_overrun_and_empty() {
+        assert!(check_chunk_len(0, 20, 10).is_err());
+        assert!(check_chunk_len(0, 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_validate_request_limits() {
+        let config = MediaUploadConfig { max_bytes: 1000 };
+        assert_eq!(
+            validate_request(&request("video/mp4", 1000), &config).unwrap(),
+            MediaType::Video
+        );
+        assert!(validate_request(&request("video/mp4", 1001), &config).is_err());
+        assert!(validate_request(&request("video/mp4", 0), &config).is_err());
+        assert!(validate_request(&request("text/plain", 10), &config).is_err());
+    }
+
+    #[test]
+    fn test_validate_request_caps_images() {
+        let config = MediaUploadConfig::default();
+        assert!(validate_request(&request("image/png", MAX_IMAGE_BYTES), &config).is_ok());
+        assert!(validate_request(&request("image/png", MAX_IMAGE_BYTES + 1), &config).is_err());
+    }
+
+    #[test]
+    fn test_validate_request_checksum_format() {
+        let config = MediaUploadConfig::default();
+        let mut req = request("video/mp4", 10);
+        req.sha256 = "xyz".to_string();
+        assert!(validate_request(&req, &config).is_err());
+    }
+}
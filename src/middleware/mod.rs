@@ -1,3 +1,5 @@
 pub mod request_id;
+pub mod session_renewal;
 
 pub use request_id::{RequestId, RequestIdLayer};
+pub use session_renewal::{SessionRenewal, SessionRenewalLayer};
@@ -0,0 +1,109 @@
+//! Tells clients when a request renewed their session.
+//!
+//! Authentication records a renewed session in the request's
+//! [`SessionRenewal`]. Once the handler has responded, a browser gets its
+//! cookie re-issued with the new lifetime, and a CLI gets the new expiry in
+//! [`SESSION_EXPIRES_HEADER`], which it stores with its token.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderValue, header::SET_COOKIE},
+    response::Response,
+};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::auth::{Session, SessionType};
+use crate::handlers::SESSION_EXPIRES_HEADER;
+
+/// Where a request's authentication leaves the session it renewed
+#[derive(Debug, Clone, Default)]
+pub struct SessionRenewal(Arc<Mutex<Option<Session>>>);
+
+impl SessionRenewal {
+    pub fn record(&self, session: &Session) {
+        *self.0.lock() = Some(session.clone());
+    }
+
+    fn take(&self) -> Option<Session> {
+        self.0.lock().take()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SessionRenewalLayer;
+
+impl<S> Layer<S> for SessionRenewalLayer {
+    type Service = SessionRenewalService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SessionRenewalService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionRenewalService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for SessionRenewalService<S>
+where
+    S: Service<Request, Response = Response<Body>> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request) -> Self::Future {
+        let renewal = SessionRenewal::default();
+        req.extensions_mut().insert(renewal.clone());
+
+        let future = self.inner.call(req);
+
+        Box::pin(async move {
+            let mut response = future.await?;
+            if let Some(session) = renewal.take() {
+                announce_renewal(&mut response, &session);
+            }
+            Ok(response)
+        })
+    }
+}
+
+fn announce_renewal(response: &mut Response, session: &Session) {
+    let headers = response.headers_mut();
+    match session.session_type {
+        SessionType::Browser => {
+            // A handler that set the cookie itself (e.g. logout) wins
+            let sets_cookie = headers.get_all(SET_COOKIE).iter().any(|value| {
+                value
+                    .to_str()
+                    .is_ok_and(|v| v.starts_with("admin_session="))
+            });
+            let cookie = crate::handlers::auth::session_cookie(session).to_string();
+            if !sets_cookie && let Ok(value) = HeaderValue::from_str(&cookie) {
+                headers.append(SET_COOKIE, value);
+            }
+        }
+        SessionType::Cli => {
+            if let Ok(expires_at) = session
+                .expires_at
+                .format(&time::format_description::well_known::Rfc3339)
+                && let Ok(value) = HeaderValue::from_str(&expires_at)
+            {
+                headers.insert(SESSION_EXPIRES_HEADER, value);
+            }
+        }
+        SessionType::Token => {}
+    }
+}
@@ -508,6 +508,14 @@ impl Node {
         )
     }
 
+    /// Visit every node in this subtree (self included) in pre-order.
+    fn visit_mut(&mut self, f: &mut impl FnMut(&mut Self)) {
+        f(self);
+        for child in &mut self.content {
+            child.visit_mut(f);
+        }
+    }
+
     /// Record every block id in this subtree into `used`.
     fn collect_block_ids(&self, used: &mut HashSet<String>) {
         if let Some(id) = self.block_id() {
@@ -1057,6 +1065,31 @@ impl Doc {
         self.0.stamp_missing_block_ids(&mut used, &mut gen_id);
     }
 
+    /// Every `figure` node in the document, mutably, in document order. The
+    /// write path uses this to resolve library `mediaId` references into the
+    /// `src`/`kind` attrs the renderer reads.
+    pub fn for_each_figure_mut(&mut self, mut f: impl FnMut(&mut Node)) {
+        self.0.visit_mut(&mut |node| {
+            if node.r#type == "figure" {
+                f(node);
+            }
+        });
+    }
+
+    /// The distinct library media ids referenced by `figure` blocks.
+    pub fn figure_media_ids(&self) -> Vec<String> {
+        let mut ids = Vec::new();
+        for (_, node) in self.outline() {
+            if node.r#type == "figure"
+                && let Some(id) = node.attrs.get("mediaId").and_then(Value::as_str)
+                && !ids.iter().any(|seen| seen == id)
+            {
+                ids.push(id.to_string());
+            }
+        }
+        ids
+    }
+
     /// Apply one operation, sourcing a fresh id from `gen_id` for inserts.
     /// Returns the affected block. A building block for [`Self::apply_all`]; it
     /// does not validate the resulting document.
@@ -2227,6 +2260,35 @@ mod path_tests {
         );
     }
 
+    #[test]
+    fn figure_media_ids_are_distinct_and_ordered() {
+        let mut doc = Doc::parse(&json!({
+            "type": "doc",
+            "content": [
+                { "type": "figure", "attrs": { "mediaId": "b" } },
+                { "type": "blockquote", "content": [
+                    { "type": "figure", "attrs": { "mediaId": "a" } }
+                ]},
+                { "type": "figure", "attrs": { "mediaId": "b" } },
+                { "type": "figure", "attrs": { "src": "https://example.com/x.png" } }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(doc.figure_media_ids(), ["b", "a"]);
+
+        let mut seen = 0;
+        doc.for_each_figure_mut(|node| {
+            seen += 1;
+            node.attrs.insert("kind".into(), json!("image"));
+        });
+        assert_eq!(seen, 4);
+        assert!(
+            doc.outline()
+                .iter()
+                .all(|(_, n)| n.r#type != "figure" || n.attrs["kind"] == "image")
+        );
+    }
+
     #[test]
     fn direct_text_is_own_line_not_descendants() {
         let doc = doc();
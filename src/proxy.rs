@@ -1,15 +1,26 @@
 use axum::{
+    body::{Body, Bytes},
     extract::{ConnectInfo, Request, State},
     http::{HeaderMap, HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use futures::StreamExt;
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::{
     assets,
-    cache::{self, CachedResponse},
-    db,
+    cache::{self, CacheOutcome, CachedResponse, FlightLeader, RenderFlight, SharedRender},
+    cache_policy,
+    cache_policy::CacheTtl,
+    conditional, db,
     encoding::negotiate_encoding,
+    middleware::SessionRenewal,
     state::{AppState, ProxyError},
     tarpit::{self, TarpitState},
     utils,
@@ -91,7 +102,7 @@ pub async fn isr_handler(State(state): State<Arc<AppState>>, req: Request) -> Re
         return response;
     }
 
-    if let Some(response) = assets::try_serve_prerendered_page(path) {
+    if let Some(response) = assets::try_serve_prerendered_page(path, &request_headers) {
         tracing::debug!(path = %path, "Serving prerendered page");
         return response;
     }
@@ -102,7 +113,7 @@ pub async fn isr_handler(State(state): State<Arc<AppState>>, req: Request) -> Re
     // so each public domain gets its own SSR variant instead of sharing the
     // first-rendered origin.
     let host = state.host_config.resolve(&request_headers);
-    let cache_key = cache::cache_key_for_target(&host, &path_with_query);
+    let base_key = cache::cache_key_for_target(&host, &path_with_query);
 
     // Build trusted headers to forward to downstream
     let mut forward_headers = HeaderMap::new();
@@ -127,6 +138,15 @@ pub async fn isr_handler(State(state): State<Arc<AppState>>, req: Request) -> Re
         forward_headers.insert("x-request-id", header_value);
     }
 
+    // Forward the request headers a render may vary on; anything else Bun
+    // never sees, so cached variants can't depend on it
+    for name in cache_policy::VARY_HEADERS {
+        if let Some(value) = request_headers.get(*name) {
+            forward_headers.insert(*name, value.clone());
+        }
+    }
+    let cache_key = state.isr_cache.lookup_key(&base_key, &forward_headers);
+
     // Strip incoming X-Session-User to prevent spoofing; re-add only if session validates
     if let Some(cookie_header) = req.headers().get(axum::http::header::COOKIE)
         && let Ok(cookie_str) = cookie_header.to_str()
@@ -145,8 +165,22 @@ pub async fn isr_handler(State(state): State<Arc<AppState>>, req: Request) -> Re
                     if let Ok(username_value) = axum::http::HeaderValue::from_str(&session.username)
                     {
                         forward_headers.insert("x-session-user", username_value);
+                        forward_headers.insert(
+                            "x-session-role",
+                            axum::http::HeaderValue::from_static(session.role.as_str()),
+                        );
                         is_authenticated = true;
-                        state.session_manager.touch_session(session_id).await;
+                        let client_ip =
+                            crate::tarpit::request_client_ip(req.headers(), req.extensions());
+                        if state
+                            .session_manager
+                            .touch_session(session_id, Some(client_ip))
+                            .await
+                            && let Some(renewal) = req.extensions().get::<SessionRenewal>()
+                            && let Some(session) = state.session_manager.get_session(session_id)
+                        {
+                            renewal.record(&session);
+                        }
                     }
                 }
                 break;
@@ -156,57 +190,87 @@ pub async fn isr_handler(State(state): State<Arc<AppState>>, req: Request) -> Re
 
     let use_cache = !is_authenticated && cache::is_cacheable_path(path);
 
-    if use_cache && let Some(cached) = state.isr_cache.get(&cache_key).await {
-        let fresh_duration = state.isr_cache.config.fresh_duration;
-        let stale_duration = state.isr_cache.config.stale_duration;
+    let cached = if use_cache {
+        state.isr_cache.get(&cache_key).await
+    } else {
+        None
+    };
 
-        if cached.is_fresh(fresh_duration) {
+    if let Some(cached) = &cached {
+        if cached.is_fresh() {
+            state.isr_cache.record(CacheOutcome::Hit);
             let age_ms = cached.age().as_millis() as u64;
             tracing::debug!(cache = "hit", age_ms, "ISR cache hit (fresh)");
 
-            return serve_cached_response(&cached, &request_headers, is_head);
-        } else if cached.is_stale_but_usable(fresh_duration, stale_duration) {
+            return serve_cached_response(cached, &request_headers, is_head);
+        } else if cached.is_stale_but_usable() {
             // Stale cache hit - serve immediately and refresh in background
+            state.isr_cache.record(CacheOutcome::Stale);
             let age_ms = cached.age().as_millis() as u64;
             tracing::debug!(cache = "stale", age_ms, "ISR cache hit (stale, refreshing)");
 
             // Spawn background refresh if not already refreshing
             if state.isr_cache.start_refresh(&cache_key) {
                 let state_clone = state.clone();
-                let host_clone = host.clone();
-                let path_clone = path_with_query.clone();
+                let key_clone = cache_key.clone();
                 tokio::spawn(async move {
-                    refresh_cache_entry(state_clone, host_clone, path_clone).await;
+                    refresh_cache_entry(state_clone, key_clone).await;
                 });
             }
 
-            return serve_cached_response(&cached, &request_headers, is_head);
+            return serve_cached_response(cached, &request_headers, is_head);
         }
         // Cache entry is too old - fall through to fetch
     }
 
+    // Past its stale window, an entry is only kept to stand in for Bun. Don't
+    // wait on a render that can't succeed.
+    let fallback = cached.filter(|cached| cached.is_usable_on_error());
+    if let Some(fallback) = &fallback
+        && !state.health_checker.check().await
+    {
+        return serve_stale_if_error(&state, fallback, &request_headers, is_head, "unhealthy");
+    }
+
+    // Cold miss: wait on a concurrent request's render of the same page rather
+    // than rendering it again. Followers fall back to their own render if the
+    // leader caches nothing, fails, or takes too long.
+    let mut flight = None;
+    if use_cache && state.isr_cache.config.enabled {
+        match state.isr_cache.join_render(&cache_key) {
+            RenderFlight::Leader(leader) => flight = Some(leader),
+            RenderFlight::Follower(follower) => {
+                if let Some(shared) = follower.wait(state.isr_cache.config.coalesce_timeout).await
+                    && shared.matches(&base_key, &forward_headers)
+                {
+                    state.isr_cache.record(CacheOutcome::Coalesced);
+                    tracing::debug!(cache = "coalesced", "ISR cache miss (shared render)");
+                    return serve_cached_response(&shared.response, &request_headers, is_head);
+                }
+            }
+        }
+    }
+
     // Cache miss or non-cacheable - fetch from Bun
+    state.isr_cache.record(if use_cache {
+        CacheOutcome::Miss
+    } else {
+        CacheOutcome::Bypass
+    });
     let start = std::time::Instant::now();
 
-    match proxy_to_bun(&path_with_query, state.clone(), forward_headers).await {
-        Ok((status, headers, body)) => {
+    match proxy_to_bun_stream(&path_with_query, &state, &forward_headers).await {
+        Ok((status, mut headers, upstream)) => {
             let duration_ms = start.elapsed().as_millis() as u64;
+            let tags = cache::take_cache_tags(&mut headers);
+            let policy = (use_cache && status.is_success())
+                .then(|| cache_policy::cache_policy(&headers, state.isr_cache.config.default_ttl()))
+                .flatten();
 
-            // Cache successful responses for public requests
-            if use_cache && status.is_success() {
-                let cached_response = CachedResponse::new(status, headers.clone(), body.clone());
-                state
-                    .isr_cache
-                    .insert(cache_key.clone(), cached_response)
-                    .await;
-                tracing::debug!(
-                    cache = "miss",
-                    status = status.as_u16(),
-                    duration_ms,
-                    "ISR request (cached)"
-                );
-            } else {
-                log_isr_request(path, status, duration_ms, "bypass");
+            if status.is_server_error()
+                && let Some(fallback) = &fallback
+            {
+                return serve_stale_if_error(&state, fallback, &request_headers, is_head, "error");
             }
 
             // Intercept error responses for HTML requests
@@ -216,32 +280,81 @@ pub async fn isr_handler(State(state): State<Arc<AppState>>, req: Request) -> Re
                 return utils::serve_error_page(status);
             }
 
+            let Some(policy) = policy else {
+                log_isr_request(path, status, duration_ms, "bypass");
+                // Nothing shareable: release any waiting requests to render themselves
+                drop(flight);
+                if is_head {
+                    return (status, headers).into_response();
+                }
+                return (status, headers, Body::from_stream(upstream.bytes_stream()))
+                    .into_response();
+            };
+
+            // Cache successful public responses that Bun allows to be shared
+            let fill = CacheFill {
+                state: state.clone(),
+                key: cache_policy::variant_key(&base_key, &policy.vary, &forward_headers),
+                status,
+                headers: headers.clone(),
+                ttl: policy.ttl,
+                tags,
+                flight: flight.take(),
+                start,
+                rendered_at: SystemTime::now(),
+            };
+            let rendered_at = fill.rendered_at;
+            headers.insert(header::LAST_MODIFIED, conditional::http_date(rendered_at));
+
+            // A revalidating client may already hold this render: wait for all
+            // of it so it can be tagged, compared, and possibly answered with a
+            // 304. Anyone else gets it streamed as Bun produces it, without an
+            // ETag (the bytes aren't known yet) but with Last-Modified.
+            if conditional::has_preconditions(&request_headers) {
+                let (body, etag) = match fill.collect(upstream).await {
+                    Ok(collected) => collected,
+                    Err(err) => {
+                        return render_failed(
+                            &state,
+                            fallback.as_deref(),
+                            &request_headers,
+                            is_head,
+                            &path_with_query,
+                            start.elapsed().as_millis() as u64,
+                            &err,
+                        );
+                    }
+                };
+                // Weak, since the compression layer may still encode it
+                let etag = conditional::weak_etag(&etag);
+                headers.insert(header::ETAG, etag.clone());
+                if conditional::is_not_modified(&request_headers, Some(&etag), Some(rendered_at)) {
+                    return conditional::not_modified(&headers);
+                }
+                return if is_head {
+                    (status, headers).into_response()
+                } else {
+                    (status, headers, body).into_response()
+                };
+            }
+
+            // A HEAD drops the body; the render still finishes caching
+            let body = fill.tee(upstream);
             if is_head {
                 (status, headers).into_response()
             } else {
                 (status, headers, body).into_response()
             }
         }
-        Err(err) => {
-            let duration_ms = start.elapsed().as_millis() as u64;
-            tracing::error!(
-                error = %err,
-                path = %path_with_query,
-                duration_ms,
-                "Failed to proxy to Bun"
-            );
-
-            // Serve 502 error page instead of plaintext
-            if utils::accepts_html(req.headers()) {
-                return utils::serve_error_page(StatusCode::BAD_GATEWAY);
-            }
-
-            (
-                StatusCode::BAD_GATEWAY,
-                format!("Failed to render page: {err}"),
-            )
-                .into_response()
-        }
+        Err(err) => render_failed(
+            &state,
+            fallback.as_deref(),
+            &request_headers,
+            is_head,
+            &path_with_query,
+            start.elapsed().as_millis() as u64,
+            &err,
+        ),
     }
 }
 
@@ -260,8 +373,19 @@ fn serve_cached_response(
         headers.insert(header::CONTENT_ENCODING, encoding_value);
     }
 
-    // Add Vary header for caching
-    headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+    // The stored body is negotiated per request, on top of whatever Bun
+    // varies on
+    headers.append(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+
+    let etag = conditional::encoded_etag(&cached.etag, actual_encoding);
+    headers.insert(header::ETAG, etag.clone());
+    headers.insert(
+        header::LAST_MODIFIED,
+        conditional::http_date(cached.rendered_at),
+    );
+    if conditional::is_not_modified(request_headers, Some(&etag), Some(cached.rendered_at)) {
+        return conditional::not_modified(&headers);
+    }
 
     if let Ok(len) = HeaderValue::from_str(&body.len().to_string()) {
         headers.insert(header::CONTENT_LENGTH, len);
@@ -274,14 +398,177 @@ fn serve_cached_response(
     }
 }
 
-/// Background task to refresh a stale cache entry.
+/// Answer a render Bun couldn't produce: a usable expired entry if there is
+/// one, otherwise a 502.
+fn render_failed(
+    state: &AppState,
+    fallback: Option<&CachedResponse>,
+    request_headers: &HeaderMap,
+    is_head: bool,
+    path_with_query: &str,
+    duration_ms: u64,
+    err: &dyn std::fmt::Display,
+) -> Response {
+    tracing::error!(
+        error = %err,
+        path = %path_with_query,
+        duration_ms,
+        "Failed to proxy to Bun"
+    );
+
+    if let Some(fallback) = fallback {
+        return serve_stale_if_error(state, fallback, request_headers, is_head, "error");
+    }
+
+    // Serve 502 error page instead of plaintext
+    if utils::accepts_html(request_headers) {
+        return utils::serve_error_page(StatusCode::BAD_GATEWAY);
+    }
+
+    (
+        StatusCode::BAD_GATEWAY,
+        format!("Failed to render page: {err}"),
+    )
+        .into_response()
+}
+
+/// A cacheable render on its way to the client, to be cached once complete
+struct CacheFill {
+    state: Arc<AppState>,
+    /// Key to store under, including the `Vary` variant
+    key: String,
+    status: StatusCode,
+    headers: HeaderMap,
+    ttl: CacheTtl,
+    tags: Vec<String>,
+    /// Set when concurrent misses are waiting on this render
+    flight: Option<FlightLeader>,
+    start: std::time::Instant,
+    /// Sent as `Last-Modified` before the body is known, and stored with it
+    rendered_at: SystemTime,
+}
+
+impl CacheFill {
+    /// Forward `upstream` to the client while collecting it. The render keeps
+    /// being read if the client goes away first, so it still gets cached; a
+    /// body that fails midway is never cached.
+    fn tee(self, upstream: reqwest::Response) -> Body {
+        let (sender, receiver) = mpsc::channel::<Result<Bytes, std::io::Error>>(16);
+        tokio::spawn(async move {
+            let mut client = Some(sender);
+            let mut collected = Vec::new();
+            let mut chunks = upstream.bytes_stream();
+            while let Some(chunk) = chunks.next().await {
+                match chunk {
+                    Ok(chunk) => {
+                        collected.extend_from_slice(&chunk);
+                        if let Some(sender) = &client
+                            && sender.send(Ok(chunk)).await.is_err()
+                        {
+                            client = None;
+                        }
+                    }
+                    Err(err) => {
+                        tracing::warn!(key = %self.key, error = %err, "Render stream failed, not caching");
+                        if let Some(sender) = client {
+                            let _ = sender.send(Err(std::io::Error::other(err))).await;
+                        }
+                        return;
+                    }
+                }
+            }
+            // End the client's body before compressing and storing
+            drop(client);
+            self.complete(Bytes::from(collected)).await;
+        });
+        Body::from_stream(ReceiverStream::new(receiver))
+    }
+
+    /// Read the whole render and cache it, returning the body and its strong
+    /// `ETag`
+    async fn collect(
+        self,
+        upstream: reqwest::Response,
+    ) -> Result<(Bytes, HeaderValue), reqwest::Error> {
+        let body = upstream.bytes().await?;
+        let etag = self.complete(body.clone()).await;
+        Ok((body, etag))
+    }
+
+    /// Cache the finished render, returning its strong `ETag`
+    async fn complete(self, body: Bytes) -> HeaderValue {
+        let response = CachedResponse::new(self.status, self.headers, body)
+            .with_ttl(self.ttl)
+            .with_tags(self.tags)
+            .with_rendered_at(self.rendered_at);
+        let etag = response.etag.clone();
+        let stored = self
+            .state
+            .isr_cache
+            .insert(self.key.clone(), response)
+            .await;
+        if let (Some(leader), Some(response)) = (self.flight, stored) {
+            leader.finish(Some(SharedRender {
+                key: self.key,
+                response,
+            }));
+        }
+        tracing::debug!(
+            cache = "miss",
+            status = self.status.as_u16(),
+            duration_ms = self.start.elapsed().as_millis() as u64,
+            "ISR request (cached)"
+        );
+        etag
+    }
+}
+
+/// Serve an expired entry in place of a render Bun couldn't produce. It is
+/// marked so clients and caches know it's stale and revalidate next time.
+fn serve_stale_if_error(
+    state: &AppState,
+    cached: &CachedResponse,
+    request_headers: &HeaderMap,
+    is_head: bool,
+    reason: &'static str,
+) -> Response {
+    state.isr_cache.record(CacheOutcome::StaleIfError);
+    let age_secs = cached.age().as_secs();
+    tracing::warn!(
+        cache = "stale-if-error",
+        reason,
+        age_secs,
+        "Serving expired page, Bun unavailable"
+    );
+
+    let mut response = serve_cached_response(cached, request_headers, is_head);
+    let headers = response.headers_mut();
+    headers.insert("x-cache", HeaderValue::from_static("stale-if-error"));
+    headers.insert(
+        header::WARNING,
+        HeaderValue::from_static("111 - \"Revalidation Failed\""),
+    );
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+/// Background task to refresh a stale cache entry. Callers claim the key with
+/// [`IsrCache::start_refresh`](crate::cache::IsrCache::start_refresh) first;
+/// it is released here. Returns whether the render succeeded.
 ///
-/// The validated `host` must be carried through so the re-render produces the
-/// same per-domain origin; rendering with an empty header set would poison the
-/// cached origin on every stale refresh.
-async fn refresh_cache_entry(state: Arc<AppState>, host: String, path_with_query: String) {
-    let mut forward_headers = HeaderMap::new();
-    if let Ok(host_value) = HeaderValue::from_str(&host) {
+/// The validated host and any `Vary` request headers are recovered from the
+/// key so the re-render produces the same per-domain origin and variant;
+/// rendering with an empty header set would poison the cached origin on every
+/// stale refresh.
+pub(crate) async fn refresh_cache_entry(state: Arc<AppState>, cache_key: String) -> bool {
+    let Some((host, path_with_query)) = cache::split_cache_key(&cache_key) else {
+        state.isr_cache.end_refresh(&cache_key);
+        return false;
+    };
+    let base_key = cache::cache_key_for_target(host, path_with_query);
+
+    let mut forward_headers = cache_policy::variant_headers(&cache_key);
+    if let Ok(host_value) = HeaderValue::from_str(host) {
         forward_headers.insert("x-forwarded-host", host_value);
     }
     forward_headers.insert(
@@ -289,17 +576,31 @@ async fn refresh_cache_entry(state: Arc<AppState>, host: String, path_with_query
         HeaderValue::from_static(state.host_config.scheme()),
     );
 
-    let cache_key = cache::cache_key_for_target(&host, &path_with_query);
-
-    match proxy_to_bun(&path_with_query, state.clone(), forward_headers).await {
-        Ok((status, headers, body)) => {
+    let refreshed = match proxy_to_bun(path_with_query, state.clone(), forward_headers.clone())
+        .await
+    {
+        Ok((status, mut headers, body)) => {
             if status.is_success() {
-                let cached_response = CachedResponse::new(status, headers, body);
-                state
-                    .isr_cache
-                    .insert(cache_key.clone(), cached_response)
-                    .await;
-                tracing::debug!(host = %host, path = %path_with_query, "Cache entry refreshed");
+                let tags = cache::take_cache_tags(&mut headers);
+                if let Some(policy) =
+                    cache_policy::cache_policy(&headers, state.isr_cache.config.default_ttl())
+                {
+                    let key = cache_policy::variant_key(&base_key, &policy.vary, &forward_headers);
+                    let cached_response = CachedResponse::new(status, headers, body)
+                        .with_ttl(policy.ttl)
+                        .with_tags(tags);
+                    state.isr_cache.insert(key, cached_response).await;
+                    tracing::debug!(host = %host, path = %path_with_query, "Cache entry refreshed");
+                } else {
+                    // The page stopped being cacheable; don't keep serving it
+                    state.isr_cache.remove(&cache_key).await;
+                    tracing::debug!(
+                        host = %host,
+                        path = %path_with_query,
+                        "Refreshed page is no longer cacheable, dropped entry"
+                    );
+                }
+                true
             } else {
                 tracing::warn!(
                     host = %host,
@@ -307,6 +608,7 @@ async fn refresh_cache_entry(state: Arc<AppState>, host: String, path_with_query
                     status = status.as_u16(),
                     "Background refresh returned non-success status, keeping stale entry"
                 );
+                false
             }
         }
         Err(err) => {
@@ -316,10 +618,12 @@ async fn refresh_cache_entry(state: Arc<AppState>, host: String, path_with_query
                 error = %err,
                 "Background refresh failed, keeping stale entry"
             );
+            false
         }
-    }
+    };
 
     state.isr_cache.end_refresh(&cache_key);
+    refreshed
 }
 
 /// Log ISR request with appropriate level based on status
@@ -365,15 +669,27 @@ fn log_isr_request(path: &str, status: StatusCode, duration_ms: u64, cache: &str
     }
 }
 
-/// Proxy a request to Bun SSR
+/// Proxy a request to Bun SSR, buffering the whole body
 pub async fn proxy_to_bun(
     path: &str,
     state: Arc<AppState>,
     forward_headers: HeaderMap,
 ) -> Result<(StatusCode, HeaderMap, axum::body::Bytes), ProxyError> {
+    let (status, headers, response) = proxy_to_bun_stream(path, &state, &forward_headers).await?;
+    let body = response.bytes().await.map_err(ProxyError::Network)?;
+    Ok((status, headers, body))
+}
+
+/// Proxy a request to Bun SSR, returning once the response head arrives. The
+/// body is left unread on the returned response.
+async fn proxy_to_bun_stream(
+    path: &str,
+    state: &AppState,
+    forward_headers: &HeaderMap,
+) -> Result<(StatusCode, HeaderMap, reqwest::Response), ProxyError> {
     // Build request with forwarded headers
     let mut request_builder = state.client.get(path);
-    for (name, value) in &forward_headers {
+    for (name, value) in forward_headers {
         request_builder = request_builder.header(name, value);
     }
 
@@ -395,12 +711,12 @@ pub async fn proxy_to_bun(
         if let Ok(header_name) = axum::http::HeaderName::try_from(name.as_str())
             && let Ok(header_value) = axum::http::HeaderValue::try_from(value.as_bytes())
         {
-            headers.insert(header_name, header_value);
+            // Append so repeated headers (Set-Cookie, Vary) all survive
+            headers.append(header_name, header_value);
         }
     }
 
-    let body = response.bytes().await.map_err(ProxyError::Network)?;
-    Ok((status, headers, body))
+    Ok((status, headers, response))
 }
 
 /// Perform health check on Bun SSR and database
@@ -483,3 +799,55 @@ pub async fn fallback_handler_tcp(
 pub async fn fallback_handler_unix(State(state): State<Arc<AppState>>, req: Request) -> Response {
     handle_request_with_optional_peer(state, None, req).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tower::{Layer, Service, ServiceExt};
+    use tower_http::compression::CompressionLayer;
+
+    /// Streamed renders go through the compression layer, which must hand on
+    /// what it has whenever Bun pauses rather than waiting for more.
+    #[tokio::test]
+    async fn compression_flushes_streamed_chunks() {
+        let (sender, receiver) = mpsc::channel::<Result<Bytes, std::io::Error>>(1);
+        let mut body = Some(Body::from_stream(ReceiverStream::new(receiver)));
+        let render = tower::service_fn(move |_: Request| {
+            let body = body.take().unwrap();
+            async move {
+                let mut response = Response::new(body);
+                response
+                    .headers_mut()
+                    .insert(header::CONTENT_TYPE, HeaderValue::from_static("text/html"));
+                Ok::<_, std::convert::Infallible>(response)
+            }
+        });
+        let mut service = CompressionLayer::new().gzip(true).layer(render);
+
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip"));
+        let response = service.ready().await.unwrap().call(request).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+
+        // Send the head of the page and keep the stream open
+        let head = "<!doctype html><html><head><title>Streamed</title></head>".repeat(20);
+        sender.send(Ok(Bytes::from(head.clone()))).await.unwrap();
+
+        let mut frames = Body::new(response.into_body()).into_data_stream();
+        let mut decoder = flate2::write::GzDecoder::new(Vec::new());
+        while decoder.get_ref().len() < head.len() {
+            let frame = tokio::time::timeout(Duration::from_secs(1), frames.next())
+                .await
+                .expect("compressed chunk was held back")
+                .unwrap()
+                .unwrap();
+            decoder.write_all(&frame).unwrap();
+            decoder.flush().unwrap();
+        }
+        assert_eq!(decoder.get_ref().as_slice(), head.as_bytes());
+        drop(sender);
+    }
+}
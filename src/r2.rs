@@ -1,15 +1,78 @@
-use opendal::{Operator, services::S3};
-use std::sync::Arc;
+use opendal::{
+    Operator,
+    services::{Fs, S3},
+};
+use std::{ops::Range, path::PathBuf, sync::Arc, time::SystemTime};
 use tokio::sync::OnceCell;
 
 static R2_CLIENT: OnceCell<Arc<R2Client>> = OnceCell::const_new();
 
+/// Default directory for the `fs` backend
+const DEFAULT_STORAGE_DIR: &str = "data/storage";
+
+/// Where media and OG images are stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// Cloudflare R2 through its S3 API, configured by the `R2_*` env vars.
+    /// Objects are served publicly from the bucket's own domain.
+    R2,
+    /// A local directory, served by the `/media/{*key}` route.
+    Fs { root: PathBuf },
+}
+
+impl StorageBackend {
+    /// `STORAGE_BACKEND=r2|fs`; when unset, R2 if `R2_ACCOUNT_ID` is present and
+    /// the local directory (`STORAGE_DIR`, default `data/storage`) otherwise.
+    pub fn from_env() -> Result<Self, String> {
+        let fs = || Self::Fs {
+            root: std::env::var("STORAGE_DIR")
+                .unwrap_or_else(|_| DEFAULT_STORAGE_DIR.to_string())
+                .into(),
+        };
+
+        match std::env::var("STORAGE_BACKEND")
+            .ok()
+            .as_deref()
+            .map(str::trim)
+        {
+            Some("r2") => Ok(Self::R2),
+            Some("fs") => Ok(fs()),
+            Some(other) => Err(format!(
+                "Invalid STORAGE_BACKEND '{other}' (expected 'r2' or 'fs')"
+            )),
+            None if std::env::var_os("R2_ACCOUNT_ID").is_some() => Ok(Self::R2),
+            None => Ok(fs()),
+        }
+    }
+
+    /// Base URL clients load stored objects from (`MEDIA_BASE_URL` overrides).
+    pub fn public_base_url(&self) -> String {
+        if let Ok(url) = std::env::var("MEDIA_BASE_URL") {
+            return url.trim_end_matches('/').to_string();
+        }
+        match self {
+            Self::R2 => "https://media.xevion.dev".to_string(),
+            Self::Fs { .. } => "/media".to_string(),
+        }
+    }
+}
+
+/// Object storage for media and OG images, backed by R2 or a local directory
+/// (see [`StorageBackend`]).
 pub struct R2Client {
     op: Operator,
+    local: bool,
 }
 
 impl R2Client {
     pub fn new() -> Result<Self, String> {
+        match StorageBackend::from_env()? {
+            StorageBackend::R2 => Self::new_r2(),
+            StorageBackend::Fs { root } => Self::new_fs(&root),
+        }
+    }
+
+    fn new_r2() -> Result<Self, String> {
         let account_id =
             std::env::var("R2_ACCOUNT_ID").map_err(|_| "R2_ACCOUNT_ID not set".to_string())?;
         let access_key_id = std::env::var("R2_ACCESS_KEY_ID")
@@ -31,7 +94,37 @@ impl R2Client {
             .map_err(|e| format!("Failed to build R2 operator: {e}"))?
             .finish();
 
-        Ok(Self { op })
+        Ok(Self { op, local: false })
+    }
+
+    /// Store objects as plain files under `root`.
+    ///
+    /// Writes are staged in `root/.tmp` and renamed into place, so a partial or
+    /// aborted upload never shows up as an object.
+    pub fn new_fs(root: &std::path::Path) -> Result<Self, String> {
+        let root = std::path::absolute(root)
+            .map_err(|e| format!("Invalid storage directory {}: {e}", root.display()))?;
+        let staging = root.join(".tmp");
+
+        let builder = Fs::default()
+            .root(&root.to_string_lossy())
+            .atomic_write_dir(&staging.to_string_lossy());
+
+        let op = Operator::new(builder)
+            .map_err(|e| format!("Failed to build storage operator: {e}"))?
+            .finish();
+
+        Ok(Self { op, local: true })
+    }
+
+    /// Wrap an existing operator (e.g. opendal's in-memory backend in tests).
+    pub const fn from_operator(op: Operator) -> Self {
+        Self { op, local: false }
+    }
+
+    /// Whether objects live on local disk and must be served by this server.
+    pub const fn is_local(&self) -> bool {
+        self.local
     }
 
     pub async fn get() -> Option<Arc<Self>> {
@@ -40,7 +133,7 @@ impl R2Client {
                 match Self::new() {
                     Ok(client) => Ok(Arc::new(client)),
                     Err(e) => {
-                        tracing::warn!(error = %e, "Failed to initialize R2 client, OG images will not be cached");
+                        tracing::warn!(error = %e, "Failed to initialize storage, media uploads and OG images are unavailable");
                         Err(e)
                     }
                 }
@@ -68,6 +161,38 @@ impl R2Client {
         Ok(())
     }
 
+    /// Open a streaming multipart upload to `key`.
+    ///
+    /// Data is flushed to R2 as one multipart part per `part_size` bytes, so large
+    /// objects never need to be held in memory. R2 (like S3) requires every part
+    /// except the last to be at least 5 MiB.
+    pub async fn start_multipart(
+        &self,
+        key: &str,
+        content_type: &str,
+        part_size: usize,
+    ) -> Result<R2MultipartUpload, String> {
+        let writer = self
+            .op
+            .writer_with(key)
+            .content_type(content_type)
+            .chunk(part_size)
+            .await
+            .map_err(|e| format!("Failed to start multipart upload to R2: {e}"))?;
+
+        Ok(R2MultipartUpload { writer })
+    }
+
+    pub async fn get_object(&self, key: &str) -> Result<Vec<u8>, String> {
+        let buffer = self
+            .op
+            .read(key)
+            .await
+            .map_err(|e| format!("Failed to read object from R2: {e}"))?;
+
+        Ok(buffer.to_vec())
+    }
+
     pub async fn delete_object(&self, key: &str) -> Result<(), String> {
         self.op
             .delete(key)
@@ -97,7 +222,168 @@ impl R2Client {
         Ok(deleted)
     }
 
+    /// Recursively list every object under `prefix`.
+    pub async fn list_objects(&self, prefix: &str) -> Result<Vec<ObjectInfo>, String> {
+        let entries = self
+            .op
+            .list_with(prefix)
+            .recursive(true)
+            .await
+            .map_err(|e| format!("Failed to list objects in R2: {e}"))?;
+
+        Ok(entries
+            .into_iter()
+            .filter(|entry| !entry.path().ends_with('/'))
+            .map(|entry| ObjectInfo {
+                last_modified: entry.metadata().last_modified().map(SystemTime::from),
+                key: entry.path().to_string(),
+            })
+            .collect())
+    }
+
     pub async fn object_exists(&self, key: &str) -> bool {
         self.op.stat(key).await.is_ok()
     }
+
+    /// Size and modification time of an object, or `None` if it doesn't exist.
+    pub async fn stat_object(&self, key: &str) -> Result<Option<ObjectStat>, String> {
+        match self.op.stat(key).await {
+            Ok(meta) if meta.is_file() => Ok(Some(ObjectStat {
+                size: meta.content_length(),
+                last_modified: meta.last_modified().map(SystemTime::from),
+            })),
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == opendal::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to stat object: {e}")),
+        }
+    }
+
+    /// Stream `range` of an object's bytes without buffering it in memory.
+    pub async fn stream_object(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> Result<impl futures::Stream<Item = std::io::Result<axum::body::Bytes>> + use<>, String>
+    {
+        self.op
+            .reader(key)
+            .await
+            .map_err(|e| format!("Failed to open object: {e}"))?
+            .into_bytes_stream(range)
+            .await
+            .map_err(|e| format!("Failed to read object: {e}"))
+    }
+}
+
+/// A listed object key with its modification time, when the backend reports one.
+#[derive(Debug, Clone)]
+pub struct ObjectInfo {
+    pub key: String,
+    pub last_modified: Option<SystemTime>,
+}
+
+/// Metadata from [`R2Client::stat_object`].
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectStat {
+    pub size: u64,
+    pub last_modified: Option<SystemTime>,
+}
+
+/// An in-progress multipart upload started by [`R2Client::start_multipart`].
+///
+/// Dropping it without [`complete`](Self::complete) leaves an incomplete upload
+/// in the bucket; call [`abort`](Self::abort) to release the uploaded parts.
+pub struct R2MultipartUpload {
+    writer: opendal::Writer,
+}
+
+impl R2MultipartUpload {
+    pub async fn write(&mut self, data: Vec<u8>) -> Result<(), String> {
+        self.writer
+            .write(data)
+            .await
+            .map_err(|e| format!("Failed to upload part to R2: {e}"))
+    }
+
+    pub async fn complete(&mut self) -> Result<(), String> {
+        self.writer
+            .close()
+            .await
+            .map_err(|e| format!("Failed to complete multipart upload to R2: {e}"))?;
+
+        Ok(())
+    }
+
+    pub async fn abort(&mut self) -> Result<(), String> {
+        self.writer
+            .abort()
+            .await
+            .map_err(|e| format!("Failed to abort multipart upload to R2: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+
+    #[tokio::test]
+    async fn test_fs_backend_round_trip() {
+        let root = std::env::temp_dir().join(format!("xevion-storage-{}", ulid::Ulid::new()));
+        let storage = R2Client::new_fs(&root).unwrap();
+        assert!(storage.is_local());
+
+        storage
+            .put_object(
+                "projects/p/a/original.png",
+                b"0123456789".to_vec(),
+                "image/png",
+                None,
+            )
+            .await
+            .unwrap();
+
+        let stat = storage
+            .stat_object("projects/p/a/original.png")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stat.size, 10);
+        assert!(stat.last_modified.is_some());
+        assert!(
+            storage
+                .stat_object("projects/p/a/missing.png")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let slice: Vec<_> = storage
+            .stream_object("projects/p/a/original.png", 2..5)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(slice.concat(), b"234");
+
+        // Aborted uploads never surface as objects
+        let mut upload = storage
+            .start_multipart("projects/p/b/original.mp4", "video/mp4", 5 * 1024 * 1024)
+            .await
+            .unwrap();
+        upload.write(vec![1; 16]).await.unwrap();
+        upload.abort().await.unwrap();
+
+        let keys: Vec<_> = storage
+            .list_objects("projects/")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|o| o.key)
+            .collect();
+        assert_eq!(keys, vec!["projects/p/a/original.png".to_string()]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 }
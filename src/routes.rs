@@ -18,15 +18,72 @@ pub fn api_routes() -> Router<Arc<AppState>> {
             get(handlers::health_handler).head(handlers::health_handler),
         )
         .route("/login", post(handlers::api_login_handler))
+        .route(
+            "/login/passkey/options",
+            post(handlers::passkey_login_options_handler),
+        )
+        .route("/login/passkey", post(handlers::passkey_login_handler))
         .route("/logout", post(handlers::api_logout_handler))
         .route("/session", get(handlers::api_session_handler))
-        .route("/sessions", get(handlers::list_sessions_handler))
+        .route("/account/password", put(handlers::change_password_handler))
+        .route("/account/totp", get(handlers::totp_status_handler))
+        .route("/account/totp/setup", post(handlers::totp_setup_handler))
+        .route(
+            "/account/totp/confirm",
+            post(handlers::totp_confirm_handler),
+        )
+        .route(
+            "/account/totp/disable",
+            post(handlers::totp_disable_handler),
+        )
+        .route(
+            "/account/totp/recovery-codes",
+            post(handlers::recovery_codes_handler),
+        )
+        .route(
+            "/account/passkeys",
+            get(handlers::list_passkeys_handler).post(handlers::register_passkey_handler),
+        )
+        .route(
+            "/account/passkeys/options",
+            post(handlers::passkey_registration_options_handler),
+        )
+        .route(
+            "/account/passkeys/{id}",
+            delete(handlers::delete_passkey_handler),
+        )
+        .route(
+            "/sessions",
+            get(handlers::list_sessions_handler).delete(handlers::revoke_other_sessions_handler),
+        )
+        .route(
+            "/sessions/lockouts",
+            get(handlers::list_lockouts_handler).delete(handlers::clear_lockouts_handler),
+        )
+        .route(
+            "/sessions/lockouts/{key}",
+            delete(handlers::clear_lockout_handler),
+        )
         .route("/sessions/{id}", delete(handlers::revoke_session_handler))
+        .route(
+            "/tokens",
+            get(handlers::list_tokens_handler).post(handlers::create_token_handler),
+        )
+        .route("/tokens/{id}", delete(handlers::revoke_token_handler))
+        .route(
+            "/users",
+            get(handlers::list_users_handler).post(handlers::create_user_handler),
+        )
+        .route(
+            "/users/{id}",
+            put(handlers::update_user_handler).delete(handlers::delete_user_handler),
+        )
         .route("/auth/device/start", post(handlers::cli_auth_start_handler))
         .route(
             "/auth/device/events/{id}",
             get(handlers::cli_auth_events_handler),
         )
+        .route("/auth/device/token", post(handlers::cli_auth_token_handler))
         .route(
             "/auth/device/info/{id}",
             get(handlers::cli_auth_info_handler),
@@ -65,6 +122,24 @@ pub fn api_routes() -> Router<Arc<AppState>> {
             "/projects/{ref}/media",
             get(handlers::get_project_media_handler).post(handlers::upload_media_handler),
         )
+        .route(
+            "/projects/{ref}/media/uploads",
+            post(handlers::create_media_upload_handler),
+        )
+        .route(
+            "/projects/{ref}/media/uploads/{upload_id}",
+            get(handlers::get_media_upload_handler)
+                .patch(handlers::append_media_upload_handler)
+                .delete(handlers::abort_media_upload_handler),
+        )
+        .route(
+            "/projects/{ref}/media/uploads/{upload_id}/complete",
+            post(handlers::complete_media_upload_handler),
+        )
+        .route(
+            "/projects/{ref}/media/attach",
+            post(handlers::attach_media_handler),
+        )
         .route(
             "/projects/{ref}/media/reorder",
             put(handlers::reorder_media_handler),
@@ -73,6 +148,14 @@ pub fn api_routes() -> Router<Arc<AppState>> {
             "/projects/{ref}/media/{media_id}",
             delete(handlers::delete_media_handler),
         )
+        .route(
+            "/media",
+            get(handlers::list_library_media_handler).post(handlers::upload_library_media_handler),
+        )
+        .route(
+            "/media/{id}",
+            get(handlers::get_library_media_handler).delete(handlers::delete_library_media_handler),
+        )
         .route(
             "/tags",
             get(handlers::list_tags_handler).post(handlers::create_tag_handler),
@@ -95,6 +178,10 @@ pub fn api_routes() -> Router<Arc<AppState>> {
         .route("/stats", get(handlers::get_admin_stats_handler))
         .route("/github/status", get(handlers::github_status_handler))
         .route("/github/sync", post(handlers::sync_all_projects_handler))
+        .route("/storage/gc", post(handlers::storage_gc_handler))
+        .route("/cache", get(handlers::list_cache_entries_handler))
+        .route("/cache/stats", get(handlers::cache_stats_handler))
+        .route("/cache/purge", post(handlers::purge_cache_handler))
         .route(
             "/settings",
             get(handlers::get_settings_handler).put(handlers::update_settings_handler),
@@ -112,6 +199,7 @@ pub fn build_base_router() -> Router<Arc<AppState>> {
             "/_app/{*path}",
             get(assets::serve_embedded_asset).head(assets::serve_embedded_asset),
         )
+        .route("/media/{*key}", get(handlers::serve_stored_object_handler))
         .route("/robots.txt", get(handlers::robots_handler))
         .route("/sitemap.xml", get(handlers::sitemap_handler))
         .route("/pgp", get(handlers::handle_pgp_route))
@@ -0,0 +1,231 @@
+//! Session lifetime policy
+//!
+//! Browser and CLI sessions each have a policy (see [`SessionPolicies`]). A
+//! new session lasts `lifetime`. A session used within `renew_within` of its
+//! expiry is renewed: its expiry moves to `lifetime` from now. Renewals never
+//! carry a session past `max_lifetime` after it was created, and a session
+//! left unused for `idle_timeout` expires early. API tokens keep the fixed
+//! expiry they were created with.
+//!
+//! Activity is recorded at most every few minutes (see
+//! [`crate::auth::SessionManager::touch_session`]), so idle timeouts shorter
+//! than that aren't meaningful.
+
+use std::str::FromStr;
+use time::{Duration, OffsetDateTime};
+
+use crate::auth::{Session, SessionType};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionPolicy {
+    /// How long a new session lasts, and how far a renewal pushes its expiry
+    pub lifetime: Duration,
+    /// Sessions used within this long of expiring are renewed; zero never renews
+    pub renew_within: Duration,
+    /// Hard cap on a session's age, however often it's renewed
+    pub max_lifetime: Option<Duration>,
+    /// Sessions unused for this long expire early
+    pub idle_timeout: Option<Duration>,
+}
+
+impl SessionPolicy {
+    /// Browser sessions last a week, renew in their last day, and end after
+    /// 30 days regardless.
+    pub const BROWSER: Self = Self {
+        lifetime: Duration::days(7),
+        renew_within: Duration::days(1),
+        max_lifetime: Some(Duration::days(30)),
+        idle_timeout: None,
+    };
+
+    /// CLI sessions last 90 days and renew in their last 30, so a CLI in
+    /// regular use stays signed in.
+    pub const CLI: Self = Self {
+        lifetime: Duration::days(90),
+        renew_within: Duration::days(30),
+        max_lifetime: None,
+        idle_timeout: None,
+    };
+
+    /// Read `<prefix>_LIFETIME_SEC`, `<prefix>_RENEW_WITHIN_SEC`,
+    /// `<prefix>_MAX_LIFETIME_SEC`, and `<prefix>_IDLE_TIMEOUT_SEC`, falling back
+    /// to `defaults`. Zero turns off renewal, the cap, and the idle timeout.
+    fn from_env(prefix: &str, defaults: &Self) -> Self {
+        let secs = |name: &str, default: Option<Duration>| {
+            env_or(
+                &format!("{prefix}_{name}_SEC"),
+                default.map_or(0, Duration::whole_seconds),
+            )
+        };
+        let optional = |secs: i64| (secs > 0).then(|| Duration::seconds(secs));
+
+        Self {
+            lifetime: Duration::seconds(
+                secs("LIFETIME", Some(defaults.lifetime)).max(Duration::HOUR.whole_seconds()),
+            ),
+            renew_within: Duration::seconds(
+                secs("RENEW_WITHIN", Some(defaults.renew_within)).max(0),
+            ),
+            max_lifetime: optional(secs("MAX_LIFETIME", defaults.max_lifetime)),
+            idle_timeout: optional(secs("IDLE_TIMEOUT", defaults.idle_timeout)),
+        }
+    }
+
+    /// Expiry of a session created at `created_at`
+    pub fn initial_expiry(&self, created_at: OffsetDateTime) -> OffsetDateTime {
+        self.capped(created_at, created_at + self.lifetime)
+    }
+
+    /// The later expiry a use at `now` renews `session` to, if it's close
+    /// enough to expiring
+    pub fn renewal(&self, session: &Session, now: OffsetDateTime) -> Option<OffsetDateTime> {
+        if self.renew_within.is_zero() || session.expires_at - now > self.renew_within {
+            return None;
+        }
+        let expires_at = self.capped(session.created_at, now + self.lifetime);
+        (expires_at > session.expires_at).then_some(expires_at)
+    }
+
+    /// Whether `session` has been unused too long, or lived past the cap
+    /// (which can shrink after it was created)
+    pub fn has_lapsed(&self, session: &Session, now: OffsetDateTime) -> bool {
+        self.idle_timeout
+            .is_some_and(|idle| now - session.last_active_at > idle)
+            || self
+                .max_lifetime
+                .is_some_and(|max| now - session.created_at > max)
+    }
+
+    fn capped(&self, created_at: OffsetDateTime, expires_at: OffsetDateTime) -> OffsetDateTime {
+        self.max_lifetime
+            .map_or(expires_at, |max| expires_at.min(created_at + max))
+    }
+}
+
+/// Lifetime policies for the session types that have one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionPolicies {
+    pub browser: SessionPolicy,
+    pub cli: SessionPolicy,
+}
+
+impl Default for SessionPolicies {
+    fn default() -> Self {
+        Self {
+            browser: SessionPolicy::BROWSER,
+            cli: SessionPolicy::CLI,
+        }
+    }
+}
+
+impl SessionPolicies {
+    /// Load the policies from `BROWSER_SESSION_*` and `CLI_SESSION_*` variables
+    /// (see [`SessionPolicy::from_env`]).
+    pub fn from_env() -> Self {
+        Self {
+            browser: SessionPolicy::from_env("BROWSER_SESSION", &SessionPolicy::BROWSER),
+            cli: SessionPolicy::from_env("CLI_SESSION", &SessionPolicy::CLI),
+        }
+    }
+
+    /// The policy for a session type; `None` for API tokens, whose expiry is
+    /// fixed when they're created.
+    pub const fn get(&self, session_type: SessionType) -> Option<&SessionPolicy> {
+        match session_type {
+            SessionType::Browser => Some(&self.browser),
+            SessionType::Cli => Some(&self.cli),
+            SessionType::Token => None,
+        }
+    }
+}
+
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::AdminRole;
+    use ulid::Ulid;
+
+    fn session(created_at: OffsetDateTime, expires_at: OffsetDateTime) -> Session {
+        Session {
+            id: Ulid::new(),
+            user_id: 1,
+            username: "admin".into(),
+            created_at,
+            last_active_at: created_at,
+            expires_at,
+            session_type: SessionType::Browser,
+            label: None,
+            role: AdminRole::Owner,
+            grant: None,
+            created_ip: None,
+            last_ip: None,
+            user_agent: None,
+        }
+    }
+
+    #[test]
+    fn renews_only_near_expiry() {
+        let policy = SessionPolicy::BROWSER;
+        let created = OffsetDateTime::now_utc();
+        let s = session(created, policy.initial_expiry(created));
+        assert_eq!(s.expires_at, created + Duration::days(7));
+
+        assert_eq!(policy.renewal(&s, created + Duration::days(2)), None);
+        let now = created + Duration::days(6) + Duration::hours(1);
+        assert_eq!(policy.renewal(&s, now), Some(now + Duration::days(7)));
+    }
+
+    #[test]
+    fn renewal_stops_at_max_lifetime() {
+        let policy = SessionPolicy::BROWSER;
+        let created = OffsetDateTime::now_utc();
+        let cap = created + Duration::days(30);
+
+        let s = session(created, created + Duration::days(28));
+        assert_eq!(policy.renewal(&s, created + Duration::days(27)), Some(cap));
+        // Already at the cap: nothing left to renew
+        let s = session(created, cap);
+        assert_eq!(policy.renewal(&s, created + Duration::days(29)), None);
+    }
+
+    #[test]
+    fn zero_renew_window_never_renews() {
+        let policy = SessionPolicy {
+            renew_within: Duration::ZERO,
+            ..SessionPolicy::CLI
+        };
+        let created = OffsetDateTime::now_utc();
+        let s = session(created, created + Duration::minutes(1));
+        assert_eq!(policy.renewal(&s, created), None);
+    }
+
+    #[test]
+    fn idle_and_overage_sessions_lapse() {
+        let policy = SessionPolicy {
+            idle_timeout: Some(Duration::hours(12)),
+            ..SessionPolicy::BROWSER
+        };
+        let created = OffsetDateTime::now_utc();
+        let mut s = session(created, created + Duration::days(7));
+        assert!(!policy.has_lapsed(&s, created + Duration::hours(11)));
+        assert!(policy.has_lapsed(&s, created + Duration::hours(13)));
+
+        s.last_active_at = created + Duration::days(29) + Duration::hours(23);
+        assert!(!policy.has_lapsed(&s, created + Duration::days(30)));
+        assert!(policy.has_lapsed(&s, created + Duration::days(30) + Duration::hours(1)));
+    }
+
+    #[test]
+    fn tokens_have_no_policy() {
+        let policies = SessionPolicies::default();
+        assert!(policies.get(SessionType::Token).is_none());
+        assert_eq!(policies.get(SessionType::Cli), Some(&SessionPolicy::CLI));
+    }
+}
@@ -5,9 +5,10 @@ use std::sync::Arc;
 
 #[cfg(feature = "server")]
 use crate::{
-    auth::SessionManager, cache::IsrCache, cli_auth::CliAuthRegistry, events::EventSender,
-    health::HealthChecker, host::HostConfig, http::HttpClient, icon_cache::IconCache,
-    tarpit::TarpitState,
+    auth::SessionManager, cache::IsrCache, cache_warmer::CacheWarmer, cli_auth::CliAuthRegistry,
+    events::EventSender, health::HealthChecker, host::HostConfig, http::HttpClient,
+    icon_cache::IconCache, login_throttle::LoginThrottle, media_queue::MediaQueue,
+    media_upload::MediaUploadRegistry, tarpit::TarpitState, webauthn::Webauthn,
 };
 
 /// Application state shared across all handlers
@@ -20,11 +21,21 @@ pub struct AppState {
     pub pool: sqlx::PgPool,
     pub session_manager: Arc<SessionManager>,
     pub isr_cache: Arc<IsrCache>,
+    /// Progress of ISR cache warming after startup and invalidations.
+    pub cache_warmer: Arc<CacheWarmer>,
     pub icon_cache: Arc<IconCache>,
     pub event_sender: EventSender,
+    /// Per-IP and per-username backoff for failed logins.
+    pub login_throttle: Arc<LoginThrottle>,
     pub cli_auth: CliAuthRegistry,
     /// Allowlist-gated public-host resolver for per-domain SSR output.
     pub host_config: HostConfig,
+    /// Passkey relying party and its pending challenges.
+    pub webauthn: Arc<Webauthn>,
+    /// Background image processing for uploaded media.
+    pub media_queue: MediaQueue,
+    /// In-progress resumable (chunked) media uploads.
+    pub media_uploads: MediaUploadRegistry,
 }
 
 /// Errors that can occur during proxying to Bun
@@ -104,6 +115,18 @@ pub enum AppError {
     #[error("Invalid credentials")]
     InvalidCredentials,
 
+    /// Authenticated, but the user's role doesn't allow the action.
+    #[error("Your role doesn't allow this")]
+    Forbidden,
+
+    /// A write authenticated by the session cookie came from another site.
+    #[error("Cross-site request rejected")]
+    CrossSite,
+
+    /// The account has 2FA enabled and the request carried no code.
+    #[error("Two-factor code required")]
+    SecondFactorRequired,
+
     #[error("{0}")]
     Validation(ValidationErrors),
 
@@ -113,6 +136,9 @@ pub enum AppError {
     #[error("{0}")]
     ServiceUnavailable(String),
 
+    #[error("Too many attempts, retry in {}s", retry_after_secs(*.0))]
+    TooManyRequests(std::time::Duration),
+
     #[error(transparent)]
     Database(sqlx::Error),
 
@@ -142,9 +168,13 @@ impl axum::response::IntoResponse for AppError {
             Self::NotFound => (StatusCode::NOT_FOUND, "NOT_FOUND"),
             Self::Unauthorized => (StatusCode::UNAUTHORIZED, "UNAUTHORIZED"),
             Self::InvalidCredentials => (StatusCode::UNAUTHORIZED, "INVALID_CREDENTIALS"),
+            Self::Forbidden => (StatusCode::FORBIDDEN, "FORBIDDEN"),
+            Self::CrossSite => (StatusCode::FORBIDDEN, "CROSS_SITE"),
+            Self::SecondFactorRequired => (StatusCode::UNAUTHORIZED, "TOTP_REQUIRED"),
             Self::Validation(_) => (StatusCode::BAD_REQUEST, "VALIDATION_ERROR"),
             Self::Conflict(_) => (StatusCode::CONFLICT, "CONFLICT"),
             Self::ServiceUnavailable(_) => (StatusCode::SERVICE_UNAVAILABLE, "SERVICE_UNAVAILABLE"),
+            Self::TooManyRequests(_) => (StatusCode::TOO_MANY_REQUESTS, "TOO_MANY_REQUESTS"),
             Self::Database(err) => {
                 tracing::error!(error = %err, "Database error");
                 (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR")
@@ -172,6 +202,18 @@ impl axum::response::IntoResponse for AppError {
                 .into_response();
         }
 
+        if let Self::TooManyRequests(retry_after) = self {
+            return (
+                status,
+                [(
+                    axum::http::header::RETRY_AFTER,
+                    retry_after_secs(retry_after).to_string(),
+                )],
+                Json(serde_json::json!({ "error": message, "code": code })),
+            )
+                .into_response();
+        }
+
         (
             status,
             Json(serde_json::json!({ "error": message, "code": code })),
@@ -180,6 +222,11 @@ impl axum::response::IntoResponse for AppError {
     }
 }
 
+/// Whole seconds for `Retry-After`, rounded up so clients never retry early
+fn retry_after_secs(retry_after: std::time::Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
+
 impl From<sqlx::Error> for AppError {
     fn from(err: sqlx::Error) -> Self {
         Self::Database(err)
@@ -224,8 +271,11 @@ impl<T> SqlxResultExt<T> for Result<T, sqlx::Error> {
 }
 
 /// Auth extractor — validates the admin session from either the browser cookie
-/// or a CLI `Authorization: Bearer` token. Use in handler signatures to require
-/// authentication.
+/// or an `Authorization: Bearer` token (CLI or API token). Use in handler
+/// signatures to require authentication; any role passes, so it guards reads
+/// and the user's own account. Browser sessions can only write from our own
+/// pages (see [`crate::csrf`]), and API tokens are additionally held to their
+/// scopes and project allowlist here.
 #[cfg(feature = "server")]
 #[derive(Debug)]
 pub struct AdminSession(pub crate::auth::Session);
@@ -238,9 +288,251 @@ impl axum::extract::FromRequestParts<Arc<AppState>> for AdminSession {
         parts: &mut axum::http::request::Parts,
         state: &Arc<AppState>,
     ) -> Result<Self, Self::Rejection> {
-        let session = crate::auth::authenticate(state, &parts.headers)
+        let session = crate::auth::authenticate(state, &parts.headers, &parts.extensions)
             .await
             .ok_or(AppError::Unauthorized)?;
+        crate::csrf::check(&session, &parts.method, &parts.headers, &state.host_config)?;
+        if let Some(grant) = &session.grant {
+            check_token_grant(parts, state, grant).await?;
+        }
+        Ok(Self(session))
+    }
+}
+
+/// Hold an API token to its grant: writes need the route's scope (see
+/// [`crate::api_tokens::required_scope`]). A token limited to some projects
+/// may only write under `/projects/{ref}`, and only reach the projects on its
+/// allowlist there.
+#[cfg(feature = "server")]
+async fn check_token_grant(
+    parts: &mut axum::http::request::Parts,
+    state: &Arc<AppState>,
+    grant: &crate::auth::TokenGrant,
+) -> Result<(), AppError> {
+    use crate::api_tokens::{required_scope, targets_one_project};
+    use axum::extract::{FromRequestParts, MatchedPath, RawPathParams};
+
+    let route = parts
+        .extensions
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .ok_or(AppError::Forbidden)?;
+    let route = route.strip_prefix("/api").unwrap_or(&route);
+
+    let method = &parts.method;
+    if !method.is_safe() {
+        let scope = required_scope(method, route).ok_or(AppError::Forbidden)?;
+        if !grant.allows(scope) {
+            return Err(AppError::Forbidden);
+        }
+    }
+
+    if grant.project_ids.is_none() {
+        return Ok(());
+    }
+    if !targets_one_project(route) {
+        return if method.is_safe() {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden)
+        };
+    }
+    let params = RawPathParams::from_request_parts(parts, state)
+        .await
+        .map_err(|_| AppError::Forbidden)?;
+    let Some((_, project_ref)) = params.iter().find(|(key, _)| *key == "ref") else {
+        return Err(AppError::Forbidden);
+    };
+    // Ids are checked as given; an unknown slug falls through to the handler's 404
+    let project_id = match uuid::Uuid::parse_str(project_ref) {
+        Ok(id) => Some(id),
+        Err(_) => crate::db::get_project_by_ref(&state.pool, project_ref)
+            .await?
+            .map(|project| project.id),
+    };
+    if project_id.is_some_and(|id| !grant.allows_project(id)) {
+        return Err(AppError::Forbidden);
+    }
+    Ok(())
+}
+
+/// Like [`AdminSession`], but the role must be allowed to change content
+/// (projects, tags, media, content, caches).
+#[cfg(feature = "server")]
+#[derive(Debug)]
+pub struct EditorSession(pub crate::auth::Session);
+
+#[cfg(feature = "server")]
+impl axum::extract::FromRequestParts<Arc<AppState>> for EditorSession {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let AdminSession(session) = AdminSession::from_request_parts(parts, state).await?;
+        if !session.role.can_edit_content() {
+            return Err(AppError::Forbidden);
+        }
         Ok(Self(session))
     }
 }
+
+/// Like [`AdminSession`], but the role must be allowed to manage the site
+/// (settings, sessions and lockouts, users, storage). API tokens never pass.
+#[cfg(feature = "server")]
+#[derive(Debug)]
+pub struct OwnerSession(pub crate::auth::Session);
+
+#[cfg(feature = "server")]
+impl axum::extract::FromRequestParts<Arc<AppState>> for OwnerSession {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let AdminSession(session) = AdminSession::from_request_parts(parts, state).await?;
+        if !session.role.can_manage_site() || session.grant.is_some() {
+            return Err(AppError::Forbidden);
+        }
+        Ok(Self(session))
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use crate::{api_tokens::ApiScope, auth::TokenGrant};
+    use axum::{
+        Router,
+        body::Body,
+        http::{Method, Request, StatusCode, header},
+    };
+    use std::net::IpAddr;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    const CLIENT_IP: &str = "203.0.113.7";
+
+    /// State whose database is never reached: the pool connects lazily, and
+    /// tokens live only in memory.
+    fn test_state() -> Arc<AppState> {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .unwrap();
+        let host_config = HostConfig::from_env();
+        let (event_sender, _) = crate::events::create_channel();
+        let (media_queue, _) = MediaQueue::new();
+        Arc::new(AppState {
+            client: HttpClient::new("http://127.0.0.1:1").unwrap(),
+            health_checker: Arc::new(HealthChecker::new(|| async { true })),
+            tarpit_state: Arc::new(TarpitState::new(crate::tarpit::TarpitConfig::from_env())),
+            pool: pool.clone(),
+            session_manager: Arc::new(SessionManager::in_memory(pool)),
+            isr_cache: Arc::new(IsrCache::new(crate::cache::IsrCacheConfig::from_env())),
+            cache_warmer: Arc::new(CacheWarmer::new()),
+            icon_cache: Arc::new(IconCache::new()),
+            event_sender,
+            login_throttle: Arc::new(LoginThrottle::new(
+                crate::login_throttle::LoginThrottleConfig::from_env(),
+            )),
+            cli_auth: CliAuthRegistry::new(),
+            webauthn: Arc::new(Webauthn::new(crate::webauthn::WebauthnConfig::from_env(
+                host_config.canonical(),
+            ))),
+            host_config,
+            media_queue,
+            media_uploads: MediaUploadRegistry::new(
+                crate::media_upload::MediaUploadConfig::from_env(),
+            ),
+        })
+    }
+
+    /// The real API router, with a token holding `scopes` (limited to
+    /// `projects` when given).
+    fn api_with_token(scopes: &[ApiScope], projects: Option<Vec<Uuid>>) -> (Router, String) {
+        let state = test_state();
+        let grant = TokenGrant {
+            scopes: scopes.to_vec(),
+            project_ids: projects,
+        };
+        let ip: IpAddr = CLIENT_IP.parse().unwrap();
+        let token = state.session_manager.insert_api_token(grant, ip);
+        let router = Router::new()
+            .nest("/api", crate::routes::api_routes())
+            .with_state(state);
+        (router, token)
+    }
+
+    async fn send(router: &Router, token: &str, method: Method, uri: &str) -> StatusCode {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("x-real-ip", CLIENT_IP)
+            .body(Body::from(r#"{"all":true}"#))
+            .unwrap();
+        router.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn token_is_accepted_on_a_granted_write() {
+        let (router, token) = api_with_token(&[ApiScope::CachePurge], None);
+        let status = send(&router, &token, Method::POST, "/api/cache/purge").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn token_is_rejected_on_a_write_outside_its_scopes() {
+        let (router, token) = api_with_token(&[ApiScope::CachePurge], None);
+        for (method, uri) in [
+            (Method::DELETE, "/api/tags/rust"),
+            (Method::PUT, "/api/projects/xevion-dev"),
+            (Method::POST, "/api/media"),
+        ] {
+            let status = send(&router, &token, method.clone(), uri).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{method} {uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn token_is_rejected_on_owner_routes() {
+        let (router, token) = api_with_token(&ApiScope::ALL, None);
+        for (method, uri) in [
+            (Method::PUT, "/api/settings"),
+            (Method::GET, "/api/sessions/lockouts"),
+            (Method::POST, "/api/tokens"),
+        ] {
+            let status = send(&router, &token, method.clone(), uri).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{method} {uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn project_limited_token_is_rejected_outside_its_projects() {
+        let allowed = Uuid::new_v4();
+        let (router, token) = api_with_token(&ApiScope::ALL, Some(vec![allowed]));
+        let other = Uuid::new_v4();
+        for (method, uri) in [
+            (Method::PUT, format!("/api/projects/{other}")),
+            (
+                Method::DELETE,
+                format!("/api/projects/{other}/media/{}", Uuid::new_v4()),
+            ),
+            (Method::POST, "/api/projects".to_owned()),
+            (Method::POST, "/api/media".to_owned()),
+            (Method::DELETE, format!("/api/media/{}", Uuid::new_v4())),
+            (Method::PUT, "/api/tags/rust".to_owned()),
+            (
+                Method::POST,
+                "/api/tags/recalculate-cooccurrence".to_owned(),
+            ),
+            (Method::POST, "/api/cache/purge".to_owned()),
+        ] {
+            let status = send(&router, &token, method.clone(), &uri).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{method} {uri}");
+        }
+    }
+}
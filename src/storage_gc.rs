@@ -0,0 +1,428 @@
+//! Reconciliation between R2 and the database.
+//!
+//! Media deletion is best-effort (prefix deletes may fail and are only logged),
+//! so objects can outlive their asset rows. The reverse happens too: a failed
+//! variant upload leaves a row pointing at keys that were never written. [`run`]
+//! finds both: orphaned objects under `projects/`, `media/` and `og/project/`,
+//! and asset keys that are absent from the bucket. With `apply`, orphans are
+//! deleted, assets missing their original are marked `failed`, and images
+//! missing only derived variants are re-queued for processing.
+
+use std::{
+    collections::HashSet,
+    time::{Duration, SystemTime},
+};
+use uuid::Uuid;
+
+use crate::{
+    cache,
+    db::{self, DbMediaAsset, MediaStatus, MediaType},
+    events::{self, EventLevel, EventType},
+    handlers::{self, MissingObject, OrphanReason, OrphanedObject, StorageGcReport},
+    r2::{ObjectInfo, R2Client},
+    state::{AppError, AppResult, AppState},
+};
+
+/// Objects younger than this are never reported as orphans: single-request
+/// uploads write the original before inserting the row.
+pub const GRACE_PERIOD: Duration = Duration::from_hours(1);
+
+const MEDIA_PREFIX: &str = "projects/";
+const LIBRARY_PREFIX: &str = "media/";
+const OG_PROJECT_PREFIX: &str = "og/project/";
+
+/// Database state the scan is checked against, loaded before listing storage
+/// so anything written in between falls inside the grace period.
+pub struct Snapshot {
+    pub media: Vec<DbMediaAsset>,
+    pub project_ids: HashSet<Uuid>,
+    /// Base paths of in-progress chunked uploads
+    pub active_uploads: HashSet<String>,
+}
+
+/// Findings of a scan, plus the follow-up work `apply` would do.
+#[derive(Debug, Default)]
+pub struct Scan {
+    pub report: StorageGcReport,
+    /// Media whose original is missing
+    lost: Vec<Uuid>,
+    /// Images whose original is present but a derived variant is missing
+    reprocess: Vec<Uuid>,
+}
+
+/// Reconcile storage against the database, applying fixes if `apply` is set.
+pub async fn run(state: &AppState, apply: bool, actor: &str) -> AppResult<StorageGcReport> {
+    let r2 = R2Client::get()
+        .await
+        .ok_or_else(|| AppError::ServiceUnavailable("Media storage is not configured".into()))?;
+
+    let snapshot = Snapshot {
+        media: db::get_all_media_assets(&state.pool).await?,
+        project_ids: db::get_all_project_ids(&state.pool)
+            .await?
+            .into_iter()
+            .collect(),
+        active_uploads: state
+            .media_uploads
+            .active_base_paths()
+            .await
+            .into_iter()
+            .collect(),
+    };
+
+    let scan = scan(&r2, &snapshot, SystemTime::now())
+        .await
+        .map_err(AppError::Internal)?;
+
+    if !apply {
+        return Ok(scan.report);
+    }
+
+    let report = apply_scan(state, &r2, scan).await?;
+
+    tracing::info!(
+        deleted = report.deleted,
+        requeued = report.requeued,
+        failed = report.failed,
+        "Storage garbage collection applied"
+    );
+    events::log_event(
+        &state.event_sender,
+        EventType::StorageGc,
+        if report.failed > 0 {
+            EventLevel::Warning
+        } else {
+            EventLevel::Info
+        },
+        Some("system"),
+        None,
+        Some(actor),
+        format!(
+            "Storage cleanup: {} orphan(s) deleted, {} media re-queued, {} media failed",
+            report.deleted, report.requeued, report.failed
+        ),
+        Some(serde_json::json!({
+            "scanned": report.scanned,
+            "orphans": report.orphans.len(),
+            "missing": report.missing.len(),
+            "deleted": report.deleted,
+            "requeued": report.requeued,
+            "failed": report.failed,
+        })),
+    );
+
+    Ok(report)
+}
+
+/// Compare the objects in storage with `snapshot`, without changing anything.
+pub async fn scan(r2: &R2Client, snapshot: &Snapshot, now: SystemTime) -> Result<Scan, String> {
+    let mut objects = r2.list_objects(MEDIA_PREFIX).await?;
+    objects.extend(r2.list_objects(LIBRARY_PREFIX).await?);
+    objects.extend(r2.list_objects(OG_PROJECT_PREFIX).await?);
+
+    let referenced: HashSet<&str> = snapshot
+        .media
+        .iter()
+        .map(|m| m.r2_base_path.trim_end_matches('/'))
+        .collect();
+    let stored: HashSet<&str> = objects.iter().map(|o| o.key.as_str()).collect();
+
+    let mut scan = Scan::default();
+    scan.report.scanned = objects.len();
+
+    for object in &objects {
+        let Some(reason) = orphan_reason(&object.key, &referenced, snapshot) else {
+            continue;
+        };
+        if is_recent(object, now) {
+            scan.report.skipped_recent += 1;
+            continue;
+        }
+        scan.report.orphans.push(OrphanedObject {
+            key: object.key.clone(),
+            reason,
+        });
+    }
+
+    for media in &snapshot.media {
+        let base_path = media.r2_base_path.trim_end_matches('/');
+        let keys = expected_keys(media);
+        let missing: Vec<_> = keys
+            .iter()
+            .filter(|(_, key)| !stored.contains(format!("{base_path}/{key}").as_str()))
+            .collect();
+        if missing.is_empty() {
+            continue;
+        }
+
+        if missing.iter().any(|(name, _)| *name == "original") {
+            if media.status != MediaStatus::Failed {
+                scan.lost.push(media.id);
+            }
+        } else if media.media_type == MediaType::Image {
+            scan.reprocess.push(media.id);
+        }
+
+        scan.report
+            .missing
+            .extend(missing.into_iter().map(|(_, key)| MissingObject {
+                media_id: media.id,
+                key: format!("{base_path}/{key}"),
+            }));
+    }
+
+    Ok(scan)
+}
+
+/// Carry out a scan's fixes, returning its report with the action counters set.
+async fn apply_scan(state: &AppState, r2: &R2Client, scan: Scan) -> AppResult<StorageGcReport> {
+    let Scan {
+        mut report,
+        lost,
+        reprocess,
+    } = scan;
+    report.applied = true;
+
+    for orphan in &report.orphans {
+        match r2.delete_object(&orphan.key).await {
+            Ok(()) => report.deleted += 1,
+            Err(e) => {
+                tracing::warn!(key = %orphan.key, error = %e, "Failed to delete orphaned object");
+            }
+        }
+    }
+
+    for &id in &lost {
+        db::record_media_processing_error(
+            &state.pool,
+            id,
+            MediaStatus::Failed,
+            "Original is missing from storage",
+        )
+        .await?;
+        report.failed += 1;
+    }
+
+    for &id in &reprocess {
+        db::reset_media_processing(&state.pool, id).await?;
+        // Figures fall back to the original until the variants are rebuilt
+        handlers::content::refresh_figure_media(&state.pool, id).await?;
+        state.media_queue.enqueue(id);
+        report.requeued += 1;
+    }
+
+    for &id in lost.iter().chain(&reprocess) {
+        cache::invalidate_media(&state.isr_cache, &state.pool, id).await;
+    }
+
+    Ok(report)
+}
+
+/// Why `key` is an orphan, or `None` if something still owns it.
+fn orphan_reason(
+    key: &str,
+    referenced: &HashSet<&str>,
+    snapshot: &Snapshot,
+) -> Option<OrphanReason> {
+    if let Some(file) = key.strip_prefix(OG_PROJECT_PREFIX) {
+        let live = file
+            .strip_suffix(".png")
+            .and_then(|id| id.parse::<Uuid>().ok())
+            .is_some_and(|id| snapshot.project_ids.contains(&id));
+        return (!live).then_some(OrphanReason::StaleOgImage);
+    }
+
+    // Media objects live at `projects/{project_id}/{asset_id}/{file}` or, for
+    // assets uploaded straight to the library, `media/{asset_id}/{file}`
+    let (base_path, _) = key.rsplit_once('/')?;
+    if referenced.contains(base_path) || snapshot.active_uploads.contains(base_path) {
+        return None;
+    }
+    if base_path.starts_with(LIBRARY_PREFIX) {
+        return Some(OrphanReason::UnreferencedMedia);
+    }
+
+    let project = base_path
+        .strip_prefix(MEDIA_PREFIX)
+        .and_then(|rest| rest.split('/').next())
+        .and_then(|id| id.parse::<Uuid>().ok());
+    match project {
+        Some(id) if snapshot.project_ids.contains(&id) => Some(OrphanReason::UnreferencedMedia),
+        _ => Some(OrphanReason::DeletedProject),
+    }
+}
+
+/// Keys (relative to the base path) a media asset expects to exist, by variant name.
+///
+/// Assets still being processed only have their original so far.
+fn expected_keys(media: &DbMediaAsset) -> Vec<(&str, &str)> {
+    let Some(variants) = media.variants.as_object() else {
+        return Vec::new();
+    };
+    let in_flight = matches!(media.status, MediaStatus::Pending | MediaStatus::Processing);
+
+    variants
+        .iter()
+        .filter(|(name, _)| !in_flight || name.as_str() == "original")
+        .filter_map(|(name, variant)| {
+            variant
+                .get("key")
+                .and_then(|k| k.as_str())
+                .map(|key| (name.as_str(), key))
+        })
+        .collect()
+}
+
+/// Whether an object is too new to be judged (unknown age counts as old).
+fn is_recent(object: &ObjectInfo, now: SystemTime) -> bool {
+    object.last_modified.is_some_and(
+        |modified| !matches!(now.duration_since(modified), Ok(age) if age >= GRACE_PERIOD),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opendal::{Operator, services::Memory};
+    use std::collections::HashMap;
+
+    fn client() -> R2Client {
+        R2Client::from_operator(Operator::new(Memory::default()).unwrap().finish())
+    }
+
+    async fn put(r2: &R2Client, key: &str) {
+        r2.put_object(key, b"x".to_vec(), "application/octet-stream", None)
+            .await
+            .unwrap();
+    }
+
+    fn media(base_path: &str, status: MediaStatus, variants: serde_json::Value) -> DbMediaAsset {
+        DbMediaAsset {
+            id: Uuid::new_v4(),
+            media_type: MediaType::Image,
+            original_filename: "original.png".to_string(),
+            r2_base_path: base_path.to_string(),
+            variants,
+            size_bytes: 1,
+            blurhash: None,
+            metadata: None,
+            status,
+            processing_error: None,
+            created_at: time::OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    fn ready_variants() -> serde_json::Value {
+        serde_json::json!({
+            "thumb": { "key": "thumb.webp" },
+            "medium": { "key": "medium.webp" },
+            "full": { "key": "full.webp" },
+            "original": { "key": "original.png" },
+        })
+    }
+
+    fn reasons(scan: &Scan) -> HashMap<&str, OrphanReason> {
+        scan.report
+            .orphans
+            .iter()
+            .map(|o| (o.key.as_str(), o.reason))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_scan_finds_orphans() {
+        let r2 = client();
+        let project = Uuid::new_v4();
+        let deleted_project = Uuid::new_v4();
+        let kept = format!("projects/{project}/kept");
+
+        for file in ["thumb.webp", "medium.webp", "full.webp", "original.png"] {
+            put(&r2, &format!("{kept}/{file}")).await;
+        }
+        put(&r2, &format!("projects/{project}/stray/original.png")).await;
+        put(&r2, &format!("projects/{deleted_project}/old/original.png")).await;
+        put(&r2, &format!("projects/{project}/uploading/original.mp4")).await;
+        put(&r2, "media/shared/original.png").await;
+        put(&r2, "media/dropped/original.png").await;
+        put(&r2, &format!("og/project/{project}.png")).await;
+        put(&r2, &format!("og/project/{deleted_project}.png")).await;
+        put(&r2, "og/index.png").await;
+
+        let snapshot = Snapshot {
+            media: vec![
+                media(&kept, MediaStatus::Ready, ready_variants()),
+                media("media/shared", MediaStatus::Pending, ready_variants()),
+            ],
+            project_ids: HashSet::from([project]),
+            active_uploads: HashSet::from([format!("projects/{project}/uploading")]),
+        };
+
+        let scan = scan(&r2, &snapshot, SystemTime::now()).await.unwrap();
+        let stray = format!("projects/{project}/stray/original.png");
+        let old = format!("projects/{deleted_project}/old/original.png");
+        let og = format!("og/project/{deleted_project}.png");
+
+        assert_eq!(scan.report.scanned, 11);
+        assert_eq!(
+            reasons(&scan),
+            HashMap::from([
+                (stray.as_str(), OrphanReason::UnreferencedMedia),
+                (
+                    "media/dropped/original.png",
+                    OrphanReason::UnreferencedMedia
+                ),
+                (old.as_str(), OrphanReason::DeletedProject),
+                (og.as_str(), OrphanReason::StaleOgImage),
+            ])
+        );
+        assert!(scan.report.missing.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_finds_missing_variants() {
+        let r2 = client();
+        let project = Uuid::new_v4();
+        let partial = format!("projects/{project}/partial");
+        let lost = format!("projects/{project}/lost");
+        let queued = format!("projects/{project}/queued");
+
+        put(&r2, &format!("{partial}/original.png")).await;
+        put(&r2, &format!("{partial}/thumb.webp")).await;
+        put(&r2, &format!("{lost}/thumb.webp")).await;
+        put(&r2, &format!("{queued}/original.png")).await;
+
+        let partial_media = media(&partial, MediaStatus::Ready, ready_variants());
+        let lost_media = media(&lost, MediaStatus::Ready, ready_variants());
+        // Only the original is expected while processing is pending
+        let queued_media = media(&queued, MediaStatus::Pending, ready_variants());
+
+        let snapshot = Snapshot {
+            media: vec![partial_media.clone(), lost_media.clone(), queued_media],
+            project_ids: HashSet::from([project]),
+            active_uploads: HashSet::new(),
+        };
+
+        let scan = scan(&r2, &snapshot, SystemTime::now()).await.unwrap();
+
+        assert!(scan.report.orphans.is_empty());
+        assert_eq!(scan.reprocess, vec![partial_media.id]);
+        assert_eq!(scan.lost, vec![lost_media.id]);
+        let missing: HashSet<_> = scan.report.missing.iter().map(|m| m.key.as_str()).collect();
+        assert_eq!(missing.len(), 5);
+        assert!(missing.contains(format!("{lost}/original.png").as_str()));
+        assert!(missing.contains(format!("{partial}/full.webp").as_str()));
+    }
+
+    #[test]
+    fn test_recent_objects_are_spared() {
+        let now = SystemTime::now();
+        let object = |age: Option<Duration>| ObjectInfo {
+            key: "projects/a/b/original.png".to_string(),
+            last_modified: age.map(|age| now - age),
+        };
+
+        assert!(is_recent(&object(Some(Duration::from_mins(1))), now));
+        assert!(!is_recent(&object(Some(GRACE_PERIOD * 2)), now));
+        assert!(!is_recent(&object(None), now));
+    }
+}
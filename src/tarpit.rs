@@ -1,7 +1,7 @@
 use axum::{
     body::Body,
     extract::{ConnectInfo, Request, State},
-    http::{HeaderMap, StatusCode},
+    http::{Extensions, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
 use dashmap::DashMap;
@@ -187,6 +187,17 @@ pub fn is_malicious_path(path: &str) -> bool {
     false
 }
 
+/// Client address of a request from its headers and the connection info the
+/// TCP listener attaches (absent on the Unix socket listener).
+pub fn request_client_ip(headers: &HeaderMap, extensions: &Extensions) -> IpAddr {
+    extract_client_ip(
+        headers,
+        extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr),
+    )
+}
+
 pub fn extract_client_ip(headers: &HeaderMap, peer_addr: Option<SocketAddr>) -> IpAddr {
     // Check X-Real-IP first (Railway sets this)
     if let Some(real_ip) = headers.get("x-real-ip")
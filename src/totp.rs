@@ -0,0 +1,203 @@
+//! Time-based one-time passwords (RFC 6238) for admin two-factor login.
+//!
+//! Codes are six-digit HMAC-SHA1 HOTP values (RFC 4226) over 30-second steps,
+//! the parameters every authenticator app defaults to. Secrets are handed to
+//! the app as an `otpauth://` URI and stored base32-encoded. Recovery codes are
+//! random one-time strings; only their SHA-256 is persisted (see
+//! [`crate::auth::hash_token`]).
+
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, KeyInit, Mac};
+use sha1::Sha1;
+
+/// Seconds per time step
+const STEP_SECS: u64 = 30;
+const DIGITS: u32 = 6;
+/// Steps either side of the current one that are accepted, for clock drift
+const SKEW_STEPS: u64 = 1;
+/// 160-bit secrets, the HMAC-SHA1 block-size recommendation of RFC 4226
+const SECRET_LEN: usize = 20;
+const ISSUER: &str = "xevion.dev";
+
+/// Number of recovery codes issued per enrollment
+pub const RECOVERY_CODE_COUNT: usize = 10;
+/// Unambiguous alphabet for recovery codes (no 0/O/1/I/L)
+const RECOVERY_ALPHABET: [char; 31] = [
+    '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'M',
+    'N', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+];
+const RECOVERY_CODE_LEN: usize = 12;
+
+/// Fresh random secret, base32-encoded for storage and authenticator apps
+pub fn generate_secret() -> String {
+    let secret: [u8; SECRET_LEN] = rand::random();
+    BASE32_NOPAD.encode(&secret)
+}
+
+/// `otpauth://` URI for enrolling `secret` in an authenticator app (usually
+/// rendered as a QR code)
+pub fn otpauth_uri(account: &str, secret: &str) -> String {
+    let label = urlencoding::encode(&format!("{ISSUER}:{account}")).into_owned();
+    format!(
+        "otpauth://totp/{label}?secret={secret}&issuer={}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECS}",
+        urlencoding::encode(ISSUER)
+    )
+}
+
+/// Time step containing `unix_secs`
+pub const fn step_at(unix_secs: u64) -> u64 {
+    unix_secs / STEP_SECS
+}
+
+/// HOTP value of `secret` for counter `step` (RFC 4226 §5.3)
+fn hotp(secret: &[u8], step: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+    let truncated = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    truncated % 10u32.pow(DIGITS)
+}
+
+/// Code for base32 `secret` at `step`, zero-padded to six digits
+pub fn code_at(secret: &str, step: u64) -> Option<String> {
+    let secret = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    Some(format!(
+        "{:0width$}",
+        hotp(&secret, step),
+        width = DIGITS as usize
+    ))
+}
+
+/// Check `code` against base32 `secret` around `unix_secs`. Returns the
+/// matching step, which must be later than `last_step` so a code can't be
+/// replayed.
+pub fn verify(secret: &str, code: &str, unix_secs: u64, last_step: Option<u64>) -> Option<u64> {
+    let code = code.trim().replace(' ', "");
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let secret = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let code: u32 = code.parse().ok()?;
+
+    let current = step_at(unix_secs);
+    (current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS)
+        .filter(|step| last_step.is_none_or(|last| *step > last))
+        .find(|step| hotp(&secret, *step) == code)
+}
+
+/// Whether `code` looks like a TOTP code rather than a recovery code
+pub fn is_totp_code(code: &str) -> bool {
+    let code = code.trim().replace(' ', "");
+    code.len() == DIGITS as usize && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Fresh recovery codes, formatted `XXXX-XXXX-XXXX`
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..RECOVERY_CODE_LEN)
+                .map(|_| RECOVERY_ALPHABET[rand::random_range(0..RECOVERY_ALPHABET.len())])
+                .collect();
+            format!("{}-{}-{}", &chars[..4], &chars[4..8], &chars[8..])
+        })
+        .collect()
+}
+
+/// Canonical form of a recovery code for hashing: uppercase, no separators
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The RFC 6238 appendix B SHA-1 secret, "12345678901234567890"
+    fn rfc_secret() -> String {
+        BASE32_NOPAD.encode(b"12345678901234567890")
+    }
+
+    #[test]
+    fn matches_rfc_6238_vectors() {
+        // Appendix B lists 8-digit codes; these are their last six digits
+        for (time, code) in [
+            (59, "287082"),
+            (1_111_111_109, "081804"),
+            (1_111_111_111, "050471"),
+            (1_234_567_890, "005924"),
+            (2_000_000_000, "279037"),
+            (20_000_000_000, "353130"),
+        ] {
+            assert_eq!(code_at(&rfc_secret(), step_at(time)).as_deref(), Some(code));
+        }
+    }
+
+    #[test]
+    fn verify_accepts_adjacent_steps_and_rejects_replays() {
+        let secret = rfc_secret();
+        let now = 1_111_111_111;
+        let step = step_at(now);
+
+        let previous = code_at(&secret, step - 1).unwrap();
+        assert_eq!(verify(&secret, &previous, now, None), Some(step - 1));
+        let current = code_at(&secret, step).unwrap();
+        assert_eq!(verify(&secret, &current, now, None), Some(step));
+        assert_eq!(
+            verify(
+                &secret,
+                &format!(" {} {}", &current[..3], &current[3..]),
+                now,
+                None
+            ),
+            Some(step)
+        );
+
+        // Already used: neither it nor anything before it is accepted again
+        assert_eq!(verify(&secret, &current, now, Some(step)), None);
+        assert_eq!(verify(&secret, &previous, now, Some(step - 1)), None);
+
+        let stale = code_at(&secret, step - 2).unwrap();
+        assert_eq!(verify(&secret, &stale, now, None), None);
+        assert_eq!(verify(&secret, "12345", now, None), None);
+        assert_eq!(verify(&secret, "abcdef", now, None), None);
+    }
+
+    #[test]
+    fn generated_secrets_are_base32_and_unique() {
+        let a = generate_secret();
+        assert_eq!(BASE32_NOPAD.decode(a.as_bytes()).unwrap().len(), SECRET_LEN);
+        assert_ne!(a, generate_secret());
+    }
+
+    #[test]
+    fn otpauth_uri_names_issuer_and_account() {
+        let uri = otpauth_uri("admin", "JBSWY3DPEHPK3PXP");
+        assert!(uri.starts_with("otpauth://totp/xevion.dev%3Aadmin?secret=JBSWY3DPEHPK3PXP&"));
+        assert!(uri.contains("issuer=xevion.dev"));
+        assert!(uri.contains("digits=6&period=30"));
+    }
+
+    #[test]
+    fn recovery_codes_normalize_for_lookup() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        let code = &codes[0];
+        assert_eq!(code.len(), 14);
+        assert!(!is_totp_code(code));
+        assert_eq!(
+            normalize_recovery_code(&code.to_lowercase().replace('-', " ")),
+            normalize_recovery_code(code)
+        );
+        assert!(is_totp_code("123 456"));
+    }
+}
@@ -133,10 +133,116 @@ pub fn validate_hex_color(color: &str) -> bool {
     color.len() == 6 && color.chars().all(|c| c.is_ascii_hexdigit())
 }
 
+/// Outcome of [`parse_byte_range`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ByteRange {
+    /// No usable single range: serve the whole object
+    Full,
+    /// Serve this slice with `206 Partial Content`
+    Partial(std::ops::Range<u64>),
+    /// Respond `416 Range Not Satisfiable`
+    Unsatisfiable,
+}
+
+/// Parse a single-range `Range: bytes=...` header against an object of `size` bytes.
+///
+/// Multi-range and malformed headers are ignored, as RFC 9110 permits.
+pub fn parse_byte_range(value: Option<&str>, size: u64) -> ByteRange {
+    let Some(spec) = value.and_then(|v| v.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    let range = match (start.trim(), end.trim()) {
+        ("", "") => return ByteRange::Full,
+        // Suffix range: the last N bytes
+        ("", suffix) => {
+            let Ok(len) = suffix.parse::<u64>() else {
+                return ByteRange::Full;
+            };
+            if len == 0 {
+                return ByteRange::Unsatisfiable;
+            }
+            size.saturating_sub(len)..size
+        }
+        (start, end) => {
+            let Ok(start) = start.parse::<u64>() else {
+                return ByteRange::Full;
+            };
+            let end = if end.is_empty() {
+                size
+            } else {
+                let Ok(end) = end.parse::<u64>() else {
+                    return ByteRange::Full;
+                };
+                if end < start {
+                    return ByteRange::Full;
+                }
+                end.saturating_add(1).min(size)
+            };
+            start..end
+        }
+    };
+
+    if range.start >= size {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(range)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(parse_byte_range(None, 100), ByteRange::Full);
+        assert_eq!(
+            parse_byte_range(Some("bytes=0-9"), 100),
+            ByteRange::Partial(0..10)
+        );
+        assert_eq!(
+            parse_byte_range(Some("bytes=90-"), 100),
+            ByteRange::Partial(90..100)
+        );
+        assert_eq!(
+            parse_byte_range(Some("bytes=-10"), 100),
+            ByteRange::Partial(90..100)
+        );
+        assert_eq!(
+            parse_byte_range(Some("bytes=50-500"), 100),
+            ByteRange::Partial(50..100)
+        );
+        assert_eq!(
+            parse_byte_range(Some("bytes=-500"), 100),
+            ByteRange::Partial(0..100)
+        );
+    }
+
+    #[test]
+    fn test_parse_byte_range_unsatisfiable_or_ignored() {
+        assert_eq!(
+            parse_byte_range(Some("bytes=100-"), 100),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(
+            parse_byte_range(Some("bytes=-0"), 100),
+            ByteRange::Unsatisfiable
+        );
+        // Multi-range and malformed headers fall back to the full body
+        assert_eq!(
+            parse_byte_range(Some("bytes=0-1,5-6"), 100),
+            ByteRange::Full
+        );
+        assert_eq!(parse_byte_range(Some("bytes=9-2"), 100), ByteRange::Full);
+        assert_eq!(parse_byte_range(Some("items=0-1"), 100), ByteRange::Full);
+    }
+
     /// Root-level shipped files served by a dedicated route rather than the
     /// generic `is_static_asset` embedded-serving path (see `routes.rs`).
     const ROUTE_SERVED: &[&str] = &["keybase.txt", "publickey.asc"];
@@ -0,0 +1,756 @@
+//! `WebAuthn` relying party for passkey login to the admin panel.
+//!
+//! Covers the two ceremonies of `WebAuthn` Level 2: registration (§7.1) stores a
+//! new public key for a signed-in admin, and authentication (§7.2) signs them
+//! in with it. Options and responses use the spec's JSON encoding (binary
+//! fields as unpadded base64url), so the browser side is a thin wrapper around
+//! `navigator.credentials`.
+//!
+//! Attestation is not requested (`"none"`): the admin registering a key is
+//! already signed in, so which authenticator model holds it doesn't matter and
+//! attestation statements are ignored. User verification is required in both
+//! ceremonies, which makes a passkey a multi-factor credential on its own.
+//!
+//! Challenges are random, single-use, and held in memory until they expire,
+//! like pending CLI device requests. Anyone can ask for login options, so a
+//! flood of them only pushes out the oldest login challenges and never refuses
+//! new ones.
+
+use ciborium::Value;
+use dashmap::DashMap;
+use data_encoding::BASE64URL_NOPAD;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+/// How long a ceremony's challenge stays valid
+const CEREMONY_TTL: Duration = Duration::from_mins(5);
+/// Cap on outstanding login challenges. Login options are unauthenticated, so
+/// past this the oldest is dropped; registration challenges don't count.
+const MAX_PENDING_LOGINS: usize = 1024;
+const CHALLENGE_LEN: usize = 32;
+
+/// COSE algorithm identifiers (RFC 9053)
+pub const ES256: i32 = -7;
+pub const EDDSA: i32 = -8;
+pub const RS256: i32 = -257;
+
+/// Authenticator data flags (`WebAuthn` §6.1)
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+#[derive(Debug, Clone)]
+pub struct WebauthnConfig {
+    /// Relying party ID: the registrable domain passkeys are scoped to
+    pub rp_id: String,
+    /// Name authenticators show next to the passkey
+    pub rp_name: String,
+    /// Exact origins ceremonies may come from. Empty accepts `https://` on the
+    /// RP ID and its subdomains (plus `http://` for `localhost`).
+    pub origins: Vec<String>,
+}
+
+impl WebauthnConfig {
+    /// Load from `WEBAUTHN_RP_ID` (default: `canonical_host`),
+    /// `WEBAUTHN_RP_NAME`, and comma-separated `WEBAUTHN_ORIGINS`.
+    pub fn from_env(canonical_host: &str) -> Self {
+        let rp_id = std::env::var("WEBAUTHN_RP_ID")
+            .ok()
+            .map(|v| v.trim().to_ascii_lowercase())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| canonical_host.to_string());
+        let rp_name = std::env::var("WEBAUTHN_RP_NAME")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| rp_id.clone());
+        let origins = std::env::var("WEBAUTHN_ORIGINS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|origin| origin.trim().trim_end_matches('/').to_string())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        Self {
+            rp_id,
+            rp_name,
+            origins,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WebauthnError {
+    #[error("Unknown or expired challenge")]
+    Challenge,
+    #[error("Unexpected client data type")]
+    ClientDataType,
+    #[error("Origin {0} is not allowed")]
+    Origin(String),
+    #[error("Malformed {0}")]
+    Malformed(&'static str),
+    #[error("Relying party ID mismatch")]
+    RpId,
+    #[error("User presence was not confirmed")]
+    UserPresence,
+    #[error("User was not verified")]
+    UserVerification,
+    #[error("Unsupported public key")]
+    UnsupportedKey,
+    #[error("Credential does not belong to this user")]
+    UserHandle,
+    #[error("Signature verification failed")]
+    Signature,
+    #[error("Signature counter did not increase; the authenticator may be cloned")]
+    Counter,
+}
+
+/// Unpadded base64url, `WebAuthn`'s JSON encoding for binary values
+pub fn encode(bytes: &[u8]) -> String {
+    BASE64URL_NOPAD.encode(bytes)
+}
+
+pub fn decode(value: &str) -> Result<Vec<u8>, WebauthnError> {
+    BASE64URL_NOPAD
+        .decode(value.trim_end_matches('=').as_bytes())
+        .map_err(|_| WebauthnError::Malformed("base64url"))
+}
+
+/// Opaque `WebAuthn` user handle of an admin user
+pub fn user_handle(user_id: i32) -> Vec<u8> {
+    user_id.to_be_bytes().to_vec()
+}
+
+/// `PublicKeyCredentialCreationOptionsJSON`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PasskeyCreationOptions {
+    pub challenge: String,
+    pub rp: PasskeyRelyingParty,
+    pub user: PasskeyUser,
+    pub pub_key_cred_params: Vec<PasskeyCredentialParameters>,
+    /// Milliseconds.
+    pub timeout: u32,
+    pub exclude_credentials: Vec<PasskeyCredentialDescriptor>,
+    pub authenticator_selection: PasskeyAuthenticatorSelection,
+    pub attestation: String,
+}
+
+/// `PublicKeyCredentialRequestOptionsJSON`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PasskeyRequestOptions {
+    pub challenge: String,
+    /// Milliseconds.
+    pub timeout: u32,
+    pub rp_id: String,
+    /// Empty: the authenticator offers whichever passkeys it holds for the RP.
+    pub allow_credentials: Vec<PasskeyCredentialDescriptor>,
+    pub user_verification: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PasskeyRelyingParty {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PasskeyUser {
+    /// Base64url user handle.
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PasskeyCredentialParameters {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub alg: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PasskeyCredentialDescriptor {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Base64url credential ID.
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PasskeyAuthenticatorSelection {
+    pub resident_key: String,
+    pub user_verification: String,
+}
+
+/// `RegistrationResponseJSON`, as produced by `PublicKeyCredential.toJSON()`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistrationCredential {
+    pub id: String,
+    pub response: AttestationResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+}
+
+/// `AuthenticationResponseJSON`, as produced by `PublicKeyCredential.toJSON()`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticationCredential {
+    pub id: String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_handle: Option<String>,
+}
+
+/// `CollectedClientData` (`WebAuthn` §5.8.1)
+#[derive(Debug, Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+    origin: String,
+    #[serde(rename = "crossOrigin", default)]
+    cross_origin: bool,
+}
+
+/// A verified registration, ready to be stored
+#[derive(Debug, Clone)]
+pub struct NewCredential {
+    pub user_id: i32,
+    pub credential_id: Vec<u8>,
+    /// The credential public key as a `COSE_Key`
+    pub public_key: Vec<u8>,
+    pub algorithm: i32,
+    pub sign_count: u32,
+}
+
+/// A registered credential, as loaded for authentication
+#[derive(Debug, Clone)]
+pub struct StoredCredential {
+    pub user_id: i32,
+    pub credential_id: Vec<u8>,
+    pub public_key: Vec<u8>,
+    pub algorithm: i32,
+    pub sign_count: u32,
+}
+
+impl From<NewCredential> for StoredCredential {
+    fn from(credential: NewCredential) -> Self {
+        Self {
+            user_id: credential.user_id,
+            credential_id: credential.credential_id,
+            public_key: credential.public_key,
+            algorithm: credential.algorithm,
+            sign_count: credential.sign_count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ceremony {
+    Registration { user_id: i32 },
+    Authentication,
+}
+
+#[derive(Debug)]
+struct Pending {
+    ceremony: Ceremony,
+    expires_at: Instant,
+}
+
+#[derive(Debug)]
+pub struct Webauthn {
+    pub config: WebauthnConfig,
+    rp_id_hash: [u8; 32],
+    /// Outstanding challenges (base64url) and the ceremony each belongs to
+    pending: DashMap<String, Pending>,
+}
+
+impl Webauthn {
+    pub fn new(config: WebauthnConfig) -> Self {
+        Self {
+            rp_id_hash: Sha256::digest(config.rp_id.as_bytes()).into(),
+            config,
+            pending: DashMap::new(),
+        }
+    }
+
+    /// Options for registering a new passkey for `user_id`. `existing` are the
+    /// IDs of its registered credentials, which authenticators skip.
+    pub fn start_registration(
+        &self,
+        user_id: i32,
+        username: &str,
+        existing: &[Vec<u8>],
+    ) -> PasskeyCreationOptions {
+        let challenge = self.issue(Ceremony::Registration { user_id });
+        PasskeyCreationOptions {
+            challenge,
+            rp: PasskeyRelyingParty {
+                id: self.config.rp_id.clone(),
+                name: self.config.rp_name.clone(),
+            },
+            user: PasskeyUser {
+                id: encode(&user_handle(user_id)),
+                name: username.to_string(),
+                display_name: username.to_string(),
+            },
+            pub_key_cred_params: [ES256, EDDSA, RS256]
+                .into_iter()
+                .map(|alg| PasskeyCredentialParameters {
+                    kind: "public-key".to_string(),
+                    alg,
+                })
+                .collect(),
+            timeout: CEREMONY_TTL.as_millis() as u32,
+            exclude_credentials: existing.iter().map(|id| descriptor(id)).collect(),
+            authenticator_selection: PasskeyAuthenticatorSelection {
+                resident_key: "required".to_string(),
+                user_verification: "required".to_string(),
+            },
+            attestation: "none".to_string(),
+        }
+    }
+
+    /// Verify a registration response (`WebAuthn` §7.1)
+    pub fn finish_registration(
+        &self,
+        credential: &RegistrationCredential,
+    ) -> Result<NewCredential, WebauthnError> {
+        let client_data_json = decode(&credential.response.client_data_json)?;
+        let Ceremony::Registration { user_id } =
+            self.verify_client_data(&client_data_json, "webauthn.create")?
+        else {
+            return Err(WebauthnError::ClientDataType);
+        };
+
+        let attestation_object = decode(&credential.response.attestation_object)?;
+        let auth_data = authenticator_data_of(&attestation_object)?;
+        let parsed = self.verify_authenticator_data(&auth_data)?;
+        let attested = parsed
+            .attested
+            .ok_or(WebauthnError::Malformed("attested credential data"))?;
+        if attested.credential_id != decode(&credential.id)? {
+            return Err(WebauthnError::Malformed("credential ID"));
+        }
+        let algorithm = PublicKey::from_cose(&attested.public_key)?.algorithm();
+
+        Ok(NewCredential {
+            user_id,
+            credential_id: attested.credential_id,
+            public_key: attested.public_key,
+            algorithm,
+            sign_count: parsed.sign_count,
+        })
+    }
+
+    /// Options for signing in with any passkey registered for this RP
+    pub fn start_authentication(&self) -> PasskeyRequestOptions {
+        let challenge = self.issue(Ceremony::Authentication);
+        PasskeyRequestOptions {
+            challenge,
+            timeout: CEREMONY_TTL.as_millis() as u32,
+            rp_id: self.config.rp_id.clone(),
+            allow_credentials: Vec::new(),
+            user_verification: "required".to_string(),
+        }
+    }
+
+    /// Verify an authentication response against the credential it names
+    /// (`WebAuthn` §7.2). Returns the new signature counter to store.
+    pub fn finish_authentication(
+        &self,
+        credential: &AuthenticationCredential,
+        stored: &StoredCredential,
+    ) -> Result<u32, WebauthnError> {
+        let client_data_json = decode(&credential.response.client_data_json)?;
+        if self.verify_client_data(&client_data_json, "webauthn.get")? != Ceremony::Authentication {
+            return Err(WebauthnError::ClientDataType);
+        }
+        if decode(&credential.id)? != stored.credential_id {
+            return Err(WebauthnError::Malformed("credential ID"));
+        }
+        if let Some(handle) = &credential.response.user_handle
+            && decode(handle)? != user_handle(stored.user_id)
+        {
+            return Err(WebauthnError::UserHandle);
+        }
+
+        let mut signed = decode(&credential.response.authenticator_data)?;
+        let parsed = self.verify_authenticator_data(&signed)?;
+        signed.extend_from_slice(&Sha256::digest(&client_data_json));
+        let signature = decode(&credential.response.signature)?;
+        let public_key = PublicKey::from_cose(&stored.public_key)?;
+        if public_key.algorithm() != stored.algorithm {
+            return Err(WebauthnError::UnsupportedKey);
+        }
+        public_key.verify(&signed, &signature)?;
+
+        // Authenticators without a counter always report zero
+        if (parsed.sign_count != 0 || stored.sign_count != 0)
+            && parsed.sign_count <= stored.sign_count
+        {
+            return Err(WebauthnError::Counter);
+        }
+        Ok(parsed.sign_count)
+    }
+
+    fn issue(&self, ceremony: Ceremony) -> String {
+        let now = Instant::now();
+        if ceremony == Ceremony::Authentication {
+            self.make_room_for_login(now);
+        }
+        let challenge: [u8; CHALLENGE_LEN] = rand::random();
+        let challenge = encode(&challenge);
+        self.pending.insert(
+            challenge.clone(),
+            Pending {
+                ceremony,
+                expires_at: now + CEREMONY_TTL,
+            },
+        );
+        challenge
+    }
+
+    /// Keep login challenges below [`MAX_PENDING_LOGINS`] before issuing
+    /// another: drop the expired ones, then the oldest.
+    fn make_room_for_login(&self, now: Instant) {
+        if self.pending.len() < MAX_PENDING_LOGINS {
+            return;
+        }
+        self.pending.retain(|_, pending| pending.expires_at > now);
+        let mut logins: Vec<(Instant, String)> = self
+            .pending
+            .iter()
+            .filter(|entry| entry.ceremony == Ceremony::Authentication)
+            .map(|entry| (entry.expires_at, entry.key().clone()))
+            .collect();
+        if logins.len() < MAX_PENDING_LOGINS {
+            return;
+        }
+        // Every challenge lives equally long, so the earliest expiry is the oldest
+        logins.sort_unstable();
+        for (_, challenge) in &logins[..=logins.len() - MAX_PENDING_LOGINS] {
+            self.pending.remove(challenge);
+        }
+    }
+
+    /// Check client data and consume its challenge, returning the ceremony the
+    /// challenge was issued for. The challenge is spent even if a later check
+    /// fails, so each one is tried at most once.
+    fn verify_client_data(&self, json: &[u8], kind: &str) -> Result<Ceremony, WebauthnError> {
+        let client: ClientData =
+            serde_json::from_slice(json).map_err(|_| WebauthnError::Malformed("clientDataJSON"))?;
+        let (_, pending) = self
+            .pending
+            .remove(&client.challenge)
+            .ok_or(WebauthnError::Challenge)?;
+        if pending.expires_at <= Instant::now() {
+            return Err(WebauthnError::Challenge);
+        }
+        if client.kind != kind {
+            return Err(WebauthnError::ClientDataType);
+        }
+        if client.cross_origin || !self.origin_allowed(&client.origin) {
+            return Err(WebauthnError::Origin(client.origin));
+        }
+        Ok(pending.ceremony)
+    }
+
+    fn origin_allowed(&self, origin: &str) -> bool {
+        if !self.config.origins.is_empty() {
+            return self.config.origins.iter().any(|allowed| allowed == origin);
+        }
+        let Some((scheme, authority)) = origin.split_once("://") else {
+            return false;
+        };
+        let host = match authority.rsplit_once(':') {
+            Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => host,
+            _ => authority,
+        };
+        let rp_id = &self.config.rp_id;
+        let host_matches = host == rp_id
+            || host
+                .strip_suffix(rp_id.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'));
+        host_matches && (scheme == "https" || (scheme == "http" && rp_id == "localhost"))
+    }
+
+    fn verify_authenticator_data(&self, data: &[u8]) -> Result<AuthenticatorData, WebauthnError> {
+        let parsed = AuthenticatorData::parse(data)?;
+        if parsed.rp_id_hash != self.rp_id_hash {
+            return Err(WebauthnError::RpId);
+        }
+        if parsed.flags & FLAG_USER_PRESENT == 0 {
+            return Err(WebauthnError::UserPresence);
+        }
+        if parsed.flags & FLAG_USER_VERIFIED == 0 {
+            return Err(WebauthnError::UserVerification);
+        }
+        Ok(parsed)
+    }
+}
+
+fn descriptor(credential_id: &[u8]) -> PasskeyCredentialDescriptor {
+    PasskeyCredentialDescriptor {
+        kind: "public-key".to_string(),
+        id: encode(credential_id),
+    }
+}
+
+/// `authData` of a CBOR attestation object (`WebAuthn` §6.5)
+fn authenticator_data_of(attestation_object: &[u8]) -> Result<Vec<u8>, WebauthnError> {
+    let malformed = || WebauthnError::Malformed("attestation object");
+    let Value::Map(entries) = ciborium::from_reader(attestation_object).map_err(|_| malformed())?
+    else {
+        return Err(malformed());
+    };
+    entries
+        .into_iter()
+        .find_map(|(key, value)| match (key, value) {
+            (Value::Text(key), Value::Bytes(data)) if key == "authData" => Some(data),
+            _ => None,
+        })
+        .ok_or_else(malformed)
+}
+
+/// Authenticator data (`WebAuthn` §6.1)
+struct AuthenticatorData {
+    rp_id_hash: [u8; 32],
+    flags: u8,
+    sign_count: u32,
+    attested: Option<AttestedCredential>,
+}
+
+struct AttestedCredential {
+    credential_id: Vec<u8>,
+    /// `COSE_Key` bytes, exactly as the authenticator encoded them
+    public_key: Vec<u8>,
+}
+
+impl AuthenticatorData {
+    fn parse(data: &[u8]) -> Result<Self, WebauthnError> {
+        let malformed = || WebauthnError::Malformed("authenticator data");
+        if data.len() < 37 {
+            return Err(malformed());
+        }
+        let rp_id_hash: [u8; 32] = data[..32].try_into().map_err(|_| malformed())?;
+        let flags = data[32];
+        let sign_count = u32::from_be_bytes(data[33..37].try_into().map_err(|_| malformed())?);
+
+        let attested = if flags & FLAG_ATTESTED_CREDENTIAL == 0 {
+            None
+        } else {
+            // AAGUID (16 bytes), then a big-endian u16 credential ID length
+            let rest = data.get(37..).ok_or_else(malformed)?;
+            let id_len = usize::from(u16::from_be_bytes(
+                rest.get(16..18)
+                    .ok_or_else(malformed)?
+                    .try_into()
+                    .map_err(|_| malformed())?,
+            ));
+            let credential_id = rest.get(18..18 + id_len).ok_or_else(malformed)?.to_vec();
+            let key_bytes = &rest[18 + id_len..];
+            // The COSE key is followed by extensions, if any; read exactly one
+            // CBOR item to find where it ends
+            let mut reader = key_bytes;
+            let _: Value = ciborium::from_reader(&mut reader).map_err(|_| malformed())?;
+            let key_len = key_bytes.len() - reader.len();
+            Some(AttestedCredential {
+                credential_id,
+                public_key: key_bytes[..key_len].to_vec(),
+            })
+        };
+
+        Ok(Self {
+            rp_id_hash,
+            flags,
+            sign_count,
+            attested,
+        })
+    }
+}
+
+/// A credential public key decoded from its `COSE_Key` (RFC 9052 §7)
+enum PublicKey {
+    /// Uncompressed SEC1 point
+    P256(Vec<u8>),
+    Ed25519(Vec<u8>),
+    Rsa {
+        n: Vec<u8>,
+        e: Vec<u8>,
+    },
+}
+
+impl PublicKey {
+    fn from_cose(bytes: &[u8]) -> Result<Self, WebauthnError> {
+        let unsupported = WebauthnError::UnsupportedKey;
+        let Value::Map(entries) = ciborium::from_reader(bytes).map_err(|_| unsupported.clone())?
+        else {
+            return Err(unsupported);
+        };
+        let int = |label: i64| {
+            entries.iter().find_map(|(key, value)| match (key, value) {
+                (Value::Integer(key), Value::Integer(value))
+                    if i128::from(*key) == i128::from(label) =>
+                {
+                    Some(i128::from(*value))
+                }
+                _ => None,
+            })
+        };
+        let bytes = |label: i64| {
+            entries.iter().find_map(|(key, value)| match (key, value) {
+                (Value::Integer(key), Value::Bytes(value))
+                    if i128::from(*key) == i128::from(label) =>
+                {
+                    Some(value.clone())
+                }
+                _ => None,
+            })
+        };
+
+        // kty (1), alg (3), and the per-type parameters (-1, -2, -3)
+        match (int(1), int(3)) {
+            (Some(2), Some(alg)) if alg == i128::from(ES256) => {
+                let (Some(1), Some(x), Some(y)) = (int(-1), bytes(-2), bytes(-3)) else {
+                    return Err(unsupported);
+                };
+                if x.len() != 32 || y.len() != 32 {
+                    return Err(unsupported);
+                }
+                let mut point = Vec::with_capacity(65);
+                point.push(0x04);
+                point.extend_from_slice(&x);
+                point.extend_from_slice(&y);
+                Ok(Self::P256(point))
+            }
+            (Some(1), Some(alg)) if alg == i128::from(EDDSA) => match (int(-1), bytes(-2)) {
+                (Some(6), Some(x)) if x.len() == 32 => Ok(Self::Ed25519(x)),
+                _ => Err(unsupported),
+            },
+            (Some(3), Some(alg)) if alg == i128::from(RS256) => match (bytes(-1), bytes(-2)) {
+                (Some(n), Some(e)) => Ok(Self::Rsa { n, e }),
+                _ => Err(unsupported),
+            },
+            _ => Err(unsupported),
+        }
+    }
+
+    const fn algorithm(&self) -> i32 {
+        match self {
+            Self::P256(_) => ES256,
+            Self::Ed25519(_) => EDDSA,
+            Self::Rsa { .. } => RS256,
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), WebauthnError> {
+        match self {
+            Self::P256(point) => UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_ASN1, point)
+                .verify(message, signature),
+            Self::Ed25519(key) => {
+                UnparsedPublicKey::new(&signature::ED25519, key).verify(message, signature)
+            }
+            Self::Rsa { n, e } => RsaPublicKeyComponents { n, e }.verify(
+                &signature::RSA_PKCS1_2048_8192_SHA256,
+                message,
+                signature,
+            ),
+        }
+        .map_err(|_| WebauthnError::Signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relying_party(rp_id: &str, origins: &[&str]) -> Webauthn {
+        Webauthn::new(WebauthnConfig {
+            rp_id: rp_id.to_string(),
+            rp_name: rp_id.to_string(),
+            origins: origins.iter().map(ToString::to_string).collect(),
+        })
+    }
+
+    #[test]
+    fn derives_allowed_origins_from_rp_id() {
+        let rp = relying_party("xevion.dev", &[]);
+        assert!(rp.origin_allowed("https://xevion.dev"));
+        assert!(rp.origin_allowed("https://www.xevion.dev"));
+        assert!(rp.origin_allowed("https://xevion.dev:8443"));
+        assert!(!rp.origin_allowed("http://xevion.dev"));
+        assert!(!rp.origin_allowed("https://notxevion.dev"));
+        assert!(!rp.origin_allowed("https://xevion.dev.evil.example"));
+        assert!(!rp.origin_allowed("xevion.dev"));
+
+        let local = relying_party("localhost", &[]);
+        assert!(local.origin_allowed("http://localhost:5173"));
+        assert!(local.origin_allowed("https://localhost"));
+    }
+
+    #[test]
+    fn explicit_origins_are_exact() {
+        let rp = relying_party("xevion.dev", &["https://admin.xevion.dev"]);
+        assert!(rp.origin_allowed("https://admin.xevion.dev"));
+        assert!(!rp.origin_allowed("https://xevion.dev"));
+    }
+
+    fn is_pending(rp: &Webauthn, challenge: &str) -> bool {
+        rp.pending.contains_key(challenge)
+    }
+
+    #[test]
+    fn login_flood_evicts_the_oldest_challenge() {
+        let rp = relying_party("xevion.dev", &[]);
+        let registration = rp.issue(Ceremony::Registration { user_id: 1 });
+        let first = rp.issue(Ceremony::Authentication);
+        let second = rp.issue(Ceremony::Authentication);
+        for _ in 0..MAX_PENDING_LOGINS - 1 {
+            rp.issue(Ceremony::Authentication);
+        }
+
+        assert!(!is_pending(&rp, &first));
+        assert!(is_pending(&rp, &second));
+        assert!(is_pending(&rp, &registration));
+        assert_eq!(rp.pending.len(), MAX_PENDING_LOGINS + 1);
+    }
+
+    #[test]
+    fn base64url_accepts_padding() {
+        assert_eq!(decode("AQI").unwrap(), vec![1, 2]);
+        assert_eq!(decode("AQI=").unwrap(), vec![1, 2]);
+        assert_eq!(decode("A+I"), Err(WebauthnError::Malformed("base64url")));
+    }
+}
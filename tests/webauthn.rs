@@ -0,0 +1,381 @@
+//! Passkey ceremonies end to end against a software authenticator.
+//!
+//! The authenticator below does what a platform authenticator does, minus the
+//! secure hardware: it holds a P-256 or Ed25519 key, builds authenticator data
+//! and attestation objects, and signs assertions. Each test drives the relying
+//! party in `api::webauthn` through the same JSON the browser would post.
+
+#![cfg(feature = "server")]
+
+use api::webauthn::{
+    self, AssertionResponse, AttestationResponse, AuthenticationCredential, NewCredential,
+    PasskeyCreationOptions, PasskeyRequestOptions, RegistrationCredential, StoredCredential,
+    Webauthn, WebauthnConfig, WebauthnError,
+};
+use ciborium::Value;
+use ring::{
+    rand::SystemRandom,
+    signature::{self, EcdsaKeyPair, Ed25519KeyPair, KeyPair},
+};
+use sha2::{Digest, Sha256};
+
+const ORIGIN: &str = "https://xevion.dev";
+const USER_ID: i32 = 7;
+
+const UP: u8 = 0x01;
+const UV: u8 = 0x04;
+const AT: u8 = 0x40;
+
+enum Key {
+    P256(EcdsaKeyPair),
+    Ed25519(Ed25519KeyPair),
+}
+
+struct SoftwareAuthenticator {
+    key: Key,
+    rng: SystemRandom,
+    rp_id: String,
+    credential_id: Vec<u8>,
+    sign_count: u32,
+    /// Whether the authenticator keeps a signature counter at all
+    counts: bool,
+    flags: u8,
+}
+
+impl SoftwareAuthenticator {
+    fn p256(rp_id: &str) -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&signature::ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key = EcdsaKeyPair::from_pkcs8(
+            &signature::ECDSA_P256_SHA256_ASN1_SIGNING,
+            pkcs8.as_ref(),
+            &rng,
+        )
+        .unwrap();
+        Self::with_key(Key::P256(key), rng, rp_id)
+    }
+
+    fn ed25519(rp_id: &str) -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        Self::with_key(Key::Ed25519(key), rng, rp_id)
+    }
+
+    fn with_key(key: Key, rng: SystemRandom, rp_id: &str) -> Self {
+        Self {
+            key,
+            rng,
+            rp_id: rp_id.to_string(),
+            credential_id: rand::random::<[u8; 16]>().to_vec(),
+            sign_count: 0,
+            counts: true,
+            flags: UP | UV,
+        }
+    }
+
+    fn cose_key(&self) -> Vec<u8> {
+        let int = |n: i64| Value::Integer(n.into());
+        let map = match &self.key {
+            Key::P256(key) => {
+                // Uncompressed SEC1 point: 0x04 || x || y
+                let point = key.public_key().as_ref();
+                vec![
+                    (int(1), int(2)),
+                    (int(3), int(-7)),
+                    (int(-1), int(1)),
+                    (int(-2), Value::Bytes(point[1..33].to_vec())),
+                    (int(-3), Value::Bytes(point[33..].to_vec())),
+                ]
+            }
+            Key::Ed25519(key) => vec![
+                (int(1), int(1)),
+                (int(3), int(-8)),
+                (int(-1), int(6)),
+                (int(-2), Value::Bytes(key.public_key().as_ref().to_vec())),
+            ],
+        };
+        cbor(&Value::Map(map))
+    }
+
+    fn authenticator_data(&self, attested: bool) -> Vec<u8> {
+        let mut data = Sha256::digest(self.rp_id.as_bytes()).to_vec();
+        data.push(if attested {
+            self.flags | AT
+        } else {
+            self.flags
+        });
+        data.extend_from_slice(&self.sign_count.to_be_bytes());
+        if attested {
+            data.extend_from_slice(&[0; 16]);
+            data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+            data.extend_from_slice(&self.credential_id);
+            data.extend_from_slice(&self.cose_key());
+        }
+        data
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match &self.key {
+            Key::P256(key) => key.sign(&self.rng, message).unwrap().as_ref().to_vec(),
+            Key::Ed25519(key) => key.sign(message).as_ref().to_vec(),
+        }
+    }
+
+    /// `navigator.credentials.create()`
+    fn create(&self, options: &PasskeyCreationOptions, origin: &str) -> RegistrationCredential {
+        let client_data = client_data("webauthn.create", &options.challenge, origin);
+        let attestation_object = cbor(&Value::Map(vec![
+            (Value::Text("fmt".into()), Value::Text("none".into())),
+            (Value::Text("attStmt".into()), Value::Map(Vec::new())),
+            (
+                Value::Text("authData".into()),
+                Value::Bytes(self.authenticator_data(true)),
+            ),
+        ]));
+        RegistrationCredential {
+            id: webauthn::encode(&self.credential_id),
+            response: AttestationResponse {
+                client_data_json: webauthn::encode(&client_data),
+                attestation_object: webauthn::encode(&attestation_object),
+            },
+        }
+    }
+
+    /// `navigator.credentials.get()`
+    fn get(&mut self, options: &PasskeyRequestOptions, origin: &str) -> AuthenticationCredential {
+        if self.counts {
+            self.sign_count += 1;
+        }
+        let client_data = client_data("webauthn.get", &options.challenge, origin);
+        let authenticator_data = self.authenticator_data(false);
+        let mut signed = authenticator_data.clone();
+        signed.extend_from_slice(&Sha256::digest(&client_data));
+        AuthenticationCredential {
+            id: webauthn::encode(&self.credential_id),
+            response: AssertionResponse {
+                client_data_json: webauthn::encode(&client_data),
+                authenticator_data: webauthn::encode(&authenticator_data),
+                signature: webauthn::encode(&self.sign(&signed)),
+                user_handle: Some(webauthn::encode(&webauthn::user_handle(USER_ID))),
+            },
+        }
+    }
+}
+
+fn cbor(value: &Value) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).unwrap();
+    bytes
+}
+
+fn client_data(kind: &str, challenge: &str, origin: &str) -> Vec<u8> {
+    serde_json::to_vec(&serde_json::json!({
+        "type": kind,
+        "challenge": challenge,
+        "origin": origin,
+        "crossOrigin": false,
+    }))
+    .unwrap()
+}
+
+fn relying_party() -> Webauthn {
+    Webauthn::new(WebauthnConfig {
+        rp_id: "xevion.dev".to_string(),
+        rp_name: "xevion.dev".to_string(),
+        origins: Vec::new(),
+    })
+}
+
+fn register(rp: &Webauthn, authenticator: &SoftwareAuthenticator) -> NewCredential {
+    let options = rp.start_registration(USER_ID, "admin", &[]);
+    rp.finish_registration(&authenticator.create(&options, ORIGIN))
+        .unwrap()
+}
+
+fn login(
+    rp: &Webauthn,
+    authenticator: &mut SoftwareAuthenticator,
+    stored: &StoredCredential,
+) -> Result<u32, WebauthnError> {
+    let options = rp.start_authentication();
+    rp.finish_authentication(&authenticator.get(&options, ORIGIN), stored)
+}
+
+#[test]
+fn registers_and_signs_in_with_p256_passkey() {
+    let rp = relying_party();
+    let mut authenticator = SoftwareAuthenticator::p256("xevion.dev");
+
+    let options = rp.start_registration(USER_ID, "admin", &[vec![1, 2, 3]]);
+    assert_eq!(options.rp.id, "xevion.dev");
+    assert_eq!(options.user.id, webauthn::encode(&USER_ID.to_be_bytes()));
+    assert_eq!(
+        options.exclude_credentials[0].id,
+        webauthn::encode(&[1, 2, 3])
+    );
+    assert_eq!(
+        options.authenticator_selection.user_verification,
+        "required"
+    );
+
+    let credential = rp
+        .finish_registration(&authenticator.create(&options, ORIGIN))
+        .unwrap();
+    assert_eq!(credential.user_id, USER_ID);
+    assert_eq!(credential.credential_id, authenticator.credential_id);
+    assert_eq!(credential.algorithm, webauthn::ES256);
+
+    let mut stored = StoredCredential::from(credential);
+    assert_eq!(login(&rp, &mut authenticator, &stored), Ok(1));
+    stored.sign_count = 1;
+    assert_eq!(login(&rp, &mut authenticator, &stored), Ok(2));
+}
+
+#[test]
+fn signs_in_with_ed25519_passkey() {
+    let rp = relying_party();
+    let mut authenticator = SoftwareAuthenticator::ed25519("xevion.dev");
+    let credential = register(&rp, &authenticator);
+    assert_eq!(credential.algorithm, webauthn::EDDSA);
+    assert_eq!(login(&rp, &mut authenticator, &credential.into()), Ok(1));
+}
+
+#[test]
+fn challenges_are_single_use() {
+    let rp = relying_party();
+    let mut authenticator = SoftwareAuthenticator::p256("xevion.dev");
+    let stored = StoredCredential::from(register(&rp, &authenticator));
+
+    let options = rp.start_authentication();
+    let assertion = authenticator.get(&options, ORIGIN);
+    assert_eq!(rp.finish_authentication(&assertion, &stored), Ok(1));
+    assert_eq!(
+        rp.finish_authentication(&assertion, &stored),
+        Err(WebauthnError::Challenge)
+    );
+
+    // A registration challenge can't be spent on a login, nor vice versa
+    let options = rp.start_registration(USER_ID, "admin", &[]);
+    let assertion = authenticator.get(
+        &PasskeyRequestOptions {
+            challenge: options.challenge,
+            ..rp.start_authentication()
+        },
+        ORIGIN,
+    );
+    assert_eq!(
+        rp.finish_authentication(&assertion, &stored),
+        Err(WebauthnError::ClientDataType)
+    );
+}
+
+#[test]
+fn rejects_foreign_origins_and_relying_parties() {
+    let rp = relying_party();
+    let authenticator = SoftwareAuthenticator::p256("xevion.dev");
+
+    let options = rp.start_registration(USER_ID, "admin", &[]);
+    let credential = authenticator.create(&options, "https://evil.example");
+    assert_eq!(
+        rp.finish_registration(&credential).unwrap_err(),
+        WebauthnError::Origin("https://evil.example".to_string())
+    );
+    let options = rp.start_registration(USER_ID, "admin", &[]);
+    let credential = authenticator.create(&options, "http://xevion.dev");
+    assert!(matches!(
+        rp.finish_registration(&credential),
+        Err(WebauthnError::Origin(_))
+    ));
+
+    // Subdomains of the RP ID are fine
+    let options = rp.start_registration(USER_ID, "admin", &[]);
+    let credential = authenticator.create(&options, "https://admin.xevion.dev:8443");
+    assert!(rp.finish_registration(&credential).is_ok());
+
+    let phished = SoftwareAuthenticator::p256("xevion.dev.evil.example");
+    let options = rp.start_registration(USER_ID, "admin", &[]);
+    assert_eq!(
+        rp.finish_registration(&phished.create(&options, ORIGIN))
+            .unwrap_err(),
+        WebauthnError::RpId
+    );
+}
+
+#[test]
+fn requires_user_verification() {
+    let rp = relying_party();
+    let mut authenticator = SoftwareAuthenticator::p256("xevion.dev");
+    let stored = StoredCredential::from(register(&rp, &authenticator));
+
+    authenticator.flags = UP;
+    assert_eq!(
+        login(&rp, &mut authenticator, &stored),
+        Err(WebauthnError::UserVerification)
+    );
+    authenticator.flags = UV;
+    assert_eq!(
+        login(&rp, &mut authenticator, &stored),
+        Err(WebauthnError::UserPresence)
+    );
+}
+
+#[test]
+fn rejects_bad_signatures_and_foreign_keys() {
+    let rp = relying_party();
+    let mut authenticator = SoftwareAuthenticator::p256("xevion.dev");
+    let stored = StoredCredential::from(register(&rp, &authenticator));
+
+    let options = rp.start_authentication();
+    let mut assertion = authenticator.get(&options, ORIGIN);
+    let mut signature = webauthn::decode(&assertion.response.signature).unwrap();
+    let last = signature.len() - 1;
+    signature[last] ^= 0x01;
+    assertion.response.signature = webauthn::encode(&signature);
+    assert_eq!(
+        rp.finish_authentication(&assertion, &stored),
+        Err(WebauthnError::Signature)
+    );
+
+    // Same credential ID, different key
+    let mut impostor = SoftwareAuthenticator::p256("xevion.dev");
+    impostor
+        .credential_id
+        .clone_from(&authenticator.credential_id);
+    assert_eq!(
+        login(&rp, &mut impostor, &stored),
+        Err(WebauthnError::Signature)
+    );
+
+    // A credential claimed by another user's handle
+    let other_user = StoredCredential {
+        user_id: USER_ID + 1,
+        ..stored
+    };
+    assert_eq!(
+        login(&rp, &mut authenticator, &other_user),
+        Err(WebauthnError::UserHandle)
+    );
+}
+
+#[test]
+fn rejects_signature_counter_regression() {
+    let rp = relying_party();
+    let mut authenticator = SoftwareAuthenticator::p256("xevion.dev");
+    let mut stored = StoredCredential::from(register(&rp, &authenticator));
+
+    // A clone of the authenticator that has fallen behind the original
+    stored.sign_count = 5;
+    assert_eq!(
+        login(&rp, &mut authenticator, &stored),
+        Err(WebauthnError::Counter)
+    );
+
+    // Authenticators without a counter report zero every time
+    let mut counterless = SoftwareAuthenticator::p256("xevion.dev");
+    counterless.counts = false;
+    let stored = StoredCredential::from(register(&rp, &counterless));
+    assert_eq!(login(&rp, &mut counterless, &stored), Ok(0));
+    assert_eq!(login(&rp, &mut counterless, &stored), Ok(0));
+}
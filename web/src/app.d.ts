@@ -3,7 +3,10 @@
 declare global {
   namespace App {
     // interface Error {}
-    // interface Locals {}
+    interface Locals {
+      /** Surrogate keys for the ISR cache; see `$lib/server/cache-tags`. */
+      cacheTags: Set<string>;
+    }
     // interface PageData {}
 
     interface PageState {
@@ -3,6 +3,7 @@ import { dev } from "$app/environment";
 import { env } from "$env/dynamic/private";
 import { initLogger } from "$lib/logger";
 import { requestContext } from "$lib/server/context";
+import { CACHE_TAGS_HEADER } from "$lib/server/cache-tags";
 import { preCacheCollections } from "$lib/server/icons";
 import { getLogger } from "@logtape/logtape";
 import { PostHog } from "posthog-node";
@@ -40,9 +41,21 @@ export const handle: Handle = async ({ event, resolve }) => {
     return new Response(undefined, { status: 404 });
   }
 
-  return requestContext.run({ requestId: requestId ?? undefined }, () => {
-    return resolve(event);
-  });
+  event.locals.cacheTags = new Set();
+
+  const response = await requestContext.run(
+    { requestId: requestId ?? undefined },
+    () => resolve(event),
+  );
+
+  if (event.locals.cacheTags.size > 0) {
+    response.headers.set(
+      CACHE_TAGS_HEADER,
+      [...event.locals.cacheTags].join(","),
+    );
+  }
+
+  return response;
 };
 
 export const handleError: HandleServerError = async ({
@@ -6,7 +6,16 @@ import type {
   AdminStats,
   ApiSiteSettings,
   ApiProjectMedia,
+  ApiMediaAsset,
   ApiSession,
+  ApiLoginLockout,
+  ApiTotpStatus,
+  ApiTotpSetup,
+  ApiRecoveryCodes,
+  ApiPasskey,
+  PasskeyCreationOptions,
+  ApiAdminUser,
+  AdminRole,
   EventLevel,
 } from "$lib/bindings";
 import type {
@@ -15,6 +24,7 @@ import type {
   CreateTagData,
   UpdateTagData,
 } from "./admin-types";
+import type { RegistrationCredentialJSON } from "./passkeys";
 import { ApiError } from "./errors";
 import { ok, err } from "true-myth/result";
 import type { Result } from "true-myth/result";
@@ -202,6 +212,14 @@ export async function uploadProjectMedia(
   });
 }
 
+export async function getProjectMedia(
+  projectId: string,
+): Promise<Result<ApiProjectMedia[], ApiError>> {
+  return clientApiFetch<ApiProjectMedia[]>(
+    `/api/projects/${projectId}/media`,
+  );
+}
+
 export async function deleteProjectMedia(
   projectId: string,
   mediaId: string,
@@ -226,12 +244,58 @@ export async function reorderProjectMedia(
   );
 }
 
+export async function attachProjectMedia(
+  projectId: string,
+  assetId: string,
+): Promise<Result<ApiProjectMedia, ApiError>> {
+  return clientApiFetch<ApiProjectMedia>(
+    `/api/projects/${projectId}/media/attach`,
+    {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ assetId }),
+    },
+  );
+}
+
+export async function getMediaLibrary(): Promise<
+  Result<ApiMediaAsset[], ApiError>
+> {
+  return clientApiFetch<ApiMediaAsset[]>("/api/media");
+}
+
+export async function getMediaAsset(
+  assetId: string,
+): Promise<Result<ApiMediaAsset, ApiError>> {
+  return clientApiFetch<ApiMediaAsset>(`/api/media/${assetId}`);
+}
+
+export async function uploadLibraryMedia(
+  file: File,
+): Promise<Result<ApiMediaAsset, ApiError>> {
+  const formData = new FormData();
+  formData.append("file", file);
+  return clientApiFetch<ApiMediaAsset>("/api/media", {
+    method: "POST",
+    body: formData,
+  });
+}
+
+export async function deleteMediaAsset(
+  assetId: string,
+): Promise<Result<ApiMediaAsset, ApiError>> {
+  return clientApiFetch<ApiMediaAsset>(`/api/media/${assetId}`, {
+    method: "DELETE",
+  });
+}
+
 export async function getAdminEvents(params?: {
   limit?: number;
   offset?: number;
   level?: EventLevel;
   entityType?: string;
   eventType?: string;
+  actor?: string;
 }): Promise<Result<ApiEvent[], ApiError>> {
   const search = new URLSearchParams();
   if (params?.limit != null) search.set("limit", String(params.limit));
@@ -239,6 +303,7 @@ export async function getAdminEvents(params?: {
   if (params?.level) search.set("level", params.level);
   if (params?.entityType) search.set("entityType", params.entityType);
   if (params?.eventType) search.set("eventType", params.eventType);
+  if (params?.actor) search.set("actor", params.actor);
   const qs = search.toString();
   return clientApiFetch<ApiEvent[]>(`/api/events${qs ? `?${qs}` : ""}`);
 }
@@ -273,6 +338,158 @@ export async function revokeSession(
   return clientApiFetch<void>(`/api/sessions/${id}`, { method: "DELETE" });
 }
 
+/** Revoke every session and API token except the current one. */
+export async function revokeOtherSessions(): Promise<
+  Result<{ success: boolean; revoked: number }, ApiError>
+> {
+  return clientApiFetch("/api/sessions", { method: "DELETE" });
+}
+
+export async function getLoginLockouts(): Promise<
+  Result<ApiLoginLockout[], ApiError>
+> {
+  return clientApiFetch<ApiLoginLockout[]>("/api/sessions/lockouts");
+}
+
+/** Clear one `kind:value` key, or every key when omitted. */
+export async function clearLoginLockouts(
+  key?: string,
+): Promise<Result<void, ApiError>> {
+  const path = key
+    ? `/api/sessions/lockouts/${encodeURIComponent(key)}`
+    : "/api/sessions/lockouts";
+  return clientApiFetch<void>(path, { method: "DELETE" });
+}
+
+/** Change the signed-in admin's password. */
+export async function changePassword(
+  currentPassword: string,
+  newPassword: string,
+  revokeOtherSessions: boolean,
+): Promise<
+  Result<{ success: boolean; revokedSessions: number }, ApiError>
+> {
+  return clientApiFetch("/api/account/password", {
+    method: "PUT",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify({ currentPassword, newPassword, revokeOtherSessions }),
+  });
+}
+
+export async function getTotpStatus(): Promise<
+  Result<ApiTotpStatus, ApiError>
+> {
+  return clientApiFetch<ApiTotpStatus>("/api/account/totp");
+}
+
+/** Issue a new, unconfirmed TOTP secret. */
+export async function setupTotp(): Promise<Result<ApiTotpSetup, ApiError>> {
+  return clientApiFetch<ApiTotpSetup>("/api/account/totp/setup", {
+    method: "POST",
+  });
+}
+
+async function postTotpCode<T>(
+  path: string,
+  totpCode: string,
+): Promise<Result<T, ApiError>> {
+  return clientApiFetch<T>(path, {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify({ totpCode }),
+  });
+}
+
+/** Enable 2FA with a code from the pending secret. */
+export async function confirmTotp(
+  totpCode: string,
+): Promise<Result<ApiRecoveryCodes, ApiError>> {
+  return postTotpCode("/api/account/totp/confirm", totpCode);
+}
+
+export async function disableTotp(
+  totpCode: string,
+): Promise<Result<{ success: boolean }, ApiError>> {
+  return postTotpCode("/api/account/totp/disable", totpCode);
+}
+
+export async function regenerateRecoveryCodes(
+  totpCode: string,
+): Promise<Result<ApiRecoveryCodes, ApiError>> {
+  return postTotpCode("/api/account/totp/recovery-codes", totpCode);
+}
+
+export async function getPasskeys(): Promise<Result<ApiPasskey[], ApiError>> {
+  return clientApiFetch<ApiPasskey[]>("/api/account/passkeys");
+}
+
+/** Options for `navigator.credentials.create()`, with a fresh challenge. */
+export async function getPasskeyRegistrationOptions(): Promise<
+  Result<PasskeyCreationOptions, ApiError>
+> {
+  return clientApiFetch<PasskeyCreationOptions>(
+    "/api/account/passkeys/options",
+    { method: "POST" },
+  );
+}
+
+/** Store a new passkey. Needs a current code once 2FA is enabled. */
+export async function registerPasskey(
+  credential: RegistrationCredentialJSON,
+  name: string,
+  totpCode?: string,
+): Promise<Result<ApiPasskey, ApiError>> {
+  return clientApiFetch<ApiPasskey>("/api/account/passkeys", {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify({ credential, name, totpCode }),
+  });
+}
+
+export async function deletePasskey(
+  id: number,
+): Promise<Result<void, ApiError>> {
+  return clientApiFetch<void>(`/api/account/passkeys/${id}`, {
+    method: "DELETE",
+  });
+}
+
+export async function getAdminUsers(): Promise<
+  Result<ApiAdminUser[], ApiError>
+> {
+  return clientApiFetch<ApiAdminUser[]>("/api/users");
+}
+
+export async function createAdminUser(data: {
+  username: string;
+  password: string;
+  role: AdminRole;
+}): Promise<Result<ApiAdminUser, ApiError>> {
+  return clientApiFetch<ApiAdminUser>("/api/users", {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify(data),
+  });
+}
+
+/** Change a user's role; applies to their signed-in sessions at once. */
+export async function updateAdminUserRole(
+  id: number,
+  role: AdminRole,
+): Promise<Result<ApiAdminUser, ApiError>> {
+  return clientApiFetch<ApiAdminUser>(`/api/users/${id}`, {
+    method: "PUT",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify({ role }),
+  });
+}
+
+export async function deleteAdminUser(
+  id: number,
+): Promise<Result<void, ApiError>> {
+  return clientApiFetch<void>(`/api/users/${id}`, { method: "DELETE" });
+}
+
 /** Details of a pending device-authorization request, for the approval page. */
 export interface DeviceAuthInfo {
   userCode: string;
@@ -289,11 +506,12 @@ export async function getDeviceAuthInfo(
 export async function approveDeviceAuth(
   requestId: string,
   userCode: string,
+  totpCode?: string,
 ): Promise<Result<{ success: boolean }, ApiError>> {
   return clientApiFetch<{ success: boolean }>("/api/auth/device/approve", {
     method: "POST",
     headers: { "Content-Type": "application/json" },
-    body: JSON.stringify({ requestId, userCode }),
+    body: JSON.stringify({ requestId, userCode, totpCode }),
   });
 }
 
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What an admin user may do. Owners manage the site (settings, sessions,
+ * users), editors manage content (projects, tags, media), viewers can only
+ * read.
+ */
+export type AdminRole = "owner" | "editor" | "viewer";
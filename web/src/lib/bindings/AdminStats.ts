@@ -1,3 +1,8 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CacheWarmStatus } from "./CacheWarmStatus";
 
-export type AdminStats = { totalProjects: number, projectsByStatus: Record<string, number>, totalTags: number, };
+export type AdminStats = { totalProjects: number, projectsByStatus: Record<string, number>, totalTags: number, 
+/**
+ * Progress of the most recent ISR cache warm; filled in by the handler
+ */
+cacheWarm: CacheWarmStatus, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AdminRole } from "./AdminRole";
+
+/**
+ * An admin user, as listed by `GET /api/users`.
+ */
+export type ApiAdminUser = { id: number, username: string, role: AdminRole, totpEnabled: boolean, passkeys: bigint, createdAt: string, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Failed-login throttle state of a client IP or username.
+ */
+export type ApiLoginLockout = { 
+/**
+ * `kind:value`, as accepted by `DELETE /api/sessions/lockouts/{key}`.
+ */
+key: string, 
+/**
+ * "ip" or "username".
+ */
+kind: string, value: string, failures: number, lastFailureAt: string, 
+/**
+ * When attempts are accepted again, if they're currently blocked.
+ */
+blockedUntil?: string, 
+/**
+ * True for a lockout, false for a backoff delay.
+ */
+locked: boolean, };
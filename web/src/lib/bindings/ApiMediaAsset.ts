@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApiMediaReferences } from "./ApiMediaReferences";
+import type { ApiMediaVariants } from "./ApiMediaVariants";
+import type { MediaMetadata } from "./MediaMetadata";
+import type { MediaStatus } from "./MediaStatus";
+import type { MediaType } from "./MediaType";
+
+/**
+ * API response type for a media library asset
+ */
+export type ApiMediaAsset = { id: string, mediaType: MediaType, originalFilename: string, variants: ApiMediaVariants, blurhash?: string, metadata?: MediaMetadata, sizeBytes: bigint, status: MediaStatus, processingError?: string, createdAt: string, 
+/**
+ * Project attachments + figure blocks + settings slots using this asset.
+ */
+refCount: bigint, 
+/**
+ * Where the asset is used; only included when fetching a single asset.
+ */
+references?: ApiMediaReferences, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApiMediaProjectRef } from "./ApiMediaProjectRef";
+
+/**
+ * Everything that keeps a library asset alive
+ */
+export type ApiMediaReferences = { 
+/**
+ * Projects with the asset in their media gallery
+ */
+projects: Array<ApiMediaProjectRef>, 
+/**
+ * Projects whose detail page has a `figure` block using the asset
+ */
+figures: Array<ApiMediaProjectRef>, 
+/**
+ * Site settings slots (`avatar`, `favicon`)
+ */
+settings: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A registered passkey, as shown on the account page.
+ */
+export type ApiPasskey = { id: number, name: string, createdAt: string, lastUsedAt?: string, };
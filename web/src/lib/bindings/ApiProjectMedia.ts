@@ -1,9 +1,22 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ApiMediaVariants } from "./ApiMediaVariants";
 import type { MediaMetadata } from "./MediaMetadata";
+import type { MediaStatus } from "./MediaStatus";
 import type { MediaType } from "./MediaType";
 
 /**
  * API response type for project media
  */
-export type ApiProjectMedia = { id: string, displayOrder: number, mediaType: MediaType, variants: ApiMediaVariants, blurhash?: string, metadata?: MediaMetadata, };
+export type ApiProjectMedia = { id: string, 
+/**
+ * The library asset this attachment points at.
+ */
+assetId: string, displayOrder: number, mediaType: MediaType, variants: ApiMediaVariants, blurhash?: string, metadata?: MediaMetadata, 
+/**
+ * Processing state; only `ready` media has its resized variants.
+ */
+status: MediaStatus, 
+/**
+ * Last processing failure, present while `failed` (or between retries).
+ */
+processingError?: string, };
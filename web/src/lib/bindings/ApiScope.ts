@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A permission granted to an API token.
+ */
+export type ApiScope = "projects:write" | "content:write" | "media:upload" | "media:write" | "tags:write" | "cache:purge";
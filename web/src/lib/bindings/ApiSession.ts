@@ -2,9 +2,21 @@
 import type { SessionType } from "./SessionType";
 
 /**
- * A session as shown in the admin sessions list.
+ * A session as shown in the admin sessions list and `xevion sessions list`.
  */
-export type ApiSession = { id: string, sessionType: SessionType, label?: string, createdAt: string, lastActiveAt: string, expiresAt: string, 
+export type ApiSession = { id: string, username: string, sessionType: SessionType, label?: string, createdAt: string, lastActiveAt: string, expiresAt: string, 
+/**
+ * Address the session was created from.
+ */
+createdIp?: string, 
+/**
+ * Address of its latest recorded request.
+ */
+lastIp?: string, userAgent?: string, 
+/**
+ * Browser and OS read from the user agent, e.g. "Firefox on Linux".
+ */
+device?: string, 
 /**
  * True for the session making this request (don't let the user lock themselves out blindly).
  */
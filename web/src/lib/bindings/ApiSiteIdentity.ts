@@ -1,3 +1,11 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type ApiSiteIdentity = { displayName: string, occupation: string, bio: string, siteTitle: string, };
+export type ApiSiteIdentity = { displayName: string, occupation: string, bio: string, siteTitle: string, 
+/**
+ * Media library asset used as the profile avatar
+ */
+avatarMediaId?: string, avatarUrl?: string, 
+/**
+ * Media library asset used as the favicon
+ */
+faviconMediaId?: string, faviconUrl?: string, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApiScope } from "./ApiScope";
+
+/**
+ * An API token as listed by `GET /api/tokens`. The secret is never listed.
+ */
+export type ApiToken = { id: string, name: string, scopes: Array<ApiScope>, 
+/**
+ * Projects the token is limited to; absent when it may touch any project.
+ */
+projectIds?: Array<string>, createdAt: string, 
+/**
+ * Last request made with the token (recorded at most every few minutes).
+ */
+lastUsedAt: string, expiresAt: string, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A freshly issued, unconfirmed TOTP secret.
+ */
+export type ApiTotpSetup = { 
+/**
+ * Base32 secret, for manual entry.
+ */
+secret: string, 
+/**
+ * `otpauth://` URI, for a QR code.
+ */
+otpauthUri: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 2FA state of the signed-in admin.
+ */
+export type ApiTotpStatus = { enabled: boolean, 
+/**
+ * A secret has been issued but not confirmed yet.
+ */
+pending: boolean, recoveryCodesRemaining: bigint, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CacheWarmTrigger } from "./CacheWarmTrigger";
+
+/**
+ * Progress of the current (or last) cache warm.
+ */
+export type CacheWarmStatus = { running: boolean, trigger?: CacheWarmTrigger, 
+/**
+ * Pages in the current (or last) warm
+ */
+total: number, 
+/**
+ * Pages rendered so far, failures included
+ */
+completed: number, failed: number, 
+/**
+ * RFC 3339
+ */
+startedAt?: string, 
+/**
+ * RFC 3339; absent while running
+ */
+finishedAt?: string, };
@@ -3,4 +3,4 @@
 /**
  * All event types in the system, serialized as dot-separated strings
  */
-export type EventType = "project.created" | "project.updated" | "project.deleted" | "project.tag_added" | "project.tag_removed" | "tag.created" | "tag.updated" | "tag.deleted" | "settings.updated" | "github.sync_completed" | "github.sync_failed" | "github.rate_limited" | "og.generated" | "og.failed" | "cache.invalidated";
+export type EventType = "project.created" | "project.updated" | "project.deleted" | "project.tag_added" | "project.tag_removed" | "tag.created" | "tag.updated" | "tag.deleted" | "settings.updated" | "github.sync_completed" | "github.sync_failed" | "github.rate_limited" | "og.generated" | "og.failed" | "cache.invalidated" | "media.uploaded" | "media.processing" | "media.processed" | "media.failed" | "storage.gc" | "auth.login_failed" | "auth.locked_out" | "auth.lockout_cleared" | "auth.totp_enabled" | "auth.totp_disabled" | "auth.recovery_code_used" | "auth.passkey_added" | "auth.passkey_removed" | "auth.password_changed" | "auth.token_created" | "auth.token_revoked" | "user.created" | "user.role_changed" | "user.deleted";
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Processing lifecycle for uploaded media, matching the `PostgreSQL` enum.
+ *
+ * Images are stored as `pending` and advanced by the background media queue;
+ * videos need no processing and are created `ready`.
+ */
+export type MediaStatus = "pending" | "processing" | "ready" | "failed";
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PasskeyAuthenticatorSelection } from "./PasskeyAuthenticatorSelection";
+import type { PasskeyCredentialDescriptor } from "./PasskeyCredentialDescriptor";
+import type { PasskeyCredentialParameters } from "./PasskeyCredentialParameters";
+import type { PasskeyRelyingParty } from "./PasskeyRelyingParty";
+import type { PasskeyUser } from "./PasskeyUser";
+
+/**
+ * `PublicKeyCredentialCreationOptionsJSON`
+ */
+export type PasskeyCreationOptions = { challenge: string, rp: PasskeyRelyingParty, user: PasskeyUser, pubKeyCredParams: Array<PasskeyCredentialParameters>, 
+/**
+ * Milliseconds.
+ */
+timeout: number, excludeCredentials: Array<PasskeyCredentialDescriptor>, authenticatorSelection: PasskeyAuthenticatorSelection, attestation: string, };
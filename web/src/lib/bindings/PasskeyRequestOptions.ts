@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PasskeyCredentialDescriptor } from "./PasskeyCredentialDescriptor";
+
+/**
+ * `PublicKeyCredentialRequestOptionsJSON`
+ */
+export type PasskeyRequestOptions = { challenge: string, 
+/**
+ * Milliseconds.
+ */
+timeout: number, rpId: string, 
+/**
+ * Empty: the authenticator offers whichever passkeys it holds for the RP.
+ */
+allowCredentials: Array<PasskeyCredentialDescriptor>, userVerification: string, };
@@ -1,7 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
 /**
- * How a session authenticates: an interactive browser cookie, or a long-lived
- * CLI bearer token.
+ * How a session authenticates: an interactive browser cookie, a long-lived
+ * CLI bearer token, or a scoped API token for automation.
  */
-export type SessionType = "browser" | "cli";
+export type SessionType = "browser" | "cli" | "token";
@@ -1,28 +1,50 @@
 // Auto-generated barrel file — do not edit manually.
 // Regenerate with: just bindings
+export type { AdminRole } from "./AdminRole";
 export type { AdminStats } from "./AdminStats";
 export type { ApiAdminProject } from "./ApiAdminProject";
+export type { ApiAdminUser } from "./ApiAdminUser";
 export type { ApiEvent } from "./ApiEvent";
+export type { ApiLoginLockout } from "./ApiLoginLockout";
+export type { ApiMediaAsset } from "./ApiMediaAsset";
+export type { ApiMediaProjectRef } from "./ApiMediaProjectRef";
+export type { ApiMediaReferences } from "./ApiMediaReferences";
 export type { ApiMediaVariant } from "./ApiMediaVariant";
 export type { ApiMediaVariants } from "./ApiMediaVariants";
+export type { ApiPasskey } from "./ApiPasskey";
 export type { ApiProject } from "./ApiProject";
 export type { ApiProjectDetail } from "./ApiProjectDetail";
 export type { ApiProjectLink } from "./ApiProjectLink";
 export type { ApiProjectMedia } from "./ApiProjectMedia";
+export type { ApiRecoveryCodes } from "./ApiRecoveryCodes";
 export type { ApiRelatedProject } from "./ApiRelatedProject";
 export type { ApiRelatedTag } from "./ApiRelatedTag";
+export type { ApiScope } from "./ApiScope";
 export type { ApiSession } from "./ApiSession";
 export type { ApiSiteIdentity } from "./ApiSiteIdentity";
 export type { ApiSiteSettings } from "./ApiSiteSettings";
 export type { ApiSocialLink } from "./ApiSocialLink";
 export type { ApiTag } from "./ApiTag";
 export type { ApiTagWithCount } from "./ApiTagWithCount";
+export type { ApiToken } from "./ApiToken";
+export type { ApiTotpSetup } from "./ApiTotpSetup";
+export type { ApiTotpStatus } from "./ApiTotpStatus";
 export type { ApiVideoOriginal } from "./ApiVideoOriginal";
+export type { CacheWarmStatus } from "./CacheWarmStatus";
+export type { CacheWarmTrigger } from "./CacheWarmTrigger";
 export type { EventLevel } from "./EventLevel";
 export type { EventType } from "./EventType";
 export type { FocalPoint } from "./FocalPoint";
 export type { MediaMetadata } from "./MediaMetadata";
+export type { MediaStatus } from "./MediaStatus";
 export type { MediaType } from "./MediaType";
+export type { PasskeyAuthenticatorSelection } from "./PasskeyAuthenticatorSelection";
+export type { PasskeyCreationOptions } from "./PasskeyCreationOptions";
+export type { PasskeyCredentialDescriptor } from "./PasskeyCredentialDescriptor";
+export type { PasskeyCredentialParameters } from "./PasskeyCredentialParameters";
+export type { PasskeyRelyingParty } from "./PasskeyRelyingParty";
+export type { PasskeyRequestOptions } from "./PasskeyRequestOptions";
+export type { PasskeyUser } from "./PasskeyUser";
 export type { ProjectStatus } from "./ProjectStatus";
 export type { SessionType } from "./SessionType";
 export type { TerminalCast } from "./TerminalCast";
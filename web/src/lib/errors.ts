@@ -3,16 +3,20 @@
  */
 export class ApiError extends Error {
   public fieldErrors?: Record<string, string>;
+  /** Machine-readable error code from the body, e.g. `TOTP_REQUIRED` */
+  public code?: string;
 
   constructor(
     public status: number,
     public statusText: string,
     message?: string,
     fieldErrors?: Record<string, string>,
+    code?: string,
   ) {
     super(message || `API error: ${status} ${statusText}`);
     this.name = "ApiError";
     this.fieldErrors = fieldErrors;
+    this.code = code;
   }
 
   /**
@@ -22,6 +26,7 @@ export class ApiError extends Error {
   static async fromResponse(response: Response): Promise<ApiError> {
     let message: string | undefined;
     let fieldErrors: Record<string, string> | undefined;
+    let code: string | undefined;
 
     try {
       const body = await response.json();
@@ -29,6 +34,9 @@ export class ApiError extends Error {
         if (typeof body.error === "string") {
           message = body.error;
         }
+        if (typeof body.code === "string") {
+          code = body.code;
+        }
         if (
           body.fieldErrors &&
           typeof body.fieldErrors === "object" &&
@@ -46,6 +54,7 @@ export class ApiError extends Error {
       response.statusText,
       message,
       fieldErrors,
+      code,
     );
   }
 
@@ -66,6 +75,13 @@ export class ApiError extends Error {
     );
   }
 
+  /**
+   * Check if the request needs a TOTP or recovery code
+   */
+  static isSecondFactorRequired(error: unknown): boolean {
+    return error instanceof ApiError && error.code === "TOTP_REQUIRED";
+  }
+
   /**
    * Check if an error is a server error (5xx)
    */
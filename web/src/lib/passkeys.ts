@@ -0,0 +1,136 @@
+/**
+ * Bridges the server's WebAuthn JSON options and `navigator.credentials`.
+ *
+ * The API speaks the spec's JSON encoding (binary fields as unpadded
+ * base64url); the browser API wants `ArrayBuffer`s. These helpers convert
+ * both ways so callers only deal in JSON.
+ */
+import type {
+  PasskeyCreationOptions,
+  PasskeyCredentialDescriptor,
+  PasskeyRequestOptions,
+} from "$lib/bindings";
+
+export interface RegistrationCredentialJSON {
+  id: string;
+  response: { clientDataJSON: string; attestationObject: string };
+}
+
+export interface AuthenticationCredentialJSON {
+  id: string;
+  response: {
+    clientDataJSON: string;
+    authenticatorData: string;
+    signature: string;
+    userHandle?: string;
+  };
+}
+
+export function isPasskeySupported(): boolean {
+  return (
+    typeof window !== "undefined" &&
+    typeof window.PublicKeyCredential !== "undefined" &&
+    !!navigator.credentials
+  );
+}
+
+/** The user dismissed or timed out the browser's passkey prompt. */
+export function isPasskeyCancelled(error: unknown): boolean {
+  return error instanceof DOMException && error.name === "NotAllowedError";
+}
+
+function toBase64Url(buffer: ArrayBuffer): string {
+  let binary = "";
+  for (const byte of new Uint8Array(buffer)) {
+    binary += String.fromCharCode(byte);
+  }
+  return btoa(binary)
+    .replace(/\+/g, "-")
+    .replace(/\//g, "_")
+    .replace(/=+$/, "");
+}
+
+function fromBase64Url(value: string): ArrayBuffer {
+  const base64 = value.replace(/-/g, "+").replace(/_/g, "/");
+  const padded = base64.padEnd(Math.ceil(base64.length / 4) * 4, "=");
+  const binary = atob(padded);
+  const bytes = new Uint8Array(binary.length);
+  for (let i = 0; i < binary.length; i++) {
+    bytes[i] = binary.charCodeAt(i);
+  }
+  return bytes.buffer;
+}
+
+function toDescriptor(
+  descriptor: PasskeyCredentialDescriptor,
+): PublicKeyCredentialDescriptor {
+  return { type: "public-key", id: fromBase64Url(descriptor.id) };
+}
+
+/** Register a new passkey with the authenticator. */
+export async function createPasskey(
+  options: PasskeyCreationOptions,
+): Promise<RegistrationCredentialJSON> {
+  const credential = (await navigator.credentials.create({
+    publicKey: {
+      challenge: fromBase64Url(options.challenge),
+      rp: options.rp,
+      user: {
+        id: fromBase64Url(options.user.id),
+        name: options.user.name,
+        displayName: options.user.displayName,
+      },
+      pubKeyCredParams: options.pubKeyCredParams.map(({ alg }) => ({
+        type: "public-key",
+        alg,
+      })),
+      timeout: options.timeout,
+      excludeCredentials: options.excludeCredentials.map(toDescriptor),
+      authenticatorSelection: {
+        residentKey: "required",
+        requireResidentKey: true,
+        userVerification: "required",
+      },
+      attestation: "none",
+    },
+  })) as PublicKeyCredential | null;
+  if (!credential) throw new Error("No credential created");
+
+  const response = credential.response as AuthenticatorAttestationResponse;
+  return {
+    id: toBase64Url(credential.rawId),
+    response: {
+      clientDataJSON: toBase64Url(response.clientDataJSON),
+      attestationObject: toBase64Url(response.attestationObject),
+    },
+  };
+}
+
+/** Sign a login challenge with any passkey the user picks. */
+export async function getPasskey(
+  options: PasskeyRequestOptions,
+): Promise<AuthenticationCredentialJSON> {
+  const credential = (await navigator.credentials.get({
+    publicKey: {
+      challenge: fromBase64Url(options.challenge),
+      timeout: options.timeout,
+      rpId: options.rpId,
+      allowCredentials: options.allowCredentials.map(toDescriptor),
+      userVerification: "required",
+    },
+  })) as PublicKeyCredential | null;
+  if (!credential) throw new Error("No credential returned");
+
+  const response = credential.response as AuthenticatorAssertionResponse;
+  return {
+    id: toBase64Url(credential.rawId),
+    response: {
+      clientDataJSON: toBase64Url(response.clientDataJSON),
+      authenticatorData: toBase64Url(response.authenticatorData),
+      signature: toBase64Url(response.signature),
+      userHandle: response.userHandle
+        ? toBase64Url(response.userHandle)
+        : undefined,
+    },
+  };
+}
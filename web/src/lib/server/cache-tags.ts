@@ -0,0 +1,33 @@
+/**
+ * Surrogate keys ("cache tags") for the Rust ISR cache.
+ *
+ * Server loads record the entities a page renders; the `handle` hook joins
+ * them into the `x-cache-tags` response header, which the proxy strips and
+ * indexes so an entity change purges exactly the pages that show it. The
+ * vocabulary mirrors `cache::tags` in the Rust crate.
+ */
+
+import type { ApiTag } from "$lib/bindings";
+
+export const CACHE_TAGS_HEADER = "x-cache-tags";
+
+/** Every page: the layout renders site identity and social links. */
+export const SETTINGS_TAG = "settings";
+/** Pages whose set of listed projects changes on create/delete/hide. */
+export const PROJECTS_TAG = "projects";
+
+export const projectTag = (id: string) => `project:${id}`;
+export const tagTag = (id: string) => `tag:${id}`;
+
+/** Record surrogate keys for the current request. */
+export function addCacheTags(locals: App.Locals, ...tags: string[]) {
+  for (const tag of tags) locals.cacheTags.add(tag);
+}
+
+/** Tags for a rendered project: the project itself and each of its tags. */
+export function projectCacheTags(project: {
+  id: string;
+  tags: ApiTag[];
+}): string[] {
+  return [projectTag(project.id), ...project.tags.map((t) => tagTag(t.id))];
+}
@@ -1,13 +1,36 @@
 import { getLogger } from "@logtape/logtape";
 import { telemetry } from "$lib/telemetry";
+import { ApiError } from "$lib/errors";
+import type { AdminRole, PasskeyRequestOptions } from "$lib/bindings";
+import { getPasskey, isPasskeyCancelled } from "$lib/passkeys";
 
 const logger = getLogger(["admin", "auth"]);
 
+/** Outcome of a login attempt. */
+export type LoginResult =
+  | "ok"
+  | "invalid"
+  | "totp_required"
+  | "throttled"
+  | "cancelled"
+  | "error";
+
 class AuthStore {
   private static STORAGE_KEY = "admin_session_active";
 
   isAuthenticated = $state(false);
   username = $state<string | null>(null);
+  role = $state<AdminRole | null>(null);
+
+  /** Projects, tags, media, and content. */
+  get canEditContent(): boolean {
+    return this.role === "owner" || this.role === "editor";
+  }
+
+  /** Settings, sessions, and users. */
+  get canManageSite(): boolean {
+    return this.role === "owner";
+  }
 
   init() {
     if (typeof window === "undefined") return;
@@ -18,31 +41,76 @@ class AuthStore {
     }
   }
 
-  async login(username: string, password: string): Promise<boolean> {
+  async login(
+    username: string,
+    password: string,
+    totpCode?: string,
+  ): Promise<LoginResult> {
     try {
       const response = await fetch("/api/login", {
         method: "POST",
         headers: {
           "Content-Type": "application/json",
         },
-        body: JSON.stringify({ username, password }),
+        body: JSON.stringify({ username, password, totpCode }),
         credentials: "include",
       });
 
-      if (response.ok) {
-        const data = await response.json();
-        this.isAuthenticated = true;
-        this.username = data.username;
-        sessionStorage.setItem(AuthStore.STORAGE_KEY, "true");
-        telemetry.identifyAdmin(data.username);
-        return true;
+      return await this.finishLogin(response);
+    } catch (error) {
+      logger.error("Login failed", { error });
+      return "error";
+    }
+  }
+
+  /** Sign in with a passkey instead of a username and password. */
+  async loginWithPasskey(): Promise<LoginResult> {
+    try {
+      const optionsResponse = await fetch("/api/login/passkey/options", {
+        method: "POST",
+        credentials: "include",
+      });
+      if (!optionsResponse.ok) {
+        return "error";
       }
+      const options: PasskeyRequestOptions = await optionsResponse.json();
+      const credential = await getPasskey(options);
 
-      return false;
+      const response = await fetch("/api/login/passkey", {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify(credential),
+        credentials: "include",
+      });
+      return await this.finishLogin(response);
     } catch (error) {
-      logger.error("Login failed", { error });
-      return false;
+      if (isPasskeyCancelled(error)) {
+        return "cancelled";
+      }
+      logger.error("Passkey login failed", { error });
+      return "error";
+    }
+  }
+
+  private async finishLogin(response: Response): Promise<LoginResult> {
+    if (response.ok) {
+      const data = await response.json();
+      this.isAuthenticated = true;
+      this.username = data.username;
+      sessionStorage.setItem(AuthStore.STORAGE_KEY, "true");
+      telemetry.identifyAdmin(data.username);
+      // The login response doesn't carry the role
+      await this.checkSession();
+      return "ok";
+    }
+
+    if (response.status === 429) {
+      return "throttled";
     }
+    const error = await ApiError.fromResponse(response);
+    return ApiError.isSecondFactorRequired(error) ? "totp_required" : "invalid";
   }
 
   async logout(): Promise<void> {
@@ -56,6 +124,7 @@ class AuthStore {
     } finally {
       this.isAuthenticated = false;
       this.username = null;
+      this.role = null;
       sessionStorage.removeItem(AuthStore.STORAGE_KEY);
       telemetry.reset();
     }
@@ -71,6 +140,7 @@ class AuthStore {
         const session = await response.json();
         this.isAuthenticated = true;
         this.username = session.username;
+        this.role = session.role;
         return true;
       }
     } catch (error) {
@@ -79,12 +149,14 @@ class AuthStore {
 
     this.isAuthenticated = false;
     this.username = null;
+    this.role = null;
     return false;
   }
 
-  setSession(username: string): void {
+  setSession(username: string, role: AdminRole): void {
     this.isAuthenticated = true;
     this.username = username;
+    this.role = role;
     sessionStorage.setItem(AuthStore.STORAGE_KEY, "true");
   }
 }
@@ -80,6 +80,7 @@ function dataAttr(name: string, fallback: string | null = null) {
  * `<figure><img|video><figcaption>` markup via `nodeMapping`. The editor keeps
  * the data on a `<figure data-figure>` shell so it survives a GUI round-trip even
  * before a dedicated node-view exists. Authored via the CLI's `content` `--node`.
+ * A `mediaId` names a media library asset; the server fills `src`/`kind` from it.
  */
 export const Figure = Node.create({
   name: "figure",
@@ -92,6 +93,7 @@ export const Figure = Node.create({
       alt: dataAttr("alt"),
       caption: dataAttr("caption"),
       kind: dataAttr("kind", "image"),
+      mediaId: dataAttr("mediaId"),
     };
   },
   parseHTML() {
@@ -3,6 +3,7 @@ import { getOGImageUrl } from "$lib/og-types";
 import { apiFetch } from "$lib/api.server";
 import type { ApiSiteSettings } from "$lib/bindings";
 import { building } from "$app/environment";
+import { addCacheTags, SETTINGS_TAG } from "$lib/server/cache-tags";
 
 export const trailingSlash = "never";
 
@@ -24,7 +25,7 @@ const DEFAULT_SETTINGS: ApiSiteSettings = {
 // (see XEV-986) — the header fix cannot reach already-prerendered HTML.
 const SITE_ORIGIN = import.meta.env.VITE_SITE_ORIGIN ?? "https://xevion.dev";
 
-export const load: LayoutServerLoad = async ({ url, fetch }) => {
+export const load: LayoutServerLoad = async ({ url, fetch, locals }) => {
   let settings: ApiSiteSettings;
 
   if (building) {
@@ -34,6 +35,7 @@ export const load: LayoutServerLoad = async ({ url, fetch }) => {
     // At runtime, fetch from API
     const result = await apiFetch<ApiSiteSettings>("/api/settings", { fetch });
     settings = result.unwrapOr(DEFAULT_SETTINGS);
+    addCacheTags(locals, SETTINGS_TAG);
   }
 
   const origin = building ? SITE_ORIGIN : url.origin;
@@ -1,8 +1,13 @@
 import type { PageServerLoad } from "./$types";
 import { apiFetch } from "$lib/api.server";
 import type { ApiAdminProject } from "$lib/bindings";
+import {
+  addCacheTags,
+  PROJECTS_TAG,
+  projectCacheTags,
+} from "$lib/server/cache-tags";
 
-export const load: PageServerLoad = async ({ fetch, parent }) => {
+export const load: PageServerLoad = async ({ fetch, parent, locals }) => {
   // Get settings from parent layout
   const parentData = await parent();
   const settings = parentData.settings;
@@ -11,8 +16,11 @@ export const load: PageServerLoad = async ({ fetch, parent }) => {
     fetch,
   });
 
+  const projects = result.unwrapOr([]);
+  addCacheTags(locals, PROJECTS_TAG, ...projects.flatMap(projectCacheTags));
+
   return {
-    projects: result.unwrapOr([]),
+    projects,
     socialLinks: settings.socialLinks,
   };
 };
@@ -1,4 +1,5 @@
 import { redirect } from "@sveltejs/kit";
+import type { AdminRole } from "$lib/bindings";
 import type { LayoutServerLoad } from "./$types";
 
 export const load: LayoutServerLoad = async ({ request, url }) => {
@@ -22,10 +23,14 @@ export const load: LayoutServerLoad = async ({ request, url }) => {
     throw redirect(302, `/admin/login?next=${encodeURIComponent(targetPath)}`);
   }
 
+  // Set alongside x-session-user by the proxy
+  const role = (request.headers.get("x-session-role") ?? "viewer") as AdminRole;
+
   return {
     session: {
       authenticated: true,
       username: sessionUser,
+      role,
     },
   };
 };
@@ -5,8 +5,13 @@ import type { ApiProjectDetail } from "$lib/bindings";
 import type { JSONContent } from "@tiptap/core";
 import { renderDetailContent } from "$lib/tiptap/render.server";
 import { getOGImageUrl } from "$lib/og-types";
+import {
+  addCacheTags,
+  projectCacheTags,
+  projectTag,
+} from "$lib/server/cache-tags";
 
-export const load: PageServerLoad = async ({ params, fetch, url }) => {
+export const load: PageServerLoad = async ({ params, fetch, url, locals }) => {
   const result = await apiFetch<ApiProjectDetail>(
     `/api/projects/${params.slug}`,
     { fetch },
@@ -16,6 +21,12 @@ export const load: PageServerLoad = async ({ params, fetch, url }) => {
     throw error(apiErr.status || 404, apiErr.statusText);
   });
 
+  addCacheTags(
+    locals,
+    ...projectCacheTags(project),
+    ...project.related.map((related) => projectTag(related.id)),
+  );
+
   // Every project has a detail page. Prose is optional — projects without
   // authored content render the hero/meta/links/related shell with no body.
   const rendered = project.detailContent